@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use clap::Parser;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
 use hardfiskur_core::{
     board::{Bitboard, Square},
@@ -11,12 +12,63 @@ use hardfiskur_core::{
     },
 };
 
+/// Magic number generator for Harðfiskur.
+///
+/// Finds magic numbers for the rook and bishop attack tables, and prints them
+/// in the format used by `ROOK_MAGICS` and `BISHOP_MAGICS`.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Seed for the random number generator.
+    ///
+    /// Running with the same seed produces the same magics. If not provided,
+    /// a random seed is picked and printed alongside the output.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Number of candidate magics to try for each square before retrying.
+    #[arg(long, default_value_t = 1_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+    search_limit: u64,
+
+    /// Number of times to retry a square, doubling the search limit each time,
+    /// before giving up.
+    #[arg(long, default_value_t = 4)]
+    max_retries: u32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum MagicType {
     Bishop,
     Rook,
 }
 
+impl MagicType {
+    fn name(self) -> &'static str {
+        match self {
+            MagicType::Bishop => "bishop",
+            MagicType::Rook => "rook",
+        }
+    }
+
+    fn attacks(
+        self,
+        blockers: Bitboard,
+        square: Square,
+        ray_attacks: &[[Bitboard; 8]; 64],
+    ) -> Bitboard {
+        match self {
+            MagicType::Bishop => bishop_attacks(blockers, square, ray_attacks),
+            MagicType::Rook => rook_attacks(blockers, square, ray_attacks),
+        }
+    }
+
+    fn blocker_mask(self, square: Square, ray_attacks: &[[Bitboard; 8]; 64]) -> Bitboard {
+        match self {
+            MagicType::Bishop => bishop_attack_blocker_mask(square, ray_attacks),
+            MagicType::Rook => rook_attack_blocker_mask(square, ray_attacks),
+        }
+    }
+}
+
 fn random_u64_few_bits(r: &mut (impl Rng + ?Sized)) -> u64 {
     r.next_u64() & r.next_u64() & r.next_u64()
 }
@@ -54,14 +106,11 @@ fn find_magic(
     square: Square,
     magic_type: MagicType,
     target_bits: u32,
-    search_limit: usize,
+    search_limit: u64,
     r: &mut (impl Rng + ?Sized),
     ray_attacks: &[[Bitboard; 8]; 64],
 ) -> Option<u64> {
-    let mask = match magic_type {
-        MagicType::Bishop => bishop_attack_blocker_mask(square, ray_attacks),
-        MagicType::Rook => rook_attack_blocker_mask(square, ray_attacks),
-    };
+    let mask = magic_type.blocker_mask(square, ray_attacks);
 
     let num_bits_in_mask = mask.pop_count();
     let all_blocker_arrangements = (0..1 << num_bits_in_mask)
@@ -69,10 +118,7 @@ fn find_magic(
         .collect::<Vec<_>>();
     let attacks_for_blocker_arrangements = all_blocker_arrangements
         .iter()
-        .map(|&blockers| match magic_type {
-            MagicType::Bishop => bishop_attacks(blockers, square, ray_attacks),
-            MagicType::Rook => rook_attacks(blockers, square, ray_attacks),
-        })
+        .map(|&blockers| magic_type.attacks(blockers, square, ray_attacks))
         .collect::<Vec<_>>();
 
     for _ in 0..search_limit {
@@ -90,6 +136,100 @@ fn find_magic(
     None
 }
 
+/// Independently checks a magic by building its attack table the same way
+/// `Lookups` does, and looking up every blocker arrangement for the square,
+/// including blockers outside the mask.
+fn verify_magic(
+    square: Square,
+    magic_type: MagicType,
+    magic: u64,
+    num_bits: u32,
+    ray_attacks: &[[Bitboard; 8]; 64],
+) -> bool {
+    let mask = magic_type.blocker_mask(square, ray_attacks);
+    let shift = 64 - num_bits;
+
+    let mut table = vec![None; 1 << num_bits];
+    for n in 0..1 << mask.pop_count() {
+        let blockers = nth_blocker_arrangement_for_mask(n, mask);
+        let attacks = magic_type.attacks(blockers, square, ray_attacks);
+        let entry = &mut table[(blockers.0.wrapping_mul(magic) >> shift) as usize];
+
+        match entry {
+            Some(existing) if *existing != attacks => return false,
+            _ => *entry = Some(attacks),
+        }
+    }
+
+    (0..1 << mask.pop_count()).all(|n| {
+        let blockers = nth_blocker_arrangement_for_mask(n, mask);
+        // Pieces outside the mask never affect the attacks
+        let occupied = blockers | !mask;
+        let index = ((occupied & mask).0.wrapping_mul(magic) >> shift) as usize;
+
+        table[index] == Some(magic_type.attacks(occupied, square, ray_attacks))
+    })
+}
+
+/// Finds and verifies magics for every square, printing them as a Rust
+/// constant. Returns the total number of attack table entries needed, or
+/// `None` if a magic couldn't be found for some square.
+fn find_magics(
+    magic_type: MagicType,
+    target_bits: &[u32; 64],
+    args: &Args,
+    r: &mut (impl Rng + ?Sized),
+    ray_attacks: &[[Bitboard; 8]; 64],
+) -> Option<usize> {
+    let name = magic_type.name();
+    let mut table_size = 0;
+
+    println!("const {}_MAGICS: [(u64, u32); 64] = [", name.to_uppercase());
+    for (i, &target_bits) in target_bits.iter().enumerate() {
+        let square = Square::from_index_unchecked(i);
+
+        let mut search_limit = args.search_limit;
+        let mut retries = 0;
+        let magic = loop {
+            if let Some(magic) = find_magic(
+                square,
+                magic_type,
+                target_bits,
+                search_limit,
+                r,
+                ray_attacks,
+            ) {
+                break magic;
+            }
+
+            if retries >= args.max_retries {
+                eprintln!(
+                    "Could not find {name} magic for {square} in {search_limit} iterations, giving up"
+                );
+                return None;
+            }
+
+            retries += 1;
+            search_limit = search_limit.saturating_mul(2);
+            eprintln!(
+                "Could not find {name} magic for {square}, retrying with {search_limit} iterations"
+            );
+        };
+
+        if !verify_magic(square, magic_type, magic, target_bits, ray_attacks) {
+            eprintln!("Found {name} magic 0x{magic:016x} for {square} failed verification");
+            return None;
+        }
+
+        table_size += 1 << target_bits;
+        println!("    (0x{magic:016x}, {target_bits}),");
+    }
+    println!("];");
+    println!("// {name} attack table size: {table_size} entries");
+
+    Some(table_size)
+}
+
 #[rustfmt::skip]
 const ROOK_TARGET_BITS: [u32; 64] = [
     12, 11, 11, 11, 11, 11, 11, 12,
@@ -115,40 +255,41 @@ const BISHOP_TARGET_BITS: [u32; 64] = [
 ];
 
 fn main() {
+    let args = Args::parse();
+    let seed = args.seed.unwrap_or_else(|| thread_rng().gen());
+
     let ray_attacks = gen_ray_attacks();
-    let mut rng = thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
 
-    println!("const ROOK_MAGICS: [(u64, u32); 64] = [");
-    for (i, &target_bits) in ROOK_TARGET_BITS.iter().enumerate() {
-        let square = Square::from_index_unchecked(i);
-        let magic = find_magic(
-            square,
-            MagicType::Rook,
-            target_bits,
-            1_000_000,
-            &mut rng,
-            &ray_attacks,
-        )
-        .expect("Could not find rook magic :(");
-        println!("    (0x{magic:016x}, {target_bits}),");
-    }
-    println!("];");
+    println!("// Generated by find_magics with --seed {seed}");
+    println!();
+
+    let Some(rook_size) = find_magics(
+        MagicType::Rook,
+        &ROOK_TARGET_BITS,
+        &args,
+        &mut rng,
+        &ray_attacks,
+    ) else {
+        std::process::exit(1);
+    };
 
     println!();
 
-    println!("const BISHOP_MAGICS: [(u64, u32); 64] = [");
-    for (i, &target_bits) in BISHOP_TARGET_BITS.iter().enumerate() {
-        let square = Square::from_index_unchecked(i);
-        let magic = find_magic(
-            square,
-            MagicType::Bishop,
-            target_bits,
-            1_000_000,
-            &mut rng,
-            &ray_attacks,
-        )
-        .expect("Could not find bishop magic :(");
-        println!("    (0x{magic:016x}, {target_bits}),");
-    }
-    println!("];");
+    let Some(bishop_size) = find_magics(
+        MagicType::Bishop,
+        &BISHOP_TARGET_BITS,
+        &args,
+        &mut rng,
+        &ray_attacks,
+    ) else {
+        std::process::exit(1);
+    };
+
+    println!();
+    println!(
+        "// Total attack table size: {} entries ({} KiB)",
+        rook_size + bishop_size,
+        (rook_size + bishop_size) * std::mem::size_of::<Bitboard>() / 1024
+    );
 }
@@ -1,13 +1,20 @@
 use core::f32;
-use std::time::{Duration, Instant};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
-use eframe::egui::{self, Align, Id, Layout, Sense, Ui};
+use eframe::egui::{self, Align, Id, Layout, RichText, Sense, Ui};
 use egui_extras::{Column, TableBuilder, TableRow};
-use hardfiskur_core::board::{Board, BoardState, Color, DrawReason, Move};
-use hardfiskur_ui::chess_board::ChessBoardUI;
+use hardfiskur_core::board::{
+    Bitboard, Board, BoardState, Castling, Color, DrawReason, Move, Piece, PieceType, Square,
+};
+use hardfiskur_ui::{base_board::BaseBoardUI, chess_board::ChessBoardUI};
 
 const SOFT_SCROLL_DELAY: Duration = Duration::from_millis(300);
 const SCROLL_OVERRIDE_MAGNITUDE: f32 = 3.5;
+const ENGINE_PV_ARROWS: usize = 3;
+const VARIATION_PREVIEW_MOVES: usize = 4;
 
 #[derive(Debug, Clone)]
 struct MoveHistoryItem {
@@ -15,6 +22,17 @@ struct MoveHistoryItem {
     san: String,
 }
 
+/// A line which was replaced in the move history by playing a different move
+/// from an earlier position, kept so that it can be switched back to.
+#[derive(Debug, Clone)]
+struct Variation {
+    /// Position in the move history the variation branches off from.
+    branch_position: usize,
+    moves: Vec<MoveHistoryItem>,
+    /// Variations which branched off this line while it was being played.
+    variations: Vec<Variation>,
+}
+
 #[derive(Debug, Clone)]
 struct BoardManagerState {
     current_board: Board,
@@ -25,6 +43,7 @@ struct BoardManagerState {
 
     white_first_move: bool,
     move_history: Vec<MoveHistoryItem>,
+    variations: Vec<Variation>,
 }
 
 impl BoardManagerState {
@@ -39,6 +58,7 @@ impl BoardManagerState {
             display_board: board,
             white_first_move,
             move_history: Vec::new(),
+            variations: Vec::new(),
         }
     }
 
@@ -69,6 +89,79 @@ impl BoardManagerState {
         }
     }
 
+    /// Plays a move in the displayed position. If that's an earlier position,
+    /// the rest of the move history is replaced by a new line starting with
+    /// this move, unless it's the same move that was played originally. The
+    /// replaced moves are kept as a variation.
+    fn play_from_display(&mut self, m: Move) -> bool {
+        if !self.is_displaying_latest_move() {
+            let position = self.move_history_position;
+            if self.move_history[position].move_repr == m {
+                self.scroll_forwards();
+                return true;
+            }
+
+            // Playing the first move of a variation goes back to it
+            if let Some(idx) = self.variations.iter().position(|variation| {
+                variation.branch_position == position && variation.moves[0].move_repr == m
+            }) {
+                self.switch_to_variation(idx);
+                self.scroll_forwards();
+                return true;
+            }
+
+            if !self.display_board.legal_moves().contains(&m) {
+                return false;
+            }
+
+            let replaced = self.split_off_variation(position);
+            self.variations.push(replaced);
+        }
+
+        self.push_move(m)
+    }
+
+    /// Makes the variation at `idx` part of the move history, keeping the
+    /// moves it replaces as a variation instead. The displayed position is
+    /// moved back to the branching point if it was past it.
+    fn switch_to_variation(&mut self, idx: usize) {
+        let variation = self.variations.remove(idx);
+        let replaced = self.split_off_variation(variation.branch_position);
+        // Nothing is replaced if the variation branches off the latest move
+        if !replaced.moves.is_empty() {
+            self.variations.push(replaced);
+        }
+        self.variations.extend(variation.variations);
+
+        for item in variation.moves {
+            assert!(self.current_board.push_move_repr(item.move_repr));
+            self.move_history.push(item);
+        }
+    }
+
+    /// Removes the moves after `branch_position` from the move history,
+    /// returning them as a variation along with any variations branching off
+    /// them.
+    fn split_off_variation(&mut self, branch_position: usize) -> Variation {
+        self.scroll_to(self.move_history_position.min(branch_position));
+
+        for _ in branch_position..self.move_history.len() {
+            self.current_board.pop_move();
+        }
+        let moves = self.move_history.split_off(branch_position);
+
+        let (variations, kept) = std::mem::take(&mut self.variations)
+            .into_iter()
+            .partition(|variation| variation.branch_position > branch_position);
+        self.variations = kept;
+
+        Variation {
+            branch_position,
+            moves,
+            variations,
+        }
+    }
+
     fn push_move(&mut self, m: Move) -> bool {
         let san = self.current_board.get_san(m);
         if self.current_board.push_move_repr(m) {
@@ -98,9 +191,45 @@ impl BoardManagerState {
             self.display_board.pop_move();
             self.move_history_position -= 1;
         }
+
+        let len = self.move_history.len();
+        self.variations
+            .retain(|variation| variation.branch_position <= len);
+    }
+
+    /// Move number for the move played from `position` in the move history,
+    /// e.g. "12." for white or "12..." for black.
+    fn move_number(&self, position: usize) -> String {
+        let plies = position + usize::from(!self.white_first_move);
+        let fullmoves = self.starting_fullmoves as usize + plies / 2;
+
+        if plies.is_multiple_of(2) {
+            format!("{fullmoves}.")
+        } else {
+            format!("{fullmoves}...")
+        }
+    }
+
+    /// Short descriptions of the variations, e.g. "12... Nf6 Bg5 h6 Bh4 ...".
+    fn variation_labels(&self) -> Vec<String> {
+        self.variations
+            .iter()
+            .map(|variation| {
+                let mut label = self.move_number(variation.branch_position);
+                for item in variation.moves.iter().take(VARIATION_PREVIEW_MOVES) {
+                    label.push(' ');
+                    label.push_str(&item.san);
+                }
+                if variation.moves.len() > VARIATION_PREVIEW_MOVES {
+                    label.push_str(" ...");
+                }
+
+                label
+            })
+            .collect()
     }
 
-    fn rows(&self) -> Vec<MoveHistoryRow> {
+    fn rows(&self) -> Vec<MoveHistoryRow<'_>> {
         let mut rows = Vec::new();
         let mut move_iter = self
             .move_history
@@ -145,6 +274,201 @@ impl BoardManagerState {
     }
 }
 
+/// Reasons the position in the editor can't be turned into a [`Board`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorError {
+    KingCount { color: Color, count: usize },
+    PawnOnBackRank { square: Square },
+    OpponentInCheck,
+}
+
+impl Display for EditorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KingCount { color, count } => {
+                write!(f, "Expected exactly one {color:?} king but found {count}")
+            }
+            Self::PawnOnBackRank { square } => write!(f, "Pawn on back rank square {square}"),
+            Self::OpponentInCheck => write!(f, "The side not to move is in check"),
+        }
+    }
+}
+
+/// A position being set up by hand, which only becomes a [`Board`] once it's
+/// been checked to be legal.
+#[derive(Debug, Clone)]
+pub struct EditorState {
+    pieces: [Option<Piece>; 64],
+    to_move: Color,
+    castling: Castling,
+    en_passant: Option<Square>,
+    fullmoves: u32,
+
+    /// The piece placed by clicking on the board, or [`None`] to remove
+    /// pieces instead.
+    palette: Option<Piece>,
+    error: Option<EditorError>,
+}
+
+impl EditorState {
+    fn new(board: &Board) -> Self {
+        let mut pieces = [None; 64];
+        for (piece, square) in board.pieces() {
+            pieces[square.index()] = Some(piece);
+        }
+
+        Self {
+            pieces,
+            to_move: board.to_move(),
+            castling: board.castling(),
+            en_passant: board.en_passant(),
+            fullmoves: board.fullmoves(),
+
+            palette: Some(Piece::WHITE_PAWN),
+            error: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.pieces = [None; 64];
+        self.castling = Castling::empty();
+        self.en_passant = None;
+    }
+
+    /// Places the palette piece on the square, or removes the piece there if
+    /// it's the same as the palette piece or the eraser is selected.
+    fn click(&mut self, square: Square) {
+        let current = &mut self.pieces[square.index()];
+        *current = match self.palette {
+            Some(piece) if *current != Some(piece) => Some(piece),
+            _ => None,
+        };
+    }
+
+    fn drag(&mut self, from: Square, to: Square) {
+        if from != to {
+            self.pieces[to.index()] = self.pieces[from.index()].take();
+        }
+    }
+
+    fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.pieces[square.index()]
+    }
+
+    /// Castling rights which are possible with the kings and rooks on their
+    /// starting squares.
+    fn possible_castling(&self) -> Castling {
+        let mut possible = Castling::empty();
+
+        for (right, king, rook, piece_color) in [
+            (
+                Castling::WHITE_KINGSIDE,
+                Square::E1,
+                Square::H1,
+                Color::White,
+            ),
+            (
+                Castling::WHITE_QUEENSIDE,
+                Square::E1,
+                Square::A1,
+                Color::White,
+            ),
+            (
+                Castling::BLACK_KINGSIDE,
+                Square::E8,
+                Square::H8,
+                Color::Black,
+            ),
+            (
+                Castling::BLACK_QUEENSIDE,
+                Square::E8,
+                Square::A8,
+                Color::Black,
+            ),
+        ] {
+            if self.piece_at(king) == Some(Piece::king(piece_color))
+                && self.piece_at(rook) == Some(Piece::rook(piece_color))
+            {
+                possible |= right;
+            }
+        }
+
+        possible
+    }
+
+    /// En passant squares the side to move could capture onto, if the
+    /// opponent had just double-pushed a pawn past them.
+    fn possible_en_passant(&self) -> Vec<Square> {
+        let (rank, forward) = match self.to_move {
+            Color::White => (5, -8),
+            Color::Black => (2, 8),
+        };
+        let our_pawn = Some(Piece::pawn(self.to_move));
+
+        (0..8)
+            .map(|file| Square::new_unchecked(rank, file))
+            .filter(|&square| {
+                let pawn_square = square.offset(forward);
+                let can_capture = [-1, 1].into_iter().any(|side| {
+                    let file = pawn_square.file() as i8 + side;
+                    (0..8).contains(&file)
+                        && self.piece_at(Square::new_unchecked(pawn_square.rank(), file as u8))
+                            == our_pawn
+                });
+
+                self.piece_at(square).is_none()
+                    && self.piece_at(square.offset(-forward)).is_none()
+                    && self.piece_at(pawn_square) == Some(Piece::pawn(self.to_move.flip()))
+                    && can_capture
+            })
+            .collect()
+    }
+
+    /// Checks the position is legal and turns it into a [`Board`]. Castling
+    /// rights and en passant squares which are no longer possible, e.g.
+    /// because a piece was moved after setting them, are dropped.
+    pub fn to_board(&self) -> Result<Board, EditorError> {
+        for color in [Color::White, Color::Black] {
+            let count = self
+                .pieces
+                .iter()
+                .filter(|&&piece| piece == Some(Piece::king(color)))
+                .count();
+            if count != 1 {
+                return Err(EditorError::KingCount { color, count });
+            }
+        }
+
+        let back_ranks = Bitboard::RANK_1 | Bitboard::RANK_8;
+        if let Some(square) = back_ranks.squares().find(|&square| {
+            self.piece_at(square)
+                .is_some_and(|piece| piece.piece_type() == PieceType::Pawn)
+        }) {
+            return Err(EditorError::PawnOnBackRank { square });
+        }
+
+        let en_passant = self
+            .en_passant
+            .filter(|square| self.possible_en_passant().contains(square));
+
+        let board = Board::new(
+            &self.pieces,
+            self.to_move,
+            self.castling & self.possible_castling(),
+            en_passant,
+            0,
+            self.fullmoves,
+        );
+
+        let opponent = self.to_move.flip();
+        if board.is_square_attacked(board.get_king(opponent), self.to_move) {
+            return Err(EditorError::OpponentInCheck);
+        }
+
+        Ok(board)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct MoveHistoryRow<'a> {
     fullmoves: u32,
@@ -156,7 +480,16 @@ pub struct BoardManager {
     state: BoardManagerState,
     chess_ui: ChessBoardUI,
 
+    perspective: Color,
+    auto_flip: bool,
+
+    engine_arrows: Vec<(Square, Square)>,
+
     last_scroll_event: Instant,
+    skip_next_animation: bool,
+
+    editor: Option<EditorState>,
+    editor_ui: BaseBoardUI,
 }
 
 impl BoardManager {
@@ -165,15 +498,39 @@ impl BoardManager {
             state: BoardManagerState::new(Board::starting_position()),
             chess_ui: ChessBoardUI::new(Id::new("hardfiskur_ui_board")),
 
+            perspective: Color::White,
+            auto_flip: false,
+
+            engine_arrows: Vec::new(),
+
             last_scroll_event: Instant::now(),
+            skip_next_animation: false,
+
+            editor: None,
+            editor_ui: BaseBoardUI::new(Id::new("hardfiskur_ui_editor_board")),
         }
     }
 
     pub fn playing(&self) -> bool {
-        matches!(self.state.current_board.state(), BoardState::InPlay { .. })
+        !self.editing() && matches!(self.state.current_board.state(), BoardState::InPlay { .. })
+    }
+
+    pub fn editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    /// Enters the position editor, starting from the position currently
+    /// being displayed.
+    pub fn start_editing(&mut self) {
+        self.editor = Some(EditorState::new(&self.state.display_board));
     }
 
     pub fn ui_board(&mut self, ui: &mut Ui) -> Option<Move> {
+        if self.editor.is_some() {
+            self.ui_editor_board(ui);
+            return None;
+        }
+
         let game_state = self.state.current_board.state();
         let playing = matches!(game_state, BoardState::InPlay { .. });
         let game_state_text = match game_state {
@@ -188,6 +545,10 @@ impl BoardManager {
             },
         };
 
+        // Moves can be played from earlier positions too, which starts a new
+        // line from there
+        let display_playing = matches!(self.state.display_board.state(), BoardState::InPlay { .. });
+
         let mut input_move = None;
 
         ui.vertical_centered(|ui| {
@@ -196,8 +557,16 @@ impl BoardManager {
 
             ui.centered_and_justified(|ui| {
                 let mut props = ChessBoardUI::props(&self.state.display_board)
-                    .can_move(playing && self.state.is_displaying_latest_move())
-                    .fade_out_board(!self.state.is_displaying_latest_move());
+                    .can_move(display_playing)
+                    .fade_out_board(!self.state.is_displaying_latest_move())
+                    .perspective(self.perspective())
+                    .skip_animation(std::mem::take(&mut self.skip_next_animation));
+
+                // The engine's PV is for the latest position, so hide it when
+                // looking back through the history
+                if self.state.is_displaying_latest_move() {
+                    props = props.engine_arrows(&self.engine_arrows);
+                }
 
                 if let Some(item) = self.state.current_display_move() {
                     props = props
@@ -220,9 +589,14 @@ impl BoardManager {
                         // this will trigger a a fast scroll to the beginning/end of the
                         // move list.
                         let scroll_magnitude = state.raw_scroll_delta.y.abs();
+                        let since_last_scroll = self.last_scroll_event.elapsed();
                         if scroll_magnitude >= SCROLL_OVERRIDE_MAGNITUDE
-                            || self.last_scroll_event.elapsed() >= SOFT_SCROLL_DELAY
+                            || since_last_scroll >= SOFT_SCROLL_DELAY
                         {
+                            // Animations would only lag behind when scrubbing
+                            // through the moves quickly
+                            self.skip_next_animation |= since_last_scroll < SOFT_SCROLL_DELAY;
+
                             if state.raw_scroll_delta.y < 0.0 {
                                 self.scroll_forwards();
                             } else if state.raw_scroll_delta.y > 0.0 {
@@ -240,10 +614,166 @@ impl BoardManager {
         input_move
     }
 
+    fn ui_editor_board(&mut self, ui: &mut Ui) {
+        let perspective = self.perspective;
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+
+        ui.vertical_centered(|ui| {
+            ui.heading("Edit position");
+            ui.label("Click to place the selected piece, drag to move pieces");
+
+            ui.centered_and_justified(|ui| {
+                let available_size = ui.available_size();
+                let size = available_size.x.min(available_size.y).min(640.0);
+
+                let props = BaseBoardUI::props()
+                    .pieces(&editor.pieces)
+                    .perspective(perspective)
+                    .allow_arrows(false)
+                    .skip_animation(true)
+                    .with_size(size);
+
+                let response = self.editor_ui.ui(ui, props);
+
+                if let Some(square) = response.clicked_square {
+                    editor.click(square);
+                }
+                if let Some((from, to)) = response.dropped {
+                    editor.drag(from, to);
+                }
+            });
+        });
+    }
+
+    /// Shows the controls for the position editor. Returns the new position
+    /// if the user finished editing it and it was legal.
+    pub fn ui_editor_controls(&mut self, ui: &mut Ui) -> Option<Board> {
+        let editor = self.editor.as_mut()?;
+        let mut committed = None;
+        let mut cancelled = false;
+
+        ui.heading("Position editor");
+
+        for color in [Color::White, Color::Black] {
+            ui.horizontal(|ui| {
+                for piece_type in PieceType::ALL.into_iter().rev() {
+                    let piece = Piece::new(color, piece_type);
+                    ui.selectable_value(
+                        &mut editor.palette,
+                        Some(piece),
+                        RichText::new(piece.as_unicode_char().to_string()).size(24.0),
+                    );
+                }
+            });
+        }
+        ui.selectable_value(&mut editor.palette, None, "Remove pieces");
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut editor.to_move, Color::White, "White to move");
+            ui.selectable_value(&mut editor.to_move, Color::Black, "Black to move");
+        });
+
+        let possible_castling = editor.possible_castling();
+        ui.horizontal(|ui| {
+            for (right, label) in [
+                (Castling::WHITE_KINGSIDE, "K"),
+                (Castling::WHITE_QUEENSIDE, "Q"),
+                (Castling::BLACK_KINGSIDE, "k"),
+                (Castling::BLACK_QUEENSIDE, "q"),
+            ] {
+                let mut allowed = editor.castling.contains(right);
+                let enabled = possible_castling.contains(right);
+                if ui
+                    .add_enabled(enabled, egui::Checkbox::new(&mut allowed, label))
+                    .changed()
+                {
+                    editor.castling.set(right, allowed);
+                }
+            }
+            ui.label("Castling");
+        });
+
+        let possible_en_passant = editor.possible_en_passant();
+        if editor
+            .en_passant
+            .is_some_and(|square| !possible_en_passant.contains(&square))
+        {
+            editor.en_passant = None;
+        }
+        egui::ComboBox::from_label("En passant")
+            .selected_text(editor.en_passant.map_or("-".to_string(), |s| s.to_string()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut editor.en_passant, None, "-");
+                for square in possible_en_passant {
+                    ui.selectable_value(&mut editor.en_passant, Some(square), square.to_string());
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui.button("Clear board").clicked() {
+                editor.clear();
+            }
+            if ui.button("Starting position").clicked() {
+                *editor = EditorState::new(&Board::starting_position());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Done").clicked() {
+                match editor.to_board() {
+                    Ok(board) => committed = Some(board),
+                    Err(e) => editor.error = Some(e),
+                }
+            }
+            if ui.button("Cancel").clicked() {
+                cancelled = true;
+            }
+        });
+
+        if let Some(error) = editor.error {
+            ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        }
+
+        if committed.is_some() || cancelled {
+            self.editor = None;
+        }
+
+        committed
+    }
+
     pub fn ui_move_history(&mut self, ui: &mut Ui) -> Option<usize> {
+        self.emit_variations(ui);
         self.emit_move_history_rows(ui)
     }
 
+    /// Lists the lines replaced by playing moves from earlier positions.
+    /// Clicking one switches the move history to it.
+    fn emit_variations(&mut self, ui: &mut Ui) {
+        let labels = self.state.variation_labels();
+        if labels.is_empty() {
+            return;
+        }
+
+        ui.label("Variations");
+        let mut clicked = None;
+        for (idx, label) in labels.into_iter().enumerate() {
+            if ui.small_button(label).clicked() {
+                clicked = Some(idx);
+            }
+        }
+        ui.separator();
+
+        if let Some(idx) = clicked {
+            let branch_position = self.state.variations[idx].branch_position;
+            self.engine_arrows.clear();
+            self.skip_next_animation = true;
+            self.state.switch_to_variation(idx);
+            self.state.scroll_to(branch_position + 1);
+        }
+    }
+
     pub fn scroll_forwards(&mut self) {
         self.state.scroll_forwards();
     }
@@ -252,15 +782,58 @@ impl BoardManager {
         self.state.scroll_backwards();
     }
 
+    pub fn scroll_to_start(&mut self) {
+        self.scroll_to(0);
+    }
+
+    pub fn scroll_to_end(&mut self) {
+        self.scroll_to(self.state.move_history.len());
+    }
+
+    /// Steps through the move history with the arrow keys, and jumps to the
+    /// start or end with Home and End.
+    pub fn handle_navigation_keys(&mut self, ctx: &egui::Context) {
+        let (back, forwards, start, end) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowLeft),
+                i.key_pressed(egui::Key::ArrowRight),
+                i.key_pressed(egui::Key::Home),
+                i.key_pressed(egui::Key::End),
+            )
+        });
+
+        if start {
+            self.scroll_to_start();
+        } else if end {
+            self.scroll_to_end();
+        } else if back {
+            self.scroll_backwards();
+        } else if forwards {
+            self.scroll_forwards();
+        }
+    }
+
     pub fn scroll_to(&mut self, move_history_position: usize) {
+        // Jumping several moves at once can't be sensibly animated
+        self.skip_next_animation |=
+            move_history_position.abs_diff(self.state.move_history_position) > 1;
         self.state.scroll_to(move_history_position);
     }
 
     pub fn push_move(&mut self, m: Move) -> bool {
+        self.engine_arrows.clear();
         self.state.push_move(m)
     }
 
+    /// Plays a move made by the user on the displayed position, see
+    /// [`BoardManagerState::play_from_display`].
+    pub fn play_move(&mut self, m: Move) -> bool {
+        self.engine_arrows.clear();
+        self.state.play_from_display(m)
+    }
+
     pub fn pop_move(&mut self) {
+        self.engine_arrows.clear();
         self.state.pop_move();
     }
 
@@ -269,13 +842,50 @@ impl BoardManager {
     }
 
     pub fn reset_to(&mut self, board: Board) {
+        self.editor = None;
+        self.engine_arrows.clear();
+        self.skip_next_animation = true;
         self.state = BoardManagerState::new(board);
     }
 
+    /// Shows the first few moves of the engine's principal variation as
+    /// arrows on the board.
+    pub fn set_engine_pv(&mut self, pv: &[Move]) {
+        self.engine_arrows = pv
+            .iter()
+            .take(ENGINE_PV_ARROWS)
+            .map(|m| (m.from_square(), m.to_square()))
+            .collect();
+    }
+
     pub fn current_board(&self) -> &Board {
         &self.state.current_board
     }
 
+    /// The position currently shown on the board, which may be earlier in the
+    /// game than [`Self::current_board`].
+    pub fn display_board(&self) -> &Board {
+        &self.state.display_board
+    }
+
+    /// The side the board is currently being viewed from.
+    pub fn perspective(&self) -> Color {
+        if self.auto_flip {
+            self.state.display_board.to_move()
+        } else {
+            self.perspective
+        }
+    }
+
+    pub fn flip_board(&mut self) {
+        self.perspective = self.perspective().flip();
+        self.auto_flip = false;
+    }
+
+    pub fn auto_flip_mut(&mut self) -> &mut bool {
+        &mut self.auto_flip
+    }
+
     fn emit_move_history_rows(&mut self, ui: &mut Ui) -> Option<usize> {
         let rows = self.state.rows();
         let mut scroll_request = None;
@@ -0,0 +1,71 @@
+use eframe::egui::{self, Align2, Color32, FontId, Rect, Sense, Ui, Vec2};
+use hardfiskur_engine::{evaluation::phase::Phase, score::Score, wdl::Wdl};
+
+use crate::search_thread::SearchUpdate;
+
+const BAR_WIDTH: f32 = 24.0;
+const BAR_HEIGHT: f32 = 240.0;
+
+/// Vertical bar showing the engine's latest evaluation, filled with white
+/// from the bottom in proportion to white's expected score.
+pub struct EvalBar {
+    latest: Option<(Score, Phase)>,
+}
+
+impl EvalBar {
+    pub fn new() -> Self {
+        Self { latest: None }
+    }
+
+    pub fn update(&mut self, update: &SearchUpdate) {
+        self.latest = Some((update.score, update.phase));
+    }
+
+    pub fn reset(&mut self) {
+        self.latest = None;
+    }
+
+    /// Fraction of the bar that should be filled for white, from 0 to 1.
+    /// Mate scores pin the bar to one end.
+    fn white_fill(&self) -> f32 {
+        match self.latest {
+            Some((score, phase)) => {
+                let wdl = Wdl::from_score(score, phase);
+                (2 * wdl.win + wdl.draw) as f32 / 2000.0
+            }
+            None => 0.5,
+        }
+    }
+
+    pub fn ui(&self, ui: &mut Ui) {
+        let (rect, _) = ui.allocate_exact_size(Vec2::new(BAR_WIDTH, BAR_HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let white_height = rect.height() * self.white_fill();
+        let split_y = rect.bottom() - white_height;
+
+        painter.rect_filled(
+            Rect::from_min_max(rect.min, egui::pos2(rect.right(), split_y)),
+            0.0,
+            Color32::from_gray(40),
+        );
+        painter.rect_filled(
+            Rect::from_min_max(egui::pos2(rect.left(), split_y), rect.max),
+            0.0,
+            Color32::from_gray(230),
+        );
+
+        if let Some((score, _)) = self.latest {
+            // Draw the score in the half belonging to the side that's ahead
+            let (pos, anchor, color) = if score.get() >= 0 {
+                (rect.center_bottom(), Align2::CENTER_BOTTOM, Color32::BLACK)
+            } else {
+                (rect.center_top(), Align2::CENTER_TOP, Color32::WHITE)
+            };
+
+            let text = score.to_string();
+            let text = text.trim_start_matches('+');
+            painter.text(pos, anchor, text, FontId::proportional(9.0), color);
+        }
+    }
+}
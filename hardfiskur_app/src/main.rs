@@ -1,24 +1,30 @@
 mod board_manager;
+mod eval_bar;
 mod fen_input;
+mod search_info_panel;
+mod search_settings;
 mod search_thread;
 mod sfx_stream;
 
-use std::time::Duration;
-
 use eframe::egui::{self, Layout, Vec2};
-use hardfiskur_core::board::{Board, Move};
+use hardfiskur_core::board::{Board, BoardState, Move};
 
 use board_manager::BoardManager;
+use eval_bar::EvalBar;
 use fen_input::FenInput;
+use search_info_panel::SearchInfoPanel;
+use search_settings::SearchSettings;
 use search_thread::SearchThread;
-use sfx_stream::SFXStream;
+use sfx_stream::{SFXStream, SoundCue};
 
 struct HardfiskurApp {
     board_manager: BoardManager,
 
     fen_input: FenInput,
+    eval_bar: EvalBar,
+    search_info: SearchInfoPanel,
 
-    move_time: Duration,
+    search_settings: SearchSettings,
     search_thread: SearchThread,
     sfx_stream: SFXStream,
 
@@ -32,9 +38,11 @@ impl HardfiskurApp {
             board_manager: BoardManager::new(),
 
             fen_input: FenInput::new(),
+            eval_bar: EvalBar::new(),
+            search_info: SearchInfoPanel::new(),
 
             search_thread: SearchThread::new(),
-            move_time: Duration::from_secs(1),
+            search_settings: SearchSettings::new(),
             sfx_stream: SFXStream::new(),
 
             automove_after_user: false,
@@ -51,7 +59,7 @@ impl HardfiskurApp {
             let ctx = ctx.clone();
             self.search_thread.send_search_request(
                 self.board_manager.current_board(),
-                self.move_time,
+                self.search_settings.limits(),
                 move || {
                     ctx.request_repaint();
                 },
@@ -59,13 +67,40 @@ impl HardfiskurApp {
         }
     }
 
+    /// Picks the sound to play after `the_move` was made, resulting in
+    /// `board`.
+    fn sound_cue(the_move: Move, board: &Board) -> SoundCue {
+        match board.state() {
+            BoardState::InPlay { checkers: 0 } => {
+                if the_move.is_castle() {
+                    SoundCue::Castle
+                } else if the_move.promotion().is_some() {
+                    SoundCue::Promotion
+                } else if the_move.is_capture() {
+                    SoundCue::Capture
+                } else {
+                    SoundCue::Move
+                }
+            }
+            BoardState::InPlay { .. } => SoundCue::Check,
+            BoardState::Draw(_) | BoardState::Win(_) => SoundCue::GameEnd,
+        }
+    }
+
     fn make_move(&mut self, ctx: &egui::Context, the_move: Move, from_user: bool) {
-        if self.board_manager.push_move(the_move) {
-            if the_move.is_capture() {
-                self.sfx_stream.play_capture();
+        let played = if from_user {
+            self.board_manager.play_move(the_move)
+        } else {
+            self.board_manager.push_move(the_move)
+        };
+
+        if played {
+            let board = if from_user {
+                self.board_manager.display_board()
             } else {
-                self.sfx_stream.play_move();
-            }
+                self.board_manager.current_board()
+            };
+            self.sfx_stream.play(Self::sound_cue(the_move, board));
         }
 
         self.search_thread.cancel_search();
@@ -78,14 +113,43 @@ impl HardfiskurApp {
 
 impl eframe::App for HardfiskurApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Updates are always sent before the final move, so handle them first
+        // to avoid showing the PV of a position that's no longer on the board
+        if let Some(update) = self.search_thread.try_receive_update() {
+            self.eval_bar.update(&update);
+            self.search_info
+                .update(&update, self.board_manager.current_board());
+            self.board_manager.set_engine_pv(&update.pv);
+        }
+
         if let Some(m) = self.search_thread.try_receive_move() {
             self.make_move(ctx, m, false);
         }
 
+        if !ctx.wants_keyboard_input() {
+            if ctx.input(|i| i.key_pressed(egui::Key::F)) {
+                self.board_manager.flip_board();
+            }
+
+            if !self.board_manager.editing() {
+                self.board_manager.handle_navigation_keys(ctx);
+            }
+        }
+
         egui::SidePanel::right("right_panel")
             .resizable(false)
             .min_width(200.0)
             .show(ctx, |ui| {
+                if self.board_manager.editing() {
+                    if let Some(board) = self.board_manager.ui_editor_controls(ui) {
+                        self.board_manager.reset_to(board);
+                        self.search_thread.reset();
+                        self.eval_bar.reset();
+                        self.search_info.reset();
+                    }
+                    return;
+                }
+
                 if ui.button("Make move").clicked() && self.board_manager.playing() {
                     self.start_search(ctx);
                 }
@@ -93,27 +157,44 @@ impl eframe::App for HardfiskurApp {
                 if ui.button("Reset").clicked() {
                     self.board_manager.reset();
                     self.search_thread.reset();
+                    self.eval_bar.reset();
+                    self.search_info.reset();
                 }
 
                 if ui.button("Undo move").clicked() {
                     self.board_manager.pop_move();
                 }
 
-                let mut move_time_secs = self.move_time.as_secs_f64();
-                ui.add(
-                    egui::DragValue::new(&mut move_time_secs)
-                        .prefix("Move time: ")
-                        .speed(0.1)
-                        .range(0.0..=600.0)
-                        .clamp_to_range(false)
-                        .suffix(" secs"),
-                );
-                self.move_time =
-                    Duration::try_from_secs_f64(move_time_secs).unwrap_or(Duration::ZERO);
+                if ui.button("Edit position").clicked() {
+                    self.search_thread.cancel_search();
+                    self.board_manager.start_editing();
+                }
+
+                self.search_settings.ui(ui);
 
                 ui.checkbox(&mut self.automove_after_user, "Move after user");
                 ui.checkbox(&mut self.automove_after_engine, "Move again after engine");
 
+                ui.horizontal(|ui| {
+                    ui.checkbox(self.sfx_stream.muted_mut(), "Mute");
+                    ui.add_enabled(
+                        !*self.sfx_stream.muted_mut(),
+                        egui::Slider::new(self.sfx_stream.volume_mut(), 0.0..=2.0).text("Volume"),
+                    );
+                });
+
+                ui.separator();
+
+                if ui.button("Flip board (F)").clicked() {
+                    self.board_manager.flip_board();
+                }
+                ui.checkbox(self.board_manager.auto_flip_mut(), "Flip to side to move");
+
+                ui.separator();
+
+                self.eval_bar.ui(ui);
+                self.search_info.ui(ui);
+
                 ui.separator();
 
                 if let Some(scroll_request) = self.board_manager.ui_move_history(ui) {
@@ -128,6 +209,8 @@ impl eframe::App for HardfiskurApp {
             {
                 if let Ok(board) = Board::try_parse_fen(&new_fen) {
                     self.board_manager.reset_to(board);
+                    self.eval_bar.reset();
+                    self.search_info.reset();
                 }
             }
 
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use eframe::egui::{Label, Ui};
+use hardfiskur_core::board::{Board, Move};
+
+use crate::search_thread::SearchUpdate;
+
+/// Text summary of the engine's latest search update: depth, score, nodes
+/// and the principal variation in SAN.
+pub struct SearchInfoPanel {
+    latest: Option<(SearchUpdate, String)>,
+}
+
+impl SearchInfoPanel {
+    pub fn new() -> Self {
+        Self { latest: None }
+    }
+
+    /// Records the latest update from a search on `board`.
+    pub fn update(&mut self, update: &SearchUpdate, board: &Board) {
+        self.latest = Some((update.clone(), Self::pv_san(&update.pv, board)));
+    }
+
+    pub fn reset(&mut self) {
+        self.latest = None;
+    }
+
+    fn pv_san(pv: &[Move], board: &Board) -> String {
+        let mut board = board.clone();
+        let mut sans = Vec::new();
+
+        for &m in pv {
+            let Some(san) = board.get_san(m) else {
+                break;
+            };
+            sans.push(san.to_string());
+            board.push_move_repr(m);
+        }
+
+        sans.join(" ")
+    }
+
+    pub fn ui(&self, ui: &mut Ui) {
+        let Some((update, pv)) = &self.latest else {
+            ui.label("Not searched yet");
+            return;
+        };
+
+        ui.label(format!(
+            "Depth {}/{}  Score {}",
+            update.depth, update.sel_depth, update.score
+        ));
+        ui.label(format!(
+            "Nodes {}  Time {:.1}s  {}",
+            update.nodes,
+            update.elapsed.as_secs_f64(),
+            Self::nps_text(update.nodes, update.elapsed)
+        ));
+        ui.add(Label::new(format!("PV: {pv}")).wrap());
+    }
+
+    fn nps_text(nodes: u64, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            format!("{:.0} knps", nodes as f64 / secs / 1000.0)
+        } else {
+            String::new()
+        }
+    }
+}
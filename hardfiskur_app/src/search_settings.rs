@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use eframe::egui::{self, Ui};
+use hardfiskur_engine::search_limits::SearchLimits;
+
+/// Which kind of limit searches started from the app stop at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitMode {
+    MoveTime,
+    Depth,
+    Nodes,
+}
+
+/// The user's choice of how long the engine should search for.
+#[derive(Debug, Clone)]
+pub struct SearchSettings {
+    mode: LimitMode,
+    move_time: Duration,
+    depth: i16,
+    nodes: u64,
+}
+
+impl SearchSettings {
+    pub fn new() -> Self {
+        Self {
+            mode: LimitMode::MoveTime,
+            move_time: Duration::from_secs(1),
+            depth: 12,
+            nodes: 1_000_000,
+        }
+    }
+
+    pub fn limits(&self) -> SearchLimits {
+        let builder = SearchLimits::builder();
+        let builder = match self.mode {
+            LimitMode::MoveTime => builder.move_time(self.move_time),
+            LimitMode::Depth => builder.depth(self.depth),
+            LimitMode::Nodes => builder.nodes(self.nodes),
+        };
+
+        builder
+            .build()
+            .expect("the UI only allows valid, finite limits")
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.mode, LimitMode::MoveTime, "Time");
+            ui.radio_value(&mut self.mode, LimitMode::Depth, "Depth");
+            ui.radio_value(&mut self.mode, LimitMode::Nodes, "Nodes");
+        });
+
+        match self.mode {
+            LimitMode::MoveTime => {
+                let mut move_time_secs = self.move_time.as_secs_f64();
+                ui.add(
+                    egui::DragValue::new(&mut move_time_secs)
+                        .prefix("Move time: ")
+                        .speed(0.1)
+                        .range(0.0..=600.0)
+                        .clamp_to_range(false)
+                        .suffix(" secs"),
+                );
+                self.move_time =
+                    Duration::try_from_secs_f64(move_time_secs).unwrap_or(Duration::ZERO);
+            }
+            LimitMode::Depth => {
+                ui.add(
+                    egui::DragValue::new(&mut self.depth)
+                        .prefix("Depth: ")
+                        .speed(0.1)
+                        .range(1..=100),
+                );
+            }
+            LimitMode::Nodes => {
+                ui.add(
+                    egui::DragValue::new(&mut self.nodes)
+                        .prefix("Nodes: ")
+                        .speed(1000.0)
+                        .range(1..=u64::MAX),
+                );
+            }
+        }
+    }
+}
@@ -5,14 +5,31 @@ use std::{
 
 use hardfiskur_core::board::{Board, Color, Move, UCIMove};
 use hardfiskur_engine::{
-    search_limits::{SearchLimits, TimeControls},
+    evaluation::phase::Phase,
+    score::Score,
+    search_limits::SearchLimits,
     search_result::{SearchInfo, SearchResult},
     Engine, SearchReporter,
 };
 
+/// Intermediate results reported by a search. The score is from white's
+/// perspective.
+#[derive(Debug, Clone)]
+pub struct SearchUpdate {
+    pub score: Score,
+    pub phase: Phase,
+    pub pv: Vec<Move>,
+    pub depth: u16,
+    pub sel_depth: u16,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
 pub struct SearchThread {
     tx: Sender<(Option<Move>, u64)>,
     rx: Receiver<(Option<Move>, u64)>,
+    info_tx: Sender<(SearchUpdate, u64)>,
+    info_rx: Receiver<(SearchUpdate, u64)>,
     engine: Engine,
 
     outstanding_request: bool,
@@ -24,6 +41,7 @@ where
     F: Fn() + Send + Sync + 'static,
 {
     tx: Sender<(Option<Move>, u64)>,
+    info_tx: Sender<(SearchUpdate, u64)>,
     generation: u64,
     to_move: Color,
     waker: F,
@@ -33,12 +51,33 @@ impl<F> GUIReporter<F>
 where
     F: Fn() + Send + Sync + 'static,
 {
-    fn print_search_info(&self, info: &SearchInfo) {
-        let score = match self.to_move {
+    fn white_score(&self, info: &SearchInfo) -> Score {
+        match self.to_move {
             Color::White => info.score,
             Color::Black => -info.score,
+        }
+    }
+
+    fn send_search_update(&self, info: &SearchInfo) {
+        // The PV is taken from the search info rather than Engine::get_pv, as
+        // that would have to wait for the search to release the TT
+        let update = SearchUpdate {
+            score: self.white_score(info),
+            phase: info.phase,
+            pv: info.pv.clone(),
+            depth: info.raw_stats.depth,
+            sel_depth: info.raw_stats.sel_depth,
+            nodes: info.raw_stats.nodes_searched,
+            elapsed: info.elapsed,
         };
 
+        // The app may have already dropped the receiver if it's shutting down
+        let _ = self.info_tx.send((update, self.generation));
+    }
+
+    fn print_search_info(&self, info: &SearchInfo) {
+        let score = self.white_score(info);
+
         print!(
             "score {score} depth {} seldepth {} time {} nodes {} tt_hits {}",
             info.raw_stats.depth,
@@ -48,6 +87,10 @@ where
             info.raw_stats.tt_hits
         );
 
+        if let Some(ebf) = info.raw_stats.effective_branching_factor() {
+            print!(" ebf {ebf:.2}");
+        }
+
         if !info.pv.is_empty() {
             print!(" pv");
             for m in info.pv.iter() {
@@ -62,10 +105,13 @@ where
 impl<F: Fn() + Send + Sync + 'static> SearchReporter for GUIReporter<F> {
     fn receive_search_info(&self, info: SearchInfo) {
         self.print_search_info(&info);
+        self.send_search_update(&info);
+        (self.waker)();
     }
 
     fn search_complete(&self, result: SearchResult) {
         self.print_search_info(&result.info);
+        self.send_search_update(&result.info);
 
         self.tx.send((result.best_move, self.generation)).unwrap();
         (self.waker)();
@@ -75,10 +121,13 @@ impl<F: Fn() + Send + Sync + 'static> SearchReporter for GUIReporter<F> {
 impl SearchThread {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::channel();
+        let (info_tx, info_rx) = mpsc::channel();
 
         Self {
             tx,
             rx,
+            info_tx,
+            info_rx,
             engine: Engine::new(),
 
             outstanding_request: false,
@@ -93,10 +142,11 @@ impl SearchThread {
     pub fn send_search_request(
         &mut self,
         board: &Board,
-        move_time: Duration,
+        limits: SearchLimits,
         waker: impl Fn() + Send + Sync + 'static,
     ) {
         let tx = self.tx.clone();
+        let info_tx = self.info_tx.clone();
 
         self.search_gen += 1;
         let search_gen = self.search_gen;
@@ -105,12 +155,10 @@ impl SearchThread {
 
         self.engine.start_search(
             board,
-            SearchLimits {
-                time_controls: TimeControls::FixedMoveTime(move_time),
-                ..SearchLimits::infinite()
-            },
+            limits,
             GUIReporter {
                 tx,
+                info_tx,
                 generation: search_gen,
                 to_move,
                 waker,
@@ -140,4 +188,14 @@ impl SearchThread {
 
         None
     }
+
+    /// Returns the most recent update reported by the current search, if any
+    /// new ones have arrived since the last call.
+    pub fn try_receive_update(&mut self) -> Option<SearchUpdate> {
+        self.info_rx
+            .try_iter()
+            .filter(|&(_, search_gen)| search_gen == self.search_gen)
+            .last()
+            .map(|(update, _)| update)
+    }
 }
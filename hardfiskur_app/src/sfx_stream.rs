@@ -1,10 +1,29 @@
-use std::io::Cursor;
+use std::{io::Cursor, time::Duration};
 
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+use rodio::{source::SineWave, Decoder, OutputStream, OutputStreamHandle, Source};
+
+const BASE_VOLUME: f32 = 0.2;
+const TONE_VOLUME: f32 = 0.15;
+
+/// The different sounds that can be played after a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    Move,
+    Capture,
+    Castle,
+    Promotion,
+    Check,
+    GameEnd,
+}
+
+type BoxedSource = Box<dyn Source<Item = f32> + Send>;
 
 pub struct SFXStream {
     _output_stream: OutputStream,
     output_stream_handle: OutputStreamHandle,
+
+    volume: f32,
+    muted: bool,
 }
 
 impl SFXStream {
@@ -14,24 +33,98 @@ impl SFXStream {
         Self {
             _output_stream: stream,
             output_stream_handle: handle,
+
+            volume: 1.0,
+            muted: false,
         }
     }
 
-    pub fn play_capture(&self) {
-        let sound = Decoder::new(Cursor::new(include_bytes!("Capture.ogg").as_slice()))
-            .unwrap()
-            .amplify(0.2)
-            .convert_samples();
+    /// Volume multiplier for all sounds, where 1.0 is the default volume.
+    pub fn volume_mut(&mut self) -> &mut f32 {
+        &mut self.volume
+    }
 
-        self.output_stream_handle.play_raw(sound).unwrap();
+    pub fn muted_mut(&mut self) -> &mut bool {
+        &mut self.muted
+    }
+
+    pub fn play(&self, cue: SoundCue) {
+        match cue {
+            SoundCue::Move => self.play_move(),
+            SoundCue::Capture => self.play_capture(),
+            SoundCue::Castle => self.play_castle(),
+            SoundCue::Promotion => self.play_promotion(),
+            SoundCue::Check => self.play_check(),
+            SoundCue::GameEnd => self.play_game_end(),
+        }
+    }
+
+    pub fn play_capture(&self) {
+        self.play_source(Self::capture_sound());
     }
 
     pub fn play_move(&self) {
-        let sound = Decoder::new(Cursor::new(include_bytes!("Move.ogg").as_slice()))
-            .unwrap()
-            .amplify(0.2)
-            .convert_samples();
+        self.play_source(Self::move_sound());
+    }
+
+    /// Two move sounds in quick succession, for the king and then the rook.
+    pub fn play_castle(&self) {
+        let sound = Self::move_sound().mix(Self::move_sound().delay(Duration::from_millis(120)));
+        self.play_source(Box::new(sound));
+    }
+
+    /// A higher pitched move sound.
+    pub fn play_promotion(&self) {
+        self.play_source(Box::new(Self::move_sound().speed(1.3)));
+    }
+
+    /// A move sound followed by a short high beep.
+    pub fn play_check(&self) {
+        let sound = Self::move_sound()
+            .mix(Self::tone(880.0, Duration::from_millis(120)).delay(Duration::from_millis(60)));
+        self.play_source(Box::new(sound));
+    }
+
+    /// A falling pair of tones.
+    pub fn play_game_end(&self) {
+        let length = Duration::from_millis(180);
+        let sound = Self::tone(660.0, length).mix(Self::tone(440.0, length * 2).delay(length));
+        self.play_source(Box::new(sound));
+    }
+
+    fn play_source(&self, sound: BoxedSource) {
+        if self.muted {
+            return;
+        }
+
+        self.output_stream_handle
+            .play_raw(sound.amplify(self.volume.max(0.0)))
+            .unwrap();
+    }
+
+    fn move_sound() -> BoxedSource {
+        Self::sample(include_bytes!("Move.ogg"))
+    }
+
+    fn capture_sound() -> BoxedSource {
+        Self::sample(include_bytes!("Capture.ogg"))
+    }
+
+    fn sample(bytes: &'static [u8]) -> BoxedSource {
+        Box::new(
+            Decoder::new(Cursor::new(bytes))
+                .unwrap()
+                .amplify(BASE_VOLUME)
+                .convert_samples(),
+        )
+    }
 
-        self.output_stream_handle.play_raw(sound).unwrap();
+    fn tone(frequency: f32, length: Duration) -> BoxedSource {
+        Box::new(
+            SineWave::new(frequency)
+                .take_duration(length)
+                .fade_in(Duration::from_millis(10))
+                .amplify(TONE_VOLUME),
+        )
     }
 }
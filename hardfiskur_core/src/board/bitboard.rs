@@ -185,32 +185,64 @@ impl Bitboard {
         self.0.count_ones()
     }
 
+    /// Returns whether this bitboard has more than one bit set.
+    ///
+    /// This is cheaper than comparing [`Self::pop_count()`] against 1.
+    pub const fn more_than_one(self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    /// Returns whether every bit set in this bitboard is also set in `other`.
+    pub const fn is_subset_of(self, other: Bitboard) -> bool {
+        self.0 & !other.0 == 0
+    }
+
     /// Mirrors this bitboard vertically, so that 1st rank becomes the 8th rank
     /// and vice versa. Files are preserved.
     pub const fn flip_vertical(self) -> Self {
         Self(self.0.swap_bytes())
     }
 
-    /// Returns the position of the most significant bit that is set.
+    /// Mirrors this bitboard horizontally, so that the a-file becomes the
+    /// h-file and vice versa. Ranks are preserved.
+    pub const fn mirror(self) -> Self {
+        // Reversing all the bits flips both ranks and files, so undo the rank
+        // flip afterwards
+        Self(self.0.reverse_bits().swap_bytes())
+    }
+
+    /// Returns the square of the most significant bit that is set.
     ///
     /// If this bitboard is empty, returns [`None`].
-    pub const fn msb(self) -> Option<u8> {
+    pub const fn msb(self) -> Option<Square> {
         match self.0 {
             0 => None,
-            x => Some(63 - x.leading_zeros() as u8),
+            x => Some(Square::from_u8_unchecked(63 - x.leading_zeros() as u8)),
         }
     }
 
-    /// Returns the position of the least significant bit that is set.
+    /// Returns the square of the least significant bit that is set.
     ///
     /// If this bitboard is empty, returns [`None`].
-    pub const fn lsb(self) -> Option<u8> {
+    pub const fn lsb(self) -> Option<Square> {
         match self.0 {
             0 => None,
-            x => Some(x.trailing_zeros() as u8),
+            x => Some(Square::from_u8_unchecked(x.trailing_zeros() as u8)),
         }
     }
 
+    /// Removes the least significant bit that is set from this bitboard, and
+    /// returns its square.
+    ///
+    /// If this bitboard is empty, returns [`None`] and leaves it unchanged.
+    pub fn pop_lsb(&mut self) -> Option<Square> {
+        let square = self.lsb();
+        self.0 &= self.0.wrapping_sub(1);
+        square
+    }
+
+    /// Returns a bitboard with only the least significant bit of this bitboard
+    /// set, or an empty bitboard if this bitboard is empty.
     pub const fn isolate_lsb(self) -> Bitboard {
         Self(self.0 & self.0.wrapping_neg())
     }
@@ -228,11 +260,7 @@ impl Bitboard {
     /// instead simply returns the [`Square`] corresponding to the least
     /// significant bit that is set.
     pub const fn to_square(self) -> Option<Square> {
-        // Have to write it this way because .map is not allowed in a const fn
-        match self.lsb() {
-            Some(x) => Some(Square::from_u8_unchecked(x)),
-            None => None,
-        }
+        self.lsb()
     }
 
     /// Returns a bitboard with only the bit at the provided index set.
@@ -751,16 +779,61 @@ mod test {
         )
     }
 
+    #[test]
+    fn bitboard_mirror() {
+        assert_eq!(Bitboard::A_FILE.mirror(), Bitboard::H_FILE);
+        assert_eq!(Bitboard::RANK_2.mirror(), Bitboard::RANK_2);
+        assert_eq!(
+            Bitboard(0x0A0B0C0_D0E0F1011).mirror(),
+            Bitboard(0x50D030B_070F00888)
+        );
+        assert_eq!(
+            Bitboard::from_square(Square::C2).mirror(),
+            Bitboard::from_square(Square::F2)
+        );
+    }
+
     #[test]
     fn bitboard_msb_lsb() {
         let b = Bitboard(0x0FFF0003_8A200000);
-        assert_eq!(b.msb(), Some(59));
-        assert_eq!(b.lsb(), Some(21));
+        assert_eq!(b.msb(), Some(Square::D8));
+        assert_eq!(b.lsb(), Some(Square::F3));
 
         assert_eq!(Bitboard::EMPTY.msb(), None);
         assert_eq!(Bitboard::EMPTY.lsb(), None);
     }
 
+    #[test]
+    fn bitboard_pop_lsb() {
+        let mut b = Bitboard::from_square(Square::B2)
+            | Bitboard::from_square(Square::E4)
+            | Bitboard::from_square(Square::H8);
+
+        assert_eq!(b.pop_lsb(), Some(Square::B2));
+        assert_eq!(b.pop_lsb(), Some(Square::E4));
+        assert_eq!(b.pop_lsb(), Some(Square::H8));
+        assert_eq!(b.pop_lsb(), None);
+        assert_eq!(b, Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn bitboard_is_subset_of() {
+        assert!(Bitboard::RANK_2.is_subset_of(Bitboard::RANK_2 | Bitboard::RANK_7));
+        assert!(Bitboard::EMPTY.is_subset_of(Bitboard::EMPTY));
+        assert!(Bitboard::RANK_2.is_subset_of(Bitboard::ALL));
+        assert!(!Bitboard::RANK_2.is_subset_of(Bitboard::A_FILE));
+        assert!(!Bitboard::ALL.is_subset_of(Bitboard::RANK_2));
+    }
+
+    #[test]
+    fn bitboard_more_than_one() {
+        assert!(!Bitboard::EMPTY.more_than_one());
+        assert!(!Bitboard::from_square(Square::A1).more_than_one());
+        assert!(!Bitboard::from_square(Square::H8).more_than_one());
+        assert!(Bitboard(0b101).more_than_one());
+        assert!(Bitboard::ALL.more_than_one());
+    }
+
     #[test]
     fn bitboard_to_square() {
         assert_eq!(b(3, 7).to_square(), Square::new(3, 7));
@@ -897,10 +970,7 @@ mod test {
             Ok(Bitboard(0x00000000_1000EF00))
         );
 
-        assert_eq!(
-            Bitboard::from_str(&std::iter::repeat('0').take(64).collect::<String>()),
-            Ok(Bitboard::EMPTY)
-        );
+        assert_eq!(Bitboard::from_str(&"0".repeat(64)), Ok(Bitboard::EMPTY));
 
         assert_eq!(
             Bitboard::from_str(
@@ -919,6 +989,6 @@ abc00def
 
         assert!(Bitboard::from_str("").is_err());
         assert!(Bitboard::from_str("0000001000000").is_err());
-        assert!(Bitboard::from_str(&std::iter::repeat('0').take(65).collect::<String>()).is_err());
+        assert!(Bitboard::from_str(&"0".repeat(65)).is_err());
     }
 }
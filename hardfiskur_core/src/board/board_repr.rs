@@ -36,6 +36,10 @@ pub struct BoardRepr {
     // 9-14: Black piece boards (see above)
     boards: [Bitboard; 15],
 
+    // Number of each piece on the board, indexed the same way as `boards`, so
+    // that counting material doesn't need to popcount the bitboards.
+    counts: [u8; 15],
+
     zobrist_hash: ZobristHash,
 }
 
@@ -60,6 +64,7 @@ impl BoardRepr {
                 let square = Square::from_index_unchecked(i);
                 repr[piece].set(square);
                 repr[piece.color()].set(square);
+                repr.counts[piece.get() as usize] += 1;
 
                 repr.zobrist_hash.toggle_piece(piece, square);
             }
@@ -102,11 +107,20 @@ impl BoardRepr {
         None
     }
 
+    /// Returns the number of the provided piece on the board.
+    ///
+    /// This is kept up to date incrementally, so is cheaper than calling
+    /// [`Bitboard::pop_count`] on the piece's bitboard.
+    #[inline]
+    pub fn count(&self, piece: Piece) -> u8 {
+        self.counts[piece.get() as usize]
+    }
+
     /// Returns a tuple (white count, black count) of the provided piece type.
     pub fn piece_count(&self, piece_type: PieceType) -> (u32, u32) {
         (
-            self[piece_type.white()].pop_count(),
-            self[piece_type.black()].pop_count(),
+            self.count(piece_type.white()) as u32,
+            self.count(piece_type.black()) as u32,
         )
     }
 
@@ -167,6 +181,10 @@ impl BoardRepr {
         let to_bb = Bitboard::from_square(to);
         let from_to_bb = from_bb ^ to_bb;
 
+        // Whether the move is being made rather than undone, which decides
+        // which way the piece counts change
+        let making = self[color].get(from);
+
         self[piece] ^= from_to_bb;
         self[color] ^= from_to_bb;
 
@@ -181,6 +199,7 @@ impl BoardRepr {
 
             self[opponent_pawn] ^= removed_pawn_bb;
             self[color.flip()] ^= removed_pawn_bb;
+            self.update_count(opponent_pawn, !making);
 
             self.zobrist_hash
                 .toggle_piece(opponent_pawn, removed_pawn_square);
@@ -188,6 +207,7 @@ impl BoardRepr {
             if let Some(capture) = the_move.captured_piece() {
                 self[capture] ^= to_bb;
                 self[capture.color()] ^= to_bb;
+                self.update_count(capture, !making);
 
                 self.zobrist_hash.toggle_piece(capture, to);
             }
@@ -195,6 +215,8 @@ impl BoardRepr {
             if let Some(promote) = the_move.promotion() {
                 self[piece] ^= to_bb;
                 self[promote] ^= to_bb;
+                self.update_count(piece, !making);
+                self.update_count(promote, making);
 
                 self.zobrist_hash.toggle_piece(piece, to);
                 self.zobrist_hash.toggle_piece(promote, to);
@@ -217,6 +239,23 @@ impl BoardRepr {
         }
     }
 
+    #[inline]
+    fn update_count(&mut self, piece: Piece, added: bool) {
+        let count = &mut self.counts[piece.get() as usize];
+        if added {
+            *count += 1;
+        } else {
+            *count -= 1;
+        }
+
+        // Only called once the piece's bitboard has been updated
+        debug_assert_eq!(
+            self.count(piece) as u32,
+            self[piece].pop_count(),
+            "cached count of {piece:?} doesn't match its bitboard"
+        );
+    }
+
     /// Returns the Zobrist hash of the current position. Only includes hash
     /// contributions from pieces on the board.
     ///
@@ -354,62 +393,6 @@ impl Display for BoardRepr {
     }
 }
 
-#[allow(unused)]
-impl BoardRepr {
-    #[cfg(debug_assertions)]
-    pub fn consistency_check(&self) {
-        let check = || {
-            let mut white_pieces = Bitboard::EMPTY;
-
-            for (_, board) in self.boards_colored(Color::White) {
-                if (white_pieces & board).has_piece() {
-                    return false;
-                }
-
-                white_pieces |= board;
-            }
-
-            if white_pieces != self[Color::White] {
-                return false;
-            }
-
-            let mut black_pieces = Bitboard::EMPTY;
-
-            for (_, board) in self.boards_colored(Color::Black) {
-                if (black_pieces & board).has_piece() {
-                    return false;
-                }
-
-                black_pieces |= board;
-            }
-
-            if black_pieces != self[Color::Black] {
-                return false;
-            }
-
-            if !(white_pieces & black_pieces).is_empty() {
-                return false;
-            }
-
-            let mut zobrist_hash = ZobristHash::default();
-            for (piece, square) in self.pieces() {
-                zobrist_hash.toggle_piece(piece, square);
-            }
-
-            if zobrist_hash != self.zobrist_hash {
-                return false;
-            }
-
-            true
-        };
-
-        assert!(check(), "BoardRepr became inconsistent, {self:?}");
-    }
-
-    #[cfg(not(debug_assertions))]
-    pub fn consistency_check(&self) {}
-}
-
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
@@ -461,7 +444,7 @@ mod test {
     #[test]
     fn board_repr_new_list_too_long() {
         let board = BoardRepr::new(
-            &vec![
+            &[
                 vec![Some(Piece::BLACK_KNIGHT), Some(Piece::WHITE_QUEEN)],
                 vec![None; 62],
                 vec![Some(Piece::WHITE_KING)],
@@ -570,6 +553,10 @@ mod test {
         assert_eq!(board.piece_count(PieceType::Rook), (0, 0));
         assert_eq!(board.piece_count(PieceType::Queen), (0, 1));
         assert_eq!(board.piece_count(PieceType::King), (1, 1));
+
+        assert_eq!(board.count(Piece::WHITE_PAWN), 2);
+        assert_eq!(board.count(Piece::BLACK_QUEEN), 1);
+        assert_eq!(board.count(Piece::BLACK_ROOK), 0);
     }
 
     #[test]
@@ -817,7 +804,7 @@ mod test {
                     .or(Bitboard::from_square(the_move.to_square()))
             );
 
-            moved_board.consistency_check();
+            assert_eq!(moved_board.consistency_check(), Ok(()));
 
             moved_board.move_unchecked(the_move);
 
@@ -866,7 +853,7 @@ mod test {
                     .without(Bitboard::from_square(the_move.to_square()))
             );
 
-            moved_board.consistency_check();
+            assert_eq!(moved_board.consistency_check(), Ok(()));
 
             moved_board.move_unchecked(the_move);
 
@@ -920,7 +907,7 @@ mod test {
                 );
             }
 
-            moved_board.consistency_check();
+            assert_eq!(moved_board.consistency_check(), Ok(()));
 
             moved_board.move_unchecked(the_move);
 
@@ -992,7 +979,7 @@ mod test {
                         | Bitboard::from_square(rook_to))
             );
 
-            moved_board.consistency_check();
+            assert_eq!(moved_board.consistency_check(), Ok(()));
 
             moved_board.move_unchecked(the_move);
 
@@ -1049,7 +1036,7 @@ mod test {
                     .without(Bitboard::from_square(captured_pawn_square))
             );
 
-            moved_board.consistency_check();
+            assert_eq!(moved_board.consistency_check(), Ok(()));
 
             moved_board.move_unchecked(the_move);
 
@@ -2,6 +2,8 @@ use std::fmt::{Display, Write};
 
 use bitflags::bitflags;
 
+use super::{Color, Square};
+
 bitflags! {
     /// Represents which directions castling moves can still be played for
     /// both players.
@@ -88,6 +90,98 @@ impl Castling {
     }
 }
 
+/// The files of the rooks each side castles with.
+///
+/// In standard chess these are always the h-file for kingside castling and
+/// the a-file for queenside castling, but in Chess960 (Fischer Random Chess)
+/// the rooks may start on any file, as long as the king is between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CastlingRooks {
+    /// Indexed by color, then 0 for kingside and 1 for queenside.
+    files: [[u8; 2]; 2],
+}
+
+impl Default for CastlingRooks {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl CastlingRooks {
+    /// The rook files in standard chess.
+    pub const STANDARD: Self = Self::new(7, 0);
+
+    /// Creates a [`CastlingRooks`] where both sides castle with rooks on the
+    /// given files, as is the case in Chess960.
+    pub const fn new(kingside_file: u8, queenside_file: u8) -> Self {
+        Self {
+            files: [[kingside_file, queenside_file]; 2],
+        }
+    }
+
+    /// Returns whether these are the rook files of standard chess.
+    pub fn is_standard(self) -> bool {
+        self == Self::STANDARD
+    }
+
+    /// Returns the file of the rook the given side castles kingside with.
+    pub const fn kingside(self, color: Color) -> u8 {
+        self.files[color.index()][0]
+    }
+
+    /// Returns the file of the rook the given side castles queenside with.
+    pub const fn queenside(self, color: Color) -> u8 {
+        self.files[color.index()][1]
+    }
+
+    /// Sets the file of the rook the given side castles kingside with.
+    pub fn set_kingside(&mut self, color: Color, file: u8) {
+        self.files[color.index()][0] = file;
+    }
+
+    /// Sets the file of the rook the given side castles queenside with.
+    pub fn set_queenside(&mut self, color: Color, file: u8) {
+        self.files[color.index()][1] = file;
+    }
+
+    /// Returns the file of the rook used for a single castling right, e.g.
+    /// [`Castling::WHITE_KINGSIDE`].
+    ///
+    /// If `right` is not exactly one castling right, returns [`None`].
+    pub fn file(self, right: Castling) -> Option<u8> {
+        match right {
+            Castling::WHITE_KINGSIDE => Some(self.kingside(Color::White)),
+            Castling::WHITE_QUEENSIDE => Some(self.queenside(Color::White)),
+            Castling::BLACK_KINGSIDE => Some(self.kingside(Color::Black)),
+            Castling::BLACK_QUEENSIDE => Some(self.queenside(Color::Black)),
+            _ => None,
+        }
+    }
+
+    /// Returns the starting square of the rook used for a single castling
+    /// right, e.g. [`Castling::WHITE_KINGSIDE`].
+    ///
+    /// If `right` is not exactly one castling right, returns [`None`].
+    pub fn square(self, right: Castling) -> Option<Square> {
+        let rank = if Castling::WHITE.contains(right) {
+            0
+        } else {
+            7
+        };
+        self.file(right)
+            .map(|file| Square::new_unchecked(rank, file))
+    }
+
+    /// Returns the castling rights that are lost when a rook moves from or
+    /// is captured on the given square.
+    pub fn rights_for_square(self, square: Square) -> Castling {
+        Castling::all()
+            .iter()
+            .filter(|&right| self.square(right) == Some(square))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -123,4 +217,46 @@ mod test {
         );
         assert_eq!(Castling::all().as_fen_str(), "KQkq");
     }
+
+    #[test]
+    fn castling_rooks_standard() {
+        let rooks = CastlingRooks::default();
+
+        assert!(rooks.is_standard());
+        assert_eq!(rooks.square(Castling::WHITE_KINGSIDE), Some(Square::H1));
+        assert_eq!(rooks.square(Castling::WHITE_QUEENSIDE), Some(Square::A1));
+        assert_eq!(rooks.square(Castling::BLACK_KINGSIDE), Some(Square::H8));
+        assert_eq!(rooks.square(Castling::BLACK_QUEENSIDE), Some(Square::A8));
+        assert_eq!(rooks.square(Castling::WHITE), None);
+        assert_eq!(rooks.square(Castling::empty()), None);
+
+        assert_eq!(
+            rooks.rights_for_square(Square::H1),
+            Castling::WHITE_KINGSIDE
+        );
+        assert_eq!(
+            rooks.rights_for_square(Square::A8),
+            Castling::BLACK_QUEENSIDE
+        );
+        assert_eq!(rooks.rights_for_square(Square::E1), Castling::empty());
+    }
+
+    #[test]
+    fn castling_rooks_chess960() {
+        let mut rooks = CastlingRooks::new(6, 1);
+        rooks.set_queenside(Color::Black, 2);
+
+        assert!(!rooks.is_standard());
+        assert_eq!(rooks.kingside(Color::White), 6);
+        assert_eq!(rooks.queenside(Color::White), 1);
+        assert_eq!(rooks.kingside(Color::Black), 6);
+        assert_eq!(rooks.queenside(Color::Black), 2);
+
+        assert_eq!(rooks.square(Castling::BLACK_QUEENSIDE), Some(Square::C8));
+        assert_eq!(
+            rooks.rights_for_square(Square::G1),
+            Castling::WHITE_KINGSIDE
+        );
+        assert_eq!(rooks.rights_for_square(Square::H1), Castling::empty());
+    }
 }
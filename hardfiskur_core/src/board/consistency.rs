@@ -0,0 +1,251 @@
+use thiserror::Error;
+
+use super::{zobrist::ZobristHash, Bitboard, Board, BoardRepr, Color, Piece, Square};
+
+/// Error type returned by [`Board::consistency_check`] and
+/// [`BoardRepr::consistency_check`], describing the first inconsistency found.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardInconsistency {
+    /// More than one piece occupies the same square.
+    #[error("Multiple pieces found on {square}")]
+    OverlappingPieces { square: Square },
+
+    /// The bitboard of all pieces of a color doesn't match the union of the
+    /// bitboards of the individual pieces of that color.
+    #[error("Bitboard of all {color:?} pieces doesn't match the individual piece bitboards")]
+    ColorBitboardMismatch { color: Color },
+
+    /// The incrementally updated count of a piece doesn't match the number of
+    /// pieces on its bitboard.
+    #[error("Cached count of {piece:?} is {cached} but found {actual} on the board")]
+    PieceCountMismatch {
+        piece: Piece,
+        cached: u8,
+        actual: u32,
+    },
+
+    /// A side doesn't have exactly one king.
+    #[error("Expected exactly one {color:?} king but found {count}")]
+    KingCount { color: Color, count: u32 },
+
+    /// The en passant square isn't one that could have been left by a double
+    /// pawn push by the opponent on the previous move.
+    #[error("En passant square {square} is not possible in this position")]
+    InvalidEnPassant { square: Square },
+
+    /// The incrementally updated zobrist hash doesn't match the hash computed
+    /// from scratch.
+    #[error("Zobrist hash {actual:?} doesn't match recomputed hash {expected:?}")]
+    ZobristHashMismatch {
+        actual: ZobristHash,
+        expected: ZobristHash,
+    },
+}
+
+impl BoardRepr {
+    /// Checks that the internal state of this [`BoardRepr`] is consistent,
+    /// i.e. that no two pieces occupy the same square, the color bitboards
+    /// match the piece bitboards, the cached piece counts match the bitboards,
+    /// and the zobrist hash matches the pieces on the board.
+    pub fn consistency_check(&self) -> Result<(), BoardInconsistency> {
+        let mut all_pieces = Bitboard::EMPTY;
+
+        for color in [Color::White, Color::Black] {
+            let mut color_pieces = Bitboard::EMPTY;
+
+            for (_, board) in self.boards_colored(color) {
+                if let Some(square) = (all_pieces & board).lsb() {
+                    return Err(BoardInconsistency::OverlappingPieces { square });
+                }
+
+                all_pieces |= board;
+                color_pieces |= board;
+            }
+
+            if color_pieces != self[color] {
+                return Err(BoardInconsistency::ColorBitboardMismatch { color });
+            }
+        }
+
+        for (piece, board) in self.boards() {
+            let cached = self.count(piece);
+            let actual = board.pop_count();
+            if cached as u32 != actual {
+                return Err(BoardInconsistency::PieceCountMismatch {
+                    piece,
+                    cached,
+                    actual,
+                });
+            }
+        }
+
+        let mut expected = ZobristHash::default();
+        for (piece, square) in self.pieces() {
+            expected.toggle_piece(piece, square);
+        }
+
+        let actual = self.zobrist_hash();
+        if actual != expected {
+            return Err(BoardInconsistency::ZobristHashMismatch { actual, expected });
+        }
+
+        Ok(())
+    }
+}
+
+impl Board {
+    /// Checks that the internal state of this [`Board`] is consistent.
+    ///
+    /// On top of the checks done by [`BoardRepr::consistency_check`], this
+    /// verifies that each side has exactly one king, that the en passant
+    /// square (if any) is plausible, and that the zobrist hash matches one
+    /// recomputed from scratch.
+    ///
+    /// This is useful to catch corruption when pushing moves with
+    /// [`Board::push_move_unchecked`].
+    pub fn consistency_check(&self) -> Result<(), BoardInconsistency> {
+        self.board.consistency_check()?;
+
+        for color in [Color::White, Color::Black] {
+            let count = self.board[Piece::king(color)].pop_count();
+            if count != 1 {
+                return Err(BoardInconsistency::KingCount { color, count });
+            }
+        }
+
+        if let Some(square) = self.en_passant {
+            if !self.en_passant_plausible(square) {
+                return Err(BoardInconsistency::InvalidEnPassant { square });
+            }
+        }
+
+        let expected = self.board.zobrist_hash()
+            ^ Self::non_board_hash(self.to_move, self.castling, self.en_passant);
+        if self.zobrist_hash != expected {
+            return Err(BoardInconsistency::ZobristHashMismatch {
+                actual: self.zobrist_hash,
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether the en passant square could have been left by the opponent
+    /// double-pushing a pawn on the previous move, i.e. it's empty and on the
+    /// correct rank, with the opponent's pawn in front of it and the square
+    /// the pawn started on empty.
+    pub(super) fn en_passant_plausible(&self, square: Square) -> bool {
+        let (rank, forward) = match self.to_move {
+            Color::White => (5, -8),
+            Color::Black => (2, 8),
+        };
+
+        let pawn_square = square.offset(forward);
+        let start_square = square.offset(-forward);
+
+        square.rank() == rank
+            && self.board.piece_at(square).is_none()
+            && self.board.piece_at(start_square).is_none()
+            && self.board.piece_at(pawn_square) == Some(Piece::pawn(self.to_move.flip()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::board::{Move, UCIMove};
+
+    #[test]
+    fn consistent_positions_pass() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            let board = Board::try_parse_fen(fen).unwrap();
+            assert_eq!(board.consistency_check(), Ok(()), "{fen}");
+        }
+    }
+
+    #[test]
+    fn consistency_check_after_moves() {
+        let mut board = Board::starting_position();
+        for m in ["e2e4", "c7c5", "e4e5", "d7d5", "e5d6", "e8d7"] {
+            let m: UCIMove = m.parse().unwrap();
+            let m: Move = board.get_move(m.from, m.to, m.promotion).unwrap();
+            board.push_move_unchecked(m);
+            assert_eq!(board.consistency_check(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn wrong_number_of_kings_fails() {
+        let board = Board::try_parse_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.consistency_check(),
+            Err(BoardInconsistency::KingCount {
+                color: Color::Black,
+                count: 0
+            })
+        );
+
+        let board = Board::try_parse_fen("k7/8/8/8/8/8/8/K6K w - - 0 1").unwrap();
+        assert_eq!(
+            board.consistency_check(),
+            Err(BoardInconsistency::KingCount {
+                color: Color::White,
+                count: 2
+            })
+        );
+    }
+
+    #[test]
+    fn implausible_en_passant_fails() {
+        // Parsing would clear these en passant squares, so they're set after
+        for (fen, square) in [
+            // En passant square is occupied
+            ("4k3/8/8/8/4P3/4n3/8/4K3 b - - 0 1", Square::E3),
+            // No pawn that could have double pushed
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+                Square::E3,
+            ),
+            // Pawn's starting square is occupied
+            ("4k3/8/8/8/4P3/8/4N3/4K3 b - - 0 1", Square::E3),
+        ] {
+            let mut board = Board::try_parse_fen(fen).unwrap();
+            board.en_passant = Some(square);
+            assert_eq!(
+                board.consistency_check(),
+                Err(BoardInconsistency::InvalidEnPassant { square }),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn corrupted_hash_fails() {
+        let mut board = Board::starting_position();
+        board.zobrist_hash ^= Board::non_board_hash(Color::Black, Default::default(), None);
+
+        assert!(matches!(
+            board.consistency_check(),
+            Err(BoardInconsistency::ZobristHashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn overlapping_pieces_fail() {
+        let mut board = Board::starting_position();
+        board.board[Piece::WHITE_QUEEN].set(Square::E1);
+
+        assert_eq!(
+            board.consistency_check(),
+            Err(BoardInconsistency::OverlappingPieces { square: Square::E1 })
+        );
+    }
+}
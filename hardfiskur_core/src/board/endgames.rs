@@ -0,0 +1,143 @@
+//! Convenience constructors for simple endgame positions, to save writing out
+//! FENs in tests and tooling.
+
+use super::{Board, Castling, Color, Piece, Square};
+
+impl Board {
+    /// Constructs a position with the given pieces, no castling rights and no
+    /// en passant square.
+    ///
+    /// Returns `None` if the position is not valid, i.e. if two pieces share a
+    /// square, a pawn is on the first or last rank, either side doesn't have
+    /// exactly one king, or the side not to move is in check.
+    pub fn from_pieces(pieces: &[(Piece, Square)], to_move: Color) -> Option<Self> {
+        let mut squares = [None; 64];
+
+        for &(piece, square) in pieces {
+            if squares[square.index()].is_some() {
+                return None;
+            }
+
+            if piece.is_pawn() && (square.rank() == 0 || square.rank() == 7) {
+                return None;
+            }
+
+            squares[square.index()] = Some(piece);
+        }
+
+        let board = Self::new(&squares, to_move, Castling::empty(), None, 0, 1);
+
+        for color in [Color::White, Color::Black] {
+            if board.board[Piece::king(color)].pop_count() != 1 {
+                return None;
+            }
+        }
+
+        if Self::king_attacked(&board.board, to_move.flip()) {
+            return None;
+        }
+
+        Some(board)
+    }
+
+    /// King and pawn versus king, with white having the pawn.
+    ///
+    /// See [`Self::from_pieces`] for when this returns `None`.
+    pub fn kpk(
+        white_king: Square,
+        white_pawn: Square,
+        black_king: Square,
+        to_move: Color,
+    ) -> Option<Self> {
+        Self::from_pieces(
+            &[
+                (Piece::WHITE_KING, white_king),
+                (Piece::WHITE_PAWN, white_pawn),
+                (Piece::BLACK_KING, black_king),
+            ],
+            to_move,
+        )
+    }
+
+    /// King and rook versus king, with white having the rook.
+    ///
+    /// See [`Self::from_pieces`] for when this returns `None`.
+    pub fn krk(
+        white_king: Square,
+        white_rook: Square,
+        black_king: Square,
+        to_move: Color,
+    ) -> Option<Self> {
+        Self::from_pieces(
+            &[
+                (Piece::WHITE_KING, white_king),
+                (Piece::WHITE_ROOK, white_rook),
+                (Piece::BLACK_KING, black_king),
+            ],
+            to_move,
+        )
+    }
+
+    /// King and queen versus king, with white having the queen.
+    ///
+    /// See [`Self::from_pieces`] for when this returns `None`.
+    pub fn kqk(
+        white_king: Square,
+        white_queen: Square,
+        black_king: Square,
+        to_move: Color,
+    ) -> Option<Self> {
+        Self::from_pieces(
+            &[
+                (Piece::WHITE_KING, white_king),
+                (Piece::WHITE_QUEEN, white_queen),
+                (Piece::BLACK_KING, black_king),
+            ],
+            to_move,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn kpk_has_correct_pieces() {
+        let board = Board::kpk(Square::E1, Square::E2, Square::E8, Color::White).unwrap();
+        assert_eq!(board.consistency_check(), Ok(()));
+
+        assert_eq!(board.fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn krk_has_correct_pieces() {
+        let board = Board::krk(Square::C3, Square::H1, Square::A8, Color::Black).unwrap();
+        assert_eq!(board.consistency_check(), Ok(()));
+
+        assert_eq!(board.fen(), "k7/8/8/8/8/2K5/8/7R b - - 0 1");
+    }
+
+    #[test]
+    fn kqk_has_correct_pieces() {
+        let board = Board::kqk(Square::G6, Square::D3, Square::H8, Color::White).unwrap();
+        assert_eq!(board.consistency_check(), Ok(()));
+
+        assert_eq!(board.fen(), "7k/8/6K1/8/8/3Q4/8/8 w - - 0 1");
+    }
+
+    #[test]
+    fn endgame_constructors_reject_invalid_positions() {
+        // Overlapping pieces
+        assert!(Board::krk(Square::E1, Square::E1, Square::E8, Color::White).is_none());
+        // Pawn on the back rank
+        assert!(Board::kpk(Square::E1, Square::A8, Square::E8, Color::White).is_none());
+        // Adjacent kings
+        assert!(Board::kqk(Square::E4, Square::A1, Square::E5, Color::White).is_none());
+        // Side not to move is in check
+        assert!(Board::krk(Square::A1, Square::E2, Square::E8, Color::White).is_none());
+        // ...but the side to move may be
+        assert!(Board::krk(Square::A1, Square::E2, Square::E8, Color::Black).is_some());
+    }
+}
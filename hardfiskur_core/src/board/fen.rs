@@ -1,9 +1,27 @@
 use thiserror::Error;
 
-use super::{Board, Castling, Color, Piece, Square};
+use super::{Bitboard, Board, Castling, CastlingRooks, Color, Piece, Square};
+
+/// How strictly [`Board::try_parse_fen_with_mode`] validates the position
+/// described by a FEN string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum FenParseMode {
+    /// Only checks that the FEN string is well-formed, accepting positions
+    /// which could never arise in a game (e.g. missing kings), as long as they
+    /// can be represented.
+    #[default]
+    Lenient,
+    /// Additionally rejects positions which can't arise in a legal game: each
+    /// side must have exactly one king, there can't be pawns on the back
+    /// ranks, the side not to move can't be in check, castling rights require
+    /// the king and rook to be on their starting squares, and the en passant
+    /// square must be one left by a double pawn push on the previous move.
+    Strict,
+}
 
-/// Error type returned by [`Board::try_parse_fen`].
-#[derive(Error, Debug)]
+/// Error type returned by [`Board::try_parse_fen`] and
+/// [`Board::try_parse_fen_with_mode`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum FenParseError {
     /// An incorrect number of fields were found in the FEN string.
     #[error("Expected 6 fields in FEN but found {actual}")]
@@ -27,8 +45,9 @@ pub enum FenParseError {
     InvalidCurrentPlayer,
 
     /// A castling state which is not `-` or some combination of the characters
-    /// `K`, `Q`, `k`, and `q` was found.
-    #[error("Expected `-` or some combination of `KQkq` as the castling state")]
+    /// `K`, `Q`, `k`, and `q` or rook files (`A`-`H` for white, `a`-`h` for
+    /// black) was found.
+    #[error("Expected `-`, some combination of `KQkq`, or rook files as the castling state")]
     InvalidCastling,
 
     /// An en passant state which is not `-` or the name of a square in
@@ -43,12 +62,58 @@ pub enum FenParseError {
     /// An invalid or non-positive integer was found for the move count.
     #[error("Expected a positive integer for the move count")]
     InvalidMoveCount,
+
+    /// The en passant square is not on the 3rd rank when black is to move or
+    /// the 6th rank when white is to move.
+    #[error("En passant square {square} is on the wrong rank for the side to move")]
+    EnPassantWrongRank { square: Square },
+
+    /// Strict mode only: a side doesn't have exactly one king.
+    #[error("Expected exactly one {color:?} king but found {count}")]
+    InvalidKingCount { color: Color, count: u32 },
+
+    /// Strict mode only: a pawn was found on the 1st or 8th rank.
+    #[error("Pawn found on back rank square {square}")]
+    PawnOnBackRank { square: Square },
+
+    /// Strict mode only: the side which isn't to move is in check.
+    #[error("The side not to move is in check")]
+    OpponentInCheck,
+
+    /// Strict mode only: castling rights were given for a side whose king or
+    /// rook is not on its starting square.
+    #[error("Castling rights `{castling}` are impossible as the king or rook has moved")]
+    ImpossibleCastling { castling: Castling },
+
+    /// Strict mode only: the en passant square could not have been left by
+    /// a double pawn push on the previous move.
+    #[error("En passant square {square} could not have been left by a double pawn push")]
+    ImpossibleEnPassant { square: Square },
 }
 
 impl Board {
     /// Convert the current board state into [Forsyth-Edwards
     /// Notation](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation).
+    ///
+    /// Castling rights are written as in
+    /// [X-FEN](https://en.wikipedia.org/wiki/X-FEN), which is identical to
+    /// standard FEN (`KQkq`) unless a side castles with a rook that isn't the
+    /// outermost one on that side of the king, which can only happen in
+    /// Chess960. In that case, the file of the rook is written instead.
     pub fn fen(&self) -> String {
+        self.fen_internal(false)
+    }
+
+    /// Convert the current board state into Shredder-FEN, which is the same
+    /// as [`Self::fen`] except that castling rights are always written as the
+    /// files of the rooks to castle with, e.g. `HAha` instead of `KQkq`.
+    ///
+    /// This is unambiguous for Chess960 positions.
+    pub fn shredder_fen(&self) -> String {
+        self.fen_internal(true)
+    }
+
+    fn fen_internal(&self, shredder: bool) -> String {
         let mut result = String::new();
 
         push_placement(self, &mut result);
@@ -57,7 +122,7 @@ impl Board {
         result.push(if self.to_move.is_white() { 'w' } else { 'b' });
         result.push(' ');
 
-        result.push_str(&self.castling.as_fen_str());
+        push_castling(self, shredder, &mut result);
         result.push(' ');
 
         match self.en_passant {
@@ -77,7 +142,26 @@ impl Board {
     /// Parse the provided FEN ([Forsyth-Edwards
     /// Notation](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation))
     /// string into a [`Board`].
+    ///
+    /// This uses [`FenParseMode::Lenient`], see
+    /// [`Self::try_parse_fen_with_mode`] to reject impossible positions.
     pub fn try_parse_fen(fen: &str) -> Result<Board, FenParseError> {
+        Self::try_parse_fen_with_mode(fen, FenParseMode::Lenient)
+    }
+
+    /// Parse the provided FEN string into a [`Board`], validating the
+    /// resulting position according to `mode`.
+    ///
+    /// ```
+    /// # use hardfiskur_core::board::{Board, FenParseError, FenParseMode};
+    /// let fen = "4k3/8/8/8/8/8/8/4K2R w KQ - 0 1";
+    /// assert!(Board::try_parse_fen_with_mode(fen, FenParseMode::Lenient).is_ok());
+    /// assert!(matches!(
+    ///     Board::try_parse_fen_with_mode(fen, FenParseMode::Strict),
+    ///     Err(FenParseError::ImpossibleCastling { .. })
+    /// ));
+    /// ```
+    pub fn try_parse_fen_with_mode(fen: &str, mode: FenParseMode) -> Result<Board, FenParseError> {
         let fields: Vec<_> = fen.split(' ').collect();
         if fields.len() != 6 {
             return Err(FenParseError::IncorrectFieldCount {
@@ -87,7 +171,7 @@ impl Board {
 
         let board = parse_placement(fields[0])?;
         let to_move = parse_to_move(fields[1])?;
-        let castling = parse_castling(fields[2])?;
+        let (castling, castling_rooks) = parse_castling(fields[2], &board)?;
         let en_passant = parse_en_passant(fields[3])?;
 
         let halfmove_clock = fields[4]
@@ -100,15 +184,105 @@ impl Board {
             return Err(FenParseError::InvalidMoveCount);
         }
 
-        Ok(Board::new(
+        if let Some(square) = en_passant {
+            let expected_rank = if to_move.is_white() { 5 } else { 2 };
+            if square.rank() != expected_rank {
+                return Err(FenParseError::EnPassantWrongRank { square });
+            }
+        }
+
+        let mut board = Board::new(
             &board,
             to_move,
             castling,
             en_passant,
             halfmove_clock,
             fullmoves,
-        ))
+        );
+        board.castling_rooks = castling_rooks;
+
+        if mode == FenParseMode::Strict {
+            validate_strict(&board)?;
+        }
+
+        board.clear_uncapturable_en_passant();
+
+        Ok(board)
+    }
+
+    /// Clears the en passant square if there's no pawn that could capture
+    /// onto it, or no pawn that could have just double-pushed past it.
+    /// Hand-written FENs often include an en passant square after every
+    /// double push, but positions reached by making moves only have one if a
+    /// capture is possible, and the two need to hash the same.
+    fn clear_uncapturable_en_passant(&mut self) {
+        let Some(square) = self.en_passant else {
+            return;
+        };
+
+        if self.en_passant_plausible(square) && self.en_passant_captures(square).next().is_some() {
+            return;
+        }
+
+        self.en_passant = None;
+        self.zobrist_hash =
+            self.board.zobrist_hash() ^ Self::non_board_hash(self.to_move, self.castling, None);
+    }
+}
+
+fn validate_strict(board: &Board) -> Result<(), FenParseError> {
+    for color in [Color::White, Color::Black] {
+        let count = board.board[Piece::king(color)].pop_count();
+        if count != 1 {
+            return Err(FenParseError::InvalidKingCount { color, count });
+        }
+    }
+
+    let pawns = board.board[Piece::WHITE_PAWN] | board.board[Piece::BLACK_PAWN];
+    if let Some(square) = (pawns & (Bitboard::RANK_1 | Bitboard::RANK_8)).lsb() {
+        return Err(FenParseError::PawnOnBackRank { square });
+    }
+
+    if Board::king_attacked(&board.board, board.to_move.flip()) {
+        return Err(FenParseError::OpponentInCheck);
+    }
+
+    for castling in board.castling.iter() {
+        let color = if Castling::WHITE.contains(castling) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let kingside = Castling::KINGSIDE.contains(castling);
+
+        // The king must be on its back rank, between the castling rooks
+        let back_rank = if color.is_white() { 0 } else { 7 };
+        let king = board.board[Piece::king(color)].to_square();
+        let rook = board.castling_rooks.square(castling);
+
+        let possible = match (king, rook) {
+            (Some(king), Some(rook)) => {
+                king.rank() == back_rank
+                    && board.board.piece_at(rook) == Some(Piece::rook(color))
+                    && (king.file() < rook.file()) == kingside
+            }
+            _ => false,
+        };
+
+        if !possible {
+            return Err(FenParseError::ImpossibleCastling { castling });
+        }
     }
+
+    if let Some(square) = board.en_passant {
+        // The last move must have been a pawn push, so the halfmove clock
+        // would have been reset
+        if board.halfmove_clock != 0 || !board.en_passant_plausible(square) {
+            return Err(FenParseError::ImpossibleEnPassant { square });
+        }
+    }
+
+    Ok(())
 }
 
 fn push_placement(board: &Board, result: &mut String) {
@@ -187,23 +361,120 @@ fn parse_to_move(to_move: &str) -> Result<Color, FenParseError> {
     }
 }
 
-fn parse_castling(castling: &str) -> Result<Castling, FenParseError> {
+fn push_castling(board: &Board, shredder: bool, result: &mut String) {
+    if board.castling.is_empty() {
+        result.push('-');
+        return;
+    }
+
+    for (right, c) in [
+        (Castling::WHITE_KINGSIDE, 'K'),
+        (Castling::WHITE_QUEENSIDE, 'Q'),
+        (Castling::BLACK_KINGSIDE, 'k'),
+        (Castling::BLACK_QUEENSIDE, 'q'),
+    ] {
+        if !board.castling.contains(right) {
+            continue;
+        }
+
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let file = board.castling_rooks.file(right).unwrap();
+        let kingside = c.eq_ignore_ascii_case(&'K');
+
+        if !shredder && outermost_rook_file(&board.board[Piece::rook(color)], color, kingside, file)
+        {
+            result.push(c);
+        } else {
+            let file_char = (b'a' + file) as char;
+            result.push(if color.is_white() {
+                file_char.to_ascii_uppercase()
+            } else {
+                file_char
+            });
+        }
+    }
+}
+
+/// Whether there is no rook of the given color further towards the edge of
+/// the board than `file` on the given side, on that color's back rank.
+fn outermost_rook_file(rooks: &Bitboard, color: Color, kingside: bool, file: u8) -> bool {
+    let back_rank = if color.is_white() { 0 } else { 7 };
+    !rooks
+        .squares()
+        .filter(|square| square.rank() == back_rank)
+        .any(|square| {
+            if kingside {
+                square.file() > file
+            } else {
+                square.file() < file
+            }
+        })
+}
+
+/// Parses the castling field of a FEN string, which may be standard FEN,
+/// X-FEN or Shredder-FEN.
+///
+/// `K`/`Q` refer to the outermost rook on that side of the king, falling back
+/// to the standard rook files if there's no rook there. Files (`A`-`H` for
+/// white, `a`-`h` for black) refer to the rook on that file, which is a
+/// kingside rook if it's on the right of the king.
+fn parse_castling(
+    castling: &str,
+    board: &[Option<Piece>; 64],
+) -> Result<(Castling, CastlingRooks), FenParseError> {
+    let mut flags = Castling::empty();
+    let mut rooks = CastlingRooks::STANDARD;
+
     if castling == "-" {
-        Ok(Castling::empty())
-    } else {
-        let mut flags = Castling::empty();
-        for c in castling.chars() {
-            match c {
-                'K' => flags |= Castling::WHITE_KINGSIDE,
-                'Q' => flags |= Castling::WHITE_QUEENSIDE,
-                'k' => flags |= Castling::BLACK_KINGSIDE,
-                'q' => flags |= Castling::BLACK_QUEENSIDE,
-                _ => return Err(FenParseError::InvalidCastling),
+        return Ok((flags, rooks));
+    }
+
+    for c in castling.chars() {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let back_rank = if color.is_white() { 0 } else { 7 };
+
+        let piece_files = |piece: Piece| {
+            (0..8).filter(move |&file| {
+                board[Square::new_unchecked(back_rank, file).index()] == Some(piece)
+            })
+        };
+        let king_file = piece_files(Piece::king(color)).next().unwrap_or(4);
+        let mut rook_files = piece_files(Piece::rook(color));
+
+        let (kingside, file) = match c.to_ascii_lowercase() {
+            'k' => (true, rook_files.rfind(|&f| f > king_file).unwrap_or(7)),
+            'q' => (false, rook_files.find(|&f| f < king_file).unwrap_or(0)),
+            file @ 'a'..='h' => {
+                let file = file as u8 - b'a';
+                (file > king_file, file)
             }
-        }
+            _ => return Err(FenParseError::InvalidCastling),
+        };
 
-        Ok(flags)
+        if kingside {
+            rooks.set_kingside(color, file);
+            flags |= match color {
+                Color::White => Castling::WHITE_KINGSIDE,
+                Color::Black => Castling::BLACK_KINGSIDE,
+            };
+        } else {
+            rooks.set_queenside(color, file);
+            flags |= match color {
+                Color::White => Castling::WHITE_QUEENSIDE,
+                Color::Black => Castling::BLACK_QUEENSIDE,
+            };
+        }
     }
+
+    Ok((flags, rooks))
 }
 
 fn parse_en_passant(en_passant: &str) -> Result<Option<Square>, FenParseError> {
@@ -223,12 +494,13 @@ mod test {
     use super::*;
     use pretty_assertions::assert_eq;
 
-    use crate::board::STARTING_POSITION_FEN;
+    use crate::board::{UCIMove, STARTING_POSITION_FEN};
 
     const VALID_FENS: &[&str] = &[
         STARTING_POSITION_FEN,
-        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
-        "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
         "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
         "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3",
         "rnb1kbnr/ppp1pppp/8/3q4/8/2N5/PPPP1PPP/R1BQKBNR b KQkq - 1 3",
@@ -274,4 +546,221 @@ mod test {
             assert!(Board::try_parse_fen(fen).is_err());
         }
     }
+
+    #[test]
+    fn parse_valid_fens_strict() {
+        // The knight on f6 is giving check with white to move, which is
+        // covered by strict_mode_rejects_impossible_positions
+        for fen in VALID_FENS
+            .iter()
+            .filter(|fen| !fen.starts_with("8/4n2k/p4N2"))
+        {
+            let board = Board::try_parse_fen_with_mode(fen, FenParseMode::Strict).unwrap();
+            assert_eq!(&board.fen(), fen);
+        }
+    }
+
+    #[test]
+    fn fen_round_trips_all_fields() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 37 80",
+            "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 112",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+            "4k3/8/8/8/8/8/8/4K3 w - - 99 1000",
+        ] {
+            let board = Board::try_parse_fen(fen).unwrap();
+            assert_eq!(board.fen(), fen);
+            assert_eq!(Board::try_parse_fen(&board.fen()).unwrap(), board);
+        }
+    }
+
+    #[test]
+    fn fen_round_trips_after_moves() {
+        let mut board = Board::starting_position();
+        for m in [
+            "e2e4", "d7d5", "e4e5", "f7f5", "g1f3", "g8f6", "f1c4", "e7e6", "e1g1", "a7a5",
+        ] {
+            let m = m.parse::<UCIMove>().unwrap();
+            board.push_move(m.from, m.to, m.promotion).unwrap();
+
+            let parsed = Board::try_parse_fen_with_mode(&board.fen(), FenParseMode::Strict)
+                .unwrap_or_else(|e| panic!("{}: {e}", board.fen()));
+            assert_eq!(parsed.fen(), board.fen());
+            assert_eq!(parsed.zobrist_hash(), board.zobrist_hash());
+        }
+    }
+
+    #[test]
+    fn uncapturable_en_passant_is_cleared() {
+        for (fen, expected) in [
+            // No pawn next to the double-pushed pawn
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            ),
+            // No pawn that could have double-pushed
+            (
+                "4k3/8/8/8/8/3p4/8/4K3 b - e3 0 1",
+                "4k3/8/8/8/8/3p4/8/4K3 b - - 0 1",
+            ),
+            // A capture is possible
+            (
+                "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",
+                "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",
+            ),
+        ] {
+            let board = Board::try_parse_fen(fen).unwrap();
+            assert_eq!(board.fen(), expected);
+
+            let normalized = Board::try_parse_fen(expected).unwrap();
+            assert_eq!(board.zobrist_hash(), normalized.zobrist_hash());
+        }
+
+        // Reaching the position by moves hashes the same as parsing the FEN
+        let mut board = Board::starting_position();
+        let m = "e2e4".parse::<UCIMove>().unwrap();
+        board.push_move(m.from, m.to, m.promotion).unwrap();
+        let parsed =
+            Board::try_parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+                .unwrap();
+        assert_eq!(board.en_passant(), None);
+        assert_eq!(board.zobrist_hash(), parsed.zobrist_hash());
+    }
+
+    #[test]
+    fn standard_castling_uses_kqkq() {
+        let board = Board::starting_position();
+        assert_eq!(board.fen(), STARTING_POSITION_FEN);
+        assert!(board.castling_rooks().is_standard());
+        assert_eq!(
+            board.shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        let board =
+            Board::try_parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1")
+                .unwrap();
+        assert_eq!(board, Board::starting_position());
+    }
+
+    #[test]
+    fn parse_chess960_castling() {
+        let shredder = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let x_fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9";
+
+        for fen in [shredder, x_fen] {
+            let board = Board::try_parse_fen_with_mode(fen, FenParseMode::Strict).unwrap();
+
+            assert_eq!(board.castling(), Castling::all());
+            assert_eq!(board.castling_rooks(), CastlingRooks::new(7, 5));
+            assert_eq!(board.fen(), x_fen);
+            assert_eq!(board.shredder_fen(), shredder);
+        }
+    }
+
+    #[test]
+    fn x_fen_uses_files_for_inner_rooks() {
+        let fen = "r2rk3/8/8/8/8/8/8/R2RK2R w KDd - 0 1";
+        let board = Board::try_parse_fen_with_mode(fen, FenParseMode::Strict).unwrap();
+
+        assert_eq!(
+            board.castling(),
+            Castling::WHITE_KINGSIDE | Castling::WHITE_QUEENSIDE | Castling::BLACK_QUEENSIDE
+        );
+        assert_eq!(board.castling_rooks().kingside(Color::White), 7);
+        assert_eq!(board.castling_rooks().queenside(Color::White), 3);
+        assert_eq!(board.castling_rooks().queenside(Color::Black), 3);
+
+        assert_eq!(board.fen(), fen);
+        assert_eq!(board.shredder_fen(), "r2rk3/8/8/8/8/8/8/R2RK2R w HDd - 0 1");
+    }
+
+    #[test]
+    fn moving_chess960_rook_removes_castling_right() {
+        let mut board = Board::try_parse_fen(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        )
+        .unwrap();
+
+        board.push_move(Square::F1, Square::F2, None).unwrap();
+        assert_eq!(board.shredder_fen().split(' ').nth(2), Some("Hhf"));
+    }
+
+    #[test]
+    fn en_passant_on_wrong_rank_rejected() {
+        assert_eq!(
+            Board::try_parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1"),
+            Err(FenParseError::EnPassantWrongRank { square: Square::E3 })
+        );
+        assert_eq!(
+            Board::try_parse_fen("4k3/8/8/8/8/8/8/4K3 b - a5 0 1"),
+            Err(FenParseError::EnPassantWrongRank { square: Square::A5 })
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_impossible_positions() {
+        let cases = [
+            (
+                "8/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenParseError::InvalidKingCount {
+                    color: Color::Black,
+                    count: 0,
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+                FenParseError::InvalidKingCount {
+                    color: Color::White,
+                    count: 2,
+                },
+            ),
+            (
+                "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenParseError::PawnOnBackRank { square: Square::H8 },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/p3K3 b - - 0 1",
+                FenParseError::PawnOnBackRank { square: Square::A1 },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2r b - - 0 1",
+                FenParseError::OpponentInCheck,
+            ),
+            (
+                "8/4n2k/p4N2/1p1p4/1P1P1Bb1/2K5/1P6/8 w - - 2 45",
+                FenParseError::OpponentInCheck,
+            ),
+            (
+                "r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                FenParseError::ImpossibleCastling {
+                    castling: Castling::BLACK_KINGSIDE,
+                },
+            ),
+            (
+                "r3k2r/8/8/8/8/8/4K3/R6R w Q - 0 1",
+                FenParseError::ImpossibleCastling {
+                    castling: Castling::WHITE_QUEENSIDE,
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+                FenParseError::ImpossibleEnPassant { square: Square::E3 },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 3 1",
+                FenParseError::ImpossibleEnPassant { square: Square::E3 },
+            ),
+        ];
+
+        for (fen, error) in cases {
+            assert!(Board::try_parse_fen(fen).is_ok(), "{fen}");
+            assert_eq!(
+                Board::try_parse_fen_with_mode(fen, FenParseMode::Strict),
+                Err(error),
+                "{fen}"
+            );
+        }
+    }
 }
@@ -3,28 +3,35 @@
 mod bitboard;
 mod board_repr;
 mod castling;
+mod consistency;
+mod endgames;
 mod fen;
 mod move_repr;
 mod piece;
 mod san;
+mod see;
 mod square;
 mod uci_move;
 mod zobrist;
 
-use std::fmt::Display;
+use std::{cmp::Reverse, fmt::Display};
 
 pub use bitboard::Bitboard;
 pub use board_repr::BoardRepr;
-pub use castling::Castling;
-pub use fen::FenParseError;
+pub use castling::{Castling, CastlingRooks};
+pub use consistency::BoardInconsistency;
+pub use fen::{FenParseError, FenParseMode};
 pub use move_repr::{Move, MoveBuilder, MoveFlags, OptionalMove};
 pub use piece::{Color, Piece, PieceType};
-pub use san::SAN;
+pub use san::{SanError, SAN};
+pub use see::SEE_PIECE_VALUES;
 pub use square::{ParseSquareError, Square};
 pub use uci_move::{ParseUCIMoveError, UCIMove};
 pub use zobrist::ZobristHash;
 
-use crate::move_gen::{self, MoveGenFlags, MoveGenResult, MoveGenerator, MoveVec};
+use crate::move_gen::{
+    self, lookups::Lookups, CastlingPath, MoveGenFlags, MoveGenResult, MoveGenerator, MoveVec,
+};
 
 pub const STARTING_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
@@ -47,9 +54,23 @@ pub enum DrawReason {
     InsufficientMaterial,
 }
 
-/// Holds relevant information needed to undo a move.
+/// Orderings for [`Board::sorted_legal_moves`], for displaying move lists.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct UnmakeData {
+pub enum MoveSortKey {
+    /// Alphabetically by the move's [`SAN`].
+    San,
+    /// By the square the move starts from, then the square it goes to (both
+    /// in a1, b1, ..., h8 order).
+    FromSquare,
+    /// Captures first, most valuable victim first and then least valuable
+    /// aggressor, followed by all the non-captures.
+    MvvLva,
+}
+
+/// Holds relevant information needed to undo a move. Returned by
+/// [`Board::make_move`] and consumed by [`Board::unmake_move`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnmakeData {
     the_move: Option<Move>,
     castling: Castling,
     en_passant: Option<Square>,
@@ -57,6 +78,18 @@ struct UnmakeData {
     zobrist_hash: ZobristHash,
 }
 
+impl UnmakeData {
+    /// The move that was made, or [`None`] for a null move.
+    pub fn the_move(&self) -> Option<Move> {
+        self.the_move
+    }
+
+    /// The Zobrist hash of the position before the move was made.
+    pub fn zobrist_hash(&self) -> ZobristHash {
+        self.zobrist_hash
+    }
+}
+
 /// Represents the current game state.
 ///
 /// Contains a bitboard representation of the board, along with other
@@ -66,6 +99,7 @@ pub struct Board {
     board: BoardRepr,
     to_move: Color,
     castling: Castling,
+    castling_rooks: CastlingRooks,
     en_passant: Option<Square>,
     halfmove_clock: u32,
     fullmoves: u32,
@@ -84,17 +118,17 @@ impl Board {
     /// * `to_move` - the [`Color`] of the current player.
     /// * `castling` - castling rights for both players, see [`Castling`]
     /// * `en_passant` - set if a double pawn push was made on the immediate
-    ///    previous ply and the current player has the option to capture en
-    ///    passant.
+    ///   previous ply and the current player has the option to capture en
+    ///   passant.
     ///
-    ///    In this case, `en_passant` should be `Some(square)`, where `square`
-    ///    is the square behind the double-moved pawn that the current player's
-    ///    pawn will land on if they choose to en passant.
+    ///   In this case, `en_passant` should be `Some(square)`, where `square`
+    ///   is the square behind the double-moved pawn that the current player's
+    ///   pawn will land on if they choose to en passant.
     /// * `halfmove_clock` - Half-move clock, represents the number of plies
-    ///    since the last capture or pawn-push. This is used for tracking the
-    ///    50-move draw rule.
+    ///   since the last capture or pawn-push. This is used for tracking the
+    ///   50-move draw rule.
     /// * `fullmoves` - Number of full moves (2 plies, a move by white and a
-    ///    move by black) since the start of the game. Starts at 1.
+    ///   move by black) since the start of the game. Starts at 1.
     pub fn new(
         board: &[Option<Piece>],
         to_move: Color,
@@ -112,6 +146,7 @@ impl Board {
             board,
             to_move,
             castling,
+            castling_rooks: CastlingRooks::STANDARD,
             en_passant,
             halfmove_clock,
             fullmoves,
@@ -139,6 +174,12 @@ impl Board {
         self.castling
     }
 
+    /// Returns the files of the rooks each side castles with, which are
+    /// only different from standard chess in Chess960 positions.
+    pub fn castling_rooks(&self) -> CastlingRooks {
+        self.castling_rooks
+    }
+
     /// Returns the square on which a pawn may be captured en passant if the
     /// previous move was a double pawn push.
     ///
@@ -189,6 +230,88 @@ impl Board {
         self.board.piece_at(square)
     }
 
+    /// Returns the piece that would be captured by moving the piece on `from`
+    /// to `to`, including the pawn captured by an en passant capture.
+    ///
+    /// This does not check that the move is legal, only that the piece on
+    /// `from` belongs to the side to move and that the captured piece belongs
+    /// to the opponent.
+    pub fn capture_target(&self, from: Square, to: Square) -> Option<Piece> {
+        let piece = self.get_piece(from)?;
+        if piece.color() != self.to_move {
+            return None;
+        }
+
+        if let Some(target) = self.get_piece(to) {
+            return (target.color() != self.to_move).then_some(target);
+        }
+
+        if piece.is_pawn() && Some(to) == self.en_passant && from.file() != to.file() {
+            let captured_square = Square::new_unchecked(from.rank(), to.file());
+            return self
+                .get_piece(captured_square)
+                .filter(|&p| p == Piece::pawn(self.to_move.flip()));
+        }
+
+        None
+    }
+
+    /// Lists the squares where the piece differs between this position and
+    /// `other`, as `(square, piece in self, piece in other)`.
+    ///
+    /// Only piece placement is compared -- side to move, castling rights, en
+    /// passant and move counters are ignored.
+    pub fn diff(&self, other: &Board) -> Vec<(Square, Option<Piece>, Option<Piece>)> {
+        Square::all()
+            .filter_map(|square| {
+                let old = self.board.piece_at(square);
+                let new = other.board.piece_at(square);
+                (old != new).then_some((square, old, new))
+            })
+            .collect()
+    }
+
+    /// Returns all the squares attacked by the pieces of the given color,
+    /// regardless of whether they're occupied or not.
+    ///
+    /// Squares that are only attacked through a pinned piece are still
+    /// included, as are squares that the king could not actually move to.
+    pub fn attacks_by(&self, color: Color) -> Bitboard {
+        move_gen::attacked_squares(
+            &self.board,
+            color.flip(),
+            Lookups::get_instance(),
+            self.board.occupied(),
+        )
+    }
+
+    /// Returns the pieces attacking the given square, as `(white, black)`.
+    pub fn attackers_of(&self, square: Square) -> (Bitboard, Bitboard) {
+        let attackers = move_gen::attackers_on(
+            &self.board,
+            self.board.occupied(),
+            square,
+            Lookups::get_instance(),
+        );
+
+        (
+            attackers & self.board[Color::White],
+            attackers & self.board[Color::Black],
+        )
+    }
+
+    /// Checks whether any pieces of the color `by` attack the given square.
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        let attackers = move_gen::attackers_on(
+            &self.board,
+            self.board.occupied(),
+            square,
+            Lookups::get_instance(),
+        );
+
+        (attackers & self.board[by]).has_piece()
+    }
+
     /// Generate all the possible legal moves in the current position.
     pub fn legal_moves(&self) -> MoveVec {
         let mut moves = MoveVec::new();
@@ -196,6 +319,57 @@ impl Board {
         moves
     }
 
+    /// Like [`Self::legal_moves`], but clears and fills `out` instead of
+    /// returning a new list, so that callers generating moves repeatedly can
+    /// reuse the same buffer.
+    pub fn legal_moves_into(&self, out: &mut MoveVec) -> MoveGenResult {
+        out.clear();
+        self.legal_moves_ex(MoveGenFlags::default(), out)
+    }
+
+    /// Like [`Self::legal_moves_into`], but only generates captures
+    /// (including capturing promotions and en passant).
+    pub fn legal_captures_into(&self, out: &mut MoveVec) -> MoveGenResult {
+        out.clear();
+        self.legal_moves_ex(MoveGenFlags::GEN_CAPTURES, out)
+    }
+
+    /// Generate all the legal moves in the current position, grouped by the
+    /// type of the piece being moved. Index the result with
+    /// [`PieceType::index`].
+    pub fn legal_moves_by_piece_type(&self) -> [MoveVec; 6] {
+        let mut groups: [MoveVec; 6] = std::array::from_fn(|_| MoveVec::new());
+
+        for m in self.legal_moves() {
+            groups[m.piece().piece_type().index()].push(m);
+        }
+
+        groups
+    }
+
+    /// Generate all the legal moves in the current position, ordered by the
+    /// given [`MoveSortKey`].
+    pub fn sorted_legal_moves(&self, by: MoveSortKey) -> MoveVec {
+        let mut moves = self.legal_moves();
+
+        match by {
+            MoveSortKey::San => {
+                moves.sort_by_cached_key(|&m| self.get_san(m).map(|san| san.to_string()))
+            }
+            MoveSortKey::FromSquare => moves.sort_by_key(|m| (m.from_square(), m.to_square())),
+            MoveSortKey::MvvLva => moves.sort_by_key(|m| match m.captured_piece() {
+                Some(victim) => (
+                    false,
+                    Reverse(victim.piece_type().index()),
+                    m.piece().piece_type().index(),
+                ),
+                None => (true, Reverse(0), 0),
+            }),
+        }
+
+        moves
+    }
+
     /// Generate all the possible legal moves in the current position, and
     /// some additional information about the position:
     /// * number of enemy pieces checking the king (of the side to move)
@@ -219,6 +393,7 @@ impl Board {
             flags,
             out_moves,
         )
+        .with_castling_rooks(self.castling_rooks)
         .legal_moves()
     }
 
@@ -232,15 +407,26 @@ impl Board {
     /// information (e.g. legal moves from the current position) to avoid extra
     /// recomputation.
     pub fn state(&self) -> BoardState {
+        self.state_with_claims(true)
+    }
+
+    /// Like [`Self::state`], but if `claim_draws` is false, the fifty-move
+    /// and threefold repetition rules don't end the game by themselves. Under
+    /// FIDE rules, these draws have to be claimed by a player, which can be
+    /// offered to them using [`Self::can_claim_draw`].
+    ///
+    /// (The automatic draws after 75 moves or a fivefold repetition aren't
+    /// modelled.)
+    pub fn state_with_claims(&self, claim_draws: bool) -> BoardState {
         let (legal_moves, move_gen_result) = self.legal_moves_and_meta();
         let in_check = move_gen_result.checker_count > 0;
 
         if !legal_moves.is_empty() {
-            if self.halfmove_clock >= 100 {
+            if claim_draws && self.halfmove_clock >= 100 {
                 BoardState::Draw(DrawReason::FiftyMoveRule)
             } else if self.check_draw_by_insufficient_material() {
                 BoardState::Draw(DrawReason::InsufficientMaterial)
-            } else if self.check_draw_by_repetition() {
+            } else if claim_draws && self.check_draw_by_repetition() {
                 BoardState::Draw(DrawReason::ThreeFoldRepetition)
             } else {
                 BoardState::InPlay {
@@ -254,6 +440,236 @@ impl Board {
         }
     }
 
+    /// Returns the reason the player to move could claim a draw, if the
+    /// fifty-move rule or threefold repetition applies. Returns `None` if the
+    /// game is already over, e.g. if the move reaching the fifty-move limit
+    /// was checkmate.
+    ///
+    /// Like [`Self::state`], this is rather slow and intended for display
+    /// purposes.
+    pub fn can_claim_draw(&self) -> Option<DrawReason> {
+        if self.legal_moves().is_empty() {
+            None
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else if self.check_draw_by_repetition() {
+            Some(DrawReason::ThreeFoldRepetition)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the provided move would leave the king of the side to move
+    /// out of check. Intended for explaining to a user why a candidate move
+    /// is illegal while in check.
+    ///
+    /// Unlike [`Self::legal_moves`], the move does not need to be legal -- it
+    /// is made on a copy of the board and the king's safety is tested
+    /// afterwards. However, only the pieces involved in the move are
+    /// validated, not whether the piece can actually move that way. Returns
+    /// `false` if the move's piece doesn't belong to the side to move or isn't
+    /// on the move's origin square, or if the captured piece doesn't match
+    /// the board.
+    pub fn move_resolves_check(&self, the_move: Move) -> bool {
+        let piece = the_move.piece();
+        if piece.color() != self.to_move || self.get_piece(the_move.from_square()) != Some(piece) {
+            return false;
+        }
+
+        let captured_square = if the_move.is_en_passant() {
+            the_move.en_passant_square()
+        } else {
+            the_move.to_square()
+        };
+        let captured_piece = self.get_piece(captured_square);
+        if captured_piece != the_move.captured_piece()
+            || captured_piece.is_some_and(|captured| captured.color() == self.to_move)
+        {
+            return false;
+        }
+
+        !self.leaves_king_in_check(the_move)
+    }
+
+    /// Checks whether the move could be made in the current position, ignoring
+    /// whether it leaves the king in check: the moved piece belongs to the
+    /// side to move and is on the origin square, it can reach the destination
+    /// without being blocked, and the capture, promotion and other flags all
+    /// match the board.
+    ///
+    /// This is much cheaper than generating all legal moves, so it's suitable
+    /// for validating moves from elsewhere, e.g. a transposition table.
+    pub fn is_pseudo_legal(&self, the_move: Move) -> bool {
+        let (from, to) = (the_move.from_square(), the_move.to_square());
+        let piece = the_move.piece();
+        if piece.color() != self.to_move || self.get_piece(from) != Some(piece) {
+            return false;
+        }
+
+        if the_move.is_castle() {
+            return self.is_pseudo_legal_castle(the_move);
+        }
+
+        let lookups = Lookups::get_instance();
+        let occupied = self.board.occupied();
+        let captured = self.get_piece(to);
+        if captured.is_some_and(|captured| captured.color() == self.to_move || captured.is_king()) {
+            return false;
+        }
+
+        let mut expected = Move::builder(from, to, piece);
+        if let Some(captured) = captured {
+            expected = expected.captures(captured);
+        }
+
+        let reachable = match piece.piece_type() {
+            PieceType::Pawn => {
+                let forward: i8 = if self.to_move.is_white() { 8 } else { -8 };
+                let start_rank = if self.to_move.is_white() { 1 } else { 6 };
+                let promotion_rank = if self.to_move.is_white() { 7 } else { 0 };
+
+                let attacks = match self.to_move {
+                    Color::White => move_gen::white_pawn_attacks(Bitboard::from_square(from)),
+                    Color::Black => move_gen::black_pawn_attacks(Bitboard::from_square(from)),
+                };
+
+                if let Some(promotion) = the_move.promotion() {
+                    if to.rank() != promotion_rank
+                        || promotion.color() != self.to_move
+                        || matches!(promotion.piece_type(), PieceType::Pawn | PieceType::King)
+                    {
+                        return false;
+                    }
+                    expected = expected.promotes_to(promotion.piece_type());
+                } else if to.rank() == promotion_rank {
+                    return false;
+                }
+
+                if attacks.get(to) {
+                    if captured.is_none() && self.en_passant == Some(to) {
+                        expected = expected
+                            .captures(Piece::pawn(self.to_move.flip()))
+                            .is_en_passant();
+                        true
+                    } else {
+                        captured.is_some()
+                    }
+                } else if captured.is_some() {
+                    false
+                } else if to.get() as i8 == from.get() as i8 + forward {
+                    true
+                } else if from.rank() == start_rank
+                    && to.get() as i8 == from.get() as i8 + 2 * forward
+                {
+                    expected = expected.is_double_pawn_push();
+                    !occupied.get(from.offset(forward))
+                } else {
+                    false
+                }
+            }
+            PieceType::Knight => lookups.get_knight_moves(from).get(to),
+            PieceType::Bishop => lookups.get_bishop_attacks(occupied, from).get(to),
+            PieceType::Rook => lookups.get_rook_attacks(occupied, from).get(to),
+            PieceType::Queen => lookups.get_queen_attacks(occupied, from).get(to),
+            PieceType::King => lookups.get_king_moves(from).get(to),
+        };
+
+        reachable && expected.build() == the_move
+    }
+
+    fn is_pseudo_legal_castle(&self, the_move: Move) -> bool {
+        let (from, to) = (the_move.from_square(), the_move.to_square());
+        if !the_move.piece().is_king() || to.rank() != from.rank() || !matches!(to.file(), 2 | 6) {
+            return false;
+        }
+
+        let color_rights = match self.to_move {
+            Color::White => Castling::WHITE,
+            Color::Black => Castling::BLACK,
+        };
+        let kingside = to.file() == 6;
+        let (side_rights, rook_file) = if kingside {
+            (
+                Castling::KINGSIDE,
+                self.castling_rooks.kingside(self.to_move),
+            )
+        } else {
+            (
+                Castling::QUEENSIDE,
+                self.castling_rooks.queenside(self.to_move),
+            )
+        };
+        if !(self.castling & color_rights).intersects(side_rights) {
+            return false;
+        }
+
+        let rook_square = Square::new_unchecked(from.rank(), rook_file);
+        let path = CastlingPath::new(from, rook_square, kingside, self.to_move);
+
+        the_move == path.the_move
+            && self.get_piece(rook_square) == Some(Piece::rook(self.to_move))
+            && (path.must_be_empty & self.board.occupied()).is_empty()
+    }
+
+    /// Checks whether the move is legal in the current position, without
+    /// generating all the legal moves. See also [`Self::is_pseudo_legal`].
+    pub fn is_legal(&self, the_move: Move) -> bool {
+        if !self.is_pseudo_legal(the_move) {
+            return false;
+        }
+
+        if the_move.is_castle() {
+            // Can't castle out of or through check. (Whether the king ends up
+            // in check is handled below.)
+            let lookups = Lookups::get_instance();
+            let occupied = self.board.occupied();
+            let from = the_move.from_square();
+            let to = the_move.to_square();
+            let passed_through = lookups.get_in_between(from, to) | Bitboard::from_square(from);
+
+            for square in passed_through.squares() {
+                let attackers = move_gen::attackers_on(&self.board, occupied, square, lookups);
+                if (attackers & self.board[self.to_move.flip()]).has_piece() {
+                    return false;
+                }
+            }
+        }
+
+        !self.leaves_king_in_check(the_move)
+    }
+
+    /// Checks if the provided legal move would put the opponent's king in
+    /// check, either directly or by discovery.
+    pub fn gives_check(&self, the_move: Move) -> bool {
+        let mut board = self.board.clone();
+        board.move_unchecked(the_move);
+
+        Self::king_attacked(&board, self.to_move.flip())
+    }
+
+    /// Generate all the legal non-capturing moves that give check in the
+    /// current position.
+    pub fn legal_quiet_checks(&self) -> MoveVec {
+        let mut moves = MoveVec::new();
+        self.legal_moves_ex(MoveGenFlags::GEN_QUIET_MOVES, &mut moves);
+        moves.retain(|m| self.gives_check(*m));
+        moves
+    }
+
+    /// Whether the king of the given color is attacked. Also returns `true`
+    /// if there is no king of that color, as such a position is not valid.
+    fn king_attacked(board: &BoardRepr, color: Color) -> bool {
+        let king = match board[Piece::king(color)].to_square() {
+            Some(king) => king,
+            None => return true,
+        };
+
+        let attackers =
+            move_gen::attackers_on(board, board.occupied(), king, Lookups::get_instance());
+
+        (attackers & board[color.flip()]).has_piece()
+    }
+
     /// Attempts to find a legal move matching the provided parameters.
     ///
     /// `promotion` should be `None` unless the move involves a pawn moving to
@@ -262,22 +678,25 @@ impl Board {
     pub fn get_move(&self, from: Square, to: Square, promotion: Option<PieceType>) -> Option<Move> {
         let legal_moves = self.legal_moves();
 
-        legal_moves.into_iter().find(|m| {
-            if m.from_square() == from
+        // In Chess960, a castle can have the same from and to squares as an
+        // ordinary king move, so prefer the ordinary move in that case
+        let exact_match = legal_moves.iter().copied().find(|m| {
+            !m.is_castle()
+                && m.from_square() == from
                 && m.to_square() == to
                 && m.promotion().map(|piece| piece.piece_type()) == promotion
-            {
-                return true;
-            }
-
-            // Special case -- a move where the king "captures" its own rook can
-            // be interpreted as a castle.
-            // TODO: test this
-            if m.is_castle() && m.from_square() == from && m.castling_rook_squares().0 == to {
-                return true;
-            }
-
-            false
+        });
+
+        exact_match.or_else(|| {
+            // A move where the king "captures" its own rook can also be
+            // interpreted as a castle, which is how castles are written in
+            // Chess960
+            legal_moves.into_iter().find(|m| {
+                m.is_castle()
+                    && m.from_square() == from
+                    && (m.to_square() == to || m.castling_rook_squares().0 == to)
+                    && promotion.is_none()
+            })
         })
     }
 
@@ -365,6 +784,46 @@ impl Board {
         self.move_history.push(unmake);
     }
 
+    /// Make a move on the board without checking its legality, returning the
+    /// data needed to undo it with [`Self::unmake_move`].
+    ///
+    /// Unlike [`Self::push_move_unchecked`], the move is not recorded in the
+    /// move history, so the caller is responsible for keeping the undo data
+    /// around. As a consequence, [`Self::pop_move`] can't undo it, and
+    /// repetition detection won't take any positions reached this way into
+    /// account.
+    ///
+    /// Ensure that the move provided is legal, otherwise you will put the board
+    /// into an invalid state.
+    pub fn make_move(&mut self, the_move: Move) -> UnmakeData {
+        self.make_move_unchecked(Some(the_move))
+    }
+
+    /// Passes the turn without recording it in the move history, like
+    /// [`Self::make_move`].
+    pub fn make_null_move(&mut self) -> UnmakeData {
+        self.make_move_unchecked(None)
+    }
+
+    /// Sets the side to move without making a move, e.g. for setting up
+    /// puzzles. The en passant square is cleared, as it only applies to the
+    /// side that was originally to move.
+    ///
+    /// The move history is cleared as well, since earlier moves could no
+    /// longer be undone consistently. Does nothing if `color` is already to
+    /// move.
+    pub fn set_to_move(&mut self, color: Color) {
+        if color == self.to_move {
+            return;
+        }
+
+        self.zobrist_hash ^= Self::non_board_hash(self.to_move, self.castling, self.en_passant)
+            ^ Self::non_board_hash(color, self.castling, None);
+        self.to_move = color;
+        self.en_passant = None;
+        self.move_history.clear();
+    }
+
     /// Undo the most recently made move on the board.
     ///
     /// Does nothing if there are no moves in the move history. Returns the move
@@ -454,9 +913,9 @@ impl Board {
         // Only kings and minor pieces are left.
 
         let [knights, white_bishops, black_bishops] = [
-            (self.board[Piece::WHITE_KNIGHT] | self.board[Piece::BLACK_KNIGHT]).pop_count(),
-            self.board[Piece::WHITE_BISHOP].pop_count(),
-            self.board[Piece::BLACK_BISHOP].pop_count(),
+            self.board.count(Piece::WHITE_KNIGHT) + self.board.count(Piece::BLACK_KNIGHT),
+            self.board.count(Piece::WHITE_BISHOP),
+            self.board.count(Piece::BLACK_BISHOP),
         ];
         let minor_piece_count = knights + white_bishops + black_bishops;
 
@@ -577,24 +1036,12 @@ impl Board {
                 //   captured pawn exposes a horizontal check by a rook or a
                 //   queen
 
-                // Rather than jank together some new code for detecting this,
-                // the move generation is already capable of handling all these
-                // cases. So even though this calling the move generation again
-                // is somewhat expensive this should be a pretty rare case that
-                // it really shouldn't matter.
-
-                let result = MoveGenerator::new(
-                    &self.board,
-                    self.to_move,
-                    // use the en passant state of the first repetition
-                    unmake_data.en_passant,
-                    self.castling,
-                    MoveGenFlags::GEN_CAPTURES,
-                    // (Don't care about the moves)
-                    &mut MoveVec::new(),
-                )
-                .legal_moves();
-                if result.en_passant_possible {
+                // Use the en passant state of the first repetition
+                let en_passant_possible = unmake_data.en_passant.is_some_and(|en_passant| {
+                    self.en_passant_captures(en_passant)
+                        .any(|m| !self.leaves_king_in_check(m))
+                });
+                if en_passant_possible {
                     // Not a true repetition
                     // (Since this position resulted from a double pawn push,
                     // it's an irreversible move and positions prior to this
@@ -609,6 +1056,54 @@ impl Board {
         }
     }
 
+    /// Returns whether en passant is available, but every en passant capture
+    /// would leave the king of the side to move in check. The trickiest case is
+    /// when the capturing and captured pawns are the only pieces between the
+    /// king and a rook or queen on the same rank, so removing both exposes the
+    /// king.
+    ///
+    /// Returns `false` if there is no en passant square or no pawn in position
+    /// to capture en passant.
+    pub fn en_passant_exposes_king(&self) -> bool {
+        let Some(en_passant) = self.en_passant else {
+            return false;
+        };
+
+        let mut captures = self.en_passant_captures(en_passant).peekable();
+        captures.peek().is_some() && captures.all(|m| self.leaves_king_in_check(m))
+    }
+
+    /// All en passant captures onto `en_passant` by the side to move, whether
+    /// or not they leave the king in check.
+    fn en_passant_captures(&self, en_passant: Square) -> impl Iterator<Item = Move> {
+        let pawn = Piece::pawn(self.to_move);
+        let captured = Piece::pawn(self.to_move.flip());
+
+        // Pawns that can capture onto the en passant square are exactly those
+        // that an opposing pawn on that square would attack
+        let en_passant_bb = Bitboard::from_square(en_passant);
+        let capturers = match self.to_move {
+            Color::White => move_gen::black_pawn_attacks(en_passant_bb),
+            Color::Black => move_gen::white_pawn_attacks(en_passant_bb),
+        } & self.board[pawn];
+
+        capturers.squares().map(move |from| {
+            Move::builder(from, en_passant, pawn)
+                .captures(captured)
+                .is_en_passant()
+                .build()
+        })
+    }
+
+    /// Whether making the move would leave the king of the side to move in
+    /// check.
+    fn leaves_king_in_check(&self, the_move: Move) -> bool {
+        let mut board = self.board.clone();
+        board.move_unchecked(the_move);
+
+        Self::king_attacked(&board, self.to_move)
+    }
+
     // TODO: Document
     pub fn last_move(&self) -> Option<Move> {
         self.move_history
@@ -624,26 +1119,93 @@ impl Board {
         attacked_bb.get(square)
     }
 
+    /// Returns the union of all squares attacked by pieces of type `piece`,
+    /// given the current occupancy. Squares occupied by friendly pieces are
+    /// included, as they would be defended.
+    pub fn piece_attacks(&self, piece: Piece) -> Bitboard {
+        let lookups = Lookups::get_instance();
+        let occupied = self.board.occupied();
+        let pieces = self.board[piece];
+
+        match piece.piece_type() {
+            PieceType::Pawn => match piece.color() {
+                Color::White => move_gen::white_pawn_attacks(pieces),
+                Color::Black => move_gen::black_pawn_attacks(pieces),
+            },
+            PieceType::Knight => pieces.squares().fold(Bitboard::EMPTY, |acc, square| {
+                acc | lookups.get_knight_moves(square)
+            }),
+            PieceType::Bishop => pieces.squares().fold(Bitboard::EMPTY, |acc, square| {
+                acc | lookups.get_bishop_attacks(occupied, square)
+            }),
+            PieceType::Rook => pieces.squares().fold(Bitboard::EMPTY, |acc, square| {
+                acc | lookups.get_rook_attacks(occupied, square)
+            }),
+            PieceType::Queen => pieces.squares().fold(Bitboard::EMPTY, |acc, square| {
+                acc | lookups.get_queen_attacks(occupied, square)
+            }),
+            PieceType::King => pieces.squares().fold(Bitboard::EMPTY, |acc, square| {
+                acc | lookups.get_king_moves(square)
+            }),
+        }
+    }
+
+    /// Returns the squares attacked by each type of piece of the given color,
+    /// indexed by [`PieceType::index`].
+    pub fn all_attacks_by_type(&self, color: Color) -> [Bitboard; 6] {
+        PieceType::ALL.map(|piece_type| self.piece_attacks(Piece::new(color, piece_type)))
+    }
+
     pub fn current_position_repeated_at_least(&self, times: u32) -> bool {
-        let mut repetitions = 0;
-        for (unmake_data, _) in self
-            .move_history
-            .iter()
-            .rev()
-            .zip(0..self.halfmove_clock)
+        times == 0
+            || self
+                .previous_occurrences()
+                .nth(times as usize - 1)
+                .is_some()
+    }
+
+    /// How many plies ago each earlier occurrence of the current position
+    /// was, most recent first, going by the Zobrist hash. Only positions since
+    /// the last capture or pawn move are considered, as no earlier position
+    /// can be repeated.
+    pub fn previous_occurrences(&self) -> impl Iterator<Item = usize> + '_ {
+        Self::occurrences_among(
+            self.zobrist_hash,
+            self.halfmove_clock,
+            self.history_hashes(),
+        )
+    }
+
+    /// Like [`Self::previous_occurrences`], but for a position with the given
+    /// hash and halfmove clock reached after the positions in `earlier`, most
+    /// recent first.
+    ///
+    /// A search that makes moves with [`Self::make_move`] can use this with its
+    /// own positions followed by [`Self::history_hashes`], and tell apart
+    /// repetitions within its own tree (fewer plies ago than the current
+    /// distance from the root) from ones involving the game history before the
+    /// root.
+    pub fn occurrences_among(
+        zobrist_hash: ZobristHash,
+        halfmove_clock: u32,
+        earlier: impl Iterator<Item = ZobristHash>,
+    ) -> impl Iterator<Item = usize> {
+        earlier
+            .zip(0..halfmove_clock)
+            .enumerate()
             .skip(1)
             .step_by(2)
-        {
-            if unmake_data.zobrist_hash == self.zobrist_hash {
-                repetitions += 1;
-
-                if repetitions >= times {
-                    return true;
-                }
-            }
-        }
+            .filter(move |(_, (hash, _))| *hash == zobrist_hash)
+            .map(|(i, _)| i + 1)
+    }
 
-        false
+    /// Zobrist hashes of the positions before each move in the move history,
+    /// most recent first.
+    pub fn history_hashes(&self) -> impl Iterator<Item = ZobristHash> + '_ {
+        self.move_history
+            .iter()
+            .rev()
+            .map(|unmake_data| unmake_data.zobrist_hash)
     }
 
     pub fn is_king_and_pawn_endgame(&self) -> bool {
@@ -670,7 +1232,7 @@ impl Board {
             ^ ZobristHash::en_passant(en_passant)
     }
 
-    fn castling_rights_removed(the_move: Move) -> Castling {
+    fn castling_rights_removed(&self, the_move: Move) -> Castling {
         let mut removed_rights = Castling::empty();
 
         if the_move.is_move_of(PieceType::King) {
@@ -679,23 +1241,13 @@ impl Board {
                 Color::Black => Castling::BLACK,
             };
         } else if the_move.is_move_of(PieceType::Rook) {
-            removed_rights |= match the_move.from_square() {
-                Square::WHITE_KINGSIDE_ROOK => Castling::WHITE_KINGSIDE,
-                Square::WHITE_QUEENSIDE_ROOK => Castling::WHITE_QUEENSIDE,
-                Square::BLACK_KINGSIDE_ROOK => Castling::BLACK_KINGSIDE,
-                Square::BLACK_QUEENSIDE_ROOK => Castling::BLACK_QUEENSIDE,
-                _ => Castling::empty(),
-            };
+            removed_rights |= self
+                .castling_rooks
+                .rights_for_square(the_move.from_square());
         }
 
         if the_move.is_capture_of(PieceType::Rook) {
-            removed_rights |= match the_move.to_square() {
-                Square::WHITE_KINGSIDE_ROOK => Castling::WHITE_KINGSIDE,
-                Square::WHITE_QUEENSIDE_ROOK => Castling::WHITE_QUEENSIDE,
-                Square::BLACK_KINGSIDE_ROOK => Castling::BLACK_KINGSIDE,
-                Square::BLACK_QUEENSIDE_ROOK => Castling::BLACK_QUEENSIDE,
-                _ => Castling::empty(),
-            };
+            removed_rights |= self.castling_rooks.rights_for_square(the_move.to_square());
         }
 
         removed_rights
@@ -714,17 +1266,21 @@ impl Board {
             self.board.move_unchecked(the_move);
 
             // Update if the move broke any castling rights
-            self.castling
-                .remove(Self::castling_rights_removed(the_move));
+            self.castling.remove(self.castling_rights_removed(the_move));
 
-            // Set the en passant square if applicable
+            // Set the en passant square if applicable. It's left unset if no
+            // pawn can capture onto it, so that the position hashes the same
+            // as if the pawn had got there some other way
             if the_move.is_double_pawn_push() {
                 // Little trick -- due to our square representation, the square
                 // inbetween two squares vertically is simply the average of the
                 // start and end square
-                let en_passant_square =
-                    (the_move.from_square().get() + the_move.to_square().get()) / 2;
-                self.en_passant = Some(Square::from_u8_unchecked(en_passant_square));
+                let en_passant_square = Square::from_u8_unchecked(
+                    (the_move.from_square().get() + the_move.to_square().get()) / 2,
+                );
+                if self.en_passant_captures(en_passant_square).next().is_some() {
+                    self.en_passant = Some(en_passant_square);
+                }
             }
         }
 
@@ -747,7 +1303,11 @@ impl Board {
         }
     }
 
-    fn unmake_move(&mut self, unmake_data: UnmakeData) {
+    /// Undo a move made with [`Self::make_move`] or [`Self::make_null_move`].
+    ///
+    /// Moves must be undone in the reverse order they were made in, and must
+    /// not be interleaved with moves pushed to the move history.
+    pub fn unmake_move(&mut self, unmake_data: UnmakeData) {
         let UnmakeData {
             the_move,
             castling,
@@ -799,36 +1359,214 @@ impl Display for Board {
     }
 }
 
-#[allow(unused)]
-impl Board {
-    #[cfg(debug_assertions)]
-    pub fn consistency_check(&self) {
-        self.board.consistency_check();
+#[cfg(test)]
+mod test {
+    use crate::test_utils::assert_in_any_order;
 
-        let check = || {
-            let mut zobrist_hash = self.board.zobrist_hash();
-            zobrist_hash ^= Self::non_board_hash(self.to_move, self.castling, self.en_passant);
+    use super::*;
+    use pretty_assertions::assert_eq;
 
-            if zobrist_hash != self.zobrist_hash {
-                return false;
+    fn bitboard_of(squares: &[Square]) -> Bitboard {
+        squares.iter().fold(Bitboard::EMPTY, |acc, &square| {
+            acc | Bitboard::from_square(square)
+        })
+    }
+
+    #[test]
+    fn piece_attacks_knights() {
+        let board = Board::try_parse_fen("4k3/8/8/8/8/2p5/8/1N2K1N1 w - - 0 1").unwrap();
+
+        assert_eq!(
+            board.piece_attacks(Piece::WHITE_KNIGHT),
+            bitboard_of(&[
+                // Knight on b1
+                Square::A3,
+                Square::C3,
+                Square::D2,
+                // Knight on g1
+                Square::E2,
+                Square::F3,
+                Square::H3,
+            ])
+        );
+        assert_eq!(board.piece_attacks(Piece::BLACK_KNIGHT), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn piece_attacks_rooks_stop_at_blockers() {
+        let board = Board::try_parse_fen("4k3/8/8/8/1p6/8/8/1R2K3 w - - 0 1").unwrap();
+
+        assert_eq!(
+            board.piece_attacks(Piece::WHITE_ROOK),
+            bitboard_of(&[
+                Square::A1,
+                Square::C1,
+                Square::D1,
+                // Own king is defended, but not x-rayed through
+                Square::E1,
+                Square::B2,
+                Square::B3,
+                // Enemy pawn is attacked, but not x-rayed through
+                Square::B4,
+            ])
+        );
+    }
+
+    #[test]
+    fn all_attacks_by_type_matches_piece_attacks() {
+        let board = Board::try_parse_fen(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        )
+        .unwrap();
+
+        for color in [Color::White, Color::Black] {
+            let attacks = board.all_attacks_by_type(color);
+            for piece_type in PieceType::ALL {
+                assert_eq!(
+                    attacks[piece_type.index()],
+                    board.piece_attacks(Piece::new(color, piece_type))
+                );
             }
+        }
+    }
 
-            true
-        };
+    #[test]
+    fn legal_quiet_checks_includes_discovered_and_direct_checks() {
+        // The bishop on d3 blocks the rook on d1 from the king on d8
+        let board = Board::try_parse_fen("3k4/8/8/8/8/3B4/8/3RK3 w - - 0 1").unwrap();
+        let quiet_checks = board.legal_quiet_checks();
+
+        // Any bishop move off the d-file is a discovered check
+        let discovered = board.get_move(Square::D3, Square::H7, None).unwrap();
+        assert!(quiet_checks.contains(&discovered));
+
+        // Bishop moves on the d-file aren't possible, and rook moves along the
+        // back rank don't give check
+        let rook_move = board.get_move(Square::D1, Square::C1, None).unwrap();
+        assert!(!quiet_checks.contains(&rook_move));
+
+        for m in quiet_checks.iter() {
+            assert!(!m.is_capture());
+            assert!(m.is_move_of(PieceType::Bishop));
+        }
+        assert_eq!(quiet_checks.len(), 11);
+    }
 
-        assert!(check(), "Board became inconsistent, {self:?}");
+    #[test]
+    fn legal_quiet_checks_excludes_capturing_checks() {
+        // Rxd7+ captures, Rh8+ is quiet
+        let board = Board::try_parse_fen("3k4/3p4/8/8/8/8/8/3RK2R w - - 0 1").unwrap();
+        let quiet_checks = board.legal_quiet_checks();
+
+        let capture = board.get_move(Square::D1, Square::D7, None).unwrap();
+        let quiet = board.get_move(Square::H1, Square::H8, None).unwrap();
+
+        assert!(!quiet_checks.contains(&capture));
+        assert!(quiet_checks.contains(&quiet));
+        assert!(board.gives_check(capture));
     }
 
-    #[cfg(not(debug_assertions))]
-    pub fn consistency_check(&self) {}
-}
+    #[test]
+    fn legal_moves_by_piece_type_partitions_legal_moves() {
+        let board = Board::try_parse_fen(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        )
+        .unwrap();
 
-#[cfg(test)]
-mod test {
-    use crate::test_utils::assert_in_any_order;
+        let groups = board.legal_moves_by_piece_type();
 
-    use super::*;
-    use pretty_assertions::assert_eq;
+        for piece_type in PieceType::ALL {
+            let group = &groups[piece_type.index()];
+            assert!(group.iter().all(|m| m.piece().piece_type() == piece_type));
+        }
+
+        let counts = groups.each_ref().map(|group| group.len());
+        // Pawn, knight, bishop, rook, queen, king
+        assert_eq!(counts, [10, 9, 11, 2, 2, 4]);
+
+        assert_in_any_order(
+            groups.into_iter().flatten().collect::<Vec<_>>(),
+            board.legal_moves().to_vec(),
+        );
+    }
+
+    #[test]
+    fn sorted_legal_moves_orders_by_key() {
+        let board = Board::try_parse_fen("4k3/8/5n2/3q4/2P1N3/8/8/3RK3 w - - 0 1").unwrap();
+
+        let by_san = board.sorted_legal_moves(MoveSortKey::San);
+        let sans = by_san
+            .iter()
+            .map(|&m| board.get_san(m).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert!(sans.is_sorted());
+        assert_in_any_order(by_san.to_vec(), board.legal_moves().to_vec());
+
+        let by_from_square = board.sorted_legal_moves(MoveSortKey::FromSquare);
+        assert!(by_from_square.is_sorted_by_key(|m| (m.from_square(), m.to_square())));
+
+        let by_mvv_lva = board.sorted_legal_moves(MoveSortKey::MvvLva);
+        let sans = by_mvv_lva[..3]
+            .iter()
+            .map(|&m| board.get_san(m).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(sans, ["cxd5", "Rxd5", "Nxf6+"]);
+        assert!(by_mvv_lva[3..].iter().all(|m| !m.is_capture()));
+    }
+
+    #[test]
+    fn move_resolves_check_blocking_and_capturing() {
+        // White king on e1 in check from the rook on e8
+        let board = Board::try_parse_fen("4r1k1/8/8/8/8/8/3R4/4K3 w - - 0 1").unwrap();
+
+        let block = MoveBuilder::new(Square::D2, Square::E2, Piece::WHITE_ROOK).build();
+        let king_steps_aside = MoveBuilder::new(Square::E1, Square::D1, Piece::WHITE_KING).build();
+
+        assert!(board.move_resolves_check(block));
+        assert!(board.move_resolves_check(king_steps_aside));
+    }
+
+    #[test]
+    fn move_resolves_check_ignoring_check() {
+        let board = Board::try_parse_fen("4r1k1/8/8/8/8/8/3R4/4K3 w - - 0 1").unwrap();
+
+        let rook_moves_away = MoveBuilder::new(Square::D2, Square::D7, Piece::WHITE_ROOK).build();
+        let king_stays_on_file =
+            MoveBuilder::new(Square::E1, Square::E2, Piece::WHITE_KING).build();
+
+        assert!(!board.move_resolves_check(rook_moves_away));
+        assert!(!board.move_resolves_check(king_stays_on_file));
+    }
+
+    #[test]
+    fn move_resolves_check_en_passant_capture_of_checker() {
+        let board = Board::try_parse_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+
+        let capture = MoveBuilder::new(Square::E4, Square::D3, Piece::BLACK_PAWN)
+            .captures(Piece::WHITE_PAWN)
+            .is_en_passant()
+            .build();
+
+        assert!(board.move_resolves_check(capture));
+    }
+
+    #[test]
+    fn move_resolves_check_rejects_moves_not_matching_board() {
+        let board = Board::try_parse_fen("4r1k1/8/8/8/8/8/3R4/4K3 w - - 0 1").unwrap();
+
+        // No piece on d3
+        let no_piece = MoveBuilder::new(Square::D3, Square::E3, Piece::WHITE_ROOK).build();
+        // Wrong side to move
+        let wrong_side = MoveBuilder::new(Square::E8, Square::E2, Piece::BLACK_ROOK).build();
+        // Claims to capture a piece that isn't there
+        let bad_capture = MoveBuilder::new(Square::D2, Square::E2, Piece::WHITE_ROOK)
+            .captures(Piece::BLACK_KNIGHT)
+            .build();
+
+        assert!(!board.move_resolves_check(no_piece));
+        assert!(!board.move_resolves_check(wrong_side));
+        assert!(!board.move_resolves_check(bad_capture));
+    }
 
     #[test]
     fn board_default_is_starting_position() {
@@ -1021,6 +1759,28 @@ Plies since last capture or pawn push: 0";
         assert_eq!(result.checker_count, 0);
     }
 
+    #[test]
+    fn legal_moves_into_replaces_buffer_contents() {
+        let board = Board::try_parse_fen("4r1k1/8/8/8/8/8/6P1/4nKn1 w - - 0 1").unwrap();
+
+        // Start from moves of a different position, which must not survive
+        let mut moves = Board::starting_position().legal_moves();
+
+        let result = board.legal_captures_into(&mut moves);
+        assert_in_any_order(
+            moves.clone(),
+            vec![MoveBuilder::new(Square::F1, Square::G1, Piece::WHITE_KING)
+                .captures(Piece::BLACK_KNIGHT)
+                .build()],
+        );
+        assert_eq!(result.checker_count, 0);
+
+        let result = board.legal_moves_into(&mut moves);
+        assert_eq!(moves, board.legal_moves());
+        assert_eq!(result.checker_count, 0);
+    }
+
+    #[allow(clippy::type_complexity)]
     fn assert_sequence_of_legal_moves(
         mut board: Board,
         ops: Vec<(&'static str, Box<dyn Fn(&Board)>)>,
@@ -1079,6 +1839,39 @@ Plies since last capture or pawn push: 0";
         assert!(!board.push_move_repr(m));
     }
 
+    #[test]
+    fn make_and_unmake_move_bypass_move_history() {
+        let mut board = Board::try_parse_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        board.push_uci("a2a4").unwrap();
+        let original = board.clone();
+
+        let mut undo_stack = Vec::new();
+        for uci in ["b4a3", "e1g1", "e8c8", "d5e6", "a3b2", "e6f7"] {
+            let UCIMove {
+                from,
+                to,
+                promotion,
+            } = uci.parse().unwrap();
+            let m = board.get_move(from, to, promotion).unwrap();
+            undo_stack.push(board.make_move(m));
+            assert_eq!(undo_stack.last().unwrap().the_move(), Some(m));
+        }
+        undo_stack.push(board.make_null_move());
+        assert_eq!(undo_stack.last().unwrap().the_move(), None);
+
+        // Only the pushed move is in the history
+        assert_eq!(board.move_history.len(), 1);
+
+        while let Some(unmake_data) = undo_stack.pop() {
+            board.unmake_move(unmake_data);
+        }
+        assert_eq!(board, original);
+        assert_eq!(board.pop_move(), original.last_move());
+    }
+
     #[test]
     fn board_pop_moves_when_no_move_history_returns_none() {
         let mut board = Board::starting_position();
@@ -1308,6 +2101,74 @@ Plies since last capture or pawn push: 0";
         );
     }
 
+    #[test]
+    fn draws_by_fifty_move_rule_can_be_claimed() {
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/4R3/4K3 w - - 100 80").unwrap();
+
+        assert_eq!(board.state(), BoardState::Draw(DrawReason::FiftyMoveRule));
+        assert_eq!(
+            board.state_with_claims(false),
+            BoardState::InPlay { checkers: 0 }
+        );
+        assert_eq!(board.can_claim_draw(), Some(DrawReason::FiftyMoveRule));
+
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/4R3/4K3 w - - 99 80").unwrap();
+        assert_eq!(board.can_claim_draw(), None);
+
+        // Checkmate takes precedence
+        let board = Board::try_parse_fen("R3k3/8/4K3/8/8/8/8/8 b - - 100 80").unwrap();
+        assert_eq!(board.state(), BoardState::Win(Color::White));
+        assert_eq!(board.can_claim_draw(), None);
+    }
+
+    #[test]
+    fn previous_occurrences_counts_plies_back() {
+        let mut board = Board::starting_position();
+        assert_eq!(board.previous_occurrences().count(), 0);
+
+        for _ in 0..2 {
+            for m in ["g1f3", "b8c6", "f3g1", "c6b8"] {
+                board.push_uci(m).unwrap();
+            }
+        }
+        assert_eq!(board.previous_occurrences().collect::<Vec<_>>(), vec![4, 8]);
+        assert!(board.current_position_repeated_at_least(2));
+        assert!(!board.current_position_repeated_at_least(3));
+
+        // A pawn move means no earlier position can repeat
+        board.push_uci("e2e4").unwrap();
+        assert_eq!(board.previous_occurrences().count(), 0);
+    }
+
+    #[test]
+    fn draws_by_repetition_can_be_claimed() {
+        let mut board = Board::starting_position();
+
+        for _ in 0..2 {
+            assert_eq!(board.can_claim_draw(), None);
+            for m in ["g1f3", "b8c6", "f3g1", "c6b8"] {
+                board.push_uci(m).unwrap();
+            }
+        }
+
+        assert_eq!(
+            board.can_claim_draw(),
+            Some(DrawReason::ThreeFoldRepetition)
+        );
+        assert_eq!(
+            board.state_with_claims(false),
+            BoardState::InPlay { checkers: 0 }
+        );
+
+        // Draws that aren't claimable still end the game
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.state_with_claims(false),
+            BoardState::Draw(DrawReason::InsufficientMaterial)
+        );
+        assert_eq!(board.can_claim_draw(), None);
+    }
+
     #[test]
     fn board_reports_draw_by_repetition() {
         let mut board = Board::starting_position();
@@ -1549,4 +2410,350 @@ Plies since last capture or pawn push: 0";
             BoardState::Draw(DrawReason::ThreeFoldRepetition)
         );
     }
+
+    const IS_LEGAL_TEST_FENS: &[&str] = &[
+        STARTING_POSITION_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "8/8/8/KPp4r/8/8/8/6k1 w - c6 0 2",
+        // Chess960 castling
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        "1r4kr/6p1/8/8/8/8/6P1/1RK3R1 w GBhb - 0 1",
+        "4k3/8/8/8/8/8/8/rR2K3 w B - 0 1",
+    ];
+
+    #[test]
+    fn is_legal_matches_legal_move_generation() {
+        let boards: Vec<_> = IS_LEGAL_TEST_FENS
+            .iter()
+            .map(|fen| Board::try_parse_fen(fen).unwrap())
+            .collect();
+
+        // Check the moves from every position against every other position,
+        // so that plenty of illegal moves get tested too
+        for board in &boards {
+            let legal_moves = board.legal_moves();
+
+            for other in &boards {
+                for m in other.legal_moves() {
+                    assert_eq!(
+                        board.is_legal(m),
+                        legal_moves.contains(&m),
+                        "{m:?} in {}",
+                        board.fen()
+                    );
+
+                    if legal_moves.contains(&m) {
+                        assert!(board.is_pseudo_legal(m));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn is_legal_en_passant_discovered_check() {
+        // Capturing en passant would remove both pawns from the fifth rank,
+        // exposing the king to the rook
+        let board = Board::try_parse_fen("8/8/8/KPp4r/8/8/8/6k1 w - c6 0 2").unwrap();
+        let en_passant = Move::builder(Square::B5, Square::C6, Piece::WHITE_PAWN)
+            .captures(Piece::BLACK_PAWN)
+            .is_en_passant()
+            .build();
+
+        assert!(board.is_pseudo_legal(en_passant));
+        assert!(!board.is_legal(en_passant));
+
+        let board = Board::try_parse_fen("8/8/8/1Pp4r/K7/8/8/6k1 w - c6 0 2").unwrap();
+        assert!(board.is_legal(en_passant));
+    }
+
+    #[test]
+    fn chess960_castling_rook_shields_destination() {
+        // The b1 rook blocks the a1 rook from attacking c1, until it castles
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/8/rR2K3 w B - 0 1").unwrap();
+        let queenside = Move::builder(Square::E1, Square::C1, Piece::WHITE_KING)
+            .is_castle()
+            .castling_rook_file(1)
+            .build();
+
+        assert!(board.is_pseudo_legal(queenside));
+        assert!(!board.is_legal(queenside));
+        assert!(!board.legal_moves().contains(&queenside));
+
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/8/1R2K3 w B - 0 1").unwrap();
+        assert!(board.is_legal(queenside));
+        assert!(board.legal_moves().contains(&queenside));
+    }
+
+    #[test]
+    fn get_move_chess960_castling() {
+        let board = Board::try_parse_fen("1r4kr/6p1/8/8/8/8/6P1/1RK3R1 w GBhb - 0 1").unwrap();
+        let kingside = Move::builder(Square::C1, Square::G1, Piece::WHITE_KING)
+            .is_castle()
+            .castling_rook_file(6)
+            .build();
+        let queenside = Move::builder(Square::C1, Square::C1, Piece::WHITE_KING)
+            .is_castle()
+            .castling_rook_file(1)
+            .build();
+
+        assert!(board.legal_moves().contains(&kingside));
+        assert!(board.legal_moves().contains(&queenside));
+
+        // Castles can be given as the king capturing its own rook
+        assert_eq!(board.get_move(Square::C1, Square::G1, None), Some(kingside));
+        assert_eq!(
+            board.get_move(Square::C1, Square::B1, None),
+            Some(queenside)
+        );
+
+        // But an ordinary king move takes priority
+        assert_eq!(
+            board.get_move(Square::C1, Square::D1, None),
+            Some(Move::builder(Square::C1, Square::D1, Piece::WHITE_KING).build())
+        );
+
+        let mut board = board;
+        board.push_move_unchecked(queenside);
+        assert_eq!(board.fen(), "1r4kr/6p1/8/8/8/8/6P1/2KR2R1 b kq - 1 1");
+        assert_eq!(board.consistency_check(), Ok(()));
+    }
+
+    #[test]
+    fn is_legal_castling_through_attacked_square() {
+        let kingside = Move::builder(Square::E1, Square::G1, Piece::WHITE_KING)
+            .is_castle()
+            .build();
+        let queenside = Move::builder(Square::E1, Square::C1, Piece::WHITE_KING)
+            .is_castle()
+            .build();
+
+        // The rooks cover b1 and f1, but only f1 matters
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/1r3r2/R3K2R w KQ - 0 1").unwrap();
+        assert!(board.is_pseudo_legal(kingside));
+        assert!(!board.is_legal(kingside));
+        assert!(board.is_legal(queenside));
+
+        // Can't castle out of check either
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1").unwrap();
+        assert!(!board.is_legal(kingside));
+        assert!(!board.is_legal(queenside));
+
+        // Or without the castling right, or with pieces in the way
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/8/RN2K2R w K - 0 1").unwrap();
+        assert!(board.is_legal(kingside));
+        assert!(!board.is_pseudo_legal(queenside));
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1").unwrap();
+        assert!(!board.is_pseudo_legal(queenside));
+    }
+
+    #[test]
+    fn en_passant_exposes_king_horizontal_pin() {
+        // Capturing removes both pawns between the king and the rook
+        let board = Board::try_parse_fen("8/8/8/KPp4r/8/8/8/6k1 w - c6 0 2").unwrap();
+        assert!(board.en_passant_exposes_king());
+
+        let board = Board::try_parse_fen("8/8/8/8/R2Pp1k1/8/8/6K1 b - d3 0 2").unwrap();
+        assert!(board.en_passant_exposes_king());
+    }
+
+    #[test]
+    fn en_passant_exposes_king_normal_capture() {
+        let board = Board::try_parse_fen("8/8/8/1Pp4r/K7/8/8/6k1 w - c6 0 2").unwrap();
+        assert!(!board.en_passant_exposes_king());
+
+        // The pawn on d5 can still capture, as the one on b5 stays in the way
+        let board = Board::try_parse_fen("8/8/8/KPpP3r/8/8/8/6k1 w - c6 0 2").unwrap();
+        assert!(!board.en_passant_exposes_king());
+    }
+
+    #[test]
+    fn en_passant_exposes_king_without_en_passant() {
+        // No pawn in position to capture
+        let board = Board::try_parse_fen("8/8/8/K1p4r/8/8/8/6k1 w - c6 0 2").unwrap();
+        assert!(!board.en_passant_exposes_king());
+
+        let board = Board::starting_position();
+        assert!(!board.en_passant_exposes_king());
+    }
+
+    #[test]
+    fn attack_maps() {
+        let board = Board::try_parse_fen("4k3/8/8/3p4/8/2N5/8/R3K3 w - - 0 1").unwrap();
+
+        let white_attacks = board.attacks_by(Color::White);
+        assert!(white_attacks.get(Square::D5));
+        assert!(white_attacks.get(Square::A8));
+        assert!(white_attacks.get(Square::D1));
+        assert!(!white_attacks.get(Square::H8));
+
+        let black_attacks = board.attacks_by(Color::Black);
+        assert!(black_attacks.get(Square::C4));
+        assert!(black_attacks.get(Square::E4));
+        assert!(!black_attacks.get(Square::D4));
+
+        assert_eq!(
+            board.attackers_of(Square::D5),
+            (Bitboard::from_square(Square::C3), Bitboard::EMPTY)
+        );
+        assert_eq!(
+            board.attackers_of(Square::E4),
+            (
+                Bitboard::from_square(Square::C3),
+                Bitboard::from_square(Square::D5)
+            )
+        );
+
+        assert!(board.is_square_attacked(Square::E4, Color::White));
+        assert!(board.is_square_attacked(Square::E4, Color::Black));
+        assert!(!board.is_square_attacked(Square::D4, Color::Black));
+        assert!(!board.is_square_attacked(Square::H5, Color::White));
+    }
+
+    #[test]
+    fn diff_after_capture() {
+        let mut board =
+            Board::try_parse_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2")
+                .unwrap();
+        let before = board.clone();
+        board.push_move(Square::E4, Square::D5, None).unwrap();
+
+        assert_eq!(
+            before.diff(&board),
+            vec![
+                (Square::E4, Some(Piece::WHITE_PAWN), None),
+                (Square::D5, Some(Piece::BLACK_PAWN), Some(Piece::WHITE_PAWN)),
+            ]
+        );
+        assert_eq!(
+            board.diff(&before),
+            vec![
+                (Square::E4, None, Some(Piece::WHITE_PAWN)),
+                (Square::D5, Some(Piece::WHITE_PAWN), Some(Piece::BLACK_PAWN)),
+            ]
+        );
+        assert_eq!(board.diff(&board), vec![]);
+    }
+
+    #[test]
+    fn diff_en_passant_and_castling() {
+        let mut board = Board::try_parse_fen("4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 2").unwrap();
+        let before = board.clone();
+        board.push_move(Square::E5, Square::D6, None).unwrap();
+
+        assert_eq!(
+            before.diff(&board),
+            vec![
+                (Square::D5, Some(Piece::BLACK_PAWN), None),
+                (Square::E5, Some(Piece::WHITE_PAWN), None),
+                (Square::D6, None, Some(Piece::WHITE_PAWN)),
+            ]
+        );
+
+        let mut board = before.clone();
+        board.push_move(Square::E1, Square::G1, None).unwrap();
+        assert_eq!(
+            before.diff(&board),
+            vec![
+                (Square::E1, Some(Piece::WHITE_KING), None),
+                (Square::F1, None, Some(Piece::WHITE_ROOK)),
+                (Square::G1, None, Some(Piece::WHITE_KING)),
+                (Square::H1, Some(Piece::WHITE_ROOK), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_target_normal_capture() {
+        let board =
+            Board::try_parse_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2")
+                .unwrap();
+
+        assert_eq!(
+            board.capture_target(Square::E4, Square::D5),
+            Some(Piece::BLACK_PAWN)
+        );
+        // Can't capture with the opponent's pieces or capture our own
+        assert_eq!(board.capture_target(Square::D5, Square::E4), None);
+        assert_eq!(board.capture_target(Square::D1, Square::E2), None);
+    }
+
+    #[test]
+    fn capture_target_en_passant() {
+        let board = Board::try_parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+
+        assert_eq!(
+            board.capture_target(Square::E5, Square::D6),
+            Some(Piece::BLACK_PAWN)
+        );
+        // Pushing the pawn forwards doesn't capture anything
+        assert_eq!(board.capture_target(Square::E5, Square::E6), None);
+    }
+
+    #[test]
+    fn capture_target_quiet_move() {
+        let board = Board::starting_position();
+
+        assert_eq!(board.capture_target(Square::E2, Square::E4), None);
+        assert_eq!(board.capture_target(Square::G1, Square::F3), None);
+        assert_eq!(board.capture_target(Square::E4, Square::E5), None);
+    }
+
+    #[test]
+    fn set_to_move_round_trip_restores_hash() {
+        let mut board = Board::try_parse_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        )
+        .unwrap();
+        let original = board.zobrist_hash();
+
+        board.set_to_move(Color::Black);
+        assert_eq!(board.to_move(), Color::Black);
+        assert_ne!(board.zobrist_hash(), original);
+        assert_eq!(
+            board.zobrist_hash(),
+            Board::try_parse_fen(
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 3"
+            )
+            .unwrap()
+            .zobrist_hash()
+        );
+
+        board.set_to_move(Color::White);
+        assert_eq!(board.zobrist_hash(), original);
+
+        // Setting the side that's already to move changes nothing
+        board.set_to_move(Color::White);
+        assert_eq!(board.zobrist_hash(), original);
+    }
+
+    #[test]
+    fn set_to_move_clears_en_passant() {
+        let mut board = Board::try_parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+
+        // Only clears en passant if the side to move actually changes
+        board.set_to_move(Color::White);
+        assert_eq!(board.en_passant(), Some(Square::D6));
+
+        board.set_to_move(Color::Black);
+        assert_eq!(board.en_passant(), None);
+        assert_eq!(board.fen(), "4k3/8/8/3pP3/8/8/8/4K3 b - - 0 2");
+        assert_eq!(
+            board.zobrist_hash(),
+            Board::try_parse_fen(&board.fen()).unwrap().zobrist_hash()
+        );
+
+        board.set_to_move(Color::White);
+        assert_eq!(
+            board.zobrist_hash(),
+            Board::try_parse_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2")
+                .unwrap()
+                .zobrist_hash()
+        );
+        assert!(board.get_move(Square::E5, Square::D6, None).is_none());
+    }
 }
@@ -6,7 +6,7 @@ use std::{
 use bitflags::bitflags;
 use zerocopy_derive::FromZeros;
 
-use super::{Piece, PieceType, Square};
+use super::{Board, Piece, PieceType, Square};
 
 bitflags! {
     /// Flags representing special kinds of moves that need special handling.
@@ -24,9 +24,9 @@ bitflags! {
         /// Whether this move is the initial double move of a pawn. This is
         /// useful for knowing if en passant is available on the next turn.
         const DOUBLE_PAWN_PUSH = 0b0001 << 24;
-        /// Whether this move is a castle. The move should be a double-step
-        /// king move, and the direction can be determined by the direction the
-        /// king moves in.
+        /// Whether this move is a castle. The king moves to the g-file when
+        /// castling kingside and the c-file when castling queenside. See
+        /// [`Move::castling_rook_squares`] for how the rook is determined.
         const CASTLE           = 0b0010 << 24;
         /// Whether this move is an en passant capture.
         const EN_PASSANT       = 0b0100 << 24;
@@ -47,15 +47,17 @@ bitflags! {
 /// ```txt
 /// 3       2    2    1    1
 /// 1       4    0    6    2      6      0
-/// 0000_0XXX CCCC_MMMM PPPP_TTTTTT_FFFFFF
-///       ^^^ ^^^^ ^^^^ ^^^^ ^^^^^^ ^^^^^^
-///         |    |    |    |      |      |
-///         |    |    |    |      |      +-- from square
-///         |    |    |    |      +--------- to square
-///         |    |    |    +---------------- promoted piece (0 if none)
-///         |    |    +--------------------- moved piece
-///         |    +-------------------------- captured piece (0 if none)
-///         +------------------------------- move flags
+/// 0RRR_RXXX CCCC_MMMM PPPP_TTTTTT_FFFFFF
+///  ^^^ ^^^^ ^^^^ ^^^^ ^^^^ ^^^^^^ ^^^^^^
+///    |    |    |    |    |      |      |
+///    |    |    |    |    |      |      +-- from square
+///    |    |    |    |    |      +--------- to square
+///    |    |    |    |    +---------------- promoted piece (0 if none)
+///    |    |    |    +--------------------- moved piece
+///    |    |    +-------------------------- captured piece (0 if none)
+///    |    +------------------------------- move flags
+///    +------------------------------------ castling rook file + 1 (0 if the
+///                                          rook is in the standard corner)
 /// ```
 ///
 /// This representation allows a move to still be uniquely identifiable in a
@@ -79,6 +81,7 @@ impl Move {
         captured_piece: Option<Piece>,
         promotion: Option<Piece>,
         flags: MoveFlags,
+        castling_rook_file: Option<u8>,
     ) -> Self {
         let from = from.get() as u32;
         let to = (to.get() as u32) << 6;
@@ -93,11 +96,15 @@ impl Move {
         }) << 20;
 
         let flags = flags.bits();
+        let castling_rook_file = (match castling_rook_file {
+            Some(file) => file as u32 + 1,
+            None => 0,
+        }) << 27;
 
         unsafe {
             // Safety: piece cannot be zero, this big OR can't be zero either
             Self(NonZeroU32::new_unchecked(
-                flags | promotion | captured_piece | piece | to | from,
+                castling_rook_file | flags | promotion | captured_piece | piece | to | from,
             ))
         }
     }
@@ -190,17 +197,37 @@ impl Move {
     /// squares of rook involved in the castle. (The `from_square` and
     /// `to_square` of this move are for the king.)
     ///
+    /// The rook starts in the corner on the side the king moves towards,
+    /// unless the move was built with [`MoveBuilder::castling_rook_file`]
+    /// (e.g. in Chess960). It ends up on the f-file after castling kingside,
+    /// or the d-file after castling queenside.
+    ///
     /// Note that this method does not actually check if this move was a castle
     /// for performance reasons. The result of this method called on a
     /// non-castling move is defined but may be unexpected.
     pub const fn castling_rook_squares(self) -> (Square, Square) {
         let (from, to) = (self.from_square(), self.to_square());
 
-        let rook_from =
-            Square::new_unchecked(from.rank(), if from.file() < to.file() { 7 } else { 0 });
-        let rook_to = Square::new_unchecked(from.rank(), (from.file() + to.file()) / 2);
+        let rook_from_file = match self.castling_rook_file() {
+            Some(file) => file,
+            None if from.file() < to.file() => 7,
+            None => 0,
+        };
+        let rook_to_file = if to.file() == 6 { 5 } else { 3 };
 
-        (rook_from, rook_to)
+        (
+            Square::new_unchecked(from.rank(), rook_from_file),
+            Square::new_unchecked(from.rank(), rook_to_file),
+        )
+    }
+
+    /// The file of the rook castled with, if it was set explicitly because
+    /// the rook isn't in the corner the king moves towards.
+    const fn castling_rook_file(self) -> Option<u8> {
+        match self.0.get() >> 27 {
+            0 => None,
+            file => Some(file as u8 - 1),
+        }
     }
 
     /// Returns true if this move is not a pawn move nor a capture.
@@ -220,6 +247,35 @@ impl Move {
         (self.0.get() & 0xFFF) as usize
     }
 
+    /// Returns the lower 16 bits of the move representation (the from and to
+    /// squares, and the promotion piece), which is enough to uniquely identify
+    /// a move in a given position. See [`Self::try_from_u16`] to convert it
+    /// back.
+    ///
+    /// Castles with an explicit rook file are encoded as the king moving to
+    /// the rook's square instead, as in Chess960 the king's destination may
+    /// also be reachable by an ordinary king move.
+    pub const fn to_u16(self) -> u16 {
+        if self.is_castle() && self.castling_rook_file().is_some() {
+            let (rook_from, _) = self.castling_rook_squares();
+            ((self.0.get() & 0xF03F) | ((rook_from.get() as u32) << 6)) as u16
+        } else {
+            (self.0.get() & 0xFFFF) as u16
+        }
+    }
+
+    /// Recovers a move from its [`Self::to_u16`] encoding. The board is needed
+    /// to fill in the moved and captured pieces and the move flags, so this
+    /// returns [`None`] if the encoded move is not legal in this position.
+    pub fn try_from_u16(value: u16, board: &Board) -> Option<Self> {
+        let from = Square::from_index((value & 0x3F) as usize)?;
+
+        board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.from_square() == from && m.to_u16() == value)
+    }
+
     /// Convert this move into a pre-populated [`MoveBuilder`]. Useful for
     /// editing just one aspect of the move.
     pub fn into_builder(self) -> MoveBuilder {
@@ -230,6 +286,7 @@ impl Move {
             captured_piece: self.captured_piece(),
             promotion: self.promotion(),
             flags: self.flags(),
+            castling_rook_file: self.castling_rook_file(),
         }
     }
 }
@@ -290,6 +347,25 @@ impl OptionalMove {
     pub fn as_option_move(self) -> Option<Move> {
         self.0.map(Move)
     }
+
+    /// The raw representation of the move, or 0 if there is no move, e.g.
+    /// for writing to disk. Can be turned back with [`Self::from_bits`].
+    pub fn to_bits(self) -> u32 {
+        self.0.map_or(0, NonZeroU32::get)
+    }
+
+    /// Inverse of [`Self::to_bits`]. Returns `None` if `bits` isn't 0 and
+    /// doesn't encode a moved piece, so corrupt data can't cause a panic
+    /// later.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        match NonZeroU32::new(bits) {
+            None => Some(Self(None)),
+            Some(value) => {
+                Piece::try_from_u8(((bits & 0x0F0000) >> 16) as u8)?;
+                Some(Self(Some(value)))
+            }
+        }
+    }
 }
 
 impl From<Option<Move>> for OptionalMove {
@@ -321,6 +397,7 @@ pub struct MoveBuilder {
     pub captured_piece: Option<Piece>,
     pub promotion: Option<Piece>,
     pub flags: MoveFlags,
+    pub castling_rook_file: Option<u8>,
 }
 
 impl MoveBuilder {
@@ -336,6 +413,7 @@ impl MoveBuilder {
             captured_piece: None,
             promotion: None,
             flags: MoveFlags::empty(),
+            castling_rook_file: None,
         }
     }
 
@@ -379,6 +457,18 @@ impl MoveBuilder {
         }
     }
 
+    /// Sets the file of the rook involved in a castle, for when it doesn't
+    /// start in the corner the king moves towards (e.g. in Chess960).
+    ///
+    /// Setting the file to the corner anyway gives a different [`Move`], so
+    /// only call this if the rook is elsewhere.
+    pub const fn castling_rook_file(self, file: u8) -> Self {
+        Self {
+            castling_rook_file: Some(file),
+            ..self
+        }
+    }
+
     /// Finalises and builds the [`Move`].
     pub const fn build(self) -> Move {
         Move::new(
@@ -388,6 +478,7 @@ impl MoveBuilder {
             self.captured_piece,
             self.promotion,
             self.flags,
+            self.castling_rook_file,
         )
     }
 }
@@ -550,4 +641,72 @@ mod test {
             assert_eq!(*move_case, new_builder);
         }
     }
+
+    #[test]
+    fn move_u16_round_trip() {
+        for fen in [
+            // Castling on both sides
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            // En passant
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",
+            // Promotions, with and without capturing
+            "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/p7/1N2K3 b - - 0 1",
+            // Chess960 castling, where the king can also step onto c1 and g1
+            "1r4kr/6p1/8/8/8/8/6P1/1RK3R1 w GBhb - 0 1",
+        ] {
+            let board = Board::try_parse_fen(fen).unwrap();
+            let legal_moves = board.legal_moves();
+
+            for &m in &legal_moves {
+                assert_eq!(Move::try_from_u16(m.to_u16(), &board), Some(m), "{fen}");
+            }
+
+            let mut encodings: Vec<_> = legal_moves.iter().map(|m| m.to_u16()).collect();
+            encodings.sort();
+            encodings.dedup();
+            assert_eq!(encodings.len(), legal_moves.len(), "{fen}");
+        }
+
+        let board = Board::try_parse_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotions: Vec<_> = board
+            .legal_moves()
+            .into_iter()
+            .filter_map(|m| m.promotion())
+            .collect();
+        assert_eq!(promotions.len(), 8);
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            assert_eq!(
+                promotions
+                    .iter()
+                    .filter(|p| p.piece_type() == piece_type)
+                    .count(),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn move_u16_rejects_illegal_moves() {
+        let board = Board::starting_position();
+
+        assert_eq!(Move::try_from_u16(0, &board), None);
+
+        // e2e5 isn't legal
+        let e2e5 = Move::builder(Square::E2, Square::E5, Piece::WHITE_PAWN).build();
+        assert_eq!(Move::try_from_u16(e2e5.to_u16(), &board), None);
+
+        // e7e5 is only legal for black
+        let e7e5 = Move::builder(Square::E7, Square::E5, Piece::BLACK_PAWN)
+            .is_double_pawn_push()
+            .build();
+        assert_eq!(Move::try_from_u16(e7e5.to_u16(), &board), None);
+    }
 }
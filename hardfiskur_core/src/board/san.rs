@@ -1,6 +1,30 @@
 use std::fmt::{Display, Write};
 
-use super::{Board, Move, PieceType, Square};
+use thiserror::Error;
+
+use super::{Board, Move, Piece, PieceType, Square};
+
+/// Error type returned by [`Board::parse_san`] and [`Board::try_push_san`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    /// The string is not syntactically valid SAN.
+    #[error("`{san}` is not valid SAN")]
+    Malformed { san: String },
+
+    /// The side to move has no piece of the given type (matching the
+    /// disambiguator, if any) on the board.
+    #[error("No {piece_type:?} that could make the move `{san}`")]
+    NoSuchPiece { san: String, piece_type: PieceType },
+
+    /// The move is well-formed, but no legal move matches it.
+    #[error("`{san}` is not a legal move")]
+    Illegal { san: String },
+
+    /// More than one legal move matches, and the SAN does not say which one is
+    /// meant.
+    #[error("`{san}` is ambiguous, could be any of {candidates:?}")]
+    Ambiguous { san: String, candidates: Vec<Move> },
+}
 
 #[derive(Debug, Clone, Copy)]
 enum Disambiguator {
@@ -174,6 +198,188 @@ impl Board {
     }
 }
 
+impl Board {
+    /// Parses a move in [Standard Algebraic Notation
+    /// (SAN)](https://en.wikipedia.org/wiki/Algebraic_notation_(chess)) into a
+    /// legal move in the current position.
+    ///
+    /// Check/checkmate suffixes and annotations (`+`, `#`, `!`, `?`) are
+    /// ignored, and both `O-O` and `0-0` are accepted for castling. If the SAN
+    /// includes `x`, the move must be a capture.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let malformed = || SanError::Malformed {
+            san: san.to_string(),
+        };
+
+        let legal_moves = self.legal_moves();
+
+        let is_long_castle = match trimmed {
+            "O-O" | "0-0" => Some(false),
+            "O-O-O" | "0-0-0" => Some(true),
+            _ => None,
+        };
+        if let Some(is_long) = is_long_castle {
+            let target_file = if is_long { 2 } else { 6 };
+            return legal_moves
+                .into_iter()
+                .find(|m| m.is_castle() && m.to_square().file() == target_file)
+                .ok_or_else(|| SanError::Illegal {
+                    san: san.to_string(),
+                });
+        }
+
+        let parsed = parse_regular_san(trimmed).ok_or_else(malformed)?;
+
+        let color = self.to_move();
+        let has_piece = self
+            .find_piece(Piece::new(color, parsed.piece_type))
+            .any(|square| parsed.matches_from(square));
+        if !has_piece {
+            return Err(SanError::NoSuchPiece {
+                san: san.to_string(),
+                piece_type: parsed.piece_type,
+            });
+        }
+
+        let candidates: Vec<Move> = legal_moves
+            .into_iter()
+            .filter(|m| {
+                !m.is_castle()
+                    && m.piece().piece_type() == parsed.piece_type
+                    && m.to_square() == parsed.to_square
+                    && parsed.matches_from(m.from_square())
+                    && m.promotion().map(|p| p.piece_type()) == parsed.promotion
+                    && (m.is_capture() || !parsed.is_capture)
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [] => Err(SanError::Illegal {
+                san: san.to_string(),
+            }),
+            &[the_move] => Ok(the_move),
+            _ => Err(SanError::Ambiguous {
+                san: san.to_string(),
+                candidates,
+            }),
+        }
+    }
+
+    /// Make a move on the board, specified in SAN. See [`Self::parse_san`] for
+    /// the accepted format.
+    ///
+    /// If the SAN refers to exactly one legal move, it is made on the board and
+    /// returned. Otherwise, the board is left untouched and the reason is
+    /// returned as a [`SanError`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use hardfiskur_core::board::{Board, SanError};
+    /// let mut board = Board::starting_position();
+    /// assert!(board.try_push_san("e4").is_ok());
+    /// assert!(board.try_push_san("e5").is_ok());
+    /// assert!(matches!(board.try_push_san("Ke3"), Err(SanError::Illegal { .. })));
+    /// ```
+    pub fn try_push_san(&mut self, san: &str) -> Result<Move, SanError> {
+        let the_move = self.parse_san(san)?;
+        self.push_move_unchecked(the_move);
+        Ok(the_move)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ParsedSAN {
+    piece_type: PieceType,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    is_capture: bool,
+    to_square: Square,
+    promotion: Option<PieceType>,
+}
+
+impl ParsedSAN {
+    fn matches_from(&self, square: Square) -> bool {
+        self.from_file.is_none_or(|file| square.file() == file)
+            && self.from_rank.is_none_or(|rank| square.rank() == rank)
+    }
+}
+
+fn parse_piece_letter(c: u8) -> Option<PieceType> {
+    match c {
+        b'N' => Some(PieceType::Knight),
+        b'B' => Some(PieceType::Bishop),
+        b'R' => Some(PieceType::Rook),
+        b'Q' => Some(PieceType::Queen),
+        b'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+fn parse_file(c: u8) -> Option<u8> {
+    (b'a'..=b'h').contains(&c).then(|| c - b'a')
+}
+
+fn parse_rank(c: u8) -> Option<u8> {
+    (b'1'..=b'8').contains(&c).then(|| c - b'1')
+}
+
+fn parse_regular_san(san: &str) -> Option<ParsedSAN> {
+    let mut rest = san.as_bytes();
+
+    let piece_type = match rest.first().copied().and_then(parse_piece_letter) {
+        Some(piece_type) => {
+            rest = &rest[1..];
+            piece_type
+        }
+        None => PieceType::Pawn,
+    };
+
+    // Promotion suffix, either `=Q` or just `Q`
+    let mut promotion = None;
+    if let Some((&last, init)) = rest.split_last() {
+        if let Some(promo) = parse_piece_letter(last) {
+            if piece_type != PieceType::Pawn || promo == PieceType::King {
+                return None;
+            }
+            promotion = Some(promo);
+            rest = init.strip_suffix(b"=").unwrap_or(init);
+        }
+    }
+
+    let (init, to) = rest.split_at_checked(rest.len().checked_sub(2)?)?;
+    let to_square = Square::new(parse_rank(to[1])?, parse_file(to[0])?)?;
+    rest = init;
+
+    let is_capture = match rest.strip_suffix(b"x") {
+        Some(init) => {
+            rest = init;
+            true
+        }
+        None => false,
+    };
+
+    let (from_file, from_rank) = match *rest {
+        [] => (None, None),
+        [c] => match (parse_file(c), parse_rank(c)) {
+            (Some(file), _) => (Some(file), None),
+            (_, Some(rank)) => (None, Some(rank)),
+            _ => return None,
+        },
+        [file, rank] => (Some(parse_file(file)?), Some(parse_rank(rank)?)),
+        _ => return None,
+    };
+
+    Some(ParsedSAN {
+        piece_type,
+        from_file,
+        from_rank,
+        is_capture,
+        to_square,
+        promotion,
+    })
+}
+
 fn get_san_disambiguator(the_move: Move, legal_moves: &[Move]) -> Option<Disambiguator> {
     if the_move.piece().is_pawn() && the_move.is_capture() {
         // Pawn captures always need to have the origin file.
@@ -213,6 +419,7 @@ fn get_san_disambiguator(the_move: Move, legal_moves: &[Move]) -> Option<Disambi
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::board::STARTING_POSITION_FEN;
     use pretty_assertions::assert_eq;
 
     fn push_move_and_get_san(
@@ -334,4 +541,93 @@ mod test {
             push_move_and_get_san(&mut board, Square::G3, Square::E5, None);
         assert_eq!(qg3_capture_e5_checkmate.to_string(), "Qg3xe5#")
     }
+
+    #[test]
+    fn push_san_round_trips() {
+        let board = Board::try_parse_fen("4k3/6Q1/8/4p3/8/2Q3Q1/8/3RK3 w Q - 0 1").unwrap();
+
+        let moves = board.legal_moves();
+        for m in moves {
+            let san = board.get_san(m).unwrap().to_string();
+            assert_eq!(board.parse_san(&san), Ok(m), "{san}");
+        }
+
+        let mut board = Board::try_parse_fen("1k6/3P4/8/8/8/8/2p5/3R1K2 w - - 0 1").unwrap();
+        let m = board.try_push_san("d8=N").unwrap();
+        assert_eq!(m.promotion(), Some(Piece::white(PieceType::Knight)));
+        let m = board.try_push_san("cxd1Q+").unwrap();
+        assert_eq!(m.promotion(), Some(Piece::black(PieceType::Queen)));
+
+        let mut board = Board::try_parse_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(board.try_push_san("O-O").unwrap().is_castle());
+        assert!(board.try_push_san("0-0-0").unwrap().is_castle());
+    }
+
+    #[test]
+    fn push_san_ambiguous() {
+        let mut board = Board::try_parse_fen("4k3/8/1n3n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+
+        // Both the knights on b6 and f6 can go to d7
+        let fen = board.fen();
+        assert!(matches!(
+            board.try_push_san("Nd7"),
+            Err(SanError::Ambiguous { candidates, .. }) if candidates.len() == 2
+        ));
+        assert_eq!(board.fen(), fen);
+
+        assert!(board.try_push_san("Nfd7").is_ok());
+    }
+
+    #[test]
+    fn push_san_illegal() {
+        let mut board = Board::starting_position();
+
+        assert_eq!(
+            board.try_push_san("e5"),
+            Err(SanError::Illegal { san: "e5".into() })
+        );
+        assert_eq!(
+            board.try_push_san("Nxf3"),
+            Err(SanError::Illegal { san: "Nxf3".into() })
+        );
+        assert_eq!(
+            board.try_push_san("O-O"),
+            Err(SanError::Illegal { san: "O-O".into() })
+        );
+        assert_eq!(board.fen(), STARTING_POSITION_FEN);
+    }
+
+    #[test]
+    fn push_san_malformed() {
+        let mut board = Board::starting_position();
+
+        for san in ["", "hello", "e9", "Ng1f3f4", "Zf3", "e8=K", "Nf3=Q", "x"] {
+            assert_eq!(
+                board.try_push_san(san),
+                Err(SanError::Malformed { san: san.into() }),
+                "{san}"
+            );
+        }
+    }
+
+    #[test]
+    fn push_san_no_such_piece() {
+        let mut board = Board::try_parse_fen("4k3/8/8/8/8/8/4P3/4K1N1 w - - 0 1").unwrap();
+
+        assert_eq!(
+            board.try_push_san("Qd4"),
+            Err(SanError::NoSuchPiece {
+                san: "Qd4".into(),
+                piece_type: PieceType::Queen
+            })
+        );
+        assert_eq!(
+            board.try_push_san("Nbd2"),
+            Err(SanError::NoSuchPiece {
+                san: "Nbd2".into(),
+                piece_type: PieceType::Knight
+            })
+        );
+        assert!(board.try_push_san("Nf3").is_ok());
+    }
 }
@@ -0,0 +1,266 @@
+use crate::move_gen::{self, lookups::Lookups};
+
+use super::{Bitboard, Board, Color, Move, Piece, PieceType, Square};
+
+/// Piece values in centipawns used by [`Board::see`], indexed by
+/// [`PieceType::index`]. The king has no value as it can never actually be
+/// captured.
+pub const SEE_PIECE_VALUES: [i32; 6] = [100, 300, 300, 500, 900, 0];
+
+fn see_value(piece_type: PieceType) -> i32 {
+    SEE_PIECE_VALUES[piece_type.index()]
+}
+
+impl Board {
+    /// Static exchange evaluation: the net material (in centipawns, see
+    /// [`SEE_PIECE_VALUES`]) that the side to move gains by making `the_move`,
+    /// assuming both sides then keep recapturing on the destination square
+    /// with their least valuable piece for as long as it's profitable.
+    ///
+    /// Pieces revealed behind others as the exchange progresses (x-rays) are
+    /// taken into account, but pins and checks are not. The move is assumed to
+    /// be legal. For a non-capture, the result is 0 if the moved piece is safe
+    /// on its destination square, and negative if it would be lost.
+    ///
+    /// # Example
+    /// ```
+    /// # use hardfiskur_core::board::{Board, Square};
+    /// // The pawn on e5 is defended by the pawn on d6
+    /// let board = Board::try_parse_fen("4k3/8/3p4/4p3/8/8/4Q3/4K3 w - - 0 1").unwrap();
+    /// let qxe5 = board.get_move(Square::E2, Square::E5, None).unwrap();
+    /// assert_eq!(board.see(qxe5), 100 - 900);
+    /// ```
+    pub fn see(&self, the_move: Move) -> i32 {
+        let lookups = Lookups::get_instance();
+        let to = the_move.to_square();
+
+        let mut occupied = self.board.occupied();
+        occupied.reset(the_move.from_square());
+        if the_move.is_en_passant() {
+            occupied.reset(the_move.en_passant_square());
+        }
+
+        // Value of the piece standing on the target square, which is next in
+        // line to be captured
+        let mut piece_on_target = the_move.piece().piece_type();
+
+        // gains[d] is the material balance from the perspective of the side
+        // making the d-th capture, if the exchange stopped right after it
+        let mut gains = [0; 32];
+        gains[0] = the_move
+            .captured_piece()
+            .map_or(0, |p| see_value(p.piece_type()));
+        if let Some(promotion) = the_move.promotion() {
+            piece_on_target = promotion.piece_type();
+            gains[0] += see_value(piece_on_target) - see_value(PieceType::Pawn);
+        }
+
+        let mut side_to_move = self.to_move.flip();
+        let mut depth = 0;
+
+        while depth + 1 < gains.len() {
+            // Recalculating attackers with the updated occupancy reveals any
+            // x-ray attackers behind pieces that already took part
+            let attackers = move_gen::attackers_on(&self.board, occupied, to, lookups) & occupied;
+            let Some((square, attacker)) = self.least_valuable_attacker(attackers, side_to_move)
+            else {
+                break;
+            };
+
+            // The king can only capture if the square is no longer defended
+            if attacker.is_king() && (attackers & self.board[side_to_move.flip()]).has_piece() {
+                break;
+            }
+
+            depth += 1;
+            gains[depth] = see_value(piece_on_target) - gains[depth - 1];
+
+            // Neither side can come out ahead by continuing
+            if (-gains[depth - 1]).max(gains[depth]) < 0 {
+                break;
+            }
+
+            occupied.reset(square);
+            piece_on_target = attacker.piece_type();
+            side_to_move = side_to_move.flip();
+        }
+
+        // Each side can choose to stop capturing if continuing loses material
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+
+        gains[0]
+    }
+
+    fn least_valuable_attacker(
+        &self,
+        attackers: Bitboard,
+        color: Color,
+    ) -> Option<(Square, Piece)> {
+        PieceType::ALL.into_iter().find_map(|piece_type| {
+            let piece = Piece::new(color, piece_type);
+            (attackers & self.board[piece])
+                .to_square()
+                .map(|square| (square, piece))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn see(fen: &str, from: Square, to: Square, promotion: Option<PieceType>) -> i32 {
+        let board = Board::try_parse_fen(fen).unwrap();
+        let the_move = board.get_move(from, to, promotion).unwrap();
+        board.see(the_move)
+    }
+
+    #[test]
+    fn see_hanging_piece() {
+        assert_eq!(
+            see(
+                "4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1",
+                Square::D1,
+                Square::D5,
+                None
+            ),
+            300
+        );
+    }
+
+    #[test]
+    fn see_even_trade() {
+        // NxN, PxN
+        assert_eq!(
+            see(
+                "4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1",
+                Square::E3,
+                Square::D5,
+                None
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn see_losing_capture() {
+        // QxP, PxQ
+        assert_eq!(
+            see(
+                "4k3/8/3p4/4p3/8/8/4Q3/4K3 w - - 0 1",
+                Square::E2,
+                Square::E5,
+                None
+            ),
+            100 - 900
+        );
+    }
+
+    #[test]
+    fn see_xray_battery() {
+        // The rook on e1 backs up the rook on e2: RxP, RxR, RxR
+        let fen = "4r1k1/8/8/4p3/8/8/4R3/4RK2 w - - 0 1";
+        assert_eq!(see(fen, Square::E2, Square::E5, None), 100);
+
+        // Without the back rook, RxP, RxR loses the exchange
+        let fen = "4r1k1/8/8/4p3/8/8/4R3/5K2 w - - 0 1";
+        assert_eq!(see(fen, Square::E2, Square::E5, None), 100 - 500);
+    }
+
+    #[test]
+    fn see_recapture_stops_when_unprofitable() {
+        // After BxN, black shouldn't recapture with the queen as the rook
+        // would take it back
+        assert_eq!(
+            see(
+                "3qk3/8/8/3n4/8/1B6/8/3RK3 w - - 0 1",
+                Square::B3,
+                Square::D5,
+                None
+            ),
+            300
+        );
+    }
+
+    #[test]
+    fn see_king_cannot_capture_defended_piece() {
+        assert_eq!(
+            see(
+                "8/8/8/3k4/8/2Q5/8/3K4 w - - 0 1",
+                Square::C3,
+                Square::D4,
+                None
+            ),
+            -900
+        );
+        assert_eq!(
+            see(
+                "8/8/8/3k4/8/2Q5/8/3RK3 w - - 0 1",
+                Square::C3,
+                Square::D4,
+                None
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn see_quiet_move() {
+        // Moving the knight to a square attacked by a pawn loses it
+        assert_eq!(
+            see(
+                "4k3/8/2p5/8/8/4N3/8/4K3 w - - 0 1",
+                Square::E3,
+                Square::D5,
+                None
+            ),
+            -300
+        );
+        assert_eq!(
+            see(
+                "4k3/8/8/8/8/4N3/8/4K3 w - - 0 1",
+                Square::E3,
+                Square::D5,
+                None
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn see_en_passant_and_promotion() {
+        assert_eq!(
+            see(
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                Square::E5,
+                Square::D6,
+                None
+            ),
+            100
+        );
+        // a8=Q, RxQ
+        assert_eq!(
+            see(
+                "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                Square::A7,
+                Square::A8,
+                Some(PieceType::Queen)
+            ),
+            800 - 900
+        );
+        // axb8=Q is undefended
+        assert_eq!(
+            see(
+                "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                Square::A7,
+                Square::B8,
+                Some(PieceType::Queen)
+            ),
+            500 + 800
+        );
+    }
+}
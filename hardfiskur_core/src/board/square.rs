@@ -7,6 +7,8 @@ use paste::paste;
 use seq_macro::seq;
 use thiserror::Error;
 
+use super::Color;
+
 /// Represents a square on the chessboard.
 ///
 /// Internally, represents a square as an integer from 0-63, ordered by
@@ -326,6 +328,73 @@ impl Square {
         // (56).
         Self(self.0 ^ 0b111000)
     }
+
+    /// Returns this square from the perspective of the given color, i.e.
+    /// unchanged for white and with the rank mirrored for black.
+    ///
+    /// This allows tables indexed by square to be written once from white's
+    /// point of view and shared by both colors.
+    ///
+    /// ```
+    /// # use hardfiskur_core::board::{Color, Square};
+    /// assert_eq!(Square::E2.relative_to(Color::White), Square::E2);
+    /// assert_eq!(Square::E2.relative_to(Color::Black), Square::E7);
+    /// ```
+    pub const fn relative_to(self, color: Color) -> Self {
+        match color {
+            Color::White => self,
+            Color::Black => self.flip(),
+        }
+    }
+
+    /// Returns the rank of this square from the perspective of the given
+    /// color, so that each side's back rank is rank 0.
+    ///
+    /// ```
+    /// # use hardfiskur_core::board::{Color, Square};
+    /// assert_eq!(Square::C7.relative_rank(Color::White), 6);
+    /// assert_eq!(Square::C7.relative_rank(Color::Black), 1);
+    /// ```
+    pub const fn relative_rank(self, color: Color) -> u8 {
+        self.relative_to(color).rank()
+    }
+
+    /// Returns the distance between this square and another, measured in king
+    /// moves.
+    ///
+    /// This is a synonym for [`Self::chebyshev_distance`].
+    ///
+    /// ```
+    /// # use hardfiskur_core::board::Square;
+    /// assert_eq!(Square::B2.distance(Square::F3), 4);
+    /// ```
+    pub fn distance(self, other: Self) -> u8 {
+        self.chebyshev_distance(other)
+    }
+
+    /// Returns the square `file_delta` files and `rank_delta` ranks away from
+    /// this one, or [`None`] if that would be off the board.
+    ///
+    /// Unlike [`Self::offset`], this never wraps around the edges of the
+    /// board.
+    ///
+    /// ```
+    /// # use hardfiskur_core::board::Square;
+    /// assert_eq!(Square::E4.shift(1, 2), Some(Square::F6));
+    /// assert_eq!(Square::E4.shift(-4, -3), Some(Square::A1));
+    /// assert_eq!(Square::H4.shift(1, 0), None);
+    /// assert_eq!(Square::A8.shift(0, 1), None);
+    /// ```
+    pub const fn shift(self, file_delta: i8, rank_delta: i8) -> Option<Self> {
+        let file = self.file() as i16 + file_delta as i16;
+        let rank = self.rank() as i16 + rank_delta as i16;
+
+        if file < 0 || file > 7 || rank < 0 || rank > 7 {
+            None
+        } else {
+            Some(Self::new_unchecked(rank as u8, file as u8))
+        }
+    }
 }
 
 impl Display for Square {
@@ -536,4 +605,51 @@ mod test {
         assert_eq!(Square::A1.offset(-8), Square::A8);
         assert_eq!(Square::H6.offset(9), Square::A8);
     }
+
+    #[test]
+    fn square_relative_to() {
+        for square in Square::all() {
+            assert_eq!(square.relative_to(Color::White), square);
+            assert_eq!(square.relative_to(Color::Black).file(), square.file());
+            assert_eq!(square.relative_rank(Color::Black), 7 - square.rank());
+        }
+
+        assert_eq!(Square::A1.relative_to(Color::Black), Square::A8);
+        assert_eq!(Square::H8.relative_to(Color::Black), Square::H1);
+        assert_eq!(Square::D4.relative_to(Color::Black), Square::D5);
+    }
+
+    #[test]
+    fn square_distance() {
+        assert_eq!(Square::A1.distance(Square::A1), 0);
+        assert_eq!(Square::A1.distance(Square::H8), 7);
+        assert_eq!(Square::A8.distance(Square::H1), 7);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+        assert_eq!(Square::H1.manhattan_distance(Square::A8), 14);
+        assert_eq!(Square::C2.distance(Square::D7), 5);
+        assert_eq!(Square::C2.manhattan_distance(Square::D7), 6);
+    }
+
+    #[test]
+    fn square_shift() {
+        assert_eq!(Square::E4.shift(0, 0), Some(Square::E4));
+        assert_eq!(Square::G1.shift(-1, 2), Some(Square::F3));
+
+        // Corners
+        assert_eq!(Square::A1.shift(7, 7), Some(Square::H8));
+        assert_eq!(Square::H8.shift(-7, -7), Some(Square::A1));
+        assert_eq!(Square::A1.shift(-1, 0), None);
+        assert_eq!(Square::A1.shift(0, -1), None);
+        assert_eq!(Square::H8.shift(1, 0), None);
+        assert_eq!(Square::H8.shift(0, 1), None);
+
+        // Edges don't wrap around
+        assert_eq!(Square::H4.shift(1, 1), None);
+        assert_eq!(Square::A5.shift(-1, 0), None);
+        assert_eq!(Square::E8.shift(0, 1), None);
+
+        // Large deltas don't overflow
+        assert_eq!(Square::H8.shift(i8::MAX, i8::MAX), None);
+        assert_eq!(Square::A1.shift(i8::MIN, i8::MIN), None);
+    }
 }
@@ -98,6 +98,20 @@ impl FromStr for UCIMove {
     }
 }
 
+impl UCIMove {
+    /// Converts a [`Move`] into UCI notation. If `chess960` is set, castles
+    /// are written as the king moving onto its own rook (e.g. `e1h1`), as
+    /// required by the `UCI_Chess960` option, instead of the king's
+    /// destination square (e.g. `e1g1`).
+    pub fn from_move(m: Move, chess960: bool) -> Self {
+        let mut uci_move = Self::from(m);
+        if chess960 && m.is_castle() {
+            uci_move.to = m.castling_rook_squares().0;
+        }
+        uci_move
+    }
+}
+
 impl From<Move> for UCIMove {
     fn from(value: Move) -> Self {
         Self {
@@ -205,4 +219,22 @@ mod test {
         assert_eq!(format!("{}", UCIMove::from_str("b8c6").unwrap()), "b8c6");
         assert_eq!(format!("{}", UCIMove::from_str("e7e8q").unwrap()), "e7e8q");
     }
+
+    #[test]
+    fn from_move_chess960_castling() {
+        let castle = Move::builder(Square::E1, Square::G1, Piece::WHITE_KING)
+            .is_castle()
+            .build();
+        assert_eq!(UCIMove::from_move(castle, false).to_string(), "e1g1");
+        assert_eq!(UCIMove::from_move(castle, true).to_string(), "e1h1");
+
+        let castle = Move::builder(Square::B8, Square::C8, Piece::BLACK_KING)
+            .is_castle()
+            .castling_rook_file(0)
+            .build();
+        assert_eq!(UCIMove::from_move(castle, true).to_string(), "b8a8");
+
+        let e2e4 = Move::builder(Square::E2, Square::E4, Piece::WHITE_PAWN).build();
+        assert_eq!(UCIMove::from_move(e2e4, true).to_string(), "e2e4");
+    }
 }
@@ -9,10 +9,42 @@ use zerocopy_derive::FromZeros;
 
 use super::{Castling, Color, Piece, Square};
 
+/// A 64-bit Zobrist hash of a position, as returned by
+/// [`Board::zobrist_hash`](super::Board::zobrist_hash).
+///
+/// The hash of a position is the XOR of the keys of every piece on its
+/// square, the side to move, the castling rights and the en passant square.
+/// The constructors below give the individual keys, so a hash can be
+/// composed or updated incrementally outside of a [`Board`](super::Board):
+///
+/// ```
+/// # use hardfiskur_core::board::{Board, Castling, Color, Piece, Square, ZobristHash};
+/// let board = Board::starting_position();
+///
+/// let mut hash = ZobristHash::default();
+/// for (piece, square) in board.pieces() {
+///     hash.toggle_piece(piece, square);
+/// }
+/// hash ^= ZobristHash::color(Color::White);
+/// hash ^= ZobristHash::castling(Castling::all());
+/// hash ^= ZobristHash::en_passant(None);
+///
+/// assert_eq!(hash, board.zobrist_hash());
+/// ```
+///
+/// These are the engine's own keys, which are generated from a fixed seed
+/// and are unrelated to the keys used for Polyglot opening books (see
+/// [`crate::polyglot`]).
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, FromZeros)]
 pub struct ZobristHash(pub u64);
 
 impl ZobristHash {
+    /// The raw 64-bit value of the hash.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Key for `piece` standing on `square`.
     pub fn piece(piece: Piece, square: Square) -> Self {
         // TODO: remove bounds check?
         let instance = ZobristTable::get_instance();
@@ -20,6 +52,8 @@ impl ZobristHash {
         Self(instance.pieces[index * 64 + square.index()])
     }
 
+    /// Key for the side to move. This is zero for white, so only positions
+    /// with black to move need to include it.
     pub fn color(color: Color) -> Self {
         let instance = ZobristTable::get_instance();
         match color {
@@ -28,12 +62,17 @@ impl ZobristHash {
         }
     }
 
+    /// Key for a set of castling rights. Each combination of rights has its
+    /// own key, so changing the rights means toggling out the key for the old
+    /// set and toggling in the key for the new one.
     pub fn castling(castling: Castling) -> Self {
         let instance = ZobristTable::get_instance();
         let index = castling.bits() as usize;
         Self(instance.castling[index])
     }
 
+    /// Key for the en passant square, which only depends on its file. This
+    /// is zero if there's no en passant square.
     pub fn en_passant(en_passant: Option<Square>) -> Self {
         let instance = ZobristTable::get_instance();
         match en_passant {
@@ -45,6 +84,8 @@ impl ZobristHash {
         }
     }
 
+    /// Adds `piece` on `square` to the hash, or removes it if it's already
+    /// part of the hash.
     pub fn toggle_piece(&mut self, piece: Piece, square: Square) {
         *self ^= Self::piece(piece, square)
     }
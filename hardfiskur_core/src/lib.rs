@@ -1,6 +1,7 @@
 pub mod board;
 pub mod move_gen;
 pub mod perft;
+pub mod polyglot;
 
 #[cfg(test)]
 mod test_utils;
@@ -136,7 +136,7 @@ fn positive_ray_attacks(
     let attacks = ray_attacks[square.index()][dir as usize];
     let blocker = attacks & occupied;
     let block_square = (blocker | Bitboard(0x8000000000000000)).lsb().unwrap();
-    attacks ^ ray_attacks[block_square as usize][dir as usize]
+    attacks ^ ray_attacks[block_square.index()][dir as usize]
 }
 
 fn negative_ray_attacks(
@@ -148,7 +148,7 @@ fn negative_ray_attacks(
     let attacks = ray_attacks[square.index()][dir as usize];
     let blocker = attacks & occupied;
     let block_square = (blocker | Bitboard(1)).msb().unwrap();
-    attacks ^ ray_attacks[block_square as usize][dir as usize]
+    attacks ^ ray_attacks[block_square.index()][dir as usize]
 }
 
 fn diagonal_attacks(
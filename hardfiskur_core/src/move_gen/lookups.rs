@@ -15,7 +15,8 @@ use super::{
 ///
 /// This struct provides methods for obtaining the attack patterns of knights,
 /// kings, bishops, and rooks given a starting square and blocking pieces, as
-/// well as obtaining the squares in between two other squares.
+/// well as obtaining unblocked rays from a square, the squares in between two
+/// other squares, and the full line through two squares.
 ///
 /// These lookup tables are initialised once at the beginning of the program and
 /// then should not change. The method [`Lookups::get_instance`] is provided to
@@ -24,7 +25,9 @@ use super::{
 pub struct Lookups {
     knight_moves: [Bitboard; 64],
     king_moves: [Bitboard; 64],
+    ray_attacks: [[Bitboard; 8]; 64],
     in_between: [[Bitboard; 64]; 64],
+    line_through: [[Bitboard; 64]; 64],
 
     magic: &'static MagicTables,
 }
@@ -37,13 +40,16 @@ impl Lookups {
         let king_moves = gen_king_moves();
         let ray_attacks = gen_ray_attacks();
         let in_between = gen_in_between(&ray_attacks);
+        let line_through = gen_line_through(&ray_attacks);
 
         let magic = MagicTables::get(&ray_attacks);
 
         Self {
             knight_moves,
             king_moves,
+            ray_attacks,
             in_between,
+            line_through,
 
             magic,
         }
@@ -189,6 +195,79 @@ impl Lookups {
         self.in_between[from.index()][to.index()]
     }
 
+    /// Gets the line through the two squares provided.
+    ///
+    /// If the two squares provided are different and on the same
+    /// rank/file/diagonal, the bitboard returned will contain the whole
+    /// rank/file/diagonal, extending past both squares to the edges of the
+    /// board and including the squares themselves. Otherwise, an empty
+    /// bitboard is returned.
+    ///
+    /// For example, a piece pinned to its king by a slider can only move along
+    /// the line through the king and the pinned piece.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hardfiskur_core::{board::Square, move_gen::lookups::Lookups};
+    /// let lookups = Lookups::get_instance();
+    /// assert_eq!(
+    ///     lookups.get_line_through(Square::C2, Square::E4),
+    ///     "
+    ///         . . . . . . . .
+    ///         . . . . . . . #
+    ///         . . . . . . # .
+    ///         . . . . . # . .
+    ///         . . . . # . . .
+    ///         . . . # . . . .
+    ///         . . # . . . . .
+    ///         . # . . . . . .
+    ///     ".parse().unwrap()
+    /// );
+    /// ```
+    pub fn get_line_through(&self, a: Square, b: Square) -> Bitboard {
+        self.line_through[a.index()][b.index()]
+    }
+
+    /// Gets the ray from the given square in the given [`Direction`], up to
+    /// the edge of the board, ignoring any blocking pieces. The square itself
+    /// is not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hardfiskur_core::{
+    /// #     board::Square,
+    /// #     move_gen::{bitboard_utils::Direction, lookups::Lookups},
+    /// # };
+    /// let lookups = Lookups::get_instance();
+    /// assert_eq!(
+    ///     lookups.get_ray_attacks(Square::C3, Direction::NorthEast),
+    ///     "
+    ///         . . . . . . . #
+    ///         . . . . . . # .
+    ///         . . . . . # . .
+    ///         . . . . # . . .
+    ///         . . . # . . . .
+    ///         . . . . . . . .
+    ///         . . . . . . . .
+    ///         . . . . . . . .
+    ///     ".parse().unwrap()
+    /// );
+    /// ```
+    #[inline]
+    pub fn get_ray_attacks(&self, square: Square, direction: Direction) -> Bitboard {
+        self.ray_attacks[square.index()][direction as usize]
+    }
+
+    /// Returns the full ray attack table, which can be indexed by square and
+    /// then by [`Direction`]. This is the same table as returned by
+    /// [`gen_ray_attacks`], and can be passed to the functions in
+    /// [`super::bitboard_utils`] that take one.
+    pub fn ray_attack_table(&self) -> &[[Bitboard; 8]; 64] {
+        &self.ray_attacks
+    }
+
     /// Returns the internal [`MagicTables`] instance for debugging purposes.
     ///
     /// This should not be used by the program normally but may be helpful in
@@ -250,6 +329,7 @@ pub fn gen_ray_attacks() -> [[Bitboard; 8]; 64] {
 ///
 /// `ray_attacks` should be a valid ray attack table which is generated by
 /// [`gen_ray_attacks`].
+#[allow(clippy::needless_range_loop)]
 pub fn gen_in_between(ray_attacks: &[[Bitboard; 8]; 64]) -> [[Bitboard; 64]; 64] {
     let mut table = [[Bitboard::default(); 64]; 64];
 
@@ -268,6 +348,35 @@ pub fn gen_in_between(ray_attacks: &[[Bitboard; 8]; 64]) -> [[Bitboard; 64]; 64]
     table
 }
 
+/// Generates a table of lines through pairs of squares.
+///
+/// The resulting table can be indexed by two squares, to retrieve the whole
+/// rank/file/diagonal passing through both, including the squares themselves.
+/// If the squares are the same or are not on the same rank/file/diagonal, then
+/// an empty bitboard is returned.
+///
+/// `ray_attacks` should be a valid ray attack table which is generated by
+/// [`gen_ray_attacks`].
+#[allow(clippy::needless_range_loop)]
+pub fn gen_line_through(ray_attacks: &[[Bitboard; 8]; 64]) -> [[Bitboard; 64]; 64] {
+    let mut table = [[Bitboard::default(); 64]; 64];
+
+    for from in 0..64 {
+        for dir in 0..4 {
+            // Directions 4-7 are the opposites of directions 0-3
+            let line =
+                ray_attacks[from][dir] | ray_attacks[from][dir + 4] | Bitboard::from_index(from);
+            for to in ray_attacks[from][dir].bits() {
+                let to = to as usize;
+                table[from][to] = line;
+                table[to][from] = line;
+            }
+        }
+    }
+
+    table
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -482,4 +591,102 @@ mod test {
             Bitboard::EMPTY
         );
     }
+
+    #[test]
+    fn lookups_get_line_through() {
+        let lookups = Lookups::get_instance();
+
+        let file = "
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+                . # . . . . . .
+            "
+        .parse()
+        .unwrap();
+        assert_eq!(lookups.get_line_through(Square::B4, Square::B6), file);
+        assert_eq!(lookups.get_line_through(Square::B6, Square::B4), file);
+
+        assert_eq!(
+            lookups.get_line_through(Square::G2, Square::C6),
+            "
+                # . . . . . . .
+                . # . . . . . .
+                . . # . . . . .
+                . . . # . . . .
+                . . . . # . . .
+                . . . . . # . .
+                . . . . . . # .
+                . . . . . . . #
+            "
+            .parse()
+            .unwrap()
+        );
+
+        // Adjacent squares still have a line through them
+        assert_eq!(
+            lookups.get_line_through(Square::D1, Square::E1),
+            Bitboard::RANK_1
+        );
+
+        // Not on the same rank/file/diagonal
+        assert_eq!(
+            lookups.get_line_through(Square::C3, Square::G1),
+            Bitboard::EMPTY
+        );
+        assert_eq!(
+            lookups.get_line_through(Square::A1, Square::B3),
+            Bitboard::EMPTY
+        );
+        assert_eq!(
+            lookups.get_line_through(Square::E4, Square::E4),
+            Bitboard::EMPTY
+        );
+    }
+
+    #[test]
+    fn lookups_line_through_contains_in_between() {
+        let lookups = Lookups::get_instance();
+
+        for a in Square::all() {
+            for b in Square::all() {
+                let line = lookups.get_line_through(a, b);
+                let in_between = lookups.get_in_between(a, b);
+
+                assert_eq!(line & in_between, in_between);
+                if line.has_piece() {
+                    assert!(line.get(a) && line.get(b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_get_ray_attacks() {
+        let lookups = Lookups::get_instance();
+
+        assert_eq!(
+            lookups.get_ray_attacks(Square::E4, Direction::South),
+            "
+                . . . . . . . .
+                . . . . . . . .
+                . . . . . . . .
+                . . . . . . . .
+                . . . . . . . .
+                . . . . # . . .
+                . . . . # . . .
+                . . . . # . . .
+            "
+            .parse()
+            .unwrap()
+        );
+        assert_eq!(
+            lookups.get_ray_attacks(Square::H4, Direction::East),
+            Bitboard::EMPTY
+        );
+    }
 }
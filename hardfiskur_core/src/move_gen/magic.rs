@@ -259,7 +259,7 @@ impl MagicTableEntryIdx {
         };
 
         let prev_end = table_buffer.len();
-        table_buffer.extend(std::iter::repeat(Bitboard::EMPTY).take(table_size));
+        table_buffer.extend(std::iter::repeat_n(Bitboard::EMPTY, table_size));
         let attack_table = &mut table_buffer[prev_end..];
 
         for i in 0..table_size {
@@ -3,7 +3,9 @@
 use arrayvec::ArrayVec;
 use bitflags::bitflags;
 
-use crate::board::{Bitboard, BoardRepr, Castling, Color, Move, Piece, PieceType, Square};
+use crate::board::{
+    Bitboard, BoardRepr, Castling, CastlingRooks, Color, Move, Piece, PieceType, Square,
+};
 
 use lookups::Lookups;
 
@@ -47,6 +49,33 @@ impl Default for MoveGenFlags {
     }
 }
 
+/// Stages of staged move generation, in the order that
+/// [`MoveGenerator::next_stage`] generates them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveGenStage {
+    /// Captures, including en passant and capturing promotions.
+    Captures,
+    /// All other moves, including quiet promotions and castling.
+    Quiets,
+}
+
+impl MoveGenStage {
+    /// The flags that generate exactly the moves in this stage.
+    pub const fn flags(self) -> MoveGenFlags {
+        match self {
+            MoveGenStage::Captures => MoveGenFlags::GEN_CAPTURES,
+            MoveGenStage::Quiets => MoveGenFlags::GEN_QUIET_MOVES,
+        }
+    }
+
+    const fn next(self) -> Option<Self> {
+        match self {
+            MoveGenStage::Captures => Some(MoveGenStage::Quiets),
+            MoveGenStage::Quiets => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct MoveGenResult {
     pub checker_count: u32,
@@ -116,7 +145,16 @@ pub struct MoveGenerator<'board, 'moves> {
     occupied: Bitboard,
     en_passant: Option<Square>,
     castling: Castling,
+    castling_rooks: CastlingRooks,
     flags: MoveGenFlags,
+    next_stage: Option<MoveGenStage>,
+
+    /// Squares an en passant capture may land on, in addition to the push
+    /// mask. Since the push mask is emptied when quiet moves aren't being
+    /// generated, this keeps track of the squares that resolve a check (and
+    /// stay along a pin) so that en passant captures can still be generated
+    /// with [`MoveGenFlags::GEN_CAPTURES`] only.
+    en_passant_push_mask: Bitboard,
 
     en_passant_possible: bool,
     out_moves: &'moves mut MoveVec,
@@ -139,13 +177,52 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
             occupied: board.occupied(),
             en_passant,
             castling,
+            castling_rooks: CastlingRooks::STANDARD,
             flags,
+            next_stage: Some(MoveGenStage::Captures),
+
+            en_passant_push_mask: Bitboard::EMPTY,
 
             en_passant_possible: false,
             out_moves,
         }
     }
 
+    /// Sets the files of the rooks each side castles with, for Chess960
+    /// positions. Defaults to [`CastlingRooks::STANDARD`].
+    pub fn with_castling_rooks(self, castling_rooks: CastlingRooks) -> Self {
+        Self {
+            castling_rooks,
+            ..self
+        }
+    }
+
+    /// Generates the next stage of legal moves, appending them to the output
+    /// moves, and returns which stage was generated. Stages not included in
+    /// the flags this generator was created with are skipped. Returns [`None`]
+    /// once all stages have been generated.
+    ///
+    /// This allows a caller to e.g. try captures first, and only pay for
+    /// generating quiet moves if none of the captures are good enough.
+    /// Generating every stage produces the same moves as [`Self::legal_moves`].
+    pub fn next_stage(&mut self) -> Option<(MoveGenStage, MoveGenResult)> {
+        let all_flags = self.flags;
+
+        while let Some(stage) = self.next_stage {
+            self.next_stage = stage.next();
+
+            if all_flags.contains(stage.flags()) {
+                self.flags = stage.flags();
+                let result = self.legal_moves();
+                self.flags = all_flags;
+
+                return Some((stage, result));
+            }
+        }
+
+        None
+    }
+
     pub fn legal_moves(&mut self) -> MoveGenResult {
         let mut push_mask = if self.flags.contains(MoveGenFlags::GEN_QUIET_MOVES) {
             Bitboard::ALL
@@ -166,6 +243,9 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
 
         let king_danger_squares = self.king_danger_squares(king_bb);
 
+        // Like the push mask, but not emptied by the flags
+        let mut block_mask = Bitboard::ALL;
+
         // Can always generate legal moves for kings
         self.legal_king_moves(king, king_danger_squares, push_mask, capture_mask);
 
@@ -199,14 +279,17 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
                 .piece_with_color_at(self.to_move.flip(), checker_square)
                 .unwrap();
 
-            push_mask &= if checker.is_slider() {
+            block_mask = if checker.is_slider() {
                 self.lookups.get_in_between(king, checker_square)
             } else {
                 // Blocking not possible, set push mask to empty
                 Bitboard::EMPTY
             };
+            push_mask &= block_mask;
         }
 
+        self.en_passant_push_mask = block_mask;
+
         // Find and generate moves for absolutely pinned pieces
         let pinned_pieces =
             self.find_and_gen_moves_for_pinned_pieces(king, push_mask, capture_mask);
@@ -395,7 +478,10 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
                     // capture the pinner...
                     self.pseudo_legal_pawn_captures(&special_pin_masks);
                     // ...or move towards the pinner in an en passant capture
+                    let block_mask = self.en_passant_push_mask;
+                    self.en_passant_push_mask &= in_between ^ pinned;
                     self.pseudo_legal_en_passants(&special_pin_masks);
+                    self.en_passant_push_mask = block_mask;
                 }
                 // If the pinned piece is a piece that can move diagonally, it
                 // can move to all the spaces between the pinner and the king,
@@ -440,11 +526,8 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
         let can_castle_kingside = (self.castling & castle_mask).intersects(Castling::KINGSIDE);
         let can_castle_queenside = (self.castling & castle_mask).intersects(Castling::QUEENSIDE);
 
-        let mut try_castle = |pass_through_file: u8, to_file: u8, rook_start_file: u8| {
-            let from = king_square;
-            let rook_square = Square::new_unchecked(from.rank(), rook_start_file);
-            let pass_through_square = Square::new_unchecked(from.rank(), pass_through_file);
-            let to = Square::new_unchecked(from.rank(), to_file);
+        let mut try_castle = |rook_start_file: u8, kingside: bool| {
+            let rook_square = Square::new_unchecked(king_square.rank(), rook_start_file);
 
             let rook = PieceType::Rook.with_color(self.to_move);
             // Check there actually is a rook to castle with
@@ -452,33 +535,91 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
                 return;
             }
 
-            let in_between = self.lookups.get_in_between(king_square, rook_square);
-            // Can't castle if there are pieces between the king and rook
-            if (in_between & self.occupied).has_piece() {
+            let path = CastlingPath::new(king_square, rook_square, kingside, self.to_move);
+
+            // Can't castle if there are pieces in the way of the king or rook
+            if (path.must_be_empty & self.occupied).has_piece() {
                 return;
             }
 
             // Can't castle if the king moves through or ends in check
-            let risk_squares =
-                Bitboard::from_square(pass_through_square) | Bitboard::from_square(to);
-            if (risk_squares & king_danger_squares).has_piece() {
+            if (path.king_path & king_danger_squares).has_piece() {
                 return;
             }
 
+            // In Chess960, the castling rook may be shielding the king's
+            // destination from an attacker further along the back rank
+            if !matches!(rook_start_file, 0 | 7) {
+                let occupied = self.occupied
+                    ^ Bitboard::from_square(king_square)
+                    ^ Bitboard::from_square(rook_square);
+                let attackers = attackers_on(self.board, occupied, path.king_to, self.lookups);
+                if (attackers & self.board[self.to_move.flip()]).has_piece() {
+                    return;
+                }
+            }
+
             // Castling is possible
-            self.out_moves.push(
-                Move::builder(from, to, PieceType::King.with_color(self.to_move))
-                    .is_castle()
-                    .build(),
-            );
+            self.out_moves.push(path.the_move);
         };
 
         if can_castle_kingside {
-            try_castle(5, 6, 7);
+            try_castle(self.castling_rooks.kingside(self.to_move), true);
         }
 
         if can_castle_queenside {
-            try_castle(3, 2, 0);
+            try_castle(self.castling_rooks.queenside(self.to_move), false);
+        }
+    }
+}
+
+/// The squares involved in castling with a particular rook, in both standard
+/// chess and Chess960.
+pub(crate) struct CastlingPath {
+    /// The castling move itself.
+    pub the_move: Move,
+    /// Where the king ends up.
+    pub king_to: Square,
+    /// The squares the king passes through or lands on, excluding its
+    /// starting square. None of these may be attacked.
+    pub king_path: Bitboard,
+    /// The squares that must be empty for the castle to be possible, i.e.
+    /// everything the king and rook pass through or land on, other than the
+    /// king and rook themselves.
+    pub must_be_empty: Bitboard,
+}
+
+impl CastlingPath {
+    pub fn new(king: Square, rook: Square, kingside: bool, color: Color) -> Self {
+        let lookups = Lookups::get_instance();
+        let rank = king.rank();
+        let (king_to_file, rook_to_file) = if kingside { (6, 5) } else { (2, 3) };
+        let king_to = Square::new_unchecked(rank, king_to_file);
+        let rook_to = Square::new_unchecked(rank, rook_to_file);
+
+        let king_path = if king == king_to {
+            Bitboard::EMPTY
+        } else {
+            lookups.get_in_between(king, king_to) | Bitboard::from_square(king_to)
+        };
+        let rook_path = lookups.get_in_between(rook, rook_to) | Bitboard::from_square(rook_to);
+        let must_be_empty =
+            (king_path | rook_path) & !Bitboard::from_square(king) & !Bitboard::from_square(rook);
+
+        // Only record the rook's file if it can't be inferred from the
+        // direction the king moves in, so standard castles are unaffected
+        let inferred_file = if king.file() < king_to_file { 7 } else { 0 };
+        let mut the_move =
+            Move::builder(king, king_to, PieceType::King.with_color(color)).is_castle();
+        if rook.file() != inferred_file {
+            the_move = the_move.castling_rook_file(rook.file());
+        }
+
+        Self {
+            the_move: the_move.build(),
+            king_to,
+            king_path,
+            must_be_empty,
         }
     }
 }
@@ -820,6 +961,124 @@ mod test {
         )
     }
 
+    #[test]
+    fn pinned_pawns_cant_push_past_pinner() {
+        let board = "
+            ....k...
+            ........
+            ........
+            ........
+            ....r...
+            ........
+            ....P...
+            ....K...
+        "
+        .parse()
+        .unwrap();
+
+        let moves = legal_moves_default_settings(&board, Color::White);
+
+        // The double push would land on the pinner, which a push can't capture
+        assert_in_any_order(
+            moves.into_iter().filter(|m| m.piece() == Piece::WHITE_PAWN),
+            vec![Move::builder(Square::E2, Square::E3, Piece::WHITE_PAWN).build()],
+        )
+    }
+
+    #[test]
+    fn pinned_pawns_cant_block_check() {
+        let board = "
+            ....q...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....P...
+            r...K..k
+        "
+        .parse()
+        .unwrap();
+
+        let moves = legal_moves_default_settings(&board, Color::White);
+
+        assert_in_any_order(
+            moves.into_iter().filter(|m| m.piece() == Piece::WHITE_PAWN),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn double_push_blocks_check_only_if_path_is_clear() {
+        // The landing square blocks the check, but the square in between is
+        // occupied
+        let board = "
+            ....k...
+            ........
+            ........
+            ........
+            K......r
+            ...NN...
+            ...PP...
+            ........
+        "
+        .parse()
+        .unwrap();
+
+        let moves = legal_moves_default_settings(&board, Color::White);
+
+        assert_in_any_order(
+            moves.into_iter().filter(|m| m.piece() == Piece::WHITE_PAWN),
+            vec![],
+        );
+
+        // Same for black, with one pawn free to block
+        let board = "
+            ........
+            ...pp...
+            ....n...
+            k......R
+            ........
+            ........
+            ........
+            ....K...
+        "
+        .parse()
+        .unwrap();
+
+        let moves = legal_moves_default_settings(&board, Color::Black);
+
+        assert_in_any_order(
+            moves.into_iter().filter(|m| m.piece() == Piece::BLACK_PAWN),
+            vec![Move::builder(Square::D7, Square::D5, Piece::BLACK_PAWN)
+                .is_double_pawn_push()
+                .build()],
+        );
+    }
+
+    #[test]
+    fn single_push_blocks_check_without_double_push() {
+        let board = "
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            K......r
+            ....P...
+            ........
+        "
+        .parse()
+        .unwrap();
+
+        let moves = legal_moves_default_settings(&board, Color::White);
+
+        assert_in_any_order(
+            moves.into_iter().filter(|m| m.piece() == Piece::WHITE_PAWN),
+            vec![Move::builder(Square::E2, Square::E3, Piece::WHITE_PAWN).build()],
+        )
+    }
+
     #[test]
     fn orthogonally_pinned_sliders() {
         let board = "
@@ -1207,4 +1466,61 @@ mod test {
 
         assert_in_any_order(moves.into_iter().filter(|m| m.is_castle()), vec![]);
     }
+
+    #[test]
+    fn staged_generation_matches_legal_moves() {
+        let fens = [
+            crate::board::STARTING_POSITION_FEN,
+            // Castling, promotions and en passant all available
+            "r3k2r/1P6/8/3pP3/8/8/6p1/R3K2R w KQkq d6 0 1",
+            // In check
+            "4k3/8/8/8/1b6/8/8/R3K3 w Q - 0 1",
+        ];
+
+        for fen in fens {
+            let board = crate::board::Board::try_parse_fen(fen).unwrap();
+
+            let mut expected = MoveVec::new();
+            board.legal_moves_ex(MoveGenFlags::all(), &mut expected);
+
+            let mut moves = MoveVec::new();
+            let mut move_gen = MoveGenerator::new(
+                board.repr(),
+                board.to_move(),
+                board.en_passant(),
+                board.castling(),
+                MoveGenFlags::all(),
+                &mut moves,
+            );
+
+            let (stage, _) = move_gen.next_stage().unwrap();
+            assert_eq!(stage, MoveGenStage::Captures);
+            let (stage, _) = move_gen.next_stage().unwrap();
+            assert_eq!(stage, MoveGenStage::Quiets);
+            assert!(move_gen.next_stage().is_none());
+
+            let capture_count = moves.iter().take_while(|m| m.is_capture()).count();
+            assert!(moves[capture_count..].iter().all(|m| !m.is_capture()));
+            assert_in_any_order(moves, expected);
+        }
+    }
+
+    #[test]
+    fn staged_generation_skips_stages_not_in_flags() {
+        let board = crate::board::Board::starting_position();
+        let mut moves = MoveVec::new();
+        let mut move_gen = MoveGenerator::new(
+            board.repr(),
+            board.to_move(),
+            None,
+            board.castling(),
+            MoveGenFlags::GEN_QUIET_MOVES,
+            &mut moves,
+        );
+
+        let (stage, _) = move_gen.next_stage().unwrap();
+        assert_eq!(stage, MoveGenStage::Quiets);
+        assert!(move_gen.next_stage().is_none());
+        assert_eq!(moves.len(), 20);
+    }
 }
@@ -97,7 +97,7 @@ mod test {
     ) -> MoveVec {
         let mut moves = MoveVec::new();
         let mut move_gen = MoveGenerator::new(
-            &board,
+            board,
             color,
             None,
             Default::default(),
@@ -342,7 +342,7 @@ mod test {
     }
 
     fn white_moves() -> Vec<Move> {
-        vec![
+        [
             white_pawn_moves(),
             white_knight_moves(),
             white_bishop_moves(),
@@ -353,7 +353,7 @@ mod test {
     }
 
     fn black_moves() -> Vec<Move> {
-        vec![
+        [
             black_pawn_moves(),
             black_knight_moves(),
             black_bishop_moves(),
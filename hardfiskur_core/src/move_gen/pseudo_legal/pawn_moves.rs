@@ -1,6 +1,6 @@
 use crate::{
     board::{Bitboard, Color, Move, PieceType, Square},
-    move_gen::{MoveGenMasks, MoveGenerator, POSSIBLE_PROMOTIONS},
+    move_gen::{MoveGenFlags, MoveGenMasks, MoveGenerator, POSSIBLE_PROMOTIONS},
 };
 
 impl<'board, 'moves> MoveGenerator<'board, 'moves> {
@@ -100,6 +100,12 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
     }
 
     pub(in crate::move_gen) fn pseudo_legal_en_passants(&mut self, masks: &MoveGenMasks) {
+        // En passant is a capture, even though it lands on an empty square --
+        // don't generate it alongside the quiet moves
+        if !self.flags.contains(MoveGenFlags::GEN_CAPTURES) {
+            return;
+        }
+
         let en_passant = match self.en_passant {
             Some(en_passant) => en_passant,
             None => return,
@@ -132,7 +138,7 @@ impl<'board, 'moves> MoveGenerator<'board, 'moves> {
 
             // Will this en passant push the pawn into a position to block a
             // check, if there is one?
-            if (en_passant_bb & masks.push).has_piece() {
+            if (en_passant_bb & (masks.push | self.en_passant_push_mask)).has_piece() {
                 // Fine -- either there is no check or the pawn is pushed into a
                 // position to block the check
             } else {
@@ -293,7 +299,7 @@ mod test {
     ) -> MoveVec {
         let mut moves = MoveVec::new();
         let mut move_gen = MoveGenerator::new(
-            &board,
+            board,
             color,
             None,
             Default::default(),
@@ -385,7 +391,7 @@ mod test {
     }
 
     fn expected_white_pawn_moves() -> Vec<Move> {
-        vec![
+        [
             expected_white_pawn_pushes(),
             expected_white_pawn_captures(),
             expected_white_pawn_en_passants(),
@@ -471,7 +477,7 @@ mod test {
     }
 
     fn expected_black_pawn_moves() -> Vec<Move> {
-        vec![
+        [
             expected_black_pawn_pushes(),
             expected_black_pawn_captures(),
             expected_black_pawn_en_passants(),
@@ -1,4 +1,9 @@
-use crate::{board::Board, move_gen::MoveVec};
+use std::{num::NonZeroUsize, ops::AddAssign};
+
+use crate::{
+    board::{Board, ZobristHash},
+    move_gen::MoveVec,
+};
 
 pub fn perft(board: &mut Board, depth: usize) -> u64 {
     if depth == 0 {
@@ -22,6 +27,145 @@ pub fn perft(board: &mut Board, depth: usize) -> u64 {
     nodes
 }
 
+/// Leaf node counts returned by [`perft_detailed`], broken down by the move
+/// played to reach each leaf and the resulting position, as in the standard
+/// perft tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PerftCounts {
+    pub nodes: u64,
+    /// Captures, including en passant captures.
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    /// Leaves where the side to move is in check, including checkmates.
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+impl AddAssign for PerftCounts {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.captures += rhs.captures;
+        self.en_passants += rhs.en_passants;
+        self.castles += rhs.castles;
+        self.promotions += rhs.promotions;
+        self.checks += rhs.checks;
+        self.checkmates += rhs.checkmates;
+    }
+}
+
+/// Like [`perft`], but also counts the kinds of moves leading to the leaf
+/// nodes, and how many leaves are checks and checkmates.
+///
+/// This has to make every move at the last ply to test for check, so it's
+/// considerably slower than [`perft`].
+pub fn perft_detailed(board: &mut Board, depth: usize) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+
+    if depth == 0 {
+        counts.nodes = 1;
+        return counts;
+    }
+
+    let mut moves = MoveVec::new();
+    board.legal_moves_ex(Default::default(), &mut moves);
+
+    for m in moves.into_iter() {
+        board.push_move_unchecked(m);
+
+        if depth == 1 {
+            counts.nodes += 1;
+            counts.captures += m.is_capture() as u64;
+            counts.en_passants += m.is_en_passant() as u64;
+            counts.castles += m.is_castle() as u64;
+            counts.promotions += m.promotion().is_some() as u64;
+
+            let (replies, result) = board.legal_moves_and_meta();
+            if result.checker_count > 0 {
+                counts.checks += 1;
+                counts.checkmates += replies.is_empty() as u64;
+            }
+        } else {
+            counts += perft_detailed(board, depth - 1);
+        }
+
+        board.pop_move().unwrap();
+    }
+
+    counts
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PerftEntry {
+    hash: ZobristHash,
+    depth: usize,
+    nodes: u64,
+}
+
+/// Cache of perft results keyed by position and depth, for use with
+/// [`perft_hashed`].
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+impl PerftTable {
+    pub fn new(size_in_mb: NonZeroUsize) -> Self {
+        const BYTES_PER_MB: usize = 1024 * 1024;
+        let num_entries = size_in_mb.get() * BYTES_PER_MB / size_of::<PerftEntry>();
+
+        Self {
+            entries: vec![PerftEntry::default(); num_entries],
+        }
+    }
+
+    fn index(&self, hash: ZobristHash) -> usize {
+        (hash.0 % self.entries.len() as u64) as usize
+    }
+
+    fn get(&self, hash: ZobristHash, depth: usize) -> Option<u64> {
+        let entry = self.entries[self.index(hash)];
+        // Depth 0 is never stored, so zeroed entries never match
+        (entry.depth == depth && entry.hash == hash).then_some(entry.nodes)
+    }
+
+    fn set(&mut self, hash: ZobristHash, depth: usize, nodes: u64) {
+        let index = self.index(hash);
+        self.entries[index] = PerftEntry { hash, depth, nodes };
+    }
+}
+
+/// Like [`perft`], but caches the node counts of subtrees in `table`, so that
+/// transpositions are only counted once. Much faster at higher depths.
+pub fn perft_hashed(board: &mut Board, depth: usize, table: &mut PerftTable) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let hash = board.zobrist_hash();
+    if let Some(nodes) = table.get(hash, depth) {
+        return nodes;
+    }
+
+    let mut moves = MoveVec::new();
+    board.legal_moves_ex(Default::default(), &mut moves);
+
+    let nodes = if depth == 1 {
+        moves.len() as _
+    } else {
+        let mut nodes = 0;
+        for m in moves.into_iter() {
+            board.push_move_unchecked(m);
+            nodes += perft_hashed(board, depth - 1, table);
+            board.pop_move().unwrap();
+        }
+        nodes
+    };
+
+    table.set(hash, depth, nodes);
+    nodes
+}
+
 #[cfg(test)]
 mod test {
     use crate::board::STARTING_POSITION_FEN;
@@ -51,6 +195,14 @@ mod test {
         }
 
         assert_eq!(got, expected_nodes);
+
+        // A single table shared across depths must not mix up results
+        let mut table = PerftTable::new(1.try_into().unwrap());
+        let got_hashed: Vec<_> = (0..expected_nodes.len())
+            .map(|i| perft_hashed(&mut board, i, &mut table))
+            .collect();
+
+        assert_eq!(got_hashed, expected_nodes);
     }
 
     #[test]
@@ -65,6 +217,64 @@ mod test {
         test_perft(KIWIPETE_FEN, EXPECTED);
     }
 
+    fn counts(
+        [nodes, captures, en_passants, castles, promotions, checks, checkmates]: [u64; 7],
+    ) -> PerftCounts {
+        PerftCounts {
+            nodes,
+            captures,
+            en_passants,
+            castles,
+            promotions,
+            checks,
+            checkmates,
+        }
+    }
+
+    #[test]
+    fn test_detailed_starting_position() {
+        let mut board = Board::starting_position();
+
+        assert_eq!(perft_detailed(&mut board, 0), counts([1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(
+            perft_detailed(&mut board, 3),
+            counts([8_902, 34, 0, 0, 0, 12, 0])
+        );
+        assert_eq!(
+            perft_detailed(&mut board, 4),
+            counts([197_281, 1_576, 0, 0, 0, 469, 8])
+        );
+    }
+
+    #[test]
+    fn test_detailed_kiwipete() {
+        let mut board = Board::try_parse_fen(KIWIPETE_FEN).unwrap();
+
+        assert_eq!(
+            perft_detailed(&mut board, 1),
+            counts([48, 8, 0, 2, 0, 0, 0])
+        );
+        assert_eq!(
+            perft_detailed(&mut board, 2),
+            counts([2_039, 351, 1, 91, 0, 3, 0])
+        );
+        assert_eq!(
+            perft_detailed(&mut board, 3),
+            counts([97_862, 17_102, 45, 3_162, 0, 993, 1])
+        );
+    }
+
+    #[test]
+    fn test_detailed_promotions() {
+        // Position 4 from the Chess Programming Wiki perft results
+        let mut board = Board::try_parse_fen(TEST_4_FEN).unwrap();
+
+        assert_eq!(
+            perft_detailed(&mut board, 3),
+            counts([9_467, 1_021, 4, 0, 120, 38, 22])
+        );
+    }
+
     #[test]
     fn test_3() {
         const EXPECTED: &[u64] = &[1, 14, 191, 2_812, 43_238, 674_624];
@@ -88,4 +298,36 @@ mod test {
         const EXPECTED: &[u64] = &[1, 46, 2_079, 89_890, 3_894_594];
         test_perft(TEST_6_FEN, EXPECTED);
     }
+
+    #[test]
+    fn test_chess960() {
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                &[1, 21, 528, 12_189, 326_672][..],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                &[1, 21, 807, 18_002, 667_366],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                &[1, 20, 479, 10_471, 273_318],
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                &[1, 22, 593, 13_440, 382_958],
+            ),
+            (
+                "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+                &[1, 28, 1_120, 31_058, 1_171_749],
+            ),
+            (
+                "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+                &[1, 29, 899, 26_578, 824_055],
+            ),
+        ] {
+            test_perft(fen, expected);
+        }
+    }
 }
@@ -0,0 +1,78 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    search_result::{SearchInfo, SearchResult},
+    SearchReporter,
+};
+
+/// Handle to a search started with [`crate::Engine::analyze`].
+///
+/// Search info is sent over a channel as each iteration completes, so it can
+/// be polled with [`Self::receiver`] or iterated over with [`Self::infos`].
+pub struct AnalysisHandle {
+    receiver: Receiver<SearchInfo>,
+    abort_flag: Arc<AtomicBool>,
+    thread: JoinHandle<SearchResult>,
+}
+
+impl AnalysisHandle {
+    pub(crate) fn new(
+        receiver: Receiver<SearchInfo>,
+        abort_flag: Arc<AtomicBool>,
+        thread: JoinHandle<SearchResult>,
+    ) -> Self {
+        Self {
+            receiver,
+            abort_flag,
+            thread,
+        }
+    }
+
+    /// The channel search info is sent over. It's disconnected once the
+    /// search finishes.
+    pub fn receiver(&self) -> &Receiver<SearchInfo> {
+        &self.receiver
+    }
+
+    /// Blocking iterator over the search info, which ends once the search
+    /// finishes.
+    pub fn infos(&self) -> mpsc::Iter<'_, SearchInfo> {
+        self.receiver.iter()
+    }
+
+    /// Asks the search to stop as soon as possible. Use [`Self::join`] to get
+    /// the result.
+    pub fn stop(&self) {
+        self.abort_flag.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the search to finish and returns its result.
+    pub fn join(self) -> SearchResult {
+        self.thread.join().expect("Search thread panicked")
+    }
+}
+
+/// Forwards search info over a channel, for [`AnalysisHandle`].
+pub(crate) struct ChannelReporter {
+    pub sender: Sender<SearchInfo>,
+}
+
+impl SearchReporter for ChannelReporter {
+    fn receive_search_info(&self, info: SearchInfo) {
+        // Nothing to do if the receiver doesn't care anymore
+        let _ = self.sender.send(info);
+    }
+
+    fn search_complete(&self, _result: SearchResult) {}
+}
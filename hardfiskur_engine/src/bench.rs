@@ -62,6 +62,8 @@ impl Engine {
     pub const DEFAULT_BENCH_DEPTH: i16 = 12;
 
     fn bench_position(&self, fen: &str, depth: i16) -> (u64, Duration) {
+        // Start each position from a clean TT and history, so that the node
+        // count doesn't depend on what was searched before it
         self.new_game();
 
         let mut board = Board::try_parse_fen(fen).expect("Invalid FEN");
@@ -70,12 +72,12 @@ impl Engine {
 
         let abort_flag = AtomicBool::new(false);
 
-        let ctx = SearchContext::new(
+        let mut ctx = SearchContext::new(
             &mut board,
-            SearchLimits {
-                depth,
-                ..SearchLimits::infinite()
-            },
+            SearchLimits::builder()
+                .depth(depth)
+                .build()
+                .expect("bench depth should be at least 1"),
             &mut persistent.tt,
             &mut persistent.history,
             &abort_flag,
@@ -86,12 +88,16 @@ impl Engine {
         (result.info.raw_stats.nodes_searched, result.info.elapsed)
     }
 
+    /// Searches every position in [`BENCH_POSITIONS`] to `depth` (or
+    /// [`Self::DEFAULT_BENCH_DEPTH`]), returning the total nodes searched and
+    /// time taken. The node count only depends on the depth and the search
+    /// itself, so it serves as a signature for detecting functional changes.
     pub fn bench(&self, depth: Option<u32>) -> (u64, Duration) {
         let mut total_nodes = 0;
         let mut total_time = Duration::ZERO;
 
         let depth = depth
-            .map(|x| x.try_into().unwrap_or(i16::MAX))
+            .map(|x| x.try_into().unwrap_or(i16::MAX).max(1))
             .unwrap_or(Self::DEFAULT_BENCH_DEPTH);
 
         for fen in BENCH_POSITIONS {
@@ -103,3 +109,21 @@ impl Engine {
         (total_nodes, total_time)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bench_node_count_is_deterministic() {
+        let engine = Engine::new();
+        let (first, _) = engine.bench(Some(3));
+
+        // Searching something else in between shouldn't affect the result
+        engine.bench_position(BENCH_POSITIONS[0], 5);
+
+        let (second, _) = engine.bench(Some(3));
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,253 @@
+//! A human-readable breakdown of the evaluation into groups of terms, for
+//! debugging.
+
+use std::fmt::Display;
+
+use hardfiskur_core::board::Board;
+
+use super::{
+    draw::is_likely_draw,
+    evaluate_for_white,
+    packed_score::S,
+    parameters::KING_ZONE_ATTACKS,
+    phase::Phase,
+    template_params::{Bishop, Black, ColorParam, Knight, Queen, Rook, White},
+    trace::{EvalTrace, NullTrace},
+    EvalContext,
+};
+use crate::score::Score;
+
+/// The groups of terms the evaluation is broken down into, in the order they
+/// appear in [`EvalBreakdown::terms`].
+pub const TERM_NAMES: [&str; 8] = [
+    "Material",
+    "Piece-square tables",
+    "Mobility",
+    "Pawn structure",
+    "King safety",
+    "Piece placement",
+    "Threats",
+    "Tempo",
+];
+
+/// The contribution of a group of terms for each side, tapered to
+/// centipawns. Each side's contribution is from its own perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermBreakdown {
+    pub name: &'static str,
+    pub white: Score,
+    pub black: Score,
+}
+
+impl TermBreakdown {
+    /// The net contribution of the term from white's perspective.
+    pub fn total(&self) -> Score {
+        Score(self.white.get() - self.black.get())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub terms: Vec<TermBreakdown>,
+    pub phase: Phase,
+    /// Whether the evaluation was scaled down for being a likely draw.
+    pub likely_draw: bool,
+    /// The final evaluation from white's perspective. This may differ
+    /// slightly from the sum of the terms, as the terms are tapered
+    /// separately.
+    pub total: Score,
+}
+
+impl EvalContext<'_> {
+    /// Breaks down the full evaluation of the position into the contribution
+    /// of each side to each group of terms.
+    pub fn explain(board: &Board) -> EvalBreakdown {
+        let eval_context = EvalContext::new(board);
+        let phase = Phase::from_board(board);
+
+        let white = eval_context.side_breakdown::<White>();
+        let black = eval_context.side_breakdown::<Black>();
+
+        let terms = TERM_NAMES
+            .iter()
+            .zip(white.into_iter().zip(black))
+            .map(|(&name, (white, black))| TermBreakdown {
+                name,
+                white: Score(phase.taper_packed(white)),
+                black: Score(-phase.taper_packed(black)),
+            })
+            .collect();
+
+        EvalBreakdown {
+            terms,
+            phase,
+            likely_draw: is_likely_draw(board),
+            total: evaluate_for_white(board),
+        }
+    }
+
+    /// Sums up the terms for one side into the groups in [`TERM_NAMES`].
+    /// Scores are from white's perspective, as returned by the terms
+    /// themselves.
+    fn side_breakdown<C: ColorParam>(&self) -> [S; TERM_NAMES.len()] {
+        let mut material = S::ZERO;
+        let mut psts = S::ZERO;
+        let mut placement = S::ZERO;
+
+        for (piece, bitboard) in self.board.repr().boards_colored(C::COLOR) {
+            let piece_type = piece.piece_type();
+            for square in bitboard.squares() {
+                material += self.material::<C>(piece_type, &mut NullTrace);
+                psts += self.piece_square_table::<C>(piece_type, square, &mut NullTrace);
+                placement += self.open_file_bonus::<C>(piece_type, square, &mut NullTrace);
+            }
+        }
+
+        material += self.material_imbalance::<C>(&mut NullTrace);
+        material += self.bishop_pair::<C>(&mut NullTrace);
+
+        // Mobility and king zone attacks are computed together, so the king
+        // zone attacks are separated back out using the trace.
+        let mut trace = EvalTrace::default();
+        let mobility_and_king_zone_attacks = self
+            .mobility_and_king_zone_attacks::<C, Knight>(&mut trace)
+            + self.mobility_and_king_zone_attacks::<C, Bishop>(&mut trace)
+            + self.mobility_and_king_zone_attacks::<C, Rook>(&mut trace)
+            + self.mobility_and_king_zone_attacks::<C, Queen>(&mut trace);
+        let king_zone_attacks = trace
+            .king_zone_attacks
+            .iter()
+            .zip(KING_ZONE_ATTACKS)
+            .fold(S::ZERO, |acc, (&count, param)| acc + param * count as i32);
+        let mobility = mobility_and_king_zone_attacks - king_zone_attacks;
+
+        let pawn_structure = self.passed_pawns::<C>(&mut NullTrace)
+            + self.doubled_pawns::<C>(&mut NullTrace)
+            + self.isolated_pawns::<C>(&mut NullTrace)
+            + self.phalanx_pawns::<C>(&mut NullTrace)
+            + self.protected_pawns::<C>(&mut NullTrace);
+
+        let king_safety = king_zone_attacks
+            + self.virtual_mobility::<C>(&mut NullTrace)
+            + self.pawn_shield::<C>(&mut NullTrace)
+            + self.king_open_files::<C>(&mut NullTrace)
+            + self.king_flight_square_attacks::<C>(&mut NullTrace)
+            + self.king_safety::<C>(&mut NullTrace);
+
+        placement += self.knight_outposts::<C>(&mut NullTrace)
+            + self.bishop_outposts::<C>(&mut NullTrace)
+            + self.rook_on_seventh::<C>(&mut NullTrace)
+            + self.connected_rooks::<C>(&mut NullTrace);
+
+        let threats = self.threats::<C>(&mut NullTrace);
+        let tempo = self.tempo::<C>(&mut NullTrace);
+
+        [
+            material,
+            psts,
+            mobility,
+            pawn_structure,
+            king_safety,
+            placement,
+            threats,
+            tempo,
+        ]
+    }
+}
+
+impl Display for EvalBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = format!("{:-<21}+{:-<9}+{:-<9}+{:-<9}", "", "", "", "");
+
+        writeln!(
+            f,
+            "{:<20} | {:>7} | {:>7} | {:>7}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(f, "{separator}")?;
+        for term in &self.terms {
+            writeln!(
+                f,
+                "{:<20} | {:>7} | {:>7} | {:>7}",
+                term.name,
+                term.white.get(),
+                term.black.get(),
+                term.total().get()
+            )?;
+        }
+        writeln!(f, "{separator}")?;
+
+        writeln!(f, "Phase: {}/{}", self.phase.0, Phase::MAX)?;
+        if self.likely_draw {
+            writeln!(f, "Likely draw: scaled towards 0")?;
+        }
+        write!(f, "Evaluation: {} (white's perspective)", self.total.get())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TEST_FENS: [&str; 3] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ];
+
+    #[test]
+    fn terms_add_up_to_evaluation() {
+        for fen in TEST_FENS {
+            let board = Board::try_parse_fen(fen).unwrap();
+            let breakdown = EvalContext::explain(&board);
+
+            assert_eq!(breakdown.terms.len(), TERM_NAMES.len());
+            assert_eq!(breakdown.total, evaluate_for_white(&board));
+            assert!(!breakdown.likely_draw);
+
+            // Each term is rounded separately when tapered
+            let sum: i32 = breakdown.terms.iter().map(|t| t.total().get()).sum();
+            let tolerance = 2 * breakdown.terms.len() as i32;
+            assert!(
+                (sum - breakdown.total.get()).abs() <= tolerance,
+                "{fen}: terms sum to {sum}, evaluation is {}",
+                breakdown.total.get()
+            );
+        }
+    }
+
+    #[test]
+    fn breakdown_is_symmetric() {
+        let white = Board::try_parse_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        )
+        .unwrap();
+        let black = Board::try_parse_fen(
+            "rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3",
+        )
+        .unwrap();
+
+        let white = EvalContext::explain(&white);
+        let black = EvalContext::explain(&black);
+
+        for (w, b) in white.terms.iter().zip(&black.terms) {
+            assert_eq!(w.white, b.black, "{}", w.name);
+            assert_eq!(w.black, b.white, "{}", w.name);
+        }
+    }
+
+    #[test]
+    fn display_lists_every_term() {
+        let breakdown = EvalContext::explain(&Board::starting_position());
+        let output = breakdown.to_string();
+
+        for name in TERM_NAMES {
+            assert!(output.contains(name), "missing {name}");
+        }
+        assert!(output.ends_with(&format!(
+            "Evaluation: {} (white's perspective)",
+            breakdown.total.get()
+        )));
+    }
+}
@@ -0,0 +1,174 @@
+//! Heuristic detection of drawish material, used to pull the evaluation
+//! towards 0. Unlike [`Board::check_draw_by_insufficient_material`], the
+//! configurations here can still be lost with bad enough play, so they're
+//! never scored as an outright draw.
+
+use hardfiskur_core::board::{Board, Color, Piece, PieceType};
+
+use crate::score::Score;
+
+/// Evaluations of likely draws are divided by this.
+pub const LIKELY_DRAW_DIVISOR: i32 = 8;
+
+/// Returns true if the material on the board is known to be drawish, which
+/// covers:
+/// * No pawns, and neither side has more than a single minor piece (e.g. KN
+///   vs K, KB vs KN).
+/// * No pawns, and one side has two knights against a bare king, which can't
+///   force mate.
+/// * No pawns, and a rook against a single minor piece.
+/// * Only bishops and pawns, with one bishop each on opposite colours, and the
+///   pawn counts differ by at most one.
+pub fn is_likely_draw(board: &Board) -> bool {
+    let count = |color, piece_type| board.repr().count(Piece::new(color, piece_type)) as u32;
+
+    let [white, black] = [Color::White, Color::Black].map(|color| MaterialCount {
+        pawns: count(color, PieceType::Pawn),
+        knights: count(color, PieceType::Knight),
+        bishops: count(color, PieceType::Bishop),
+        rooks: count(color, PieceType::Rook),
+        queens: count(color, PieceType::Queen),
+    });
+
+    if white.pawns == 0 && black.pawns == 0 {
+        if white.is_at_most_one_minor() && black.is_at_most_one_minor() {
+            return true;
+        }
+
+        if (white.is_two_knights() && black.is_bare())
+            || (black.is_two_knights() && white.is_bare())
+        {
+            return true;
+        }
+
+        if (white.is_single_rook() && black.is_single_minor())
+            || (black.is_single_rook() && white.is_single_minor())
+        {
+            return true;
+        }
+    }
+
+    if white.is_single_bishop_and_pawns()
+        && black.is_single_bishop_and_pawns()
+        && white.pawns.abs_diff(black.pawns) <= 1
+    {
+        let white_bishop = board
+            .get_bitboard_for_piece(Piece::WHITE_BISHOP)
+            .to_square()
+            .unwrap();
+        let black_bishop = board
+            .get_bitboard_for_piece(Piece::BLACK_BISHOP)
+            .to_square()
+            .unwrap();
+
+        if white_bishop.parity() != black_bishop.parity() {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Scales the score towards 0 if the position is a likely draw.
+pub fn scale_for_likely_draw(board: &Board, score: Score) -> Score {
+    if is_likely_draw(board) {
+        Score(score.get() / LIKELY_DRAW_DIVISOR)
+    } else {
+        score
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MaterialCount {
+    pawns: u32,
+    knights: u32,
+    bishops: u32,
+    rooks: u32,
+    queens: u32,
+}
+
+impl MaterialCount {
+    fn minors(self) -> u32 {
+        self.knights + self.bishops
+    }
+
+    fn majors(self) -> u32 {
+        self.rooks + self.queens
+    }
+
+    fn is_bare(self) -> bool {
+        self.pawns == 0 && self.minors() == 0 && self.majors() == 0
+    }
+
+    fn is_at_most_one_minor(self) -> bool {
+        self.pawns == 0 && self.minors() <= 1 && self.majors() == 0
+    }
+
+    fn is_single_minor(self) -> bool {
+        self.pawns == 0 && self.minors() == 1 && self.majors() == 0
+    }
+
+    fn is_two_knights(self) -> bool {
+        self.pawns == 0 && self.knights == 2 && self.bishops == 0 && self.majors() == 0
+    }
+
+    fn is_single_rook(self) -> bool {
+        self.pawns == 0 && self.minors() == 0 && self.rooks == 1 && self.queens == 0
+    }
+
+    fn is_single_bishop_and_pawns(self) -> bool {
+        self.knights == 0 && self.bishops == 1 && self.majors() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn likely_draw(fen: &str) -> bool {
+        is_likely_draw(&Board::try_parse_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn likely_draws() {
+        // KN vs K
+        assert!(likely_draw("8/8/4k3/8/8/3N4/4K3/8 w - - 0 1"));
+        // KB vs KN
+        assert!(likely_draw("8/8/4k3/4n3/8/3B4/4K3/8 w - - 0 1"));
+        // KNN vs K
+        assert!(likely_draw("8/8/4k3/8/8/3NN3/4K3/8 w - - 0 1"));
+        // KR vs KB
+        assert!(likely_draw("8/8/4k3/4b3/8/3R4/4K3/8 w - - 0 1"));
+        // Opposite coloured bishops, a pawn up
+        assert!(likely_draw("8/5pp1/4kb2/8/8/3B1PPP/4K3/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn not_likely_draws() {
+        // Starting position
+        assert!(!likely_draw(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        ));
+        // KBN vs K is a forced mate
+        assert!(!likely_draw("8/8/4k3/8/8/3BN3/4K3/8 w - - 0 1"));
+        // KNN vs KP can be won
+        assert!(!likely_draw("8/8/4k3/4p3/8/3NN3/4K3/8 w - - 0 1"));
+        // KR vs K
+        assert!(!likely_draw("8/8/4k3/8/8/3R4/4K3/8 w - - 0 1"));
+        // Same coloured bishops
+        assert!(!likely_draw("8/5pp1/4k1b1/8/8/3B1PPP/4K3/8 w - - 0 1"));
+        // Opposite coloured bishops, two pawns up
+        assert!(!likely_draw("8/6p1/4kb2/8/8/3B1PPP/4K3/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn likely_draws_are_scaled() {
+        let board = Board::try_parse_fen("8/8/4k3/4b3/8/3R4/4K3/8 w - - 0 1").unwrap();
+        assert_eq!(scale_for_likely_draw(&board, Score(400)), Score(50));
+        assert_eq!(scale_for_likely_draw(&board, Score(-400)), Score(-50));
+
+        let board = Board::starting_position();
+        assert_eq!(scale_for_likely_draw(&board, Score(400)), Score(400));
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use hardfiskur_core::board::Board;
+
+use super::{
+    evaluate_with_kind,
+    nnue::{Accumulator, Network},
+    EvalKind,
+};
+use crate::score::Score;
+
+/// A static evaluation function. Scores are relative to the side to move.
+pub trait Evaluator: Send + Sync {
+    fn evaluate(&self, board: &Board) -> Score;
+}
+
+/// The handcrafted evaluation, which is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HceEvaluator {
+    pub kind: EvalKind,
+}
+
+impl Evaluator for HceEvaluator {
+    fn evaluate(&self, board: &Board) -> Score {
+        evaluate_with_kind(board, self.kind)
+    }
+}
+
+/// Evaluates with a neural network. This refreshes the accumulator from
+/// scratch on every call -- the search instead keeps an
+/// [`AccumulatorStack`](super::nnue::AccumulatorStack) updated as it makes
+/// and unmakes moves.
+#[derive(Debug, Clone)]
+pub struct NnueEvaluator {
+    pub network: Arc<Network>,
+}
+
+impl Evaluator for NnueEvaluator {
+    fn evaluate(&self, board: &Board) -> Score {
+        Accumulator::new(&self.network, board).evaluate(&self.network, board.to_move())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::evaluation::{evaluate, nnue::test::test_network};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hce_evaluator_matches_evaluate() {
+        let board = Board::try_parse_fen(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        )
+        .unwrap();
+
+        assert_eq!(HceEvaluator::default().evaluate(&board), evaluate(&board));
+    }
+
+    #[test]
+    fn nnue_evaluator_is_relative_to_side_to_move() {
+        let evaluator = NnueEvaluator {
+            network: Arc::new(test_network()),
+        };
+
+        let white = Board::try_parse_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let black = Board::try_parse_fen("4k3/4p3/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluator.evaluate(&white), evaluator.evaluate(&black));
+    }
+}
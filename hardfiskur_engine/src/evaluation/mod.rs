@@ -1,4 +1,8 @@
+pub mod breakdown;
+pub mod draw;
+pub mod evaluator;
 pub mod lookups;
+pub mod nnue;
 pub mod packed_score;
 pub mod parameters;
 pub mod pawn_structure;
@@ -7,6 +11,7 @@ pub mod template_params;
 pub mod terms;
 pub mod trace;
 
+use draw::scale_for_likely_draw;
 use hardfiskur_core::{
     board::{Bitboard, Board, Color, Square},
     move_gen::lookups::Lookups,
@@ -19,9 +24,21 @@ use trace::{NullTrace, Trace};
 
 use crate::score::Score;
 
+/// Which evaluation function to use for a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalKind {
+    /// The full classical evaluation.
+    #[default]
+    Full,
+    /// Only material and piece-square tables, for debugging search in
+    /// isolation from the rest of the evaluation.
+    Material,
+}
+
 pub fn evaluate_for_white_ex(board: &Board) -> (Score, Phase) {
     let eval_context = EvalContext::new(board);
-    eval_context.evaluate_ex(&mut NullTrace)
+    let (score, phase) = eval_context.evaluate_ex(&mut NullTrace);
+    (scale_for_likely_draw(board, score), phase)
 }
 
 pub fn evaluate_ex(board: &Board) -> (Score, Phase) {
@@ -43,6 +60,50 @@ pub fn evaluate(board: &Board) -> Score {
     evaluate_ex(board).0
 }
 
+pub fn evaluate_for_white_with_kind(board: &Board, kind: EvalKind) -> Score {
+    let eval_context = EvalContext::new(board);
+    match kind {
+        EvalKind::Full => scale_for_likely_draw(board, eval_context.evaluate_ex(&mut NullTrace).0),
+        EvalKind::Material => eval_context.evaluate_material_ex(&mut NullTrace).0,
+    }
+}
+
+pub fn evaluate_with_kind(board: &Board, kind: EvalKind) -> Score {
+    let white_score = evaluate_for_white_with_kind(board, kind);
+
+    match board.to_move() {
+        Color::White => white_score,
+        Color::Black => -white_score,
+    }
+}
+
+/// Like [`evaluate_with_kind`], but adds a pseudo-random offset of up to `variety`
+/// centipawns in either direction, so that the engine doesn't always pick the
+/// same move among near-equal ones. The offset is derived from the zobrist
+/// hash, so the same position always gets the same evaluation.
+pub fn evaluate_with_variety(board: &Board, kind: EvalKind, variety: i32) -> Score {
+    add_variety(board, evaluate_with_kind(board, kind), variety)
+}
+
+/// Adds the pseudo-random offset described in [`evaluate_with_variety`] to an
+/// existing evaluation.
+pub fn add_variety(board: &Board, score: Score, variety: i32) -> Score {
+    if variety <= 0 {
+        return score;
+    }
+
+    // splitmix64 finalizer, to spread nearby hashes out
+    let mut x = board.zobrist_hash().0;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^= x >> 31;
+
+    let range = 2 * variety as u64 + 1;
+    let jitter = (x % range) as i32 - variety;
+
+    Score(score.get() + jitter)
+}
+
 pub struct EvalContext<'a> {
     board: &'a Board,
     lookups: &'static Lookups,
@@ -52,6 +113,9 @@ pub struct EvalContext<'a> {
     pawns: PawnStructure,
     kings: [Square; 2],
     king_zones: [Bitboard; 2],
+
+    /// All squares attacked by each side.
+    attacks: [Bitboard; 2],
 }
 
 impl<'a> EvalContext<'a> {
@@ -80,6 +144,11 @@ impl<'a> EvalContext<'a> {
             pawns,
             kings: [white_king, black_king],
             king_zones: [white_king_zone, black_king_zone],
+
+            attacks: [
+                board.attacks_by(Color::White),
+                board.attacks_by(Color::Black),
+            ],
         }
     }
 
@@ -144,12 +213,112 @@ impl<'a> EvalContext<'a> {
         score += self.pawn_shield::<White>(trace);
         score += self.pawn_shield::<Black>(trace);
 
+        // Open files around the king
+        score += self.king_open_files::<White>(trace);
+        score += self.king_open_files::<Black>(trace);
+
         // Outposts
         score += self.knight_outposts::<White>(trace);
         score += self.knight_outposts::<Black>(trace);
         score += self.bishop_outposts::<White>(trace);
         score += self.bishop_outposts::<Black>(trace);
 
+        // Material imbalance
+        score += self.material_imbalance::<White>(trace);
+        score += self.material_imbalance::<Black>(trace);
+
+        // Heavy pieces covering the enemy king's flight squares
+        score += self.king_flight_square_attacks::<White>(trace);
+        score += self.king_flight_square_attacks::<Black>(trace);
+
+        score += self.tempo::<White>(trace);
+        score += self.tempo::<Black>(trace);
+
+        score += self.bishop_pair::<White>(trace);
+        score += self.bishop_pair::<Black>(trace);
+
+        score += self.rook_on_seventh::<White>(trace);
+        score += self.rook_on_seventh::<Black>(trace);
+
+        score += self.connected_rooks::<White>(trace);
+        score += self.connected_rooks::<Black>(trace);
+
+        score += self.king_safety::<White>(trace);
+        score += self.king_safety::<Black>(trace);
+
+        score += self.threats::<White>(trace);
+        score += self.threats::<Black>(trace);
+
+        (Score(phase.taper_packed(score)), phase)
+    }
+
+    /// Evaluates only material and piece-square tables.
+    pub fn evaluate_material_ex(&self, trace: &mut impl Trace) -> (Score, Phase) {
+        let mut phase = Phase(0);
+        let mut score = PackedScore::ZERO;
+
+        for (piece, bitboard) in self.board.repr().boards_colored(Color::White) {
+            for square in bitboard.squares() {
+                phase.apply_phase(piece);
+                score += self.material::<White>(piece.piece_type(), trace);
+                score += self.piece_square_table::<White>(piece.piece_type(), square, trace);
+            }
+        }
+
+        for (piece, bitboard) in self.board.repr().boards_colored(Color::Black) {
+            for square in bitboard.squares() {
+                phase.apply_phase(piece);
+                score += self.material::<Black>(piece.piece_type(), trace);
+                score += self.piece_square_table::<Black>(piece.piece_type(), square, trace);
+            }
+        }
+
         (Score(phase.taper_packed(score)), phase)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TEST_FENS: [&str; 3] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        "8/5k2/8/3P4/8/8/5K2/8 b - - 0 1",
+    ];
+
+    #[test]
+    fn evaluate_without_variety_is_exact() {
+        for fen in TEST_FENS {
+            let board = Board::try_parse_fen(fen).unwrap();
+            assert_eq!(
+                evaluate_with_variety(&board, EvalKind::Full, 0),
+                evaluate(&board)
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_with_variety_is_bounded_and_deterministic() {
+        let variety = 5;
+        let mut jitters = Vec::new();
+
+        for fen in TEST_FENS {
+            let board = Board::try_parse_fen(fen).unwrap();
+            let exact = evaluate(&board);
+            let varied = evaluate_with_variety(&board, EvalKind::Full, variety);
+
+            assert!((varied.get() - exact.get()).abs() <= variety);
+            assert_eq!(
+                evaluate_with_variety(&board, EvalKind::Full, variety),
+                varied
+            );
+
+            jitters.push(varied.get() - exact.get());
+        }
+
+        // Not every position should get the same offset
+        assert!(jitters.iter().any(|&j| j != jitters[0]));
+    }
+}
@@ -0,0 +1,427 @@
+//! An efficiently updatable neural network evaluation.
+//!
+//! The network is a single perspective feature transformer, with 768 inputs
+//! (one for each piece on each square, relative to the perspective's side)
+//! feeding a hidden layer of arbitrary size. The hidden layers for the side
+//! to move and the other side go through a clipped ReLU and are concatenated
+//! before a single output neuron.
+//!
+//! Network files are a flat list of little-endian `i16`s: the feature weights
+//! (`768 * hidden` values, grouped by feature), the feature biases (`hidden`),
+//! the output weights (`2 * hidden`, side to move first) and the output bias.
+
+use std::{fs, path::Path};
+
+use hardfiskur_core::board::{Board, Color, Move, Piece, Square};
+use thiserror::Error;
+
+use crate::score::Score;
+
+pub const INPUT_SIZE: usize = 768;
+
+/// Activations are clipped to `0..=QA`.
+const QA: i32 = 255;
+/// Output weights are quantised by `QB`.
+const QB: i32 = 64;
+/// Converts the network's output into centipawns.
+const EVAL_SCALE: i32 = 400;
+
+#[derive(Error, Debug)]
+pub enum NnueError {
+    #[error("network file is {0} bytes, which doesn't match any hidden layer size")]
+    InvalidLength(usize),
+    #[error("hidden neuron {0} can overflow with 32 pieces on the board")]
+    AccumulatorOverflow(usize),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    hidden_size: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl Network {
+    /// Creates a network from its weights.
+    ///
+    /// # Panics
+    /// If the weights don't all have sizes consistent with the length of
+    /// `feature_biases`.
+    pub fn new(
+        feature_weights: Vec<i16>,
+        feature_biases: Vec<i16>,
+        output_weights: Vec<i16>,
+        output_bias: i16,
+    ) -> Self {
+        let hidden_size = feature_biases.len();
+        assert_eq!(feature_weights.len(), INPUT_SIZE * hidden_size);
+        assert_eq!(output_weights.len(), 2 * hidden_size);
+
+        Self {
+            hidden_size,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, NnueError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NnueError> {
+        // 768 * hidden + hidden + 2 * hidden + 1 values in total
+        let values = bytes.len() / 2;
+        let per_hidden = INPUT_SIZE + 3;
+        if !bytes.len().is_multiple_of(2) || values < 1 || !(values - 1).is_multiple_of(per_hidden)
+        {
+            return Err(NnueError::InvalidLength(bytes.len()));
+        }
+        let hidden_size = (values - 1) / per_hidden;
+        if hidden_size == 0 {
+            return Err(NnueError::InvalidLength(bytes.len()));
+        }
+
+        let mut values = bytes
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]));
+
+        let feature_weights = values.by_ref().take(INPUT_SIZE * hidden_size).collect();
+        let feature_biases = values.by_ref().take(hidden_size).collect();
+        let output_weights = values.by_ref().take(2 * hidden_size).collect();
+        let output_bias = values.next().unwrap();
+
+        let network = Self::new(feature_weights, feature_biases, output_weights, output_bias);
+        network.validate()?;
+
+        Ok(network)
+    }
+
+    /// Checks that no hidden neuron can leave the range of an `i16`, however
+    /// the (at most 32) pieces are placed on the board.
+    fn validate(&self) -> Result<(), NnueError> {
+        for (neuron, &bias) in self.feature_biases.iter().enumerate() {
+            let biggest_weight = (0..INPUT_SIZE)
+                .map(|feature| self.feature_weights(feature)[neuron].unsigned_abs() as i32)
+                .max()
+                .unwrap_or(0);
+
+            if (bias as i32).abs() + 32 * biggest_weight > i16::MAX as i32 {
+                return Err(NnueError::AccumulatorOverflow(neuron));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.feature_weights
+            .iter()
+            .chain(&self.feature_biases)
+            .chain(&self.output_weights)
+            .chain([&self.output_bias])
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
+    fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden_size..(feature + 1) * self.hidden_size]
+    }
+
+    /// Evaluates the position from the side to move's perspective, given the
+    /// accumulated hidden layers.
+    fn output(&self, us: &[i16], them: &[i16]) -> Score {
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden_size);
+
+        // Summed as i64, as a large hidden layer could overflow an i32
+        let weighted_sum = |hidden: &[i16], weights: &[i16]| -> i64 {
+            hidden
+                .iter()
+                .zip(weights)
+                .map(|(&x, &w)| (x as i32).clamp(0, QA) as i64 * w as i64)
+                .sum()
+        };
+
+        let output = weighted_sum(us, our_weights)
+            + weighted_sum(them, their_weights)
+            + self.output_bias as i64;
+
+        let max_eval = Score::MAX_EVAL.get() as i64;
+        let eval = (output * EVAL_SCALE as i64 / (QA * QB) as i64).clamp(-max_eval, max_eval);
+
+        Score(eval as i32)
+    }
+}
+
+/// Index of the input feature for `piece` on `square`, as seen from
+/// `perspective`. Each side sees its own pieces first, with the board flipped
+/// for black so that both sides see their pieces starting at the bottom.
+fn feature_index(perspective: Color, piece: Piece, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (piece.color().index(), square),
+        Color::Black => (piece.color().flip().index(), square.flip()),
+    };
+
+    side * 384 + piece.piece_type().index() * 64 + square.index()
+}
+
+/// The hidden layer values for both perspectives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+    /// Computes the accumulator for the position from scratch.
+    pub fn new(network: &Network, board: &Board) -> Self {
+        let mut accumulator = Self {
+            values: [
+                network.feature_biases.clone(),
+                network.feature_biases.clone(),
+            ],
+        };
+
+        for (piece, square) in board.pieces() {
+            accumulator.add(network, piece, square);
+        }
+
+        accumulator
+    }
+
+    fn add(&mut self, network: &Network, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = network.feature_weights(feature_index(perspective, piece, square));
+            // Wrapping, so that removing a feature always undoes adding it
+            // even if a network's weights do overflow
+            for (value, &weight) in self.values[perspective.index()].iter_mut().zip(weights) {
+                *value = value.wrapping_add(weight);
+            }
+        }
+    }
+
+    fn remove(&mut self, network: &Network, piece: Piece, square: Square) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = network.feature_weights(feature_index(perspective, piece, square));
+            for (value, &weight) in self.values[perspective.index()].iter_mut().zip(weights) {
+                *value = value.wrapping_sub(weight);
+            }
+        }
+    }
+
+    /// Updates the accumulator for `m` being played. Castling, en passant and
+    /// promotions are all handled from the move flags alone.
+    pub fn apply_move(&mut self, network: &Network, m: Move) {
+        let piece = m.piece();
+        let (from, to) = (m.from_square(), m.to_square());
+
+        if let Some(captured) = m.captured_piece() {
+            let captured_square = if m.is_en_passant() {
+                m.en_passant_square()
+            } else {
+                to
+            };
+            self.remove(network, captured, captured_square);
+        }
+
+        self.remove(network, piece, from);
+        self.add(network, m.promotion().unwrap_or(piece), to);
+
+        if m.is_castle() {
+            let rook = Piece::rook(piece.color());
+            let (rook_from, rook_to) = m.castling_rook_squares();
+            self.remove(network, rook, rook_from);
+            self.add(network, rook, rook_to);
+        }
+    }
+
+    pub fn evaluate(&self, network: &Network, to_move: Color) -> Score {
+        network.output(
+            &self.values[to_move.index()],
+            &self.values[to_move.flip().index()],
+        )
+    }
+}
+
+/// Accumulators for each ply of a search, so that unmaking a move is just
+/// dropping back to the previous one.
+#[derive(Debug, Clone)]
+pub struct AccumulatorStack<'a> {
+    network: &'a Network,
+    stack: Vec<Accumulator>,
+    len: usize,
+}
+
+impl<'a> AccumulatorStack<'a> {
+    pub fn new(network: &'a Network, board: &Board) -> Self {
+        Self {
+            network,
+            stack: vec![Accumulator::new(network, board)],
+            len: 1,
+        }
+    }
+
+    fn push_copy(&mut self) {
+        // Entries above `len` are reused to avoid reallocating every move
+        if self.len == self.stack.len() {
+            self.stack.push(self.stack[self.len - 1].clone());
+        } else {
+            let (below, above) = self.stack.split_at_mut(self.len);
+            above[0].clone_from(&below[self.len - 1]);
+        }
+        self.len += 1;
+    }
+
+    pub fn push_move(&mut self, m: Move) {
+        self.push_copy();
+        self.stack[self.len - 1].apply_move(self.network, m);
+    }
+
+    pub fn push_null_move(&mut self) {
+        self.push_copy();
+    }
+
+    pub fn pop(&mut self) {
+        assert!(self.len > 1, "Popped the root accumulator");
+        self.len -= 1;
+    }
+
+    pub fn current(&self) -> &Accumulator {
+        &self.stack[self.len - 1]
+    }
+
+    pub fn evaluate(&self, to_move: Color) -> Score {
+        self.current().evaluate(self.network, to_move)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A small network with arbitrary but deterministic weights.
+    pub(crate) fn test_network() -> Network {
+        let hidden_size = 8;
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = |range: i16| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (2 * range as u64 + 1)) as i16 - range
+        };
+
+        let feature_weights = (0..INPUT_SIZE * hidden_size).map(|_| next(40)).collect();
+        let feature_biases = (0..hidden_size).map(|_| next(100)).collect();
+        let output_weights = (0..2 * hidden_size).map(|_| next(60)).collect();
+
+        Network::new(feature_weights, feature_biases, output_weights, next(500))
+    }
+
+    #[test]
+    fn network_bytes_round_trip() {
+        let network = test_network();
+        let bytes = network.to_bytes();
+
+        assert_eq!(bytes.len(), 2 * (771 * 8 + 1));
+        assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+        assert!(matches!(
+            Network::from_bytes(&bytes[2..]),
+            Err(NnueError::InvalidLength(_))
+        ));
+        assert!(matches!(
+            Network::from_bytes(&[]),
+            Err(NnueError::InvalidLength(_))
+        ));
+    }
+
+    #[test]
+    fn networks_which_can_overflow_are_rejected() {
+        let hidden_size = 2;
+        let mut feature_weights = vec![0; INPUT_SIZE * hidden_size];
+        // 32 pieces each adding this weight would take the second neuron past
+        // i16::MAX
+        feature_weights[4 * 64 * hidden_size + 1] = 1100;
+        let network = Network::new(feature_weights, vec![0; 2], vec![0; 4], 0);
+
+        assert!(matches!(
+            Network::from_bytes(&network.to_bytes()),
+            Err(NnueError::AccumulatorOverflow(1))
+        ));
+    }
+
+    #[test]
+    fn output_is_never_a_mate_score() {
+        let hidden_size = 8;
+        let network = Network::new(
+            vec![0; INPUT_SIZE * hidden_size],
+            vec![i16::MAX; hidden_size],
+            vec![i16::MAX; 2 * hidden_size],
+            i16::MAX,
+        );
+        let board = Board::starting_position();
+
+        let eval = Accumulator::new(&network, &board).evaluate(&network, Color::White);
+        assert_eq!(eval, Score::MAX_EVAL);
+        assert!(!eval.is_mate());
+    }
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let network = test_network();
+
+        // Includes castling, en passant, captures and a promotion
+        let mut board = Board::try_parse_fen(
+            "r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/1PPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let mut stack = AccumulatorStack::new(&network, &board);
+
+        for uci in ["e1g1", "c7c5", "d5c6", "e8g8", "b7a8q", "b4c3", "b2c3"] {
+            let m = board.push_uci(uci).unwrap();
+            stack.push_move(m);
+
+            assert_eq!(
+                stack.current(),
+                &Accumulator::new(&network, &board),
+                "after {uci}"
+            );
+        }
+
+        for _ in 0..3 {
+            board.pop_move();
+            stack.pop();
+        }
+        assert_eq!(stack.current(), &Accumulator::new(&network, &board));
+
+        board.push_null_move();
+        stack.push_null_move();
+        assert_eq!(stack.current(), &Accumulator::new(&network, &board));
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        let network = test_network();
+
+        let white = Board::try_parse_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        )
+        .unwrap();
+        let black = Board::try_parse_fen(
+            "rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3",
+        )
+        .unwrap();
+
+        let white_eval = Accumulator::new(&network, &white).evaluate(&network, Color::White);
+        let black_eval = Accumulator::new(&network, &black).evaluate(&network, Color::Black);
+        assert_eq!(white_eval, black_eval);
+    }
+}
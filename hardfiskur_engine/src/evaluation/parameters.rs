@@ -107,6 +107,8 @@ pub const PROTECTED_PAWNS: S = s!(22,18);
 
 pub const PAWN_SHIELD_CLOSE: S = s!(50,-25);
 pub const PAWN_SHIELD_FAR: S = s!(33,-10);
+pub const KING_OPEN_FILES: S = s!(-14,0);
+pub const KING_SEMI_OPEN_FILES: S = s!(-7,0);
 
 pub const SEMI_OPEN_FILE_BONUSES: [S; 3] = [
     s!(15,18), s!(4,20), s!(-22,26), 
@@ -121,3 +123,37 @@ pub const BISHOP_OUTPOSTS: S = s!(56,-1);
 pub const KING_ZONE_ATTACKS: [S; 6] = [
     s!(0), s!(17,-3), s!(15,-6), s!(20,-7), s!(11,16), s!(0), 
 ];
+
+pub const KNIGHT_PAIR: S = s!(-8,-12);
+pub const ROOK_PAIR: S = s!(-16,-24);
+pub const KNIGHT_PAWN_ADJUSTMENT: S = s!(2,3);
+pub const ROOK_PAWN_ADJUSTMENT: S = s!(-3,-6);
+
+pub const KING_FLIGHT_SQUARE_ATTACKS: S = s!(9,0);
+
+pub const TEMPO: S = s!(14,6);
+pub const BISHOP_PAIR: S = s!(24,52);
+
+pub const ROOK_ON_SEVENTH: S = s!(8,21);
+pub const CONNECTED_ROOKS: S = s!(10,3);
+
+pub const KING_SAFETY: [S; 20] = [
+    s!(0), s!(-2,0), s!(-6,-1), s!(-14,-3), s!(-24,-5), s!(-38,-8), s!(-54,-11), s!(-74,-15), s!(-96,-19), s!(-122,-24), s!(-150,-30), s!(-182,-36), s!(-216,-43), s!(-254,-51), s!(-294,-59), s!(-338,-68), s!(-384,-77), s!(-400,-80), s!(-400,-80), s!(-400,-80), 
+];
+
+pub const PAWN_THREATS: [S; 6] = [
+    s!(0), s!(48,28), s!(46,34), s!(62,22), s!(54,18), s!(0), 
+];
+pub const MINOR_THREATS: [S; 6] = [
+    s!(0), s!(0), s!(0), s!(38,24), s!(42,30), s!(0), 
+];
+pub const HANGING_PIECES: S = s!(28,16);
+
+pub const PASSED_PAWN_OWN_KING_DISTANCE: [S; 8] = [
+    s!(0,8), s!(4,18), s!(2,10), s!(0,2), s!(-2,-6), s!(-4,-12), s!(-6,-16), s!(-6,-20), 
+];
+pub const PASSED_PAWN_ENEMY_KING_DISTANCE: [S; 8] = [
+    s!(0,-30), s!(-2,-20), s!(0,-8), s!(2,2), s!(4,12), s!(4,20), s!(4,26), s!(4,30), 
+];
+pub const PASSED_PAWN_BLOCKED: S = s!(-8,-22);
+pub const PASSED_PAWN_DEFENDED_STOP: S = s!(6,14);
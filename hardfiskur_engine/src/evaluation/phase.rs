@@ -1,12 +1,49 @@
-use hardfiskur_core::board::PieceType;
+use hardfiskur_core::board::{Board, PieceType};
 
 use super::packed_score::PackedScore;
 
+/// Curves that can be used to remap a [`Phase`] before tapering, for
+/// experimenting with how quickly evaluation shifts from the midgame to the
+/// endgame terms.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaperCurve {
+    /// Taper proportionally to the non-pawn material left on the board.
+    #[default]
+    Linear,
+    /// Stay closer to the midgame or endgame values near either end, and
+    /// change fastest in between.
+    Smoothstep,
+}
+
+/// How far the game has progressed, measured by the non-pawn material on the
+/// board, for interpolating between midgame and endgame evaluation terms.
+///
+/// Ranges from 0 (only kings and pawns left, i.e. a pure endgame) to
+/// [`Phase::MAX`] (all pieces still on the board). Phases above the max, e.g.
+/// after promotions, taper as if they were a pure midgame.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Phase(pub i32);
 
 impl Phase {
-    pub const FULL_ENDGAME_PHASE: i32 = 24;
+    /// The phase of the starting position: 4 knights and 4 bishops worth 1
+    /// each, 4 rooks worth 2 each, and 2 queens worth 4 each.
+    pub const MAX: i32 = 24;
+
+    #[deprecated(note = "renamed to `Phase::MAX`, as it's the pure midgame end of the range")]
+    pub const FULL_ENDGAME_PHASE: i32 = Self::MAX;
+
+    /// Computes the phase of the position from the pieces on the board.
+    pub fn from_board(board: &Board) -> Self {
+        let repr = board.repr();
+        let phase = PieceType::ALL
+            .into_iter()
+            .map(|piece_type| {
+                let (white, black) = repr.piece_count(piece_type);
+                Self::phase_modifier(piece_type) * (white + black) as i32
+            })
+            .sum();
+        Self(phase)
+    }
 
     pub fn phase_modifier(piece_type: impl Into<PieceType>) -> i32 {
         match piece_type.into() {
@@ -22,12 +59,68 @@ impl Phase {
         self.0 += Self::phase_modifier(piece_type);
     }
 
+    /// The phase as a fraction, from 0.0 for a pure endgame to 1.0 for a
+    /// pure midgame.
+    pub fn fraction(&self) -> f32 {
+        self.0.clamp(0, Self::MAX) as f32 / Self::MAX as f32
+    }
+
+    /// Remaps this phase along the given [`TaperCurve`], so that tapering with
+    /// the result follows the curve instead of being linear.
+    pub fn with_curve(self, curve: TaperCurve) -> Self {
+        match curve {
+            TaperCurve::Linear => self,
+            TaperCurve::Smoothstep => {
+                let x = self.0.clamp(0, Self::MAX);
+                Self(x * x * (3 * Self::MAX - 2 * x) / (Self::MAX * Self::MAX))
+            }
+        }
+    }
+
     pub fn taper(&self, midgame_eval: i32, endgame_eval: i32) -> i32 {
-        (midgame_eval * self.0 + endgame_eval * (Self::FULL_ENDGAME_PHASE - self.0))
-            / Self::FULL_ENDGAME_PHASE
+        let phase = self.0.clamp(0, Self::MAX);
+        (midgame_eval * phase + endgame_eval * (Self::MAX - phase)) / Self::MAX
     }
 
     pub fn taper_packed(&self, packed_score: PackedScore) -> i32 {
         self.taper(packed_score.mg(), packed_score.eg())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn starting_position_is_max_phase() {
+        let board = Board::starting_position();
+        assert_eq!(Phase::from_board(&board), Phase(Phase::MAX));
+        assert_eq!(Phase::from_board(&board).fraction(), 1.0);
+
+        let board = Board::try_parse_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap();
+        assert_eq!(Phase::from_board(&board), Phase(0));
+        assert_eq!(Phase::from_board(&board).fraction(), 0.0);
+    }
+
+    #[test]
+    fn smoothstep_curve_keeps_endpoints_and_midpoint() {
+        for phase in [0, Phase::MAX / 2, Phase::MAX] {
+            assert_eq!(
+                Phase(phase).with_curve(TaperCurve::Smoothstep),
+                Phase(phase)
+            );
+        }
+
+        assert!(Phase(6).with_curve(TaperCurve::Smoothstep) < Phase(6));
+        assert!(Phase(18).with_curve(TaperCurve::Smoothstep) > Phase(18));
+    }
+
+    #[test]
+    fn taper_clamps_phases_outside_the_range() {
+        // e.g. after promoting to extra queens
+        assert_eq!(Phase(Phase::MAX + 8).taper(100, -100), 100);
+        assert_eq!(Phase(-4).taper(100, -100), -100);
+        assert_eq!(Phase(Phase::MAX / 2).taper(100, -100), 0);
+    }
+}
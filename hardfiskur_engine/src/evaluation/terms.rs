@@ -4,11 +4,15 @@ use super::{
     lookups::{PAWN_SHIELD_CLOSE_MASKS, PAWN_SHIELD_FAR_MASKS, SENSIBLE_KING_MASKS},
     packed_score::S,
     parameters::*,
-    template_params::{ColorParam, PieceTypeParam},
+    template_params::{ColorParam, PieceTypeParam, Rook},
     trace::Trace,
     EvalContext,
 };
 
+/// Attack units contributed by each piece type attacking the enemy king zone,
+/// for [`EvalContext::king_safety`].
+const KING_ATTACKER_WEIGHTS: [usize; 6] = [0, 2, 2, 3, 5, 0];
+
 impl<'a> EvalContext<'a> {
     #[inline]
     pub fn material<C: ColorParam>(&self, piece_type: PieceType, trace: &mut impl Trace) -> S {
@@ -163,19 +167,50 @@ impl<'a> EvalContext<'a> {
         C::SIGN * VIRTUAL_MOBILITY[virtual_mobility]
     }
 
+    /// Passed pawns are scored by square, which covers their distance to
+    /// promotion, as well as by how close each king is to the square in
+    /// front of the pawn and whether that square is blocked or defended.
     #[inline]
     pub fn passed_pawns<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
         let mut total = S::ZERO;
 
+        let own_king = self.kings[C::INDEX];
+        let enemy_king = self.kings[C::Flip::INDEX];
+        let enemy_pieces = self.board.get_bitboard_for_color(C::Flip::COLOR);
+
         for square in self.pawns.passed_pawns[C::INDEX].squares() {
+            let stop_square = square.offset(if C::IS_WHITE { 8 } else { -8 });
+            let own_distance = own_king.chebyshev_distance(stop_square) as usize;
+            let enemy_distance = enemy_king.chebyshev_distance(stop_square) as usize;
+            let blocked = enemy_pieces.get(stop_square);
+            let defended = self.attacks[C::INDEX].get(stop_square);
+
             let square = if C::IS_WHITE { square.flip() } else { square };
 
-            trace.add(|t| t.passed_pawns[square.index()] += C::COEFF);
+            trace.add(|t| {
+                t.passed_pawns[square.index()] += C::COEFF;
+                t.passed_pawn_own_king_distance[own_distance] += C::COEFF;
+                t.passed_pawn_enemy_king_distance[enemy_distance] += C::COEFF;
+                if blocked {
+                    t.passed_pawn_blocked += C::COEFF;
+                }
+                if defended {
+                    t.passed_pawn_defended_stop += C::COEFF;
+                }
+            });
 
-            total += C::SIGN * PASSED_PAWNS[square.index()];
+            total += PASSED_PAWNS[square.index()]
+                + PASSED_PAWN_OWN_KING_DISTANCE[own_distance]
+                + PASSED_PAWN_ENEMY_KING_DISTANCE[enemy_distance];
+            if blocked {
+                total += PASSED_PAWN_BLOCKED;
+            }
+            if defended {
+                total += PASSED_PAWN_DEFENDED_STOP;
+            }
         }
 
-        total
+        C::SIGN * total
     }
 
     // Weirdly, setting #[inline(never)] here compiles the method into a
@@ -251,6 +286,49 @@ impl<'a> EvalContext<'a> {
         }
     }
 
+    pub fn king_open_files<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        // Open lines near the king only matter if the opponent has rooks or
+        // queens to use them, so scale the penalty by how many they have.
+        let enemy_heavy_pieces = (self
+            .board
+            .get_bitboard_for_piece(Piece::rook(C::COLOR.flip()))
+            | self
+                .board
+                .get_bitboard_for_piece(Piece::queen(C::COLOR.flip())))
+        .pop_count() as i32;
+
+        if enemy_heavy_pieces == 0 {
+            return S::ZERO;
+        }
+
+        let king_file = self.kings[C::INDEX].file();
+        let mut open_files = 0;
+        let mut semi_open_files = 0;
+
+        for file in king_file.saturating_sub(1)..=(king_file + 1).min(7) {
+            let square = Square::new_unchecked(0, file);
+            if !self.pawns.semi_open_files[C::INDEX].get(square) {
+                continue;
+            }
+
+            if self.pawns.semi_open_files[C::COLOR.flip().index()].get(square) {
+                open_files += 1;
+            } else {
+                semi_open_files += 1;
+            }
+        }
+
+        let open_files = open_files * enemy_heavy_pieces;
+        let semi_open_files = semi_open_files * enemy_heavy_pieces;
+
+        trace.add(|t| {
+            t.king_open_files += C::COEFF * open_files as i16;
+            t.king_semi_open_files += C::COEFF * semi_open_files as i16;
+        });
+
+        C::SIGN * (KING_OPEN_FILES * open_files + KING_SEMI_OPEN_FILES * semi_open_files)
+    }
+
     pub fn knight_outposts<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
         let knights_in_outposts = self.pawns.outposts[C::INDEX]
             & self.board.get_bitboard_for_piece(Piece::knight(C::COLOR));
@@ -270,4 +348,588 @@ impl<'a> EvalContext<'a> {
 
         C::SIGN * BISHOP_OUTPOSTS * count
     }
+
+    pub fn material_imbalance<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let knights = self
+            .board
+            .get_bitboard_for_piece(Piece::knight(C::COLOR))
+            .pop_count() as i32;
+        let rooks = self
+            .board
+            .get_bitboard_for_piece(Piece::rook(C::COLOR))
+            .pop_count() as i32;
+
+        // A second knight or rook adds less than the first, as the pieces
+        // largely duplicate each other's roles.
+        let knight_pair = i32::from(knights >= 2);
+        let rook_pair = i32::from(rooks >= 2);
+
+        // Knights gain value in closed positions with many pawns, while rooks
+        // want open lines and so lose value. Measured relative to 5 pawns.
+        let pawn_delta = self.pawns.pawns[C::INDEX].pop_count() as i32 - 5;
+        let knight_pawns = knights * pawn_delta;
+        let rook_pawns = rooks * pawn_delta;
+
+        trace.add(|t| {
+            t.knight_pair += C::COEFF * knight_pair as i16;
+            t.rook_pair += C::COEFF * rook_pair as i16;
+            t.knight_pawn_adjustment += C::COEFF * knight_pawns as i16;
+            t.rook_pawn_adjustment += C::COEFF * rook_pawns as i16;
+        });
+
+        C::SIGN
+            * (KNIGHT_PAIR * knight_pair
+                + ROOK_PAIR * rook_pair
+                + KNIGHT_PAWN_ADJUSTMENT * knight_pawns
+                + ROOK_PAWN_ADJUSTMENT * rook_pawns)
+    }
+
+    pub fn king_flight_square_attacks<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        // The squares the enemy king could step to if it needed to escape
+        let flight_squares =
+            self.lookups.get_king_moves(self.kings[C::Flip::INDEX]) & !self.occupied;
+        if flight_squares.is_empty() {
+            return S::ZERO;
+        }
+
+        let rooks = self.board.get_bitboard_for_piece(Piece::rook(C::COLOR));
+        let queens = self.board.get_bitboard_for_piece(Piece::queen(C::COLOR));
+
+        let heavy_attacks = (rooks | queens)
+            .squares()
+            .map(|square| self.lookups.get_rook_attacks(self.occupied, square))
+            .chain(
+                queens
+                    .squares()
+                    .map(|square| self.lookups.get_bishop_attacks(self.occupied, square)),
+            )
+            .fold(Bitboard::EMPTY, Bitboard::or);
+
+        let count = (flight_squares & heavy_attacks).pop_count() as i32;
+
+        trace.add(|t| t.king_flight_square_attacks += C::COEFF * count as i16);
+
+        C::SIGN * KING_FLIGHT_SQUARE_ATTACKS * count
+    }
+
+    /// Small bonus for being the side to move, as it can usually improve its
+    /// position by at least this much.
+    pub fn tempo<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        if self.board.to_move() != C::COLOR {
+            return S::ZERO;
+        }
+
+        trace.add(|t| t.tempo += C::COEFF);
+
+        C::SIGN * TEMPO
+    }
+
+    pub fn bishop_pair<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let (white_bishops, black_bishops) = self.board.repr().piece_count(PieceType::Bishop);
+        let bishops = if C::IS_WHITE {
+            white_bishops
+        } else {
+            black_bishops
+        };
+
+        if bishops < 2 {
+            return S::ZERO;
+        }
+
+        trace.add(|t| t.bishop_pair += C::COEFF);
+
+        C::SIGN * BISHOP_PAIR
+    }
+
+    /// Rooks on the relative 7th rank attack pawns that haven't moved yet and
+    /// can trap the enemy king on the back rank.
+    pub fn rook_on_seventh<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let seventh_rank = Bitboard::rank_mask(if C::IS_WHITE { 6 } else { 1 });
+        let rooks = self
+            .board
+            .get_bitboard_for_piece(Piece::new(C::COLOR, Rook::PIECE_TYPE));
+
+        let count = (rooks & seventh_rank).pop_count() as i32;
+
+        trace.add(|t| t.rook_on_seventh += C::COEFF * count as i16);
+
+        C::SIGN * ROOK_ON_SEVENTH * count
+    }
+
+    /// Penalty for C's king, which grows non-linearly with the weighted
+    /// number of enemy pieces attacking its king zone, so that a single
+    /// attacker is harmless but several together are dangerous.
+    pub fn king_safety<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let king_zone = self.king_zones[C::INDEX];
+        let enemy = C::Flip::COLOR;
+
+        let mut attack_units = 0;
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            let pieces = self
+                .board
+                .get_bitboard_for_piece(Piece::new(enemy, piece_type));
+
+            for square in pieces.squares() {
+                let attacks = match piece_type {
+                    PieceType::Knight => self.lookups.get_knight_moves(square),
+                    PieceType::Bishop => self.lookups.get_bishop_attacks(self.occupied, square),
+                    PieceType::Rook => self.lookups.get_rook_attacks(self.occupied, square),
+                    _ => self.lookups.get_queen_attacks(self.occupied, square),
+                };
+
+                if (attacks & king_zone).has_piece() {
+                    attack_units += KING_ATTACKER_WEIGHTS[piece_type.index()];
+                }
+            }
+        }
+
+        if attack_units == 0 {
+            return S::ZERO;
+        }
+
+        let idx = attack_units.min(KING_SAFETY.len() - 1);
+
+        trace.add(|t| t.king_safety[idx] += C::COEFF);
+
+        C::SIGN * KING_SAFETY[idx]
+    }
+
+    /// Bonus for C attacking enemy pieces with less valuable ones, and for
+    /// enemy pieces that are attacked but not defended at all.
+    pub fn threats<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let enemy = C::Flip::COLOR;
+        let enemy_pieces = |piece_type| {
+            self.board
+                .get_bitboard_for_piece(Piece::new(enemy, piece_type))
+        };
+
+        let pawn_attacks = self.pawns.pawn_attacks[C::INDEX];
+        let minor_attacks = [PieceType::Knight, PieceType::Bishop]
+            .into_iter()
+            .flat_map(|piece_type| {
+                self.board
+                    .get_bitboard_for_piece(Piece::new(C::COLOR, piece_type))
+                    .squares()
+                    .map(move |square| (piece_type, square))
+            })
+            .map(|(piece_type, square)| match piece_type {
+                PieceType::Knight => self.lookups.get_knight_moves(square),
+                _ => self.lookups.get_bishop_attacks(self.occupied, square),
+            })
+            .fold(Bitboard::EMPTY, Bitboard::or);
+
+        let mut total = S::ZERO;
+
+        for piece_type in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ] {
+            let idx = piece_type.index();
+            let victims = enemy_pieces(piece_type);
+
+            let pawn_threats = (victims & pawn_attacks).pop_count() as i32;
+            trace.add(|t| t.pawn_threats[idx] += C::COEFF * pawn_threats as i16);
+            total += PAWN_THREATS[idx] * pawn_threats;
+
+            if matches!(piece_type, PieceType::Rook | PieceType::Queen) {
+                let minor_threats = (victims & minor_attacks).pop_count() as i32;
+                trace.add(|t| t.minor_threats[idx] += C::COEFF * minor_threats as i16);
+                total += MINOR_THREATS[idx] * minor_threats;
+            }
+        }
+
+        let hanging = self.board.get_bitboard_for_color(enemy)
+            & !enemy_pieces(PieceType::Pawn)
+            & !enemy_pieces(PieceType::King)
+            & self.attacks[C::INDEX]
+            & !self.attacks[C::Flip::INDEX];
+        let hanging_count = hanging.pop_count() as i32;
+
+        trace.add(|t| t.hanging_pieces += C::COEFF * hanging_count as i16);
+        total += HANGING_PIECES * hanging_count;
+
+        C::SIGN * total
+    }
+
+    /// Bonus for a pair of rooks defending each other along a rank or file.
+    pub fn connected_rooks<C: ColorParam>(&self, trace: &mut impl Trace) -> S {
+        let rooks = self
+            .board
+            .get_bitboard_for_piece(Piece::new(C::COLOR, Rook::PIECE_TYPE));
+
+        let connected = rooks.squares().any(|square| {
+            (self.lookups.get_rook_attacks(self.occupied, square) & rooks).has_piece()
+        });
+        if !connected {
+            return S::ZERO;
+        }
+
+        trace.add(|t| t.connected_rooks += C::COEFF);
+
+        C::SIGN * CONNECTED_ROOKS
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::{Board, STARTING_POSITION_FEN};
+
+    use crate::evaluation::{
+        evaluate_for_white,
+        template_params::{Bishop, Black, Knight, White},
+        trace::{EvalTrace, NullTrace},
+    };
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn imbalance<C: ColorParam>(fen: &str) -> S {
+        let board = Board::try_parse_fen(fen).unwrap();
+        EvalContext::new(&board).material_imbalance::<C>(&mut NullTrace)
+    }
+
+    fn king_open_files<C: ColorParam>(fen: &str) -> S {
+        let board = Board::try_parse_fen(fen).unwrap();
+        EvalContext::new(&board).king_open_files::<C>(&mut NullTrace)
+    }
+
+    #[test]
+    fn passed_pawn_king_distance_and_stop_square() {
+        let board = Board::try_parse_fen("4k3/8/8/3KP3/8/8/8/8 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).passed_pawns::<White>(&mut trace);
+
+        // The stop square e6 is next to the white king, which also defends
+        // it, and two squares from the black king
+        assert_eq!(trace.passed_pawn_own_king_distance[1], 1);
+        assert_eq!(trace.passed_pawn_enemy_king_distance[2], 1);
+        assert_eq!(trace.passed_pawn_defended_stop, 1);
+        assert_eq!(trace.passed_pawn_blocked, 0);
+
+        let blockaded = Board::try_parse_fen("8/8/4k3/3KP3/8/8/8/8 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&blockaded).passed_pawns::<White>(&mut trace);
+        assert_eq!(trace.passed_pawn_enemy_king_distance[0], 1);
+        assert_eq!(trace.passed_pawn_blocked, 1);
+
+        let passed_pawns = |fen: &str| {
+            let board = Board::try_parse_fen(fen).unwrap();
+            EvalContext::new(&board).passed_pawns::<White>(&mut NullTrace)
+        };
+        let far_enemy_king = passed_pawns("8/8/8/3KP3/8/8/8/k7 w - - 0 1");
+        assert!(far_enemy_king.eg() > passed_pawns("4k3/8/8/3KP3/8/8/8/8 w - - 0 1").eg());
+        assert!(far_enemy_king.eg() > passed_pawns("8/8/4k3/3KP3/8/8/8/8 w - - 0 1").eg());
+    }
+
+    #[test]
+    fn passed_pawn_terms_are_symmetric() {
+        let white = Board::try_parse_fen("8/8/4k3/3KP3/8/8/8/8 w - - 0 1").unwrap();
+        let black = Board::try_parse_fen("8/8/8/8/3kp3/4K3/8/8 b - - 0 1").unwrap();
+
+        let white = EvalContext::new(&white).passed_pawns::<White>(&mut NullTrace);
+        let black = EvalContext::new(&black).passed_pawns::<Black>(&mut NullTrace);
+        assert!(white == -black);
+    }
+
+    #[test]
+    fn mobility_counts_safe_squares_per_piece_type() {
+        // The knight on d4 can't go to e2 (own pawn), or c6 and e6 (attacked
+        // by the d7 pawn), leaving 5 safe squares
+        let board = Board::try_parse_fen("7k/3p4/8/8/3N4/8/4P3/7K w - - 0 1").unwrap();
+        let eval_context = EvalContext::new(&board);
+
+        let mut trace = EvalTrace::default();
+        let score = eval_context.mobility_and_king_zone_attacks::<White, Knight>(&mut trace);
+
+        let mut expected = EvalTrace::default();
+        expected.knight_mobility[5] = 1;
+        assert_eq!(trace.knight_mobility, expected.knight_mobility);
+        assert_eq!(trace.bishop_mobility, expected.bishop_mobility);
+        assert!(score == KNIGHT_MOBILITY[5]);
+
+        // Each piece type is scored from its own table
+        let board = Board::try_parse_fen("7k/3p4/8/8/3B4/8/4P3/7K w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).mobility_and_king_zone_attacks::<White, Bishop>(&mut trace);
+        assert_eq!(trace.knight_mobility, [0; 9]);
+        assert_eq!(trace.bishop_mobility.iter().sum::<i16>(), 1);
+    }
+
+    #[test]
+    fn king_on_open_file_is_penalised_more_than_behind_pawns() {
+        let sheltered = king_open_files::<White>("r5k1/ppp2ppp/8/8/8/8/PPP2PPP/6K1 w - - 0 1");
+        let exposed = king_open_files::<White>("r5k1/ppp2ppp/8/8/8/8/PPPPP3/6K1 w - - 0 1");
+
+        assert_eq!((sheltered.mg(), sheltered.eg()), (0, 0));
+        assert!(exposed.mg() < sheltered.mg());
+    }
+
+    #[test]
+    fn king_open_files_scales_with_enemy_heavy_pieces() {
+        let no_heavies = king_open_files::<White>("6k1/ppp5/8/8/8/8/PPPPP3/6K1 w - - 0 1");
+        let one_rook = king_open_files::<White>("r5k1/ppp5/8/8/8/8/PPPPP3/6K1 w - - 0 1");
+        let rook_and_queen = king_open_files::<White>("rq4k1/ppp5/8/8/8/8/PPPPP3/6K1 w - - 0 1");
+
+        assert_eq!((no_heavies.mg(), no_heavies.eg()), (0, 0));
+        assert!(one_rook.mg() < 0);
+        assert_eq!(rook_and_queen.mg(), one_rook.mg() * 2);
+    }
+
+    #[test]
+    fn king_open_files_trace() {
+        // f-file semi-open for white (black pawn on f7), g and h fully open
+        let board = Board::try_parse_fen("r5k1/ppppp3/5p2/8/8/8/PPPPP3/6K1 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).king_open_files::<White>(&mut trace);
+
+        assert_eq!(trace.king_open_files, 2);
+        assert_eq!(trace.king_semi_open_files, 1);
+    }
+
+    #[test]
+    fn two_rooks_are_worth_less_than_twice_one_rook() {
+        let rook = MATERIAL[PieceType::Rook.index()];
+
+        // Five pawns each, so the pawn adjustment doesn't come into play
+        let one_rook = rook + imbalance::<White>("4k3/ppppp3/8/8/8/8/PPPPP3/R3K3 w - - 0 1");
+        let two_rooks = rook * 2 + imbalance::<White>("4k3/ppppp3/8/8/8/8/PPPPP3/R3K2R w - - 0 1");
+
+        assert!(two_rooks.mg() < one_rook.mg() * 2);
+        assert!(two_rooks.eg() < one_rook.eg() * 2);
+        assert!(two_rooks.mg() > one_rook.mg());
+        assert!(two_rooks.eg() > one_rook.eg());
+    }
+
+    #[test]
+    fn rook_value_decreases_with_more_pawns() {
+        let few_pawns = imbalance::<White>("4k3/8/8/8/8/8/PPP5/R3K3 w - - 0 1");
+        let many_pawns = imbalance::<White>("4k3/8/8/8/8/8/PPPPPPP1/R3K3 w - - 0 1");
+
+        assert!(few_pawns.mg() > many_pawns.mg());
+        assert!(few_pawns.eg() > many_pawns.eg());
+    }
+
+    #[test]
+    fn knight_value_increases_with_more_pawns() {
+        let few_pawns = imbalance::<White>("4k3/8/8/8/8/8/PPP5/1N2K3 w - - 0 1");
+        let many_pawns = imbalance::<White>("4k3/8/8/8/8/8/PPPPPPP1/1N2K3 w - - 0 1");
+
+        assert!(few_pawns.mg() < many_pawns.mg());
+        assert!(few_pawns.eg() < many_pawns.eg());
+    }
+
+    #[test]
+    fn material_imbalance_is_symmetric() {
+        let white = imbalance::<White>("4k3/ppp5/8/8/8/8/PPPPPPP1/RN2K1NR w - - 0 1");
+        let black = imbalance::<Black>("rn2k1nr/ppppppp1/8/8/8/8/PPP5/4K3 w - - 0 1");
+
+        assert_eq!(white.mg(), -black.mg());
+        assert_eq!(white.eg(), -black.eg());
+    }
+
+    #[test]
+    fn material_imbalance_trace() {
+        let board = Board::try_parse_fen("4k3/8/8/8/8/8/PPP5/RN2K1NR w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).material_imbalance::<White>(&mut trace);
+
+        assert_eq!(trace.knight_pair, 1);
+        assert_eq!(trace.rook_pair, 1);
+        assert_eq!(trace.knight_pawn_adjustment, -4);
+        assert_eq!(trace.rook_pawn_adjustment, -4);
+    }
+
+    fn king_flight_square_attacks<C: ColorParam>(fen: &str) -> S {
+        let board = Board::try_parse_fen(fen).unwrap();
+        EvalContext::new(&board).king_flight_square_attacks::<C>(&mut NullTrace)
+    }
+
+    #[test]
+    fn restricting_king_flight_squares_increases_eval() {
+        // The rook on a7 cuts off the black king's flight squares on the 7th
+        // rank, while on a1 it only covers squares far from the king
+        let free = "6k1/8/8/8/8/8/8/R5K1 w - - 0 1";
+        let restricted = "6k1/R7/8/8/8/8/8/6K1 w - - 0 1";
+
+        let free_score = king_flight_square_attacks::<White>(free);
+        let restricted_score = king_flight_square_attacks::<White>(restricted);
+        assert_eq!((free_score.mg(), free_score.eg()), (0, 0));
+        assert!(restricted_score.mg() > free_score.mg());
+
+        let free_board = Board::try_parse_fen(free).unwrap();
+        let restricted_board = Board::try_parse_fen(restricted).unwrap();
+        assert!(evaluate_for_white(&restricted_board).0 > evaluate_for_white(&free_board).0);
+    }
+
+    #[test]
+    fn king_flight_square_attacks_trace() {
+        // Rook on a7 covers f7, g7 and h7, and the queen on d6 covers f8
+        // diagonally, leaving h8 as the only free flight square
+        let board = Board::try_parse_fen("6k1/R7/3Q4/8/8/8/8/6K1 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).king_flight_square_attacks::<White>(&mut trace);
+
+        assert_eq!(trace.king_flight_square_attacks, 4);
+
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).king_flight_square_attacks::<Black>(&mut trace);
+        assert_eq!(trace.king_flight_square_attacks, 0);
+    }
+
+    #[test]
+    fn tempo_only_for_side_to_move() {
+        let board = Board::starting_position();
+        let context = EvalContext::new(&board);
+
+        assert!(context.tempo::<White>(&mut NullTrace) == TEMPO);
+        assert!(context.tempo::<Black>(&mut NullTrace) == S::ZERO);
+
+        // Being the side to move is worth the same to both sides
+        let board =
+            Board::try_parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        assert!(EvalContext::new(&board).tempo::<Black>(&mut NullTrace) == -TEMPO);
+    }
+
+    #[test]
+    fn bishop_pair_trace() {
+        // White has both bishops, black has traded one for a knight
+        let board = Board::try_parse_fen("rn1qk1nr/8/8/8/8/8/8/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        let mut trace = EvalTrace::default();
+        let context = EvalContext::new(&board);
+        let white = context.bishop_pair::<White>(&mut trace);
+        let black = context.bishop_pair::<Black>(&mut trace);
+
+        assert!(white == BISHOP_PAIR);
+        assert!(black == S::ZERO);
+        assert_eq!(trace.bishop_pair, 1);
+    }
+
+    #[test]
+    fn rook_on_seventh_trace() {
+        // White has rooks on a7 and h1, black has rooks on b2 and c2
+        let board = Board::try_parse_fen("4k3/R7/8/8/8/8/1rr5/4K2R w - - 0 1").unwrap();
+        let context = EvalContext::new(&board);
+
+        let mut trace = EvalTrace::default();
+        context.rook_on_seventh::<White>(&mut trace);
+        assert_eq!(trace.rook_on_seventh, 1);
+
+        let mut trace = EvalTrace::default();
+        context.rook_on_seventh::<Black>(&mut trace);
+        assert_eq!(trace.rook_on_seventh, -2);
+    }
+
+    #[test]
+    fn connected_rooks_trace() {
+        let connected = Board::try_parse_fen("3rr1k1/8/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+        let context = EvalContext::new(&connected);
+
+        // White's rooks are blocked by the king, black's are next to each other
+        let mut trace = EvalTrace::default();
+        context.connected_rooks::<White>(&mut trace);
+        assert_eq!(trace.connected_rooks, 0);
+
+        let mut trace = EvalTrace::default();
+        context.connected_rooks::<Black>(&mut trace);
+        assert_eq!(trace.connected_rooks, -1);
+
+        // Connected along a file
+        let on_file = Board::try_parse_fen("6k1/8/8/8/R7/8/8/R5K1 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&on_file).connected_rooks::<White>(&mut trace);
+        assert_eq!(trace.connected_rooks, 1);
+    }
+
+    fn king_safety_trace<C: ColorParam>(fen: &str) -> [i16; 20] {
+        let board = Board::try_parse_fen(fen).unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).king_safety::<C>(&mut trace);
+        trace.king_safety
+    }
+
+    #[test]
+    fn king_safety_without_attackers_is_zero() {
+        let board = Board::starting_position();
+        let context = EvalContext::new(&board);
+
+        assert!(context.king_safety::<White>(&mut NullTrace) == S::ZERO);
+        assert!(context.king_safety::<Black>(&mut NullTrace) == S::ZERO);
+        assert_eq!(king_safety_trace::<White>(STARTING_POSITION_FEN), [0; 20]);
+    }
+
+    #[test]
+    fn king_safety_counts_weighted_attackers() {
+        // Queen on h5 and knight on g5 both attack the black king zone
+        let fen = "rnbqkb1r/pppp1ppp/5n2/4p1NQ/8/8/PPPPPPPP/RNB1KB1R b KQkq - 0 1";
+        let mut expected = [0; 20];
+        expected[5 + 2] = -1;
+        assert_eq!(king_safety_trace::<Black>(fen), expected);
+
+        let board = Board::try_parse_fen(fen).unwrap();
+        assert!(EvalContext::new(&board).king_safety::<Black>(&mut NullTrace) == -KING_SAFETY[7]);
+    }
+
+    #[test]
+    fn king_safety_saturates() {
+        // Far more attackers than the table has entries for
+        let fen = "6k1/8/4QQQ1/4QQQ1/8/8/8/K7 w - - 0 1";
+        let mut expected = [0; 20];
+        expected[19] = -1;
+        assert_eq!(king_safety_trace::<Black>(fen), expected);
+    }
+
+    #[test]
+    fn king_safety_is_symmetric() {
+        let white = Board::try_parse_fen("6k1/5ppp/8/6N1/7Q/8/5PPP/6K1 w - - 0 1").unwrap();
+        let black = Board::try_parse_fen("6k1/5ppp/8/7q/6n1/8/5PPP/6K1 b - - 0 1").unwrap();
+
+        let white_attacks = EvalContext::new(&white).king_safety::<Black>(&mut NullTrace);
+        let black_attacks = EvalContext::new(&black).king_safety::<White>(&mut NullTrace);
+
+        assert!(white_attacks != S::ZERO);
+        assert!(white_attacks == -black_attacks);
+    }
+
+    #[test]
+    fn threats_trace() {
+        // The e4 pawn forks the knight on d5 and the rook on f5, the bishop on
+        // b2 attacks the queen on g7, and only the rook is undefended
+        let board = Board::try_parse_fen("6k1/6q1/8/3n1r2/4P3/8/1B6/4K3 w - - 0 1").unwrap();
+        let mut trace = EvalTrace::default();
+        EvalContext::new(&board).threats::<White>(&mut trace);
+
+        assert_eq!(trace.pawn_threats, [0, 1, 0, 1, 0, 0]);
+        assert_eq!(trace.minor_threats, [0, 0, 0, 0, 1, 0]);
+        assert_eq!(trace.hanging_pieces, 1);
+    }
+
+    #[test]
+    fn threats_are_symmetric() {
+        let white = Board::try_parse_fen("6k1/6q1/8/3n1r2/4P3/8/1B6/4K3 w - - 0 1").unwrap();
+        let black = Board::try_parse_fen("4k3/1b6/8/4p3/3N1R2/8/6Q1/6K1 b - - 0 1").unwrap();
+
+        let white_threats = EvalContext::new(&white).threats::<White>(&mut NullTrace);
+        let black_threats = EvalContext::new(&black).threats::<Black>(&mut NullTrace);
+
+        assert!(white_threats != S::ZERO);
+        assert!(white_threats == -black_threats);
+    }
+
+    #[test]
+    fn no_threats_in_starting_position() {
+        let board = Board::starting_position();
+        let context = EvalContext::new(&board);
+
+        assert!(context.threats::<White>(&mut NullTrace) == S::ZERO);
+        assert!(context.threats::<Black>(&mut NullTrace) == S::ZERO);
+    }
 }
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use zerocopy::FromZeros;
+use zerocopy::{transmute_mut, transmute_ref, FromZeros};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes};
 
 use super::{packed_score::PackedScore, parameters::*};
@@ -16,7 +16,7 @@ impl Trace for NullTrace {
     fn add(&mut self, _f: impl Fn(&mut EvalTrace)) {}
 }
 
-#[derive(Debug, Clone, FromBytes, IntoBytes)]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable)]
 #[repr(C)]
 pub struct EvalTrace {
     pub material: [i16; 6],
@@ -43,6 +43,8 @@ pub struct EvalTrace {
 
     pub pawn_shield_close: i16,
     pub pawn_shield_far: i16,
+    pub king_open_files: i16,
+    pub king_semi_open_files: i16,
 
     pub semi_open_file_bonuses: [i16; 3],
     pub open_file_bonuses: [i16; 3],
@@ -51,6 +53,30 @@ pub struct EvalTrace {
     pub bishop_outposts: i16,
 
     pub king_zone_attacks: [i16; 6],
+
+    pub knight_pair: i16,
+    pub rook_pair: i16,
+    pub knight_pawn_adjustment: i16,
+    pub rook_pawn_adjustment: i16,
+
+    pub king_flight_square_attacks: i16,
+
+    pub tempo: i16,
+    pub bishop_pair: i16,
+
+    pub rook_on_seventh: i16,
+    pub connected_rooks: i16,
+
+    pub king_safety: [i16; 20],
+
+    pub pawn_threats: [i16; 6],
+    pub minor_threats: [i16; 6],
+    pub hanging_pieces: i16,
+
+    pub passed_pawn_own_king_distance: [i16; 8],
+    pub passed_pawn_enemy_king_distance: [i16; 8],
+    pub passed_pawn_blocked: i16,
+    pub passed_pawn_defended_stop: i16,
 }
 
 impl EvalTrace {
@@ -69,6 +95,56 @@ impl Trace for EvalTrace {
     }
 }
 
+/// The nonzero coefficients of an [`EvalTrace`], as `(index, value)` pairs
+/// in order of index. Most coefficients are zero for any given position, so
+/// this is far more compact for storing many traces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseTrace {
+    entries: Box<[(u16, i16)]>,
+}
+
+impl SparseTrace {
+    pub fn new(trace: &EvalTrace) -> Self {
+        let coeffs: &[i16; EvalTrace::LEN] = transmute_ref!(trace);
+
+        Self {
+            entries: coeffs
+                .iter()
+                .enumerate()
+                .filter(|(_, &value)| value != 0)
+                .map(|(index, &value)| (index as u16, value))
+                .collect(),
+        }
+    }
+
+    pub fn entries(&self) -> &[(u16, i16)] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn to_dense(&self) -> EvalTrace {
+        let mut trace = EvalTrace::default();
+        let coeffs: &mut [i16; EvalTrace::LEN] = transmute_mut!(&mut trace);
+        for &(index, value) in self.entries() {
+            coeffs[index as usize] = value;
+        }
+        trace
+    }
+}
+
+impl From<&EvalTrace> for SparseTrace {
+    fn from(trace: &EvalTrace) -> Self {
+        Self::new(trace)
+    }
+}
+
 pub type Parameter = [f64; 2];
 
 #[derive(Debug, Clone, FromBytes, IntoBytes, Immutable)]
@@ -98,6 +174,8 @@ pub struct EvalParameters {
 
     pub pawn_shield_close: Parameter,
     pub pawn_shield_far: Parameter,
+    pub king_open_files: Parameter,
+    pub king_semi_open_files: Parameter,
 
     pub semi_open_file_bonuses: [Parameter; 3],
     pub open_file_bonuses: [Parameter; 3],
@@ -106,6 +184,30 @@ pub struct EvalParameters {
     pub bishop_outposts: Parameter,
 
     pub king_zone_attacks: [Parameter; 6],
+
+    pub knight_pair: Parameter,
+    pub rook_pair: Parameter,
+    pub knight_pawn_adjustment: Parameter,
+    pub rook_pawn_adjustment: Parameter,
+
+    pub king_flight_square_attacks: Parameter,
+
+    pub tempo: Parameter,
+    pub bishop_pair: Parameter,
+
+    pub rook_on_seventh: Parameter,
+    pub connected_rooks: Parameter,
+
+    pub king_safety: [Parameter; 20],
+
+    pub pawn_threats: [Parameter; 6],
+    pub minor_threats: [Parameter; 6],
+    pub hanging_pieces: Parameter,
+
+    pub passed_pawn_own_king_distance: [Parameter; 8],
+    pub passed_pawn_enemy_king_distance: [Parameter; 8],
+    pub passed_pawn_blocked: Parameter,
+    pub passed_pawn_defended_stop: Parameter,
 }
 
 impl EvalParameters {
@@ -113,6 +215,8 @@ impl EvalParameters {
 }
 
 const _: () = assert!(EvalTrace::LEN == EvalParameters::LEN);
+// Indices of a SparseTrace must fit in a u16
+const _: () = assert!(EvalTrace::LEN <= u16::MAX as usize);
 
 impl EvalParameters {
     fn writeln_if_pretty(f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -240,6 +344,8 @@ impl Default for EvalParameters {
 
             pawn_shield_close: PAWN_SHIELD_CLOSE.into(),
             pawn_shield_far: PAWN_SHIELD_FAR.into(),
+            king_open_files: KING_OPEN_FILES.into(),
+            king_semi_open_files: KING_SEMI_OPEN_FILES.into(),
 
             semi_open_file_bonuses: convert_packed_score_array(SEMI_OPEN_FILE_BONUSES),
             open_file_bonuses: convert_packed_score_array(OPEN_FILE_BONUSES),
@@ -248,6 +354,34 @@ impl Default for EvalParameters {
             bishop_outposts: BISHOP_OUTPOSTS.into(),
 
             king_zone_attacks: convert_packed_score_array(KING_ZONE_ATTACKS),
+
+            knight_pair: KNIGHT_PAIR.into(),
+            rook_pair: ROOK_PAIR.into(),
+            knight_pawn_adjustment: KNIGHT_PAWN_ADJUSTMENT.into(),
+            rook_pawn_adjustment: ROOK_PAWN_ADJUSTMENT.into(),
+
+            king_flight_square_attacks: KING_FLIGHT_SQUARE_ATTACKS.into(),
+
+            tempo: TEMPO.into(),
+            bishop_pair: BISHOP_PAIR.into(),
+
+            rook_on_seventh: ROOK_ON_SEVENTH.into(),
+            connected_rooks: CONNECTED_ROOKS.into(),
+
+            king_safety: convert_packed_score_array(KING_SAFETY),
+
+            pawn_threats: convert_packed_score_array(PAWN_THREATS),
+            minor_threats: convert_packed_score_array(MINOR_THREATS),
+            hanging_pieces: HANGING_PIECES.into(),
+
+            passed_pawn_own_king_distance: convert_packed_score_array(
+                PASSED_PAWN_OWN_KING_DISTANCE,
+            ),
+            passed_pawn_enemy_king_distance: convert_packed_score_array(
+                PASSED_PAWN_ENEMY_KING_DISTANCE,
+            ),
+            passed_pawn_blocked: PASSED_PAWN_BLOCKED.into(),
+            passed_pawn_defended_stop: PASSED_PAWN_DEFENDED_STOP.into(),
         }
     }
 }
@@ -301,6 +435,8 @@ impl Display for EvalParameters {
 
         Self::fmt_single(f, "PAWN_SHIELD_CLOSE", self.pawn_shield_close, None)?;
         Self::fmt_single(f, "PAWN_SHIELD_FAR", self.pawn_shield_far, None)?;
+        Self::fmt_single(f, "KING_OPEN_FILES", self.king_open_files, None)?;
+        Self::fmt_single(f, "KING_SEMI_OPEN_FILES", self.king_semi_open_files, None)?;
         Self::writeln_if_pretty(f)?;
 
         Self::fmt_array(
@@ -319,6 +455,62 @@ impl Display for EvalParameters {
         Self::fmt_array(f, "KING_ZONE_ATTACKS", &self.king_zone_attacks, None)?;
         Self::writeln_if_pretty(f)?;
 
+        Self::fmt_single(f, "KNIGHT_PAIR", self.knight_pair, None)?;
+        Self::fmt_single(f, "ROOK_PAIR", self.rook_pair, None)?;
+        Self::fmt_single(
+            f,
+            "KNIGHT_PAWN_ADJUSTMENT",
+            self.knight_pawn_adjustment,
+            None,
+        )?;
+        Self::fmt_single(f, "ROOK_PAWN_ADJUSTMENT", self.rook_pawn_adjustment, None)?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_single(
+            f,
+            "KING_FLIGHT_SQUARE_ATTACKS",
+            self.king_flight_square_attacks,
+            None,
+        )?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_single(f, "TEMPO", self.tempo, None)?;
+        Self::fmt_single(f, "BISHOP_PAIR", self.bishop_pair, None)?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_single(f, "ROOK_ON_SEVENTH", self.rook_on_seventh, None)?;
+        Self::fmt_single(f, "CONNECTED_ROOKS", self.connected_rooks, None)?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_array(f, "KING_SAFETY", &self.king_safety, None)?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_array(f, "PAWN_THREATS", &self.pawn_threats, None)?;
+        Self::fmt_array(f, "MINOR_THREATS", &self.minor_threats, None)?;
+        Self::fmt_single(f, "HANGING_PIECES", self.hanging_pieces, None)?;
+        Self::writeln_if_pretty(f)?;
+
+        Self::fmt_array(
+            f,
+            "PASSED_PAWN_OWN_KING_DISTANCE",
+            &self.passed_pawn_own_king_distance,
+            None,
+        )?;
+        Self::fmt_array(
+            f,
+            "PASSED_PAWN_ENEMY_KING_DISTANCE",
+            &self.passed_pawn_enemy_king_distance,
+            None,
+        )?;
+        Self::fmt_single(f, "PASSED_PAWN_BLOCKED", self.passed_pawn_blocked, None)?;
+        Self::fmt_single(
+            f,
+            "PASSED_PAWN_DEFENDED_STOP",
+            self.passed_pawn_defended_stop,
+            None,
+        )?;
+        Self::writeln_if_pretty(f)?;
+
         Ok(())
     }
 }
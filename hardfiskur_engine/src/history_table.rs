@@ -47,8 +47,23 @@ impl HistoryTable {
         (300 * (depth as i32) - 300).clamp(0, MAX_BONUS)
     }
 
+    /// History gravity: the closer the entry is to the cap, the smaller the
+    /// effect of a bonus pushing it further, so entries can't saturate and
+    /// still respond to new cutoffs.
     fn apply_bonus(target: &mut i32, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_BONUS, MAX_BONUS);
         *target += bonus - *target * bonus.abs() / MAX_HISTORY;
+        *target = (*target).clamp(-MAX_HISTORY, MAX_HISTORY);
+    }
+
+    /// Halves every entry, so that history from previous searches still
+    /// guides move ordering but is quickly overridden by new results.
+    pub fn age(&mut self) {
+        for table in self.quiets.iter_mut() {
+            for entry in table.iter_mut() {
+                *entry /= 2;
+            }
+        }
     }
 
     pub fn clear(&mut self) {
@@ -63,3 +78,62 @@ impl Default for HistoryTable {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::{Piece, Square};
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn quiet(from: Square, to: Square) -> Move {
+        Move::builder(from, to, Piece::WHITE_KNIGHT).build()
+    }
+
+    #[test]
+    fn repeated_cutoffs_stay_within_bounds() {
+        let mut history = HistoryTable::new();
+        let good = quiet(Square::G1, Square::F3);
+        let bad = quiet(Square::B1, Square::A3);
+
+        for _ in 0..10_000 {
+            history.update_quiets(Color::White, 20, good, &[bad]);
+        }
+
+        let good_score = history.get_quiet_history(Color::White, good);
+        let bad_score = history.get_quiet_history(Color::White, bad);
+        assert!((0..=MAX_HISTORY).contains(&good_score), "{good_score}");
+        assert!((-MAX_HISTORY..=0).contains(&bad_score), "{bad_score}");
+
+        // The other side's history is unaffected
+        assert_eq!(history.get_quiet_history(Color::Black, good), 0);
+
+        // A saturated entry still reacts to a different move becoming the
+        // best one
+        for _ in 0..100 {
+            history.update_quiets(Color::White, 20, bad, &[good]);
+        }
+        assert!(
+            history.get_quiet_history(Color::White, bad)
+                > history.get_quiet_history(Color::White, good)
+        );
+    }
+
+    #[test]
+    fn aging_halves_entries_and_keeps_ordering() {
+        let mut history = HistoryTable::new();
+        let good = quiet(Square::G1, Square::F3);
+        let okay = quiet(Square::G1, Square::H3);
+        let bad = quiet(Square::B1, Square::A3);
+
+        history.update_quiets(Color::White, 10, good, &[bad]);
+        history.update_quiets(Color::White, 4, okay, &[]);
+
+        let before = [good, okay, bad].map(|m| history.get_quiet_history(Color::White, m));
+        history.age();
+        let after = [good, okay, bad].map(|m| history.get_quiet_history(Color::White, m));
+
+        assert_eq!(after, before.map(|score| score / 2));
+        assert!(after[0] > after[1] && after[1] > after[2]);
+    }
+}
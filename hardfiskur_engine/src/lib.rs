@@ -1,17 +1,29 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering as AtomicOrdering},
-    Arc, Mutex,
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use evaluation::evaluate_for_white;
-use hardfiskur_core::board::{Board, Move};
+use analysis::{AnalysisHandle, ChannelReporter};
+use evaluation::{
+    evaluator::{Evaluator, HceEvaluator, NnueEvaluator},
+    nnue::Network,
+    EvalKind,
+};
+use hardfiskur_core::board::{Board, Color, Move};
 use history_table::HistoryTable;
+use parameters::SearchParams;
 use score::Score;
 use search::SearchContext;
-use search_limits::SearchLimits;
-use search_result::{SearchInfo, SearchResult};
-use transposition_table::{TranspositionEntry, TranspositionTable};
+use search_limits::{SearchLimits, TimeControls};
+use search_result::{CurrMoveInfo, PonderOutcome, SearchInfo, SearchResult};
+use time_manager::PonderHit;
+use transposition_table::{TTFileError, TranspositionEntry, TranspositionFlag, TranspositionTable};
 
+pub mod analysis;
 pub mod bench;
 pub mod effort_table;
 pub mod evaluation;
@@ -25,20 +37,43 @@ pub mod search_result;
 pub mod search_stats;
 pub mod time_manager;
 pub mod transposition_table;
+pub mod wdl;
 
 pub struct Engine {
-    curr_abort_flag: Arc<AtomicBool>,
+    curr_abort_flag: Mutex<Arc<AtomicBool>>,
+    curr_ponder_hit: Mutex<Arc<PonderHit>>,
     persistent: Arc<Mutex<Persistent>>,
+    options: SearchOptions,
+}
+
+/// Settings applied to every search started by the engine.
+#[derive(Debug, Clone, Default)]
+struct SearchOptions {
+    eval_kind: EvalKind,
+    /// Evaluates with this network instead of the handcrafted evaluation if
+    /// set.
+    nnue: Option<Arc<Network>>,
+    variety: i32,
+    params: SearchParams,
+    /// Number of lines to search, where 0 is treated the same as 1.
+    multi_pv: usize,
+    contempt: i32,
+    /// Overrides [`SearchLimits::move_overhead`] if set.
+    move_overhead: Option<Duration>,
 }
 
 impl Engine {
     pub fn new() -> Self {
         Self {
-            curr_abort_flag: Arc::new(AtomicBool::new(false)),
+            curr_abort_flag: Mutex::new(Arc::new(AtomicBool::new(false))),
+            curr_ponder_hit: Mutex::new(Arc::new(PonderHit::new())),
             persistent: Arc::new(Mutex::new(Persistent {
                 tt: TranspositionTable::new(32.try_into().unwrap()),
                 history: HistoryTable::new(),
+                last_ponder_outcome: None,
+                white_draw_score: 0,
             })),
+            options: SearchOptions::default(),
         }
     }
 
@@ -48,33 +83,168 @@ impl Engine {
         search_limits: SearchLimits,
         reporter: impl SearchReporter,
     ) {
-        let mut board = board.clone();
-
-        self.curr_abort_flag = Arc::new(AtomicBool::new(false));
-        let abort_flag = self.curr_abort_flag.clone();
-
+        let board = board.clone();
+        let (abort_flag, ponder_hit) = self.new_search_handles();
         let persistent = self.persistent.clone();
+        let options = self.options.clone();
 
         std::thread::spawn(move || {
-            let persistent = &mut *persistent.lock().unwrap();
-            let ctx = SearchContext::new(
-                &mut board,
+            let result = Self::run_search(
+                &persistent,
+                &board,
                 search_limits,
-                &mut persistent.tt,
-                &mut persistent.history,
                 &abort_flag,
+                &ponder_hit,
+                options,
+                &reporter,
             );
 
-            let result = ctx.iterative_deepening_search(|info| {
-                reporter.receive_search_info(info);
-            });
-
             reporter.search_complete(result);
         });
     }
 
+    /// Starts a search in the background, returning a handle that search info
+    /// can be polled from as the search progresses. This is an alternative to
+    /// implementing [`SearchReporter`].
+    pub fn analyze(&self, board: &Board, search_limits: SearchLimits) -> AnalysisHandle {
+        let board = board.clone();
+        let (abort_flag, ponder_hit) = self.new_search_handles();
+        let persistent = self.persistent.clone();
+        let options = self.options.clone();
+
+        let (sender, receiver) = mpsc::channel();
+
+        let thread = {
+            let abort_flag = abort_flag.clone();
+            std::thread::spawn(move || {
+                Self::run_search(
+                    &persistent,
+                    &board,
+                    search_limits,
+                    &abort_flag,
+                    &ponder_hit,
+                    options,
+                    &ChannelReporter { sender },
+                )
+            })
+        };
+
+        AnalysisHandle::new(receiver, abort_flag, thread)
+    }
+
+    /// Runs a search on the calling thread, returning once it completes.
+    ///
+    /// Search info is still sent to `reporter` as the search progresses, but
+    /// the result is returned rather than passed to
+    /// [`SearchReporter::search_complete`]. The search can be stopped from
+    /// another thread with [`Self::abort_search`].
+    pub fn search_blocking(
+        &self,
+        board: &Board,
+        search_limits: SearchLimits,
+        reporter: &impl SearchReporter,
+    ) -> SearchResult {
+        let (abort_flag, ponder_hit) = self.new_search_handles();
+
+        Self::run_search(
+            &self.persistent,
+            board,
+            search_limits,
+            &abort_flag,
+            &ponder_hit,
+            self.options.clone(),
+            reporter,
+        )
+    }
+
+    fn new_search_handles(&self) -> (Arc<AtomicBool>, Arc<PonderHit>) {
+        let abort_flag = Arc::new(AtomicBool::new(false));
+        *self.curr_abort_flag.lock().unwrap() = abort_flag.clone();
+
+        let ponder_hit = Arc::new(PonderHit::new());
+        *self.curr_ponder_hit.lock().unwrap() = ponder_hit.clone();
+
+        (abort_flag, ponder_hit)
+    }
+
+    fn run_search(
+        persistent: &Mutex<Persistent>,
+        board: &Board,
+        mut search_limits: SearchLimits,
+        abort_flag: &AtomicBool,
+        ponder_hit: &PonderHit,
+        options: SearchOptions,
+        reporter: &impl SearchReporter,
+    ) -> SearchResult {
+        let mut board = board.clone();
+        let pondering = search_limits.pondering;
+
+        if let Some(move_overhead) = options.move_overhead {
+            search_limits.move_overhead = move_overhead;
+        }
+
+        let persistent = &mut *persistent.lock().unwrap();
+        // Draw scores found with contempt depend on which side was to move at
+        // the root, so entries from a search with a different view of draws
+        // would be wrong
+        let white_draw_score = match board.to_move() {
+            Color::White => -options.contempt,
+            Color::Black => options.contempt,
+        };
+        if white_draw_score != persistent.white_draw_score {
+            persistent.tt.clear();
+            persistent.white_draw_score = white_draw_score;
+        }
+        persistent.tt.new_generation();
+        // History from earlier moves in the game is still useful, but
+        // shouldn't outweigh what this search finds
+        persistent.history.age();
+
+        let report_curr_move = |info| reporter.receive_curr_move(info);
+        let mut ctx = SearchContext::new(
+            &mut board,
+            search_limits,
+            &mut persistent.tt,
+            &mut persistent.history,
+            abort_flag,
+        )
+        .with_ponder_hit(ponder_hit)
+        .with_curr_move_reporter(&report_curr_move)
+        .with_eval_kind(options.eval_kind)
+        .with_nnue(options.nnue.as_deref())
+        .with_variety(options.variety)
+        .with_params(options.params)
+        .with_multi_pv(options.multi_pv)
+        .with_contempt(options.contempt);
+
+        let mut result = ctx.iterative_deepening_search(|info| {
+            reporter.receive_search_info(info);
+        });
+
+        if pondering {
+            result.ponder_outcome = Some(if ponder_hit.was_signalled() {
+                PonderOutcome::Hit
+            } else {
+                PonderOutcome::Miss
+            });
+            persistent.last_ponder_outcome = result.ponder_outcome;
+        }
+
+        result
+    }
+
     pub fn abort_search(&self) {
-        self.curr_abort_flag.store(true, AtomicOrdering::Relaxed);
+        self.curr_abort_flag
+            .lock()
+            .unwrap()
+            .store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn ponder_hit(&self, time_controls: TimeControls) {
+        self.curr_ponder_hit
+            .lock()
+            .unwrap()
+            .signal(Instant::now(), time_controls);
     }
 
     pub fn new_game(&self) {
@@ -82,6 +252,12 @@ impl Engine {
         self.persistent.lock().unwrap().clear();
     }
 
+    /// Whether the most recent search started with `go ponder` ended in a
+    /// ponderhit or not. Waits for any running search to finish.
+    pub fn last_ponder_outcome(&self) -> Option<PonderOutcome> {
+        self.persistent.lock().unwrap().last_ponder_outcome
+    }
+
     pub fn get_tt_entry(&self, current_board: &Board) -> Option<TranspositionEntry> {
         let persistent = self.persistent.lock().unwrap();
         persistent.tt.get(current_board.zobrist_hash())
@@ -92,14 +268,126 @@ impl Engine {
         persistent.tt.extract_pv(&mut current_board.clone())
     }
 
+    /// Walks the principal variation stored in the TT from `current_board`,
+    /// returning up to `max_len` moves along with the score of the position
+    /// each move is played from. Scores are from the perspective of the side
+    /// to move in that position, so they alternate in sign along the PV.
+    /// Entries further along the PV may only have been searched with a null
+    /// window, so each score comes with whether it's exact or just a bound.
+    pub fn pv_with_scores(
+        &self,
+        current_board: &Board,
+        max_len: usize,
+    ) -> Vec<(Move, Score, TranspositionFlag)> {
+        let persistent = self.persistent.lock().unwrap();
+        persistent
+            .tt
+            .extract_pv_with_scores(&mut current_board.clone(), max_len)
+    }
+
+    /// Evaluates the position from white's perspective, with the same
+    /// evaluation the search is using.
     pub fn debug_eval(&self, current_board: &Board) -> Score {
-        evaluate_for_white(current_board)
+        let score = self.evaluator().evaluate(current_board);
+        match current_board.to_move() {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+
+    /// The evaluation function searches use, which is the network set by
+    /// [`Self::set_nnue`] if there is one, or the handcrafted evaluation.
+    pub fn evaluator(&self) -> Box<dyn Evaluator> {
+        match &self.options.nnue {
+            Some(network) => Box::new(NnueEvaluator {
+                network: network.clone(),
+            }),
+            None => Box::new(HceEvaluator {
+                kind: self.options.eval_kind,
+            }),
+        }
+    }
+
+    /// Evaluates with the given network instead of the handcrafted
+    /// evaluation, or goes back to the handcrafted evaluation if `None`.
+    pub fn set_nnue(&mut self, network: Option<Network>) {
+        self.options.nnue = network.map(Arc::new);
+    }
+
+    /// Whether searches are using a network rather than the handcrafted
+    /// evaluation.
+    pub fn uses_nnue(&self) -> bool {
+        self.options.nnue.is_some()
     }
 
     pub fn set_tt_size(&mut self, size_in_mb: usize) {
         let mut persistent = self.persistent.lock().unwrap();
         persistent.tt.resize(size_in_mb.try_into().unwrap());
     }
+
+    /// Saves the transposition table to `path`, so that an analysis can be
+    /// resumed later with [`Self::load_tt`].
+    pub fn save_tt(&self, path: impl AsRef<Path>) -> Result<(), TTFileError> {
+        let persistent = self.persistent.lock().unwrap();
+        persistent.tt.save(path)
+    }
+
+    /// Loads a transposition table saved with [`Self::save_tt`]. The hash size
+    /// must be the same as when the table was saved. Draw scores in the table
+    /// are only right for searches with the same contempt, and the same side
+    /// to move at the root, as the searches that filled it.
+    pub fn load_tt(&mut self, path: impl AsRef<Path>) -> Result<(), TTFileError> {
+        let mut persistent = self.persistent.lock().unwrap();
+        persistent.tt.load(path)
+    }
+
+    /// Adds a deterministic per-position offset of up to `variety`
+    /// centipawns to evaluations, so that the engine varies its choice among
+    /// near-equal moves. Zero (the default) disables it.
+    pub fn set_variety(&mut self, variety: i32) {
+        self.options.variety = variety;
+    }
+
+    /// Searches the best `multi_pv` lines rather than just the best one,
+    /// reporting each line separately. This slows down the search for the
+    /// best move, so should only be used for analysis.
+    pub fn set_multipv(&mut self, multi_pv: usize) {
+        self.options.multi_pv = multi_pv;
+    }
+
+    /// Scores draws as `contempt` centipawns worse than equal for the side to
+    /// move at the root, so that it avoids them against weaker opponents.
+    /// Negative values make it prefer draws instead.
+    pub fn set_contempt(&mut self, contempt: i32) {
+        self.options.contempt = contempt;
+    }
+
+    /// Reserves this much time on every move for communication delays,
+    /// instead of the overhead given in each search's [`SearchLimits`].
+    pub fn set_move_overhead(&mut self, move_overhead: Duration) {
+        self.options.move_overhead = Some(move_overhead);
+    }
+
+    /// Switches between the full evaluation and a material-only one, for
+    /// debugging search independently of the evaluation.
+    pub fn set_eval_kind(&mut self, kind: EvalKind) {
+        self.options.eval_kind = kind;
+    }
+
+    pub fn search_params(&self) -> SearchParams {
+        self.options.params
+    }
+
+    /// Overrides the search parameters used by subsequent searches, e.g. for
+    /// tuning.
+    pub fn set_search_params(&mut self, params: SearchParams) {
+        self.options.params = params;
+    }
+
+    pub fn clear_tt(&self) {
+        self.abort_search();
+        self.persistent.lock().unwrap().tt.clear();
+    }
 }
 
 impl Default for Engine {
@@ -110,25 +398,34 @@ impl Default for Engine {
 
 impl Drop for Engine {
     fn drop(&mut self) {
-        self.curr_abort_flag.store(true, AtomicOrdering::Relaxed);
+        self.abort_search();
     }
 }
 
 struct Persistent {
     tt: TranspositionTable,
     history: HistoryTable,
+    last_ponder_outcome: Option<PonderOutcome>,
+    /// Score of a draw from white's point of view in the searches that filled
+    /// `tt`.
+    white_draw_score: i32,
 }
 
 impl Persistent {
     fn clear(&mut self) {
         self.tt.clear();
         self.history.clear();
+        self.last_ponder_outcome = None;
     }
 }
 
 pub trait SearchReporter: Send + Sync + 'static {
     fn receive_search_info(&self, info: SearchInfo);
     fn search_complete(&self, result: SearchResult);
+
+    /// Called periodically during long searches with the root move currently
+    /// being searched.
+    fn receive_curr_move(&self, _info: CurrMoveInfo) {}
 }
 
 pub struct NullReporter;
@@ -137,3 +434,305 @@ impl SearchReporter for NullReporter {
     fn receive_search_info(&self, _info: SearchInfo) {}
     fn search_complete(&self, _result: SearchResult) {}
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use hardfiskur_core::board::Square;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn search_blocking_finds_mate_and_fills_tt() {
+        let engine = Engine::new();
+        let board = Board::try_parse_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+
+        let result = engine.search_blocking(
+            &board,
+            SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            },
+            &NullReporter,
+        );
+
+        let back_rank_mate = board.get_move(Square::A1, Square::A8, None);
+        assert_eq!(result.best_move, back_rank_mate);
+        assert_eq!(result.info.score.as_mate_in(), Some(1));
+        assert_eq!(
+            engine
+                .get_tt_entry(&board)
+                .and_then(|entry| entry.best_move),
+            back_rank_mate
+        );
+    }
+
+    #[test]
+    fn pv_with_scores_is_legal_and_alternates_sign() {
+        let engine = Engine::new();
+        // White is a queen up
+        let board = Board::try_parse_fen("4k3/pp4pp/8/8/8/8/PP4PP/3QK3 w - - 0 1").unwrap();
+
+        engine.search_blocking(
+            &board,
+            SearchLimits {
+                depth: 8,
+                ..SearchLimits::infinite()
+            },
+            &NullReporter,
+        );
+
+        let pv = engine.pv_with_scores(&board, 4);
+        assert!(!pv.is_empty());
+        assert!(pv.len() <= 4);
+
+        // The root's entry comes from the completed search at the final depth
+        assert_eq!(pv[0].2, TranspositionFlag::Exact);
+
+        let mut board = board.clone();
+        for (i, &(m, score, flag)) in pv.iter().enumerate() {
+            assert!(board.is_legal(m));
+
+            // Scores are relative to the side to move, which is white at even
+            // plies. Bounds don't say which side of zero the real score is on
+            if flag == TranspositionFlag::Exact {
+                if i % 2 == 0 {
+                    assert!(score > Score(0), "{i}: {m:?} {score}");
+                } else {
+                    assert!(score < Score(0), "{i}: {m:?} {score}");
+                }
+            }
+
+            board.push_move_repr(m);
+        }
+
+        assert_eq!(engine.pv_with_scores(&board, 0), vec![]);
+    }
+
+    #[test]
+    fn analyze_streams_search_info() {
+        let engine = Engine::new();
+        let board = Board::starting_position();
+
+        let handle = engine.analyze(
+            &board,
+            SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            },
+        );
+
+        let depths: Vec<_> = handle.infos().map(|info| info.raw_stats.depth).collect();
+        let result = handle.join();
+
+        assert!(!depths.is_empty());
+        assert!(depths.windows(2).all(|w| w[0] < w[1]));
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn analyze_can_be_stopped() {
+        let engine = Engine::new();
+        let handle = engine.analyze(&Board::starting_position(), SearchLimits::infinite());
+
+        // Wait for the first iteration so that there's a move to return
+        handle.receiver().recv().unwrap();
+        handle.stop();
+
+        let result = handle.join();
+        assert!(result.best_move.is_some());
+    }
+
+    fn ponder_search(engine: &Engine, on_ponder: impl FnOnce() + Send) -> SearchResult {
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                on_ponder();
+            });
+
+            engine.search_blocking(
+                &Board::starting_position(),
+                SearchLimits {
+                    pondering: true,
+                    ..SearchLimits::infinite()
+                },
+                &NullReporter,
+            )
+        })
+    }
+
+    #[test]
+    fn ponder_miss_is_recorded() {
+        let engine = Engine::new();
+
+        // The opponent played a different move, so the GUI stops the search
+        let result = ponder_search(&engine, || engine.abort_search());
+
+        assert_eq!(result.ponder_outcome, Some(PonderOutcome::Miss));
+        assert_eq!(engine.last_ponder_outcome(), Some(PonderOutcome::Miss));
+    }
+
+    #[test]
+    fn ponder_hit_is_recorded() {
+        let engine = Engine::new();
+
+        let result = ponder_search(&engine, || {
+            engine.ponder_hit(TimeControls::FixedMoveTime(Duration::from_millis(20)))
+        });
+
+        assert_eq!(result.ponder_outcome, Some(PonderOutcome::Hit));
+        assert_eq!(engine.last_ponder_outcome(), Some(PonderOutcome::Hit));
+
+        // Regular searches don't touch the outcome
+        let result = engine.search_blocking(
+            &Board::starting_position(),
+            SearchLimits {
+                depth: 2,
+                ..SearchLimits::infinite()
+            },
+            &NullReporter,
+        );
+        assert_eq!(result.ponder_outcome, None);
+        assert_eq!(engine.last_ponder_outcome(), Some(PonderOutcome::Hit));
+    }
+
+    #[test]
+    fn material_eval_kind_only_counts_material_and_pst() {
+        use evaluation::{trace::EvalTrace, EvalContext};
+        use zerocopy::IntoBytes;
+
+        let mut engine = Engine::new();
+        engine.set_eval_kind(EvalKind::Material);
+
+        let board = Board::try_parse_fen(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        )
+        .unwrap();
+
+        let mut trace = EvalTrace::default();
+        let (score, _) = EvalContext::new(&board).evaluate_material_ex(&mut trace);
+        assert_eq!(engine.debug_eval(&board), score);
+
+        // Only the material and PST terms should have been touched
+        trace.material = [0; 6];
+        trace.pawn_pst = [0; 64];
+        trace.knight_pst = [0; 64];
+        trace.bishop_pst = [0; 64];
+        trace.rook_pst = [0; 64];
+        trace.queen_pst = [0; 64];
+        trace.king_pst = [0; 64];
+        assert!(trace.as_bytes().iter().all(|&b| b == 0));
+
+        engine.set_eval_kind(EvalKind::Full);
+        assert_ne!(engine.debug_eval(&board), score);
+    }
+
+    #[test]
+    fn search_with_nnue_returns_legal_move() {
+        let network = evaluation::nnue::test::test_network();
+        let board = Board::try_parse_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        let mut engine = Engine::new();
+        engine.set_nnue(Some(network.clone()));
+        assert_eq!(
+            engine.debug_eval(&board),
+            evaluation::nnue::Accumulator::new(&network, &board).evaluate(&network, Color::White)
+        );
+
+        // The search makes and unmakes moves with every kind of special move
+        // from here, so the accumulator stack must stay in sync throughout
+        let result = engine.search_blocking(
+            &board,
+            SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            },
+            &NullReporter,
+        );
+        assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+
+        engine.set_nnue(None);
+        assert_eq!(
+            engine.debug_eval(&board),
+            evaluation::evaluate_for_white(&board)
+        );
+    }
+
+    #[test]
+    fn search_with_material_eval_kind_returns_legal_move() {
+        let mut engine = Engine::new();
+        engine.set_eval_kind(EvalKind::Material);
+
+        let board = Board::starting_position();
+        let result = engine.search_blocking(
+            &board,
+            SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            },
+            &NullReporter,
+        );
+
+        let best_move = result.best_move.unwrap();
+        assert!(board.legal_moves().contains(&best_move));
+    }
+
+    #[test]
+    fn search_blocking_can_be_aborted_from_another_thread() {
+        let engine = Engine::new();
+        // Abort flags from previous searches shouldn't affect a new search
+        engine.abort_search();
+
+        let result = std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                engine.abort_search();
+            });
+
+            engine.search_blocking(
+                &Board::starting_position(),
+                SearchLimits::infinite(),
+                &NullReporter,
+            )
+        });
+
+        assert!(result.best_move.is_some());
+        assert!(result.info.raw_stats.depth > 1);
+    }
+
+    #[test]
+    fn tt_is_cleared_when_contempt_changes_sides() {
+        let depth_2 = SearchLimits {
+            depth: 2,
+            ..SearchLimits::infinite()
+        };
+        let white_root = Board::starting_position();
+        let mut black_root = white_root.clone();
+        black_root.push_uci("e2e4").unwrap();
+
+        let mut engine = Engine::new();
+        engine.search_blocking(&white_root, depth_2.clone(), &NullReporter);
+
+        // Without contempt, draws are the same for both sides
+        engine.search_blocking(&black_root, depth_2.clone(), &NullReporter);
+        assert!(engine.get_tt_entry(&white_root).is_some());
+
+        // With contempt, the side at the root matters...
+        engine.set_contempt(50);
+        engine.search_blocking(&white_root, depth_2.clone(), &NullReporter);
+        engine.search_blocking(&black_root, depth_2.clone(), &NullReporter);
+        assert!(engine.get_tt_entry(&white_root).is_none());
+
+        // ...but positions searched with the same side at the root are kept
+        engine.search_blocking(&white_root, depth_2.clone(), &NullReporter);
+        let mut other_white_root = black_root.clone();
+        other_white_root.push_uci("e7e5").unwrap();
+        engine.search_blocking(&other_white_root, depth_2, &NullReporter);
+        assert!(engine.get_tt_entry(&white_root).is_some());
+    }
+}
@@ -42,6 +42,6 @@ impl KillerTableBucket {
     }
 
     fn contains(&self, m: Move) -> bool {
-        self.killers.iter().any(|i| *i == Some(m))
+        self.killers.contains(&Some(m))
     }
 }
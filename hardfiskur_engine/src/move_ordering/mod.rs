@@ -3,7 +3,7 @@ mod see;
 
 use hardfiskur_core::{
     board::{Board, Color, Move, Piece},
-    move_gen::MoveVec,
+    move_gen::{MoveGenStage, MoveVec},
 };
 
 pub use killer_table::KillerTable;
@@ -11,18 +11,34 @@ pub use see::Seer;
 
 use crate::history_table::HistoryTable;
 
+/// Picks moves out of a move list, most promising first. The list is owned by
+/// the caller, so that the search can reuse a buffer for each ply, and has to
+/// be passed to every call of [`Self::next_move`].
 pub struct MovePicker {
-    moves: MoveVec,
     tt_move: Option<Move>,
     scores: Vec<i32>,
+    quiets_pending: bool,
 }
 
 impl MovePicker {
-    pub fn new(moves: MoveVec, tt_move: Option<Move>) -> Self {
+    /// Creates a move picker for a list of all the legal moves in a position.
+    pub fn new(tt_move: Option<Move>) -> Self {
         Self {
-            moves,
             tt_move,
             scores: Vec::new(),
+            quiets_pending: false,
+        }
+    }
+
+    /// Creates a move picker for a list of only the legal captures in a
+    /// position. Quiet moves are added to the list once no winning captures
+    /// are left, so they don't need to be generated at all if one of the
+    /// captures causes a cutoff.
+    pub fn staged(tt_move: Option<Move>) -> Self {
+        Self {
+            tt_move,
+            scores: Vec::new(),
+            quiets_pending: true,
         }
     }
 
@@ -33,57 +49,86 @@ impl MovePicker {
 
     pub fn next_move(
         &mut self,
+        moves: &mut MoveVec,
         board: &Board,
         ply_from_root: u16,
         killers: &KillerTable,
         history: &HistoryTable,
     ) -> Option<Move> {
         if let Some(tt_move) = self.tt_move.take() {
-            if let Some(idx) = self.moves.iter().position(|&m| m == tt_move) {
-                return Some(self.moves.swap_remove(idx));
+            // The TT move can only be trusted if it's actually legal here, so
+            // quiet TT moves require generating the quiets first
+            if self.quiets_pending && !tt_move.is_capture() {
+                self.generate_quiets(moves, board);
             }
-        }
 
-        if self.moves.is_empty() {
-            return None;
+            if let Some(idx) = moves.iter().position(|&m| m == tt_move) {
+                return Some(moves.swap_remove(idx));
+            }
         }
 
-        if self.scores.is_empty() {
-            self.fill_scores(board, ply_from_root, killers, history);
+        loop {
+            if self.scores.len() < moves.len() {
+                self.fill_scores(moves, board, ply_from_root, killers, history);
+            }
+
+            let max_idx = self.highest_scoring_index();
+
+            // Quiets score below winning captures but above losing ones, so
+            // they're needed once the best remaining capture isn't winning
+            if self.quiets_pending
+                && max_idx.is_none_or(|i| self.scores[i] < Self::WINNING_CAPTURE_BIAS)
+            {
+                self.generate_quiets(moves, board);
+                continue;
+            }
+
+            let max_idx = max_idx?;
+            self.scores.swap_remove(max_idx);
+            return Some(moves.swap_remove(max_idx));
         }
+    }
 
-        Some(self.next_highest_move())
+    fn generate_quiets(&mut self, moves: &mut MoveVec, board: &Board) {
+        board.legal_moves_ex(MoveGenStage::Quiets.flags(), moves);
+        self.quiets_pending = false;
     }
 
+    /// Scores any moves that don't have a score yet, i.e. all of them on the
+    /// first call, and newly generated quiets afterwards.
     fn fill_scores(
         &mut self,
+        moves: &MoveVec,
         board: &Board,
         ply_from_root: u16,
         killers: &KillerTable,
         history: &HistoryTable,
     ) {
         let seer = Seer::new(board);
-        self.scores = vec![0; self.moves.len()];
-        for (i, &m) in self.moves.iter().enumerate() {
-            self.scores[i] =
-                Self::score_move(board.to_move(), ply_from_root, &seer, killers, history, m);
+        for &m in &moves[self.scores.len()..] {
+            self.scores.push(Self::score_move(
+                board.to_move(),
+                ply_from_root,
+                &seer,
+                killers,
+                history,
+                m,
+            ));
         }
     }
 
-    fn next_highest_move(&mut self) -> Move {
-        // Assumes non-empty scores and moves
-        let mut max_idx = 0;
-        let mut max_score = self.scores[0];
+    fn highest_scoring_index(&self) -> Option<usize> {
+        let mut max_idx = None;
+        let mut max_score = i32::MIN;
 
-        for i in 1..self.scores.len() {
-            if self.scores[i] > max_score {
-                max_idx = i;
-                max_score = self.scores[i];
+        for (i, &score) in self.scores.iter().enumerate() {
+            if max_idx.is_none() || score > max_score {
+                max_idx = Some(i);
+                max_score = score;
             }
         }
 
-        self.scores.swap_remove(max_idx);
-        self.moves.swap_remove(max_idx)
+        max_idx
     }
 
     pub fn score_move(
@@ -124,3 +169,58 @@ impl MovePicker {
         - (aggressor.piece_type() as i32)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::Square;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn picked_moves(mut picker: MovePicker, mut moves: MoveVec, board: &Board) -> Vec<Move> {
+        let killers = KillerTable::default();
+        let history = HistoryTable::new();
+
+        std::iter::from_fn(|| picker.next_move(&mut moves, board, 0, &killers, &history)).collect()
+    }
+
+    #[test]
+    fn staged_picker_matches_eager_picker() {
+        let board = Board::try_parse_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        for tt_move in [
+            None,
+            board.get_move(Square::E5, Square::F7, None),
+            board.get_move(Square::E1, Square::G1, None),
+        ] {
+            let eager = picked_moves(MovePicker::new(tt_move), board.legal_moves(), &board);
+
+            let mut captures = MoveVec::new();
+            board.legal_captures_into(&mut captures);
+            let staged = picked_moves(MovePicker::staged(tt_move), captures, &board);
+
+            assert_eq!(staged.len(), eager.len());
+            assert_eq!(staged.first(), eager.first());
+
+            // Winning captures come first, then quiets, then losing captures.
+            // Moves with equal scores may come out in a different order.
+            let seer = Seer::new(&board);
+            let score = |&m: &Move| {
+                MovePicker::score_move(
+                    board.to_move(),
+                    0,
+                    &seer,
+                    &KillerTable::default(),
+                    &HistoryTable::new(),
+                    m,
+                )
+            };
+            let eager_scores: Vec<_> = eager.iter().skip(1).map(score).collect();
+            let staged_scores: Vec<_> = staged.iter().skip(1).map(score).collect();
+            assert_eq!(staged_scores, eager_scores);
+        }
+    }
+}
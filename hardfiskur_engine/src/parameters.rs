@@ -1,13 +1,35 @@
+use std::time::Duration;
+
 // In practice, we should never get to this search depth; however it avoids
 // pathlogical behavior if the search function has a bug that immediately
 // returns, for example.
 pub const MAX_DEPTH: i16 = 256;
+// Hard limit on the distance from the root, which extensions can push past
+// the nominal search depth.
+pub const MAX_PLY: u16 = MAX_DEPTH as u16;
 pub const MAX_EXTENSIONS: i16 = 16;
 
 // Aspiration Search parameters
 pub const ASPIRATION_MIN_DEPTH: i16 = 5;
 pub const ASPIRATION_INITIAL_WINDOW: i32 = 25;
 
+/// Search parameters that can be changed at runtime, e.g. for tuning. Each
+/// defaults to the constant of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchParams {
+    pub aspiration_min_depth: i16,
+    pub aspiration_initial_window: i32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            aspiration_min_depth: ASPIRATION_MIN_DEPTH,
+            aspiration_initial_window: ASPIRATION_INITIAL_WINDOW,
+        }
+    }
+}
+
 // Reverse Futility Pruning parameters
 pub const RFP_MAX_DEPTH: i16 = 6;
 pub const RFP_MARGIN: i32 = 80;
@@ -35,5 +57,33 @@ pub const FP_MAX_DEPTH: i16 = 5;
 pub const FP_MARGIN: i32 = 100;
 pub const FP_MARGIN_BASE: i32 = 100;
 
+// Quiescence search parameters
+// Number of plies from the start of the quiescence search in which quiet
+// checks are searched.
+pub const QS_CHECK_PLIES: u16 = 1;
+// Captures are skipped if winning the captured piece for free plus this margin
+// still wouldn't raise alpha.
+pub const DELTA_PRUNING_MARGIN: i32 = 200;
+
 // Internal Iterative Reduction
 pub const IIR_MIN_DEPTH: i16 = 4;
+
+// Once the halfmove clock passes this, static evaluations are scaled linearly
+// towards zero, reaching it when the fifty-move rule would apply.
+pub const FIFTY_MOVE_SCALING_THRESHOLD: u32 = 20;
+
+// Only report the root move being searched once the search has run for this
+// long, to avoid flooding the output in short searches.
+pub const CURR_MOVE_REPORT_DELAY: Duration = Duration::from_secs(1);
+
+// When searching for a mate with `go mate`, give up once the depth reaches
+// this multiple of the mate's length in plies without finding one.
+pub const MATE_SEARCH_MAX_DEPTH_FACTOR: i16 = 2;
+
+// Win/draw/loss model parameters. The win probability is modelled as a
+// logistic function of the score, with the midpoint (the score at which a win
+// is 50% likely) and scale tapered between the midgame and endgame values.
+pub const WDL_MIDPOINT_MG: f64 = 250.0;
+pub const WDL_MIDPOINT_EG: f64 = 160.0;
+pub const WDL_SCALE_MG: f64 = 80.0;
+pub const WDL_SCALE_EG: f64 = 55.0;
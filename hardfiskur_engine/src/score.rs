@@ -1,8 +1,10 @@
 use std::{
     fmt::{Display, Write},
     ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
 };
 
+use thiserror::Error;
 use zerocopy_derive::FromZeros;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, FromZeros)]
@@ -15,6 +17,10 @@ impl Score {
     const MATE_SCORE: i32 = 20_000_000;
     const MATE_THRESHOLD: i32 = 1_000_000;
 
+    /// The largest score which can't be mistaken for a mate score. Static
+    /// evaluations need to stay within `-MAX_EVAL..=MAX_EVAL`.
+    pub const MAX_EVAL: Self = Self(Self::MATE_THRESHOLD - 1);
+
     pub const fn get(self) -> i32 {
         self.0
     }
@@ -23,6 +29,22 @@ impl Score {
         Self(Self::MATE_SCORE - ply_from_root as i32)
     }
 
+    /// Score for delivering mate in the given number of full moves, as
+    /// reported by UCI's `mate N`. Mate in 1 is 1 ply away.
+    ///
+    /// Even [`u16::MAX`] moves is far enough from the mate threshold
+    /// that the result is still a mate score.
+    pub const fn mate_in(moves: u16) -> Self {
+        let plies = 2 * moves as i32 - 1;
+        Self(Self::MATE_SCORE - if plies > 0 { plies } else { 0 })
+    }
+
+    /// Score for getting mated in the given number of full moves, as reported
+    /// by UCI's `mate -N`. Mated in 1 is 2 plies away.
+    pub const fn mated_in(moves: u16) -> Self {
+        Self(-Self::MATE_SCORE + 2 * moves as i32)
+    }
+
     pub const fn is_mate(self) -> bool {
         self.0.abs() > Self::MATE_THRESHOLD
     }
@@ -35,6 +57,9 @@ impl Score {
         self.0 < -Self::MATE_THRESHOLD
     }
 
+    /// Full moves until mate, positive if we're delivering it and negative if
+    /// we're getting mated. Inverse of [`Self::mate_in`] and
+    /// [`Self::mated_in`].
     pub const fn as_mate_in(self) -> Option<i32> {
         if self.0.abs() > Self::MATE_THRESHOLD {
             Some(self.0.signum() * (Self::MATE_SCORE - self.0.abs() + 1) / 2)
@@ -62,7 +87,7 @@ impl Score {
     pub const fn sub_plies_for_mate(self, ply_from_root: u16) -> Self {
         if self.0 > Self::MATE_THRESHOLD {
             Self(self.0 + ply_from_root as i32)
-        } else if self.0 < Self::MATE_THRESHOLD {
+        } else if self.0 < -Self::MATE_THRESHOLD {
             Self(self.0 - ply_from_root as i32)
         } else {
             self
@@ -72,7 +97,7 @@ impl Score {
     pub const fn add_plies_for_mate(self, ply_from_root: u16) -> Self {
         if self.0 > Self::MATE_THRESHOLD {
             Self(self.0 - ply_from_root as i32)
-        } else if self.0 < Self::MATE_THRESHOLD {
+        } else if self.0 < -Self::MATE_THRESHOLD {
             Self(self.0 + ply_from_root as i32)
         } else {
             self
@@ -181,3 +206,96 @@ impl Display for Score {
         }
     }
 }
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("invalid score")]
+pub struct ParseScoreError;
+
+/// Parses scores in UCI's `cp N` and `mate N` forms.
+impl FromStr for Score {
+    type Err = ParseScoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let kind = parts.next().ok_or(ParseScoreError)?;
+        let value: i32 = parts
+            .next()
+            .and_then(|x| x.parse().ok())
+            .ok_or(ParseScoreError)?;
+        if parts.next().is_some() {
+            return Err(ParseScoreError);
+        }
+
+        match kind {
+            "cp" if value.abs() <= Self::MATE_THRESHOLD => Ok(Self(value)),
+            "mate" => {
+                let moves = u16::try_from(value.unsigned_abs()).map_err(|_| ParseScoreError)?;
+                Ok(if value > 0 {
+                    Self::mate_in(moves)
+                } else {
+                    Self::mated_in(moves)
+                })
+            }
+            _ => Err(ParseScoreError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn mate_in_moves_round_trip() {
+        for moves in 1..50 {
+            let mate = Score::mate_in(moves);
+            assert_eq!(mate.as_mate_in(), Some(moves as i32));
+            assert_eq!(mate.as_mate_in_plies(), Some(2 * moves as i32 - 1));
+
+            let mated = Score::mated_in(moves);
+            assert_eq!(mated.as_mate_in(), Some(-(moves as i32)));
+            assert_eq!(mated.as_mate_in_plies(), Some(-2 * moves as i32));
+        }
+    }
+
+    #[test]
+    fn long_mates_dont_overflow() {
+        let mate = Score::mate_in(u16::MAX);
+        assert!(mate.is_mate_for_us());
+        assert_eq!(mate.as_mate_in(), Some(u16::MAX as i32));
+
+        let mated = Score::mated_in(u16::MAX);
+        assert!(mated.is_mate_for_them());
+        assert_eq!(mated.as_mate_in(), Some(-(u16::MAX as i32)));
+
+        assert_eq!(Score::mate_in(0), Score::mate_in_plies(0));
+    }
+
+    #[test]
+    fn mate_in_plies_rounds_to_moves() {
+        assert_eq!(Score::mate_in_plies(1).as_mate_in(), Some(1));
+        assert_eq!(Score::mate_in_plies(3).as_mate_in(), Some(2));
+        assert_eq!((-Score::mate_in_plies(0)).as_mate_in(), Some(0));
+        assert_eq!((-Score::mate_in_plies(2)).as_mate_in(), Some(-1));
+        assert_eq!((-Score::mate_in_plies(4)).as_mate_in(), Some(-2));
+    }
+
+    #[test]
+    fn parse_uci_scores() {
+        assert_eq!("cp 35".parse(), Ok(Score(35)));
+        assert_eq!("cp -120".parse(), Ok(Score(-120)));
+        assert_eq!("mate 3".parse(), Ok(Score::mate_in(3)));
+        assert_eq!("mate -2".parse(), Ok(Score::mated_in(2)));
+        assert_eq!("mate 40000".parse(), Ok(Score::mate_in(40000)));
+        assert_eq!("mate -40000".parse(), Ok(Score::mated_in(40000)));
+
+        assert_eq!("".parse::<Score>(), Err(ParseScoreError));
+        assert_eq!("cp".parse::<Score>(), Err(ParseScoreError));
+        assert_eq!("cp 1 2".parse::<Score>(), Err(ParseScoreError));
+        assert_eq!("mate x".parse::<Score>(), Err(ParseScoreError));
+        assert_eq!("cp 99999999".parse::<Score>(), Err(ParseScoreError));
+        assert_eq!("mate 70000".parse::<Score>(), Err(ParseScoreError));
+    }
+}
@@ -1,18 +1,15 @@
-use crate::{
-    parameters::{ASPIRATION_INITIAL_WINDOW, ASPIRATION_MIN_DEPTH},
-    score::Score,
-};
+use crate::score::Score;
 
 use super::{node_types::Root, SearchContext};
 
 impl<'a> SearchContext<'a> {
     pub fn aspiration_search(&mut self, prev_score: Score, depth: i16) -> Score {
         // Skip doing the aspiration search when the depth is low, as the score is very unstable at low depths.
-        if depth < ASPIRATION_MIN_DEPTH {
+        if depth < self.params.aspiration_min_depth {
             return self.negamax::<Root>(depth, 0, -Score::INF, Score::INF);
         }
 
-        let mut delta = ASPIRATION_INITIAL_WINDOW;
+        let mut delta = self.params.aspiration_initial_window;
         let mut alpha = prev_score.saturating_sub(delta).max(-Score::INF);
         let mut beta = prev_score.saturating_add(delta).min(Score::INF);
         let mut reduction = 0;
@@ -25,7 +22,15 @@ impl<'a> SearchContext<'a> {
                 return Score(0);
             }
 
-            if score <= alpha {
+            if score.is_mate() && (alpha > -Score::INF || beta < Score::INF || reduction > 0) {
+                // Mate scores found through a narrow or reduced window may
+                // come with a truncated PV, so re-search them at full depth
+                // with a full window to recover the whole mating line.
+                alpha = -Score::INF;
+                beta = Score::INF;
+                reduction = 0;
+                continue;
+            } else if score <= alpha {
                 // Fail-low, grow the window downwards.
                 alpha = alpha.saturating_sub(delta).max(-Score::INF);
                 beta = alpha.midpoint(beta);
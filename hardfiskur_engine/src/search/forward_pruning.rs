@@ -59,15 +59,15 @@ impl<'a> SearchContext<'a> {
     ) -> Option<Score> {
         if depth >= NMP_MIN_DEPTH
             && static_eval > beta
-            && self.board.last_move().is_some()
+            && self.last_move().is_some()
             && !self.board.is_king_and_pawn_endgame()
         {
-            self.board.push_null_move();
+            self.make_null_move();
 
             let score =
                 -self.negamax::<NonPV>(depth - NMP_REDUCTION, ply_from_root + 1, -beta, -beta + 1);
 
-            self.board.pop_move();
+            self.unmake_move();
 
             return if score.is_mate_for_us() {
                 Some(beta)
@@ -0,0 +1,41 @@
+use crate::score::Score;
+
+use super::{node_types::Root, SearchContext};
+
+impl<'a> SearchContext<'a> {
+    /// Searches for a forced mate in at most `mate_plies` plies. Only mates
+    /// within the limit raise alpha, so anything else fails low and
+    /// `prev_score` is returned instead, keeping the PV from the previous
+    /// iteration.
+    pub fn mate_search(&mut self, prev_score: Score, depth: i16, mate_plies: u16) -> Score {
+        let prev_pv_table = self.pv_table.clone();
+        let alpha = Score(Score::mate_in_plies(mate_plies).get() - 1);
+        let score = self.negamax::<Root>(depth, 0, alpha, Score::INF);
+
+        if score > alpha && !self.should_exit_search() {
+            score
+        } else {
+            self.pv_table = prev_pv_table;
+            prev_score
+        }
+    }
+
+    /// Mate distance pruning for mate searches: narrows the window to the
+    /// scores still achievable from this ply, returning a score if that
+    /// leaves nothing to search. Combined with the window set up by
+    /// [`Self::mate_search`], this prunes every line that can no longer mate
+    /// within the limit.
+    pub(super) fn mate_distance_pruning(
+        &self,
+        ply_from_root: u16,
+        alpha: &mut Score,
+        beta: &mut Score,
+    ) -> Option<Score> {
+        self.mate_plies?;
+
+        *alpha = (*alpha).max(-Score::mate_in_plies(ply_from_root));
+        *beta = (*beta).min(Score::mate_in_plies(ply_from_root + 1));
+
+        (*alpha >= *beta).then_some(*alpha)
+    }
+}
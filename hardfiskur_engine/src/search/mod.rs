@@ -1,29 +1,48 @@
 mod aspiration;
 mod extensions;
 mod forward_pruning;
+mod mate_search;
+mod multi_pv;
 mod negamax;
 mod node_types;
+mod pv_table;
 mod quiescence;
 
 use std::sync::atomic::AtomicBool;
 
-use hardfiskur_core::board::{Board, Move};
+use hardfiskur_core::{
+    board::{Board, Color, Move, UnmakeData},
+    move_gen::MoveVec,
+};
+use pv_table::PvTable;
 
 use crate::{
     effort_table::EffortTable,
+    evaluation::{
+        add_variety, evaluate_with_variety,
+        nnue::{AccumulatorStack, Network},
+        phase::Phase,
+        EvalKind,
+    },
     history_table::HistoryTable,
     move_ordering::KillerTable,
-    parameters::MAX_DEPTH,
+    parameters::{
+        SearchParams, CURR_MOVE_REPORT_DELAY, FIFTY_MOVE_SCALING_THRESHOLD,
+        MATE_SEARCH_MAX_DEPTH_FACTOR, MAX_DEPTH, MAX_PLY,
+    },
     score::Score,
     search_limits::SearchLimits,
-    search_result::{SearchInfo, SearchResult},
+    search_result::{CurrMoveInfo, SearchInfo, SearchResult},
     search_stats::SearchStats,
-    time_manager::TimeManager,
+    time_manager::{PonderHit, TimeManager},
     transposition_table::TranspositionTable,
 };
 
 pub struct SearchContext<'a> {
     pub board: &'a mut Board,
+    /// Undo data for the moves made in the search, which aren't recorded in
+    /// the board's move history.
+    pub undo_stack: Vec<UnmakeData>,
     pub stats: SearchStats,
 
     pub time_manager: TimeManager<'a>,
@@ -33,20 +52,60 @@ pub struct SearchContext<'a> {
     pub history: &'a mut HistoryTable,
     pub killers: KillerTable,
     pub effort: EffortTable,
+    pub pv_table: PvTable,
+
+    /// Move lists, one per ply, which nodes generate their moves into
+    /// instead of making a new list each time. Children are always a ply
+    /// further from the root, so they never overwrite their parent's moves.
+    pub move_buffers: Vec<MoveVec>,
 
     pub best_root_move: Option<Move>,
+    pub root_moves: Vec<Move>,
+
+    /// Number of lines to search with MultiPV, where each line after the
+    /// first excludes the best moves of the earlier lines.
+    pub multi_pv: usize,
+    pub excluded_root_moves: Vec<Move>,
+
+    /// Score (in centipawns) the side to move at the root gives up to avoid
+    /// a draw. Negative values make the engine seek draws instead.
+    pub contempt: i32,
+    pub root_color: Color,
+
+    /// Set for `go mate`, the maximum number of plies a mate may take to be
+    /// accepted. Lines that can't mate within this are pruned.
+    pub mate_plies: Option<u16>,
+
+    pub curr_move_reporter: Option<&'a dyn Fn(CurrMoveInfo)>,
+
+    /// Maximum random offset (in centipawns) added to static evaluations.
+    pub variety: i32,
+    pub eval_kind: EvalKind,
+    /// Set when evaluating with a neural network instead of the handcrafted
+    /// evaluation, kept in sync with the board as moves are made.
+    pub nnue: Option<AccumulatorStack<'a>>,
+
+    pub params: SearchParams,
 }
 
 impl<'a> SearchContext<'a> {
     pub fn new(
         board: &'a mut Board,
-        search_limits: SearchLimits,
+        mut search_limits: SearchLimits,
         tt: &'a mut TranspositionTable,
         history: &'a mut HistoryTable,
         abort_flag: &'a AtomicBool,
     ) -> Self {
+        let root_moves = std::mem::take(&mut search_limits.search_moves);
+        let root_color = board.to_move();
+        // Mates longer than the ply limit can't be found anyway
+        let mate_plies = search_limits
+            .mate
+            .map(|moves| (2 * moves.max(1) as u32 - 1).min(MAX_PLY as u32) as u16);
+
         Self {
             board,
+            undo_stack: Vec::with_capacity(MAX_PLY as usize + 1),
             stats: SearchStats::default(),
 
             time_manager: TimeManager::new(search_limits, abort_flag),
@@ -56,13 +115,180 @@ impl<'a> SearchContext<'a> {
             history,
             killers: KillerTable::default(),
             effort: EffortTable::default(),
+            pv_table: PvTable::default(),
+
+            move_buffers: vec![MoveVec::new(); MAX_PLY as usize + 1],
 
             best_root_move: None,
+            root_moves,
+            mate_plies,
+
+            multi_pv: 1,
+            excluded_root_moves: Vec::new(),
+
+            contempt: 0,
+            root_color,
+
+            curr_move_reporter: None,
+
+            variety: 0,
+            eval_kind: EvalKind::Full,
+            nnue: None,
+
+            params: SearchParams::default(),
+        }
+    }
+
+    pub fn with_ponder_hit(mut self, ponder_hit: &'a PonderHit) -> Self {
+        self.time_manager = self.time_manager.with_ponder_hit(ponder_hit);
+        self
+    }
+
+    pub fn with_curr_move_reporter(mut self, reporter: &'a dyn Fn(CurrMoveInfo)) -> Self {
+        self.curr_move_reporter = Some(reporter);
+        self
+    }
+
+    pub fn with_variety(mut self, variety: i32) -> Self {
+        self.variety = variety;
+        self
+    }
+
+    pub fn with_eval_kind(mut self, eval_kind: EvalKind) -> Self {
+        self.eval_kind = eval_kind;
+        self
+    }
+
+    pub fn with_nnue(mut self, network: Option<&'a Network>) -> Self {
+        self.nnue = network.map(|network| AccumulatorStack::new(network, self.board));
+        self
+    }
+
+    pub fn with_params(mut self, params: SearchParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_multi_pv(mut self, multi_pv: usize) -> Self {
+        self.multi_pv = multi_pv.max(1);
+        self
+    }
+
+    pub fn with_contempt(mut self, contempt: i32) -> Self {
+        self.contempt = contempt;
+        self
+    }
+
+    /// Score of a drawn position from the point of view of the side to move,
+    /// taking contempt into account.
+    pub fn draw_score(&self) -> Score {
+        if self.board.to_move() == self.root_color {
+            Score(-self.contempt)
+        } else {
+            Score(self.contempt)
+        }
+    }
+
+    /// Whether the current position should be scored as a draw by
+    /// repetition. A single repetition is enough if the earlier occurrence is
+    /// within the search tree, as the side that could deviate can be assumed
+    /// to repeat again. If it's from the game before the root, the position
+    /// has to have occurred twice already, i.e. be a real threefold
+    /// repetition.
+    pub fn is_repetition(&self, ply_from_root: u16) -> bool {
+        let mut game_occurrences = 0;
+
+        let earlier = self
+            .undo_stack
+            .iter()
+            .rev()
+            .map(UnmakeData::zobrist_hash)
+            .chain(self.board.history_hashes());
+        let occurrences = Board::occurrences_among(
+            self.board.zobrist_hash(),
+            self.board.halfmove_clock(),
+            earlier,
+        );
+
+        for plies_ago in occurrences {
+            if plies_ago <= ply_from_root as usize {
+                return true;
+            }
+
+            game_occurrences += 1;
+            if game_occurrences >= 2 {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn evaluate(&self) -> Score {
+        let score = match &self.nnue {
+            Some(nnue) => add_variety(
+                self.board,
+                nnue.evaluate(self.board.to_move()),
+                self.variety,
+            ),
+            None => evaluate_with_variety(self.board, self.eval_kind, self.variety),
+        };
+        Self::scale_for_fifty_move_rule(score, self.board.halfmove_clock())
+    }
+
+    /// Makes a move on the board, keeping the NNUE accumulator in sync.
+    pub fn make_move(&mut self, m: Move) {
+        self.undo_stack.push(self.board.make_move(m));
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push_move(m);
+        }
+    }
+
+    pub fn make_null_move(&mut self) {
+        self.undo_stack.push(self.board.make_null_move());
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push_null_move();
         }
     }
 
+    /// Unmakes the last move made with [`Self::make_move`] or
+    /// [`Self::make_null_move`].
+    pub fn unmake_move(&mut self) {
+        let unmake_data = self
+            .undo_stack
+            .pop()
+            .expect("unmake_move called without a move to unmake");
+        self.board.unmake_move(unmake_data);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.pop();
+        }
+    }
+
+    /// The move that led to the current position, or [`None`] if it was a
+    /// null move.
+    pub fn last_move(&self) -> Option<Move> {
+        match self.undo_stack.last() {
+            Some(unmake_data) => unmake_data.the_move(),
+            None => self.board.last_move(),
+        }
+    }
+
+    /// Scales `score` towards zero as the halfmove clock approaches 100, since
+    /// an advantage is worth less when a fifty-move draw is close. Mate scores
+    /// are left untouched.
+    fn scale_for_fifty_move_rule(score: Score, halfmove_clock: u32) -> Score {
+        if score.is_mate() || halfmove_clock <= FIFTY_MOVE_SCALING_THRESHOLD {
+            return score;
+        }
+
+        let remaining = 100u32.saturating_sub(halfmove_clock) as i32;
+        let window = (100 - FIFTY_MOVE_SCALING_THRESHOLD) as i32;
+
+        Score(score.get() * remaining / window)
+    }
+
     pub fn consistency_check(&self) {
-        self.board.consistency_check();
+        debug_assert_eq!(self.board.consistency_check(), Ok(()), "{:?}", self.board);
     }
 
     pub fn should_exit_search(&mut self) -> bool {
@@ -88,50 +314,122 @@ impl<'a> SearchContext<'a> {
     }
 
     pub fn get_search_info(&mut self, score: Score) -> SearchInfo {
+        // Prefer the PV collected during search, as the TT may have lost part
+        // of it to overwrites
+        let pv = match self.pv_table.root_line() {
+            [] => self.tt.extract_pv(self.board),
+            line => line.to_vec(),
+        };
+
+        #[cfg(debug_assertions)]
+        assert_pv_legal(self.board, &pv);
+
         SearchInfo {
             score,
             raw_stats: self.stats.clone(),
             elapsed: self.time_manager.start_time().elapsed(),
-            pv: self.tt.extract_pv(self.board),
+            pv,
             hash_full: self.tt.occupancy(),
+            phase: Phase::from_board(self.board),
+            multi_pv: None,
+        }
+    }
+
+    pub fn report_curr_move(&self, depth: i16, curr_move: Move, curr_move_number: u32) {
+        if let Some(reporter) = self.curr_move_reporter {
+            if self.time_manager.start_time().elapsed() >= CURR_MOVE_REPORT_DELAY {
+                reporter(CurrMoveInfo {
+                    depth,
+                    curr_move,
+                    curr_move_number,
+                });
+            }
         }
     }
 
     pub fn iterative_deepening_search(
-        mut self,
+        &mut self,
         send_search_info: impl Fn(SearchInfo),
     ) -> SearchResult {
         let mut best_score = Score(0);
         let mut best_move = None;
+        let mut extra_line_scores = Vec::new();
 
         for depth in 1..=MAX_DEPTH {
-            let score = self.aspiration_search(best_score, depth);
+            let score = match self.mate_plies {
+                // Do a normal search first so there's a move to fall back on
+                // if there's no mate
+                Some(mate_plies) if depth > 1 => self.mate_search(best_score, depth, mate_plies),
+                _ => self.aspiration_search(best_score, depth),
+            };
 
             // Accept the found best move, even from a partial search.
             if let Some(m) = self.best_root_move.take() {
                 best_move = Some(m);
+            }
+
+            self.stats.depth = depth as _;
+
+            // Partial iterations would skew the branching factor
+            if !self.search_cancelled {
+                self.stats.record_iteration();
+
+                // Write the PV back into the TT so that later PV extraction
+                // (e.g. when analysing) sees the full line
+                let pv = self.pv_table.root_line().to_vec();
+                self.tt.reinsert_pv(self.board, &pv);
 
                 // Already found a mate, don't need to look any further --
                 // although, don't trust mate scores that are greater than the
                 // current depth, as they may be from the TT or extensions
-                if let Some(signed_plies) = best_score.as_mate_in_plies() {
-                    if signed_plies.abs() <= depth as i32 {
+                if let Some(signed_plies) = score.as_mate_in_plies() {
+                    let within_mate_limit = self
+                        .mate_plies
+                        .is_some_and(|limit| (1..=limit as i32).contains(&signed_plies));
+
+                    if best_move.is_some()
+                        && (signed_plies.abs() <= depth as i32 || within_mate_limit)
+                    {
+                        best_score = score;
                         break;
                     }
                 }
-            }
 
-            self.stats.depth = depth as _;
+                // Reductions mean a mate may only be found a few iterations
+                // after the depth reaches its length, but give up eventually
+                if self.mate_plies.is_some_and(|limit| {
+                    depth as i32 >= MATE_SEARCH_MAX_DEPTH_FACTOR as i32 * limit as i32
+                }) {
+                    best_score = score;
+                    break;
+                }
+            }
 
             // Update soft bound parameters on the time manager
             self.time_manager.on_iteration_end(
                 depth,
+                best_move,
                 match best_move {
                     Some(m) => self.effort.get_effort(m, self.stats.nodes_searched),
                     None => 0.0,
                 },
             );
 
+            // The other lines are searched before checking the soft bound, so
+            // that they're also reported for the last iteration
+            if self.multi_pv > 1 && !self.search_cancelled {
+                let mut info = self.get_search_info(score);
+                info.multi_pv = Some(1);
+                send_search_info(info);
+
+                self.search_extra_lines(
+                    depth,
+                    best_move,
+                    &mut extra_line_scores,
+                    &send_search_info,
+                );
+            }
+
             // Must search to at least depth 1.
             if depth > 1 && self.check_soft_bound(depth) {
                 break;
@@ -139,19 +437,27 @@ impl<'a> SearchContext<'a> {
 
             best_score = score;
 
-            send_search_info(self.get_search_info(best_score));
+            if self.multi_pv <= 1 {
+                send_search_info(self.get_search_info(best_score));
+            }
         }
 
         // In the rare case that the engine doesn't return a move, just play the
         // first one in this position
         if best_move.is_none() {
             eprintln!("Search did not return root best move, engine is probably going to blunder!");
-            best_move = self.board.legal_moves().first().copied();
+            let legal_moves = self.board.legal_moves();
+            best_move = legal_moves
+                .iter()
+                .find(|m| self.root_moves.contains(m))
+                .or(legal_moves.first())
+                .copied();
         }
 
         SearchResult {
             best_move,
             info: self.get_search_info(best_score),
+            ponder_outcome: None,
         }
     }
 
@@ -169,3 +475,494 @@ impl<'a> SearchContext<'a> {
         }
     }
 }
+
+/// Replays the PV on a copy of the board, panicking on the first move that is
+/// not legal in the position it is played from.
+#[cfg(debug_assertions)]
+fn assert_pv_legal(board: &Board, pv: &[Move]) {
+    let mut board = board.clone();
+
+    for (i, &m) in pv.iter().enumerate() {
+        assert!(
+            board.legal_moves().contains(&m),
+            "illegal move {m:?} at index {i} of PV {pv:?} (fen: {})",
+            board.fen()
+        );
+        board.push_move_unchecked(m);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::{BoardState, Color, Square, UCIMove, STARTING_POSITION_FEN};
+
+    use crate::{history_table::HistoryTable, parameters::MAX_PLY};
+
+    use super::*;
+
+    /// Runs `f` with a search context for the position, using its own small
+    /// transposition table and history table.
+    fn with_search_context<T>(
+        fen: &str,
+        search_limits: SearchLimits,
+        f: impl FnOnce(SearchContext) -> T,
+    ) -> T {
+        let mut board = Board::try_parse_fen(fen).unwrap();
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+        let mut history = HistoryTable::new();
+        let abort_flag = AtomicBool::new(false);
+
+        f(SearchContext::new(
+            &mut board,
+            search_limits,
+            &mut tt,
+            &mut history,
+            &abort_flag,
+        ))
+    }
+
+    fn search_with_limits(fen: &str, search_limits: SearchLimits) -> SearchResult {
+        with_search_context(fen, search_limits, |mut ctx| {
+            ctx.iterative_deepening_search(|_| {})
+        })
+    }
+
+    #[test]
+    fn repetitions_before_root_need_to_be_threefold() {
+        with_search_context(
+            STARTING_POSITION_FEN,
+            SearchLimits::infinite(),
+            |mut ctx| {
+                // Moves played in the game before the search starts
+                for m in ["g1f3", "b8c6", "f3g1", "c6b8"] {
+                    ctx.board.push_uci(m).unwrap();
+                }
+
+                // The root position occurred once before in the game
+                assert!(!ctx.is_repetition(0));
+
+                // Repeating it within the tree is enough, however
+                for m in ["g1f3", "b8c6", "f3g1", "c6b8"] {
+                    let m = m.parse::<UCIMove>().unwrap();
+                    let m = ctx.board.get_move(m.from, m.to, m.promotion).unwrap();
+                    ctx.make_move(m);
+                }
+                assert!(ctx.is_repetition(4));
+
+                // Positions that only occurred once before the root aren't
+                for _ in 0..4 {
+                    ctx.unmake_move();
+                }
+                let m = ctx.board.get_move(Square::G1, Square::F3, None).unwrap();
+                ctx.make_move(m);
+                assert!(!ctx.is_repetition(1));
+            },
+        );
+    }
+
+    fn static_eval(fen: &str) -> Score {
+        with_search_context(fen, SearchLimits::infinite(), |ctx| ctx.evaluate())
+    }
+
+    fn quiescence_eval(fen: &str) -> Score {
+        with_search_context(fen, SearchLimits::infinite(), |mut ctx| {
+            ctx.quiescence(0, -Score::INF, Score::INF)
+        })
+    }
+
+    #[test]
+    fn quiescence_finds_quiet_checking_fork() {
+        // White is down the exchange, but Nc7+ forks the king and rook, which
+        // only a quiet check in the quiescence search can see
+        let fen = "r3k3/7p/8/1N6/8/8/7P/6K1 w - - 0 1";
+
+        assert!(static_eval(fen) < Score(0));
+        assert!(quiescence_eval(fen) > Score(100));
+    }
+
+    #[test]
+    fn quiescence_scores_checkmate_when_in_check() {
+        // Ra8# is a quiet check, after which black is in check with no
+        // evasions
+        let score = quiescence_eval("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(score, Score::mate_in_plies(1));
+
+        // Already checkmated
+        let score = quiescence_eval("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        assert_eq!(score, -Score::mate_in_plies(0));
+    }
+
+    #[test]
+    fn quiescence_delta_prunes_hopeless_captures() {
+        // White is a queen down, so winning the e4 pawn can't get anywhere
+        // near alpha
+        let fen = "4k3/8/8/3q4/4p3/3P4/8/4K3 w - - 0 1";
+
+        with_search_context(fen, SearchLimits::infinite(), |mut ctx| {
+            let score = ctx.quiescence(0, Score(0), Score(1));
+
+            assert_eq!(score, static_eval(fen));
+            assert_eq!(ctx.stats.quiescence_nodes, 1);
+        });
+    }
+
+    #[test]
+    fn quiescence_searches_quiet_checks_despite_delta_pruning() {
+        // White is far too much material down for any capture to raise
+        // alpha, but Ra8# is a quiet check which mates
+        let fen = "6k1/5ppp/7q/7q/8/8/8/R5K1 w - - 0 1";
+
+        let score = with_search_context(fen, SearchLimits::infinite(), |mut ctx| {
+            ctx.quiescence(0, Score(0), Score(1))
+        });
+
+        assert_eq!(score, Score::mate_in_plies(1));
+    }
+
+    #[test]
+    fn stalemate_is_not_forward_pruned() {
+        // Black has no captures and is stalemated, but the static evaluation
+        // is far enough above beta for reverse futility pruning to fire
+        let fen = "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1";
+
+        let score = with_search_context(fen, SearchLimits::infinite(), |mut ctx| {
+            ctx.negamax::<node_types::NonPV>(2, 1, Score(-5001), Score(-5000))
+        });
+
+        assert_eq!(score, Score(0));
+    }
+
+    #[test]
+    fn advantage_shrinks_near_fifty_move_rule() {
+        // Equal material, but white's centralised knight beats the cornered
+        // bishop. The pawns keep this out of the likely draw heuristics.
+        let early = static_eval("7k/7p/8/2N5/8/8/7P/b3K3 w - - 10 60");
+        let late = static_eval("7k/7p/8/2N5/8/8/7P/b3K3 w - - 90 100");
+
+        assert!(early > Score(0));
+        assert!(late > Score(0));
+        assert!(late < early);
+    }
+
+    #[test]
+    fn fifty_move_scaling_leaves_mate_scores_alone() {
+        let mate = Score::mate_in_plies(3);
+        assert_eq!(SearchContext::scale_for_fifty_move_rule(mate, 95), mate);
+        assert_eq!(SearchContext::scale_for_fifty_move_rule(-mate, 95), -mate);
+
+        assert_eq!(
+            SearchContext::scale_for_fifty_move_rule(Score(200), FIFTY_MOVE_SCALING_THRESHOLD),
+            Score(200)
+        );
+        assert_eq!(
+            SearchContext::scale_for_fifty_move_rule(Score(200), 100),
+            Score(0)
+        );
+    }
+
+    fn nodes_to_depth(aspiration_min_depth: i16, depth: i16) -> u64 {
+        let result = with_search_context(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+            SearchLimits {
+                depth,
+                ..SearchLimits::infinite()
+            },
+            |ctx| {
+                ctx.with_params(SearchParams {
+                    aspiration_min_depth,
+                    ..Default::default()
+                })
+                .iterative_deepening_search(|_| {})
+            },
+        );
+
+        result.info.raw_stats.nodes_searched
+    }
+
+    #[test]
+    fn aspiration_min_depth_controls_when_narrow_windows_start() {
+        // No aspiration windows are used below the minimum depth, so searches
+        // that stop before then are identical...
+        assert_eq!(nodes_to_depth(5, 4), nodes_to_depth(MAX_DEPTH, 4));
+
+        // ...but starting them earlier changes the search at those depths
+        assert_ne!(nodes_to_depth(1, 4), nodes_to_depth(MAX_DEPTH, 4));
+        assert_ne!(nodes_to_depth(5, 7), nodes_to_depth(MAX_DEPTH, 7));
+    }
+
+    #[test]
+    fn mate_search_finds_mate_within_limit() {
+        let fen = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";
+        let board = Board::try_parse_fen(fen).unwrap();
+
+        // Mate in 3 starting with Bc5+
+        let result = search_with_limits(
+            fen,
+            SearchLimits {
+                mate: Some(3),
+                ..SearchLimits::infinite()
+            },
+        );
+        assert_eq!(result.info.score.as_mate_in(), Some(3));
+        assert_eq!(
+            result.best_move,
+            board.get_move(Square::F8, Square::C5, None)
+        );
+
+        // There's no mate in 2, so the search gives up without one
+        let result = search_with_limits(
+            fen,
+            SearchLimits {
+                mate: Some(2),
+                ..SearchLimits::infinite()
+            },
+        );
+        assert!(!result.info.score.is_mate());
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn mate_search_without_mate_keeps_root_move() {
+        // There's no mate in 2, so every iteration after the first fails low
+        with_search_context(
+            "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1",
+            SearchLimits {
+                mate: Some(2),
+                ..SearchLimits::infinite()
+            },
+            |mut ctx| {
+                let result = ctx.iterative_deepening_search(|_| {});
+                let root_entry = ctx.tt.get(ctx.board.zobrist_hash()).unwrap();
+
+                assert!(result.best_move.is_some());
+                assert_eq!(result.info.pv.first().copied(), result.best_move);
+                assert_eq!(root_entry.best_move, result.best_move);
+                assert_eq!(
+                    ctx.tt.extract_pv(ctx.board).first().copied(),
+                    result.best_move
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn huge_mate_limits_are_clamped() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let board = Board::try_parse_fen(fen).unwrap();
+
+        for moves in [9000, 40000, u16::MAX] {
+            let result = search_with_limits(
+                fen,
+                SearchLimits {
+                    mate: Some(moves),
+                    ..SearchLimits::infinite()
+                },
+            );
+            assert_eq!(result.info.score.as_mate_in(), Some(1));
+            assert_eq!(
+                result.best_move,
+                board.get_move(Square::A1, Square::A8, None)
+            );
+        }
+    }
+
+    #[test]
+    fn multi_pv_lines_exclude_earlier_best_moves() {
+        let infos = std::cell::RefCell::new(Vec::new());
+
+        let (result, root_entry) = with_search_context(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+            SearchLimits {
+                depth: 5,
+                ..SearchLimits::infinite()
+            },
+            |ctx| {
+                let mut ctx = ctx.with_multi_pv(3);
+                let result = ctx.iterative_deepening_search(|info| infos.borrow_mut().push(info));
+                (result, ctx.tt.get(ctx.board.zobrist_hash()).unwrap())
+            },
+        );
+
+        let infos = infos.into_inner();
+        let last_depth: Vec<_> = infos
+            .iter()
+            .filter(|info| info.raw_stats.depth == 5)
+            .collect();
+        assert_eq!(
+            last_depth
+                .iter()
+                .map(|info| info.multi_pv)
+                .collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(3)]
+        );
+
+        // Each line starts with a different move, the first being the best
+        let first_moves: Vec<_> = last_depth.iter().map(|info| info.pv[0]).collect();
+        assert_eq!(Some(first_moves[0]), result.best_move);
+        assert_ne!(first_moves[0], first_moves[1]);
+        assert_ne!(first_moves[0], first_moves[2]);
+        assert_ne!(first_moves[1], first_moves[2]);
+
+        // Lines are reported best first
+        assert!(last_depth[0].score >= last_depth[1].score);
+        assert!(last_depth[1].score >= last_depth[2].score);
+
+        // The extra lines don't overwrite the root entry with scores from
+        // searches that left out the best move
+        assert_eq!(root_entry.best_move, result.best_move);
+        assert_eq!(root_entry.get_score(0), last_depth[0].score);
+    }
+
+    #[test]
+    fn contempt_scores_draws_for_the_root_side() {
+        // Drawn by insufficient material whatever happens
+        let fen = "4k3/8/8/8/3B4/8/8/4K3 w - - 0 1";
+
+        for contempt in [50, -50] {
+            let depth_4 = SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            };
+            let result = with_search_context(fen, depth_4, |ctx| {
+                ctx.with_contempt(contempt)
+                    .iterative_deepening_search(|_| {})
+            });
+
+            assert_eq!(result.info.score, Score(-contempt));
+        }
+    }
+
+    #[test]
+    fn insufficient_material_is_scored_as_draw() {
+        for fen in [
+            // Centralised bishop
+            "4k3/8/8/8/3B4/8/8/4K3 w - - 0 1",
+            // Cornered bishop
+            "4k3/8/8/8/8/8/8/B3K3 w - - 0 1",
+            "4k3/8/8/8/3B4/8/8/4K3 b - - 0 1",
+        ] {
+            // The static evaluation still thinks the bishop is worth something
+            assert_ne!(static_eval(fen), Score(0));
+
+            let result = search_with_limits(
+                fen,
+                SearchLimits {
+                    depth: 6,
+                    ..SearchLimits::infinite()
+                },
+            );
+
+            assert_eq!(result.info.score, Score(0), "{fen}");
+            assert!(result.best_move.is_some());
+        }
+    }
+
+    #[test]
+    fn search_never_exceeds_ply_limit() {
+        // Lots of captures and checks available, so both the main search and
+        // quiescence search would go well past the limit if allowed to
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        with_search_context(fen, SearchLimits::infinite(), |mut ctx| {
+            // Pretend extensions have already pushed the search close to the
+            // limit
+            let score = ctx.negamax::<node_types::PV>(6, MAX_PLY - 3, -Score::INF, Score::INF);
+
+            assert!(score > -Score::INF && score < Score::INF);
+            assert!(ctx.stats.sel_depth <= MAX_PLY);
+        });
+    }
+
+    #[test]
+    fn mate_in_3_pv_is_complete() {
+        let depth_10 = SearchLimits {
+            depth: 10,
+            ..SearchLimits::infinite()
+        };
+
+        with_search_context(
+            "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1",
+            depth_10,
+            |mut ctx| {
+                let result = ctx.iterative_deepening_search(|_| {});
+
+                assert_eq!(result.info.score.as_mate_in(), Some(3));
+                assert_eq!(result.info.pv.len(), 5);
+
+                // The PV should also be recoverable from the TT afterwards
+                let tt_pv = ctx.tt.extract_pv(ctx.board);
+                assert_eq!(tt_pv, result.info.pv);
+
+                for m in tt_pv {
+                    ctx.board.push_move_unchecked(m);
+                }
+                assert_eq!(ctx.board.state(), BoardState::Win(Color::White));
+            },
+        );
+    }
+
+    #[test]
+    fn search_moves_restricts_root_moves() {
+        // Qxa4 wins the queen, but we only let the engine look at quiet moves
+        let fen = "4k3/8/8/8/q7/8/8/3QK3 w - - 0 1";
+        let board = Board::try_parse_fen(fen).unwrap();
+        let capture = board.get_move(Square::D1, Square::A4, None).unwrap();
+        let quiet_a = board.get_move(Square::E1, Square::E2, None).unwrap();
+        let quiet_b = board.get_move(Square::E1, Square::F2, None).unwrap();
+
+        let result = search_with_limits(
+            fen,
+            SearchLimits {
+                depth: 4,
+                search_moves: vec![quiet_a, quiet_b],
+                ..SearchLimits::infinite()
+            },
+        );
+
+        let best_move = result.best_move.unwrap();
+        assert_ne!(best_move, capture);
+        assert!([quiet_a, quiet_b].contains(&best_move));
+        assert_eq!(result.info.pv.first(), Some(&best_move));
+    }
+
+    #[test]
+    fn empty_search_moves_searches_all_moves() {
+        let fen = "4k3/8/8/8/q7/8/8/3QK3 w - - 0 1";
+        let board = Board::try_parse_fen(fen).unwrap();
+        let capture = board.get_move(Square::D1, Square::A4, None).unwrap();
+
+        let result = search_with_limits(
+            fen,
+            SearchLimits {
+                depth: 4,
+                ..SearchLimits::infinite()
+            },
+        );
+
+        assert_eq!(result.best_move, Some(capture));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn assert_pv_legal_accepts_legal_pv() {
+        let board = Board::starting_position();
+        let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
+
+        let mut after_e4 = board.clone();
+        after_e4.push_move_unchecked(e4);
+        let e5 = after_e4.get_move(Square::E7, Square::E5, None).unwrap();
+
+        assert_pv_legal(&board, &[e4, e5]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "illegal move")]
+    fn assert_pv_legal_panics_on_corrupted_pv() {
+        let board = Board::starting_position();
+        let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
+
+        // White plays e4 twice in a row, which can't happen
+        assert_pv_legal(&board, &[e4, e4]);
+    }
+}
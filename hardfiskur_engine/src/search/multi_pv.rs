@@ -0,0 +1,69 @@
+use hardfiskur_core::board::Move;
+
+use crate::{score::Score, search_result::SearchInfo};
+
+use super::SearchContext;
+
+impl<'a> SearchContext<'a> {
+    /// Searches the lines after the first for MultiPV at the given depth,
+    /// each one excluding the best moves of the lines before it, and reports
+    /// them as they complete. `scores` holds the score of each extra line from
+    /// the previous iteration, for the aspiration windows.
+    ///
+    /// The PV table and the PV in the transposition table are left holding
+    /// the main line afterwards.
+    pub(super) fn search_extra_lines(
+        &mut self,
+        depth: i16,
+        best_move: Option<Move>,
+        scores: &mut Vec<Score>,
+        send_search_info: &impl Fn(SearchInfo),
+    ) {
+        let Some(best_move) = best_move else {
+            return;
+        };
+
+        let root_moves = self.board.legal_moves();
+        let root_move_count = if root_moves.iter().any(|m| self.root_moves.contains(m)) {
+            root_moves
+                .iter()
+                .filter(|m| self.root_moves.contains(m))
+                .count()
+        } else {
+            root_moves.len()
+        };
+        let lines = self.multi_pv.min(root_move_count);
+
+        let main_pv_table = std::mem::take(&mut self.pv_table);
+        self.excluded_root_moves.push(best_move);
+
+        for line in 1..lines {
+            let prev_score = scores.get(line - 1).copied().unwrap_or_default();
+            let score = self.aspiration_search(prev_score, depth);
+
+            let Some(m) = self.best_root_move.take() else {
+                break;
+            };
+            if self.search_cancelled {
+                break;
+            }
+
+            self.excluded_root_moves.push(m);
+            if scores.len() < line {
+                scores.push(score);
+            } else {
+                scores[line - 1] = score;
+            }
+
+            let mut info = self.get_search_info(score);
+            info.multi_pv = Some(line as u32 + 1);
+            send_search_info(info);
+        }
+
+        self.excluded_root_moves.clear();
+        self.pv_table = main_pv_table;
+
+        let pv = self.pv_table.root_line().to_vec();
+        self.tt.reinsert_pv(self.board, &pv);
+    }
+}
@@ -1,10 +1,11 @@
 use hardfiskur_core::board::Move;
-use hardfiskur_core::move_gen::MoveVec;
+use hardfiskur_core::move_gen::{MoveGenStage, MoveVec};
 
 use crate::{
-    evaluation::evaluate,
     move_ordering::MovePicker,
-    parameters::{IIR_MIN_DEPTH, LMR_BASE, LMR_DIVISOR, LMR_MIN_DEPTH, LMR_MIN_MOVES_PLAYED},
+    parameters::{
+        IIR_MIN_DEPTH, LMR_BASE, LMR_DIVISOR, LMR_MIN_DEPTH, LMR_MIN_MOVES_PLAYED, MAX_PLY,
+    },
     score::Score,
     search::forward_pruning::MovePruning,
     transposition_table::{TranspositionEntry, TranspositionFlag},
@@ -21,35 +22,47 @@ impl<'a> SearchContext<'a> {
         mut depth: i16,
         ply_from_root: u16,
         mut alpha: Score,
-        beta: Score,
+        mut beta: Score,
     ) -> Score {
         self.consistency_check();
         debug_assert!(NT::IS_PV || beta - alpha == Score(1));
 
-        // Repetition & 50-move-rule handling
-        if self
-            .board
-            .current_position_repeated_at_least(if ply_from_root >= 2 { 1 } else { 2 })
+        if NT::IS_PV {
+            self.pv_table.clear_ply(ply_from_root);
+        }
+
+        // Repetition, 50-move-rule & insufficient material handling. (The
+        // root still needs to search so that a move is returned.)
+        if self.is_repetition(ply_from_root)
             || self.board.halfmove_clock() >= 100
+            || (!NT::IS_ROOT && self.board.check_draw_by_insufficient_material())
         {
-            return Score(0);
+            return self.draw_score();
         }
 
-        let (legal_moves, move_gen_result) = self.board.legal_moves_and_meta();
+        if !NT::IS_ROOT {
+            if let Some(score) = self.mate_distance_pruning(ply_from_root, &mut alpha, &mut beta) {
+                return score;
+            }
+        }
 
-        // Handle checkmate/stalemate
-        let in_check = move_gen_result.checker_count > 0;
-        if legal_moves.is_empty() {
-            return if in_check {
-                // Checkmate
-                -Score::mate_in_plies(ply_from_root)
-            } else {
-                // Stalemate
-                Score(0)
-            };
+        // Extensions can keep adding plies, so stop at the ply limit no matter
+        // how much depth is left
+        if ply_from_root >= MAX_PLY {
+            return self.evaluate();
         }
 
         if depth <= 0 {
+            // Quiescence search only detects checkmate, not stalemate, as it
+            // doesn't generate quiet moves when not in check, so handle that
+            // first
+            let legal_moves = &mut self.move_buffers[ply_from_root as usize];
+            let move_gen_result = self.board.legal_moves_into(legal_moves);
+            if legal_moves.is_empty() {
+                let in_check = move_gen_result.checker_count > 0;
+                return self.no_legal_moves_score(in_check, ply_from_root);
+            }
+
             return self.quiescence(ply_from_root, alpha, beta);
         }
 
@@ -62,25 +75,68 @@ impl<'a> SearchContext<'a> {
         let tt_entry = if let Some(entry) = self.tt.get(self.board.zobrist_hash()) {
             // TODO: If this is a beta cutoff, it needs to do killer/history
             // updates etc.
-            if !NT::IS_PV && Self::should_cutoff(&entry, depth, ply_from_root, alpha, beta) {
+            let score = entry.get_score(ply_from_root);
+            if !NT::IS_PV && Self::should_cutoff(&entry, score, depth, alpha, beta) {
                 self.stats.tt_hits += 1;
 
                 // Sanity check
                 assert!(!NT::IS_ROOT);
 
-                return entry.get_score(ply_from_root);
+                return score;
             }
 
             Some(entry)
         } else {
             None
         };
+        let tt_move = tt_entry.as_ref().and_then(|entry| entry.best_move);
+
+        // Outside of the root, only generate captures for now and leave the
+        // quiets to the move picker, unless a quiet TT move needs them anyway
+        let mut staged = !NT::IS_ROOT && tt_move.is_none_or(|m| m.is_capture());
+
+        let legal_moves = &mut self.move_buffers[ply_from_root as usize];
+        let move_gen_result = if staged {
+            self.board.legal_captures_into(legal_moves)
+        } else {
+            self.board.legal_moves_into(legal_moves)
+        };
+
+        // Having no captures doesn't mean having no moves, and forward pruning
+        // could return before the move picker gets round to the quiets, so
+        // generate them now in case this is stalemate. Forward pruning is
+        // skipped in check, so checkmate can wait until after the move loop.
+        let in_check = move_gen_result.checker_count > 0;
+        if staged && legal_moves.is_empty() && !in_check {
+            self.board
+                .legal_moves_ex(MoveGenStage::Quiets.flags(), legal_moves);
+            staged = false;
+        }
+
+        // Handle checkmate/stalemate
+        if !staged && legal_moves.is_empty() {
+            return self.no_legal_moves_score(in_check, ply_from_root);
+        }
+
+        // Only search the requested moves at the root, unless none of them
+        // are legal, in which case fall back to searching everything
+        if NT::IS_ROOT
+            && !self.root_moves.is_empty()
+            && legal_moves.iter().any(|m| self.root_moves.contains(m))
+        {
+            legal_moves.retain(|m| self.root_moves.contains(m));
+        }
+
+        // With MultiPV, skip the moves already chosen for earlier lines
+        if NT::IS_ROOT && !self.excluded_root_moves.is_empty() {
+            legal_moves.retain(|m| !self.excluded_root_moves.contains(m));
+        }
 
         // Internal Iterative Reductions
         if depth >= IIR_MIN_DEPTH
             && tt_entry
                 .as_ref()
-                .map_or(true, |entry| entry.depth + 4 <= depth)
+                .is_none_or(|entry| entry.depth + 4 <= depth)
         {
             depth -= 1;
         }
@@ -88,11 +144,13 @@ impl<'a> SearchContext<'a> {
         let static_eval = match tt_entry.as_ref() {
             None if in_check => -Score::INF,
             Some(entry) => entry.get_score(ply_from_root),
-            None => evaluate(self.board),
+            None => self.evaluate(),
         };
 
-        // Forward pruning
-        if !NT::IS_ROOT && !NT::IS_PV && !in_check {
+        // Forward pruning. Skipped when searching for mate, as the margins
+        // are meaningless against a window made of mate scores
+        let mate_search = self.mate_plies.is_some();
+        if !NT::IS_ROOT && !NT::IS_PV && !in_check && !mate_search {
             if let Some(score) =
                 self.forward_pruning(depth, ply_from_root, static_eval, alpha, beta)
             {
@@ -100,8 +158,11 @@ impl<'a> SearchContext<'a> {
             }
         }
 
-        let mut ordered_moves =
-            MovePicker::new(legal_moves, tt_entry.and_then(|entry| entry.best_move));
+        let mut ordered_moves = if staged {
+            MovePicker::staged(tt_move)
+        } else {
+            MovePicker::new(tt_move)
+        };
 
         let mut best_score = -Score::INF;
         let mut best_move = None;
@@ -109,12 +170,16 @@ impl<'a> SearchContext<'a> {
         let mut previously_played_quiets = MoveVec::new();
 
         let mut moves_played = 0;
-        'move_loop: while let Some(m) =
-            ordered_moves.next_move(self.board, ply_from_root, &self.killers, self.history)
-        {
+        'move_loop: while let Some(m) = ordered_moves.next_move(
+            &mut self.move_buffers[ply_from_root as usize],
+            self.board,
+            ply_from_root,
+            &self.killers,
+            self.history,
+        ) {
             // Move forward pruning. Don't perform if we're in the root, not
             // played any moves yet, or possibly losing to a mating attack
-            if !NT::IS_ROOT && moves_played > 0 && !best_score.is_mate_for_them() {
+            if !NT::IS_ROOT && moves_played > 0 && !best_score.is_mate_for_them() && !mate_search {
                 match self.move_forward_pruning::<NT>(
                     m,
                     depth,
@@ -131,8 +196,19 @@ impl<'a> SearchContext<'a> {
 
             let prev_total_nodes = self.stats.nodes_searched;
 
-            self.board.push_move_unchecked(m);
             moves_played += 1;
+            if NT::IS_ROOT {
+                self.report_curr_move(depth, m, moves_played as u32);
+            }
+
+            // Don't let the PV from a previous sibling leak into this move's
+            // line if the child isn't searched as a PV node
+            if NT::IS_PV {
+                self.pv_table.clear_ply(ply_from_root + 1);
+            }
+
+            self.make_move(m);
+            self.tt.prefetch(self.board.zobrist_hash());
 
             let eval = if moves_played == 1 {
                 -self.negamax::<NT::Next>(depth - 1, ply_from_root + 1, -beta, -alpha)
@@ -142,7 +218,7 @@ impl<'a> SearchContext<'a> {
                 self.principal_variation_search::<NT>(depth, ply_from_root, reduction, alpha, beta)
             };
 
-            self.board.pop_move();
+            self.unmake_move();
 
             if NT::IS_ROOT {
                 let subtree_nodes = self.stats.nodes_searched - prev_total_nodes;
@@ -160,6 +236,10 @@ impl<'a> SearchContext<'a> {
                 alpha = eval;
                 best_move = Some(m);
 
+                if NT::IS_PV {
+                    self.pv_table.update(ply_from_root, m);
+                }
+
                 if NT::IS_ROOT {
                     self.best_root_move = Some(m);
                 }
@@ -175,6 +255,14 @@ impl<'a> SearchContext<'a> {
             }
         }
 
+        if moves_played == 0 {
+            // Only reachable in check with staged generation, as stalemate
+            // was ruled out above. Forward pruning is skipped in check and
+            // move pruning never skips the first move, so there really were
+            // no legal moves
+            return self.no_legal_moves_score(in_check, ply_from_root);
+        }
+
         let tt_flag = Self::determine_tt_flag(best_score, original_alpha, beta);
         if tt_flag == TranspositionFlag::Lowerbound {
             self.stats.beta_cutoffs += 1;
@@ -193,14 +281,34 @@ impl<'a> SearchContext<'a> {
             }
         }
 
-        self.tt.set(
-            self.board.zobrist_hash(),
-            TranspositionEntry::new(tt_flag, depth, best_score, best_move, ply_from_root),
-        );
+        // Mate searches that fail low at the root have no best move, so keep
+        // the one from the previous iteration instead of overwriting it
+        if NT::IS_ROOT && mate_search {
+            best_move = best_move.or(tt_move);
+        }
+
+        // Root searches for the extra MultiPV lines leave out the best moves,
+        // so their results aren't valid for the position
+        if !NT::IS_ROOT || self.excluded_root_moves.is_empty() {
+            self.tt.set(
+                self.board.zobrist_hash(),
+                TranspositionEntry::new(tt_flag, depth, best_score, best_move, ply_from_root),
+            );
+        }
 
         best_score
     }
 
+    pub(super) fn no_legal_moves_score(&self, in_check: bool, ply_from_root: u16) -> Score {
+        if in_check {
+            // Checkmate
+            -Score::mate_in_plies(ply_from_root)
+        } else {
+            // Stalemate
+            self.draw_score()
+        }
+    }
+
     fn principal_variation_search<NT: NodeType>(
         &mut self,
         depth: i16,
@@ -247,16 +355,16 @@ impl<'a> SearchContext<'a> {
 
     fn should_cutoff(
         entry: &TranspositionEntry,
+        score: Score,
         depth: i16,
-        ply_from_root: u16,
         alpha: Score,
         beta: Score,
     ) -> bool {
         match entry.flag {
             _ if depth > entry.depth => false,
             TranspositionFlag::Exact => true,
-            TranspositionFlag::Lowerbound => entry.get_score(ply_from_root) >= beta,
-            TranspositionFlag::Upperbound => entry.get_score(ply_from_root) <= alpha,
+            TranspositionFlag::Lowerbound => score >= beta,
+            TranspositionFlag::Upperbound => score <= alpha,
         }
     }
 
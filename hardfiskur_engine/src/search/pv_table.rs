@@ -0,0 +1,81 @@
+use hardfiskur_core::board::Move;
+
+/// Triangular table of principal variations, indexed by ply from root.
+///
+/// The line at each ply is the best move found at that ply followed by the
+/// line at the next ply. Unlike extracting the PV from the transposition
+/// table, this can't be truncated or corrupted by later overwrites, which
+/// matters most for long forced lines like mates.
+#[derive(Debug, Clone, Default)]
+pub struct PvTable {
+    lines: Vec<Vec<Move>>,
+}
+
+impl PvTable {
+    pub fn clear_ply(&mut self, ply_from_root: u16) {
+        let ply = ply_from_root as usize;
+        if self.lines.len() <= ply {
+            self.lines.resize_with(ply + 1, Vec::new);
+        }
+
+        self.lines[ply].clear();
+    }
+
+    pub fn update(&mut self, ply_from_root: u16, m: Move) {
+        let ply = ply_from_root as usize;
+        if self.lines.len() <= ply + 1 {
+            self.lines.resize_with(ply + 2, Vec::new);
+        }
+
+        let (parents, children) = self.lines.split_at_mut(ply + 1);
+        let line = &mut parents[ply];
+
+        line.clear();
+        line.push(m);
+        line.extend_from_slice(&children[0]);
+    }
+
+    pub fn root_line(&self) -> &[Move] {
+        self.lines.first().map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::{Board, Square};
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn update_prepends_move_to_child_line() {
+        let mut board = Board::starting_position();
+        let e4 = board.push_move(Square::E2, Square::E4, None).unwrap();
+        let e5 = board.push_move(Square::E7, Square::E5, None).unwrap();
+        let nf3 = board.push_move(Square::G1, Square::F3, None).unwrap();
+
+        let mut pv_table = PvTable::default();
+        pv_table.clear_ply(0);
+        pv_table.clear_ply(1);
+        pv_table.clear_ply(2);
+        pv_table.update(2, nf3);
+        pv_table.update(1, e5);
+        pv_table.update(0, e4);
+
+        assert_eq!(pv_table.root_line(), &[e4, e5, nf3]);
+    }
+
+    #[test]
+    fn cleared_child_line_truncates_pv() {
+        let mut board = Board::starting_position();
+        let e4 = board.push_move(Square::E2, Square::E4, None).unwrap();
+        let e5 = board.push_move(Square::E7, Square::E5, None).unwrap();
+
+        let mut pv_table = PvTable::default();
+        pv_table.update(1, e5);
+        pv_table.clear_ply(1);
+        pv_table.update(0, e4);
+
+        assert_eq!(pv_table.root_line(), &[e4]);
+    }
+}
@@ -1,8 +1,8 @@
-use hardfiskur_core::move_gen::{MoveGenFlags, MoveVec};
+use hardfiskur_core::board::{Bitboard, Piece, PieceType, SEE_PIECE_VALUES};
 
 use crate::{
-    evaluation::evaluate,
     move_ordering::MovePicker,
+    parameters::{DELTA_PRUNING_MARGIN, MAX_PLY, QS_CHECK_PLIES},
     score::Score,
     transposition_table::{TranspositionEntry, TranspositionFlag},
 };
@@ -10,7 +10,19 @@ use crate::{
 use super::SearchContext;
 
 impl<'a> SearchContext<'a> {
-    pub fn quiescence(&mut self, ply_from_root: u16, mut alpha: Score, beta: Score) -> Score {
+    pub fn quiescence(&mut self, ply_from_root: u16, alpha: Score, beta: Score) -> Score {
+        self.quiescence_ex(ply_from_root, 0, alpha, beta)
+    }
+
+    /// Quiescence search, where `qs_ply` is the number of plies since the
+    /// quiescence search was entered.
+    fn quiescence_ex(
+        &mut self,
+        ply_from_root: u16,
+        qs_ply: u16,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
         self.consistency_check();
 
         // Increment stats
@@ -18,52 +30,118 @@ impl<'a> SearchContext<'a> {
         self.stats.quiescence_nodes += 1;
         self.stats.sel_depth = self.stats.sel_depth.max(ply_from_root);
 
-        let (mut best_score, tt_entry) = if let Some(entry) = self.tt.get(self.board.zobrist_hash())
-        {
-            if Self::should_cutoff_quiescence(&entry, alpha, beta, ply_from_root) {
+        if ply_from_root >= MAX_PLY {
+            return self.evaluate();
+        }
+
+        // Captures can only ever reduce the material further, so there's no
+        // need to search on
+        if self.board.check_draw_by_insufficient_material() {
+            return self.draw_score();
+        }
+
+        let tt_entry = self.tt.get(self.board.zobrist_hash());
+        if let Some(entry) = &tt_entry {
+            let score = entry.get_score(ply_from_root);
+            if Self::should_cutoff_quiescence(entry, score, alpha, beta) {
                 self.stats.tt_hits += 1;
 
-                return entry.get_score(ply_from_root);
+                return score;
             }
+        }
 
-            (entry.get_score(ply_from_root), Some(entry))
+        let moves = &mut self.move_buffers[ply_from_root as usize];
+        let in_check = self.board.legal_captures_into(moves).checker_count > 0;
+
+        // Static evaluation captures are measured against for delta pruning,
+        // which is only valid when not in check
+        let mut delta_stand_pat = None;
+
+        let mut best_score = if in_check {
+            // Standing pat isn't an option when in check, so all evasions
+            // need to be searched, not just captures
+            self.board.legal_moves_into(moves);
+
+            if moves.is_empty() {
+                return self.no_legal_moves_score(true, ply_from_root);
+            }
+
+            -Score::INF
         } else {
             // Score from standing pat.
-            (evaluate(&self.board), None)
-        };
+            let stand_pat = match &tt_entry {
+                Some(entry) => entry.get_score(ply_from_root),
+                None => self.evaluate(),
+            };
+
+            if stand_pat >= beta {
+                // Beta cutoff!
+                self.stats.beta_cutoffs += 1;
+                return stand_pat;
+            }
 
-        if best_score >= beta {
-            // Beta cutoff!
-            self.stats.beta_cutoffs += 1;
-            return best_score;
-        }
+            // Delta pruning: if even the biggest possible swing in material
+            // can't raise alpha, there's no point searching any captures. Quiet
+            // checks could still lead to mate though, so only return early if
+            // they won't be searched
+            let search_quiet_checks = qs_ply < QS_CHECK_PLIES;
+            if !stand_pat.is_mate() && !alpha.is_mate() {
+                if !search_quiet_checks
+                    && stand_pat.get() + self.biggest_possible_swing() + DELTA_PRUNING_MARGIN
+                        < alpha.get()
+                {
+                    return stand_pat;
+                }
 
-        alpha = alpha.max(best_score);
+                delta_stand_pat = Some(stand_pat);
+            }
 
-        let capturing_moves = {
-            let mut moves = MoveVec::new();
-            self.board
-                .legal_moves_ex(MoveGenFlags::GEN_CAPTURES, &mut moves);
+            if search_quiet_checks {
+                // Quiet checks can win material (e.g. forks) or lead to mate,
+                // but only look at them near the start of the quiescence
+                // search, as they'd otherwise blow up the node count
+                self.move_buffers[ply_from_root as usize].extend(self.board.legal_quiet_checks());
+            }
 
-            moves
+            stand_pat
         };
 
-        let mut ordered_moves =
-            MovePicker::new(capturing_moves, tt_entry.and_then(|entry| entry.best_move));
+        alpha = alpha.max(best_score);
+
+        let mut ordered_moves = MovePicker::new(tt_entry.and_then(|entry| entry.best_move));
 
         let mut best_move = None;
-        while let Some(m) =
-            ordered_moves.next_move(self.board, ply_from_root, &self.killers, self.history)
-        {
-            if !m.is_capture() {
+        while let Some(m) = ordered_moves.next_move(
+            &mut self.move_buffers[ply_from_root as usize],
+            self.board,
+            ply_from_root,
+            &self.killers,
+            self.history,
+        ) {
+            // SEE pruning: skip moves which lose material outright, unless
+            // they're needed to get out of check
+            if !in_check && self.board.see(m) < 0 {
                 continue;
             }
 
-            self.board.push_move_unchecked(m);
+            // Delta pruning: skip captures that can't raise alpha even if the
+            // captured piece is won for free
+            if let (Some(stand_pat), Some(captured)) = (delta_stand_pat, m.captured_piece()) {
+                if m.promotion().is_none()
+                    && stand_pat.get()
+                        + SEE_PIECE_VALUES[captured.piece_type().index()]
+                        + DELTA_PRUNING_MARGIN
+                        < alpha.get()
+                {
+                    continue;
+                }
+            }
 
-            let eval = -self.quiescence(ply_from_root + 1, -beta, -alpha);
+            self.make_move(m);
 
-            self.board.pop_move();
+            let eval = -self.quiescence_ex(ply_from_root + 1, qs_ply + 1, -beta, -alpha);
+
+            self.unmake_move();
 
             if eval > best_score {
                 best_score = eval;
@@ -92,16 +170,49 @@ impl<'a> SearchContext<'a> {
         best_score
     }
 
+    /// The most material the side to move could gain with a single move:
+    /// capturing the opponent's most valuable piece, plus promoting if they
+    /// have a pawn about to promote.
+    fn biggest_possible_swing(&self) -> i32 {
+        let us = self.board.to_move();
+        let them = us.flip();
+
+        let biggest_capture = [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ]
+        .into_iter()
+        .find(|&piece_type| {
+            self.board
+                .get_bitboard_for_piece(Piece::new(them, piece_type))
+                .has_piece()
+        })
+        .unwrap_or(PieceType::Pawn);
+
+        let seventh_rank = Bitboard::rank_mask(if us.is_white() { 6 } else { 1 });
+        let can_promote =
+            (self.board.get_bitboard_for_piece(Piece::pawn(us)) & seventh_rank).has_piece();
+        let promotion_gain = if can_promote {
+            SEE_PIECE_VALUES[PieceType::Queen.index()] - SEE_PIECE_VALUES[PieceType::Pawn.index()]
+        } else {
+            0
+        };
+
+        SEE_PIECE_VALUES[biggest_capture.index()] + promotion_gain
+    }
+
     fn should_cutoff_quiescence(
         entry: &TranspositionEntry,
+        score: Score,
         alpha: Score,
         beta: Score,
-        ply_from_root: u16,
     ) -> bool {
         match entry.flag {
             TranspositionFlag::Exact => true,
-            TranspositionFlag::Lowerbound => entry.get_score(ply_from_root) >= beta,
-            TranspositionFlag::Upperbound => entry.get_score(ply_from_root) <= alpha,
+            TranspositionFlag::Lowerbound => score >= beta,
+            TranspositionFlag::Upperbound => score <= alpha,
         }
     }
 }
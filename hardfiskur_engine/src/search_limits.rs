@@ -1,10 +1,27 @@
 use std::time::Duration;
 
+use hardfiskur_core::board::Move;
+use thiserror::Error;
+
+use crate::time_manager::MOVE_OVERHEAD;
+
 #[derive(Debug, Clone)]
 pub struct SearchLimits {
     pub time_controls: TimeControls,
     pub node_budget: u64,
     pub depth: i16,
+    /// Restricts the moves considered at the root. An empty list means all
+    /// legal moves are searched.
+    pub search_moves: Vec<Move>,
+    /// Whether this search was started with `go ponder`, in which case the
+    /// result records whether the ponder move was actually played.
+    pub pondering: bool,
+    /// Time reserved for communication delays on each move, which is taken
+    /// off the time budget.
+    pub move_overhead: Duration,
+    /// If set, searches only for a forced mate in at most this many moves,
+    /// stopping as soon as one is found.
+    pub mate: Option<u16>,
 }
 
 impl SearchLimits {
@@ -13,7 +30,113 @@ impl SearchLimits {
             time_controls: TimeControls::Infinite,
             node_budget: u64::MAX,
             depth: i16::MAX,
+            search_moves: Vec::new(),
+            pondering: false,
+            move_overhead: MOVE_OVERHEAD,
+            mate: None,
+        }
+    }
+
+    /// Starts building limits with no bounds set. At least one of the bounds
+    /// has to be set before building, unless [`SearchLimitsBuilder::infinite`]
+    /// is called.
+    ///
+    /// The search stops as soon as any of the bounds is reached.
+    pub fn builder() -> SearchLimitsBuilder {
+        SearchLimitsBuilder::default()
+    }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchLimitsError {
+    #[error("no depth, node, time or mate limit set, and the search wasn't marked as infinite")]
+    Unbounded,
+    #[error("depth limit must be at least 1, but was {0}")]
+    InvalidDepth(i16),
+    #[error("mate limit must be at least 1 move")]
+    InvalidMate,
+}
+
+/// Builder for [`SearchLimits`], see [`SearchLimits::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimitsBuilder {
+    depth: Option<i16>,
+    nodes: Option<u64>,
+    time_controls: Option<TimeControls>,
+    mate: Option<u16>,
+    infinite: bool,
+    search_moves: Vec<Move>,
+}
+
+impl SearchLimitsBuilder {
+    /// Stops after completing an iteration at this depth.
+    pub fn depth(mut self, depth: i16) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Stops after searching this many nodes.
+    pub fn nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Searches for exactly this long.
+    pub fn move_time(self, move_time: Duration) -> Self {
+        self.time_controls(TimeControls::FixedMoveTime(move_time))
+    }
+
+    /// Budgets time according to the given time controls.
+    /// [`TimeControls::Infinite`] is the same as not setting a time limit.
+    pub fn time_controls(mut self, time_controls: TimeControls) -> Self {
+        self.time_controls = Some(time_controls);
+        self
+    }
+
+    /// Searches only for a forced mate in at most this many moves.
+    pub fn mate(mut self, moves: u16) -> Self {
+        self.mate = Some(moves);
+        self
+    }
+
+    /// Allows building without any bounds, in which case the search only
+    /// stops when aborted.
+    pub fn infinite(mut self) -> Self {
+        self.infinite = true;
+        self
+    }
+
+    /// Restricts the moves considered at the root.
+    pub fn search_moves(mut self, search_moves: Vec<Move>) -> Self {
+        self.search_moves = search_moves;
+        self
+    }
+
+    pub fn build(self) -> Result<SearchLimits, SearchLimitsError> {
+        if let Some(depth) = self.depth.filter(|&depth| depth < 1) {
+            return Err(SearchLimitsError::InvalidDepth(depth));
+        }
+        if self.mate == Some(0) {
+            return Err(SearchLimitsError::InvalidMate);
         }
+
+        let time_controls = self.time_controls.unwrap_or(TimeControls::Infinite);
+        let bounded = self.depth.is_some()
+            || self.nodes.is_some()
+            || self.mate.is_some()
+            || !matches!(time_controls, TimeControls::Infinite);
+        if !bounded && !self.infinite {
+            return Err(SearchLimitsError::Unbounded);
+        }
+
+        Ok(SearchLimits {
+            time_controls,
+            node_budget: self.nodes.unwrap_or(u64::MAX),
+            depth: self.depth.unwrap_or(i16::MAX),
+            search_moves: self.search_moves,
+            mate: self.mate,
+            ..SearchLimits::infinite()
+        })
     }
 }
 
@@ -31,3 +154,61 @@ pub enum TimeControls {
         moves_to_go: u32,
     },
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn builder_requires_a_bound() {
+        assert_eq!(
+            SearchLimits::builder().build().unwrap_err(),
+            SearchLimitsError::Unbounded
+        );
+        assert_eq!(
+            SearchLimits::builder()
+                .time_controls(TimeControls::Infinite)
+                .build()
+                .unwrap_err(),
+            SearchLimitsError::Unbounded
+        );
+
+        let limits = SearchLimits::builder().infinite().build().unwrap();
+        assert_eq!(limits.depth, i16::MAX);
+        assert_eq!(limits.node_budget, u64::MAX);
+        assert!(matches!(limits.time_controls, TimeControls::Infinite));
+    }
+
+    #[test]
+    fn builder_sets_all_bounds() {
+        let limits = SearchLimits::builder()
+            .depth(12)
+            .nodes(100_000)
+            .move_time(Duration::from_millis(250))
+            .mate(3)
+            .build()
+            .unwrap();
+
+        assert_eq!(limits.depth, 12);
+        assert_eq!(limits.node_budget, 100_000);
+        assert!(matches!(
+            limits.time_controls,
+            TimeControls::FixedMoveTime(time) if time == Duration::from_millis(250)
+        ));
+        assert_eq!(limits.mate, Some(3));
+        assert!(!limits.pondering);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_bounds() {
+        assert_eq!(
+            SearchLimits::builder().depth(0).build().unwrap_err(),
+            SearchLimitsError::InvalidDepth(0)
+        );
+        assert_eq!(
+            SearchLimits::builder().mate(0).build().unwrap_err(),
+            SearchLimitsError::InvalidMate
+        );
+    }
+}
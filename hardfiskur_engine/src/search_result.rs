@@ -2,12 +2,24 @@ use std::time::Duration;
 
 use hardfiskur_core::board::Move;
 
-use crate::{score::Score, search_stats::SearchStats};
+use crate::{evaluation::phase::Phase, score::Score, search_stats::SearchStats};
 
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub best_move: Option<Move>,
     pub info: SearchInfo,
+    /// For searches started while pondering, whether the opponent played the
+    /// expected move.
+    pub ponder_outcome: Option<PonderOutcome>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PonderOutcome {
+    /// The opponent played the ponder move and the search carried on.
+    Hit,
+    /// The search was stopped without a ponderhit, i.e. the opponent played
+    /// something else.
+    Miss,
 }
 
 #[derive(Debug, Clone)]
@@ -17,4 +29,19 @@ pub struct SearchInfo {
     pub elapsed: Duration,
     pub pv: Vec<Move>,
     pub hash_full: u64,
+    /// Game phase of the root position, used to estimate win probabilities.
+    pub phase: Phase,
+    /// Which line this is (starting from 1) when searching several lines
+    /// with MultiPV, or `None` when only the best line is searched.
+    pub multi_pv: Option<u32>,
+}
+
+/// Reports the root move currently being searched, so that UIs can display
+/// progress through long searches.
+#[derive(Debug, Clone)]
+pub struct CurrMoveInfo {
+    pub depth: i16,
+    pub curr_move: Move,
+    /// 1-based index of the move in the root move ordering.
+    pub curr_move_number: u32,
 }
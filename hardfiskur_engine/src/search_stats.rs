@@ -7,6 +7,28 @@ pub struct SearchStats {
     pub beta_cutoffs: u64,
     pub tt_hits: u64,
     pub move_ordering: MoveOrderingStats,
+    // Nodes searched in each completed iteration of iterative deepening,
+    // indexed by depth - 1.
+    pub iteration_nodes: Vec<u64>,
+}
+
+impl SearchStats {
+    pub fn record_iteration(&mut self) {
+        let previous_nodes: u64 = self.iteration_nodes.iter().sum();
+        self.iteration_nodes
+            .push(self.nodes_searched.saturating_sub(previous_nodes));
+    }
+
+    /// Ratio of nodes searched in the last completed iteration to the nodes
+    /// searched in the iteration before it.
+    ///
+    /// Returns `None` if fewer than two iterations have been completed.
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        match self.iteration_nodes.as_slice() {
+            [.., prev, last] if *prev > 0 => Some(*last as f64 / *prev as f64),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -27,3 +49,34 @@ impl MoveOrderingStats {
         self.beta_cutoff_move_idxs[idx] += 1;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_iteration_stores_nodes_per_iteration() {
+        let mut stats = SearchStats::default();
+
+        for nodes in [20, 100, 400] {
+            stats.nodes_searched = nodes;
+            stats.record_iteration();
+        }
+
+        assert_eq!(stats.iteration_nodes, vec![20, 80, 300]);
+    }
+
+    #[test]
+    fn effective_branching_factor_needs_two_iterations() {
+        let mut stats = SearchStats::default();
+        assert_eq!(stats.effective_branching_factor(), None);
+
+        stats.nodes_searched = 20;
+        stats.record_iteration();
+        assert_eq!(stats.effective_branching_factor(), None);
+
+        stats.nodes_searched = 100;
+        stats.record_iteration();
+        assert_eq!(stats.effective_branching_factor(), Some(4.0));
+    }
+}
@@ -1,8 +1,13 @@
 use std::{
-    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 
+use hardfiskur_core::board::Move;
+
 use crate::search_limits::{SearchLimits, TimeControls};
 
 pub const MOVE_OVERHEAD: Duration = Duration::from_millis(15);
@@ -13,44 +18,143 @@ pub const INCREMENT_MULTIPLIER: f64 = 0.75;
 
 pub const CYCLIC_SOFT_MULTIPLIER: f64 = 0.8;
 pub const CYCLIC_HARD_MULTIPLIER: f64 = 4.0;
+// Extra moves to plan for on top of movestogo, so the last moves before the
+// time control don't get squeezed
+pub const CYCLIC_MOVES_TO_GO_BUFFER: u32 = 2;
+
+// When the remaining time falls below this many multiples of the move
+// overhead, stop budgeting normally and just play a move quickly.
+pub const PANIC_OVERHEAD_MULTIPLIER: u32 = 5;
+pub const PANIC_MOVE_TIME: Duration = Duration::from_millis(10);
 
 pub const SOFT_BOUND_ADJUSTMENT_MIN_DEPTH: i16 = 10;
 
 pub const NODE_ADJUSTMENT_BIAS: f64 = 2.0;
 pub const NODE_ADJUSTMENT_WEIGHT: f64 = -1.5;
 
+// Soft bound multipliers indexed by the number of consecutive iterations the
+// best move has stayed the same. A best move that keeps changing deserves more
+// time, while one that has been stable for a while can be played sooner.
+pub const BEST_MOVE_STABILITY_SCALES: [f64; 5] = [2.5, 1.2, 0.9, 0.8, 0.75];
+
+/// Signals to a search started with `go ponder` that the opponent played the
+/// expected move, so the search should start budgeting its time.
+#[derive(Debug, Default)]
+pub struct PonderHit {
+    hit: AtomicBool,
+    // Unlike `hit`, stays set once the ponderhit has been handled
+    signalled: AtomicBool,
+    time_controls: Mutex<Option<(Instant, TimeControls)>>,
+}
+
+impl PonderHit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the ponder move was played at `instant`, and that the
+    /// search should now use `time_controls`.
+    pub fn signal(&self, instant: Instant, time_controls: TimeControls) {
+        *self.time_controls.lock().unwrap() = Some((instant, time_controls));
+        self.hit.store(true, AtomicOrdering::Release);
+        self.signalled.store(true, AtomicOrdering::Release);
+    }
+
+    /// Whether [`Self::signal`] has been called at any point.
+    pub fn was_signalled(&self) -> bool {
+        self.signalled.load(AtomicOrdering::Acquire)
+    }
+
+    fn take(&self) -> Option<(Instant, TimeControls)> {
+        if !self.hit.swap(false, AtomicOrdering::Acquire) {
+            return None;
+        }
+
+        self.time_controls.lock().unwrap().take()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TimeManager<'a> {
     start_time: Instant,
+    // When the clock started for the purposes of the time bounds. Differs from
+    // start_time after a ponderhit.
+    clock_start: Instant,
     soft_bound: Duration,
     hard_bound: Duration,
+    move_overhead: Duration,
 
     max_depth: i16,
     max_nodes: u64,
 
     best_move_effort: f64,
+    prev_best_move: Option<Move>,
+    best_move_stability: usize,
 
     abort_flag: &'a AtomicBool,
+    ponder_hit: Option<&'a PonderHit>,
 }
 
 impl<'a> TimeManager<'a> {
     pub fn new(limits: SearchLimits, abort_flag: &'a AtomicBool) -> Self {
-        let (soft_bound, hard_bound) = Self::time_bounds(limits.time_controls);
+        let (soft_bound, hard_bound) =
+            Self::time_bounds(limits.time_controls, limits.move_overhead);
+        let start_time = Instant::now();
 
         Self {
-            start_time: Instant::now(),
+            start_time,
+            clock_start: start_time,
             soft_bound,
             hard_bound,
+            move_overhead: limits.move_overhead,
             max_depth: limits.depth,
             max_nodes: limits.node_budget,
 
             best_move_effort: 1.0,
+            prev_best_move: None,
+            best_move_stability: 0,
 
             abort_flag,
+            ponder_hit: None,
+        }
+    }
+
+    pub fn with_ponder_hit(mut self, ponder_hit: &'a PonderHit) -> Self {
+        self.ponder_hit = Some(ponder_hit);
+        self
+    }
+
+    /// Restarts the clock from `instant`, budgeting time according to
+    /// `time_controls`. Used when the opponent plays the move that was being
+    /// pondered on.
+    pub fn on_ponder_hit(&mut self, instant: Instant, time_controls: TimeControls) {
+        let (soft_bound, hard_bound) = Self::time_bounds(time_controls, self.move_overhead);
+
+        self.clock_start = instant;
+        self.soft_bound = soft_bound;
+        self.hard_bound = hard_bound;
+    }
+
+    fn poll_ponder_hit(&mut self) {
+        if let Some((instant, time_controls)) = self.ponder_hit.and_then(PonderHit::take) {
+            self.on_ponder_hit(instant, time_controls);
         }
     }
 
-    pub fn on_iteration_end(&mut self, depth: i16, best_move_effort: f64) {
+    /// The instant at which the search will be stopped regardless of its
+    /// progress, or `None` if there is no time limit.
+    pub fn hard_deadline(&self) -> Option<Instant> {
+        self.clock_start.checked_add(self.hard_bound)
+    }
+
+    pub fn on_iteration_end(&mut self, depth: i16, best_move: Option<Move>, best_move_effort: f64) {
+        if best_move.is_some() && best_move == self.prev_best_move {
+            self.best_move_stability += 1;
+        } else {
+            self.best_move_stability = 0;
+        }
+        self.prev_best_move = best_move;
+
         // Results from first few iterations are not very stable
         if depth < SOFT_BOUND_ADJUSTMENT_MIN_DEPTH {
             return;
@@ -63,36 +167,60 @@ impl<'a> TimeManager<'a> {
         NODE_ADJUSTMENT_BIAS + NODE_ADJUSTMENT_WEIGHT * self.best_move_effort
     }
 
-    pub fn check_soft_bound(&self, depth: i16, nodes: u64) -> bool {
-        if depth >= self.max_depth || nodes >= self.max_nodes {
-            return true;
+    fn stability_adjustment(&self) -> f64 {
+        let index = self
+            .best_move_stability
+            .min(BEST_MOVE_STABILITY_SCALES.len() - 1);
+        BEST_MOVE_STABILITY_SCALES[index]
+    }
+
+    /// Time after which no new iteration should be started, having finished
+    /// an iteration at `depth`. Adjusted by how settled the search seems to
+    /// be, once the depth is high enough for that to be meaningful.
+    pub fn soft_bound(&self, depth: i16) -> Duration {
+        if depth < SOFT_BOUND_ADJUSTMENT_MIN_DEPTH {
+            return self.soft_bound;
         }
 
-        let soft_bound = if depth < SOFT_BOUND_ADJUSTMENT_MIN_DEPTH {
-            self.soft_bound
-        } else {
-            // Adjust the soft bound based on several parameters.
-            let mut soft_bound = self.soft_bound.as_secs_f64();
+        // Adjust the soft bound based on several parameters.
+        let mut soft_bound = self.soft_bound.as_secs_f64();
 
-            soft_bound *= self.node_adjustment();
+        soft_bound *= self.node_adjustment();
+        soft_bound *= self.stability_adjustment();
 
-            Duration::try_from_secs_f64(soft_bound).unwrap_or(Duration::MAX)
-        };
+        Duration::try_from_secs_f64(soft_bound)
+            .unwrap_or(Duration::MAX)
+            .min(self.hard_bound)
+    }
 
-        self.start_time.elapsed() >= soft_bound
+    /// Time after which the search is stopped immediately.
+    pub fn hard_bound(&self) -> Duration {
+        self.hard_bound
     }
 
-    pub fn check_hard_bound(&self, nodes: u64) -> bool {
+    pub fn check_soft_bound(&mut self, depth: i16, nodes: u64) -> bool {
+        self.poll_ponder_hit();
+
+        if depth >= self.max_depth || nodes >= self.max_nodes {
+            return true;
+        }
+
+        self.clock_start.elapsed() >= self.soft_bound(depth)
+    }
+
+    pub fn check_hard_bound(&mut self, nodes: u64) -> bool {
         if nodes >= self.max_nodes {
             return true;
         }
 
         // Avoid syscalls a bit
-        if nodes % 2048 != 0 {
+        if !nodes.is_multiple_of(2048) {
             return false;
         }
 
-        self.start_time.elapsed() >= self.hard_bound
+        self.poll_ponder_hit();
+
+        self.clock_start.elapsed() >= self.hard_bound()
             || self.abort_flag.load(AtomicOrdering::Relaxed)
     }
 
@@ -100,8 +228,19 @@ impl<'a> TimeManager<'a> {
         self.start_time
     }
 
-    fn time_bounds(controls: TimeControls) -> (Duration, Duration) {
+    /// The initial soft and hard time bounds for a search with the given time
+    /// controls, before any adjustments made as the search progresses. Both
+    /// are [`Duration::MAX`] if there's no time limit.
+    pub fn time_bounds(controls: TimeControls, move_overhead: Duration) -> (Duration, Duration) {
         let (soft, hard) = match controls {
+            TimeControls::FischerTime { remaining, .. }
+            | TimeControls::Cyclic { remaining, .. }
+                if remaining < move_overhead * PANIC_OVERHEAD_MULTIPLIER =>
+            {
+                // Nearly out of time -- a soft bound of zero means no new
+                // iterations are started after the first one.
+                return (Duration::ZERO, PANIC_MOVE_TIME.min(remaining / 2));
+            }
             TimeControls::FixedMoveTime(duration) => (duration, duration),
             TimeControls::FischerTime {
                 remaining,
@@ -121,7 +260,7 @@ impl<'a> TimeManager<'a> {
             } => {
                 // Plan to use an even amount of time for each move in
                 // moves_to_go
-                let move_alloc = remaining / moves_to_go;
+                let move_alloc = remaining / (moves_to_go + CYCLIC_MOVES_TO_GO_BUFFER);
                 let increment = increment.mul_f64(INCREMENT_MULTIPLIER);
 
                 let soft = (move_alloc + increment).mul_f64(CYCLIC_SOFT_MULTIPLIER);
@@ -133,8 +272,192 @@ impl<'a> TimeManager<'a> {
         };
 
         (
-            soft.saturating_sub(MOVE_OVERHEAD),
-            hard.saturating_sub(MOVE_OVERHEAD),
+            soft.saturating_sub(move_overhead),
+            hard.saturating_sub(move_overhead),
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hardfiskur_core::board::{MoveBuilder, Piece, Square};
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn e4() -> Move {
+        MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()
+    }
+
+    fn d4() -> Move {
+        MoveBuilder::new(Square::D2, Square::D4, Piece::WHITE_PAWN).build()
+    }
+
+    fn fischer_time_manager(abort_flag: &AtomicBool) -> TimeManager<'_> {
+        TimeManager::new(
+            SearchLimits {
+                time_controls: TimeControls::FischerTime {
+                    remaining: Duration::from_secs(60),
+                    increment: Duration::ZERO,
+                },
+                ..SearchLimits::infinite()
+            },
+            abort_flag,
+        )
+    }
+
+    #[test]
+    fn stable_best_move_contracts_soft_bound() {
+        let abort_flag = AtomicBool::new(false);
+        let mut time_manager = fischer_time_manager(&abort_flag);
+        let m = Some(e4());
+
+        let depth = SOFT_BOUND_ADJUSTMENT_MIN_DEPTH;
+        time_manager.on_iteration_end(depth, m, 0.5);
+        let unsettled = time_manager.soft_bound(depth);
+
+        for depth in depth + 1..depth + 5 {
+            time_manager.on_iteration_end(depth, m, 0.5);
+        }
+        let settled = time_manager.soft_bound(depth + 4);
+
+        assert!(settled < unsettled);
+        assert!(settled <= time_manager.hard_bound());
+    }
+
+    #[test]
+    fn changing_best_move_extends_soft_bound() {
+        let abort_flag = AtomicBool::new(false);
+        let mut time_manager = fischer_time_manager(&abort_flag);
+        let depth = SOFT_BOUND_ADJUSTMENT_MIN_DEPTH;
+
+        time_manager.on_iteration_end(depth, Some(e4()), 0.5);
+        time_manager.on_iteration_end(depth + 1, Some(e4()), 0.5);
+        let stable = time_manager.soft_bound(depth + 1);
+
+        time_manager.on_iteration_end(depth + 2, Some(d4()), 0.5);
+        let changed = time_manager.soft_bound(depth + 2);
+
+        assert!(changed > stable);
+    }
+
+    #[test]
+    fn soft_bound_is_unadjusted_at_low_depths() {
+        let abort_flag = AtomicBool::new(false);
+        let mut time_manager = fischer_time_manager(&abort_flag);
+        let base = time_manager.soft_bound(1);
+
+        time_manager.on_iteration_end(1, Some(e4()), 0.5);
+        time_manager.on_iteration_end(2, Some(d4()), 0.5);
+
+        assert_eq!(time_manager.soft_bound(2), base);
+    }
+
+    #[test]
+    fn cyclic_time_is_split_over_moves_to_go_plus_buffer() {
+        let remaining = Duration::from_secs(60);
+        let (soft, hard) = TimeManager::time_bounds(
+            TimeControls::Cyclic {
+                remaining,
+                increment: Duration::ZERO,
+                moves_to_go: 10,
+            },
+            Duration::ZERO,
+        );
+
+        let move_alloc = remaining / (10 + CYCLIC_MOVES_TO_GO_BUFFER);
+        assert_eq!(soft, move_alloc.mul_f64(CYCLIC_SOFT_MULTIPLIER));
+        assert_eq!(hard, move_alloc.mul_f64(CYCLIC_HARD_MULTIPLIER));
+
+        // movestogo 0 shouldn't divide by zero
+        let (soft, hard) = TimeManager::time_bounds(
+            TimeControls::Cyclic {
+                remaining,
+                increment: Duration::ZERO,
+                moves_to_go: 0,
+            },
+            Duration::ZERO,
+        );
+        assert!(soft <= hard && hard <= remaining);
+    }
+
+    #[test]
+    fn panics_when_nearly_out_of_time() {
+        let move_overhead = Duration::from_millis(50);
+        let (soft, hard) = TimeManager::time_bounds(
+            TimeControls::FischerTime {
+                remaining: Duration::from_millis(200),
+                increment: Duration::from_secs(1),
+            },
+            move_overhead,
+        );
+
+        assert_eq!(soft, Duration::ZERO);
+        assert_eq!(hard, PANIC_MOVE_TIME);
+
+        // The same clock with a smaller overhead is budgeted normally
+        let (soft, _) = TimeManager::time_bounds(
+            TimeControls::FischerTime {
+                remaining: Duration::from_millis(200),
+                increment: Duration::from_secs(1),
+            },
+            Duration::from_millis(10),
+        );
+        assert!(soft > Duration::ZERO);
+    }
+
+    #[test]
+    fn move_overhead_comes_from_search_limits() {
+        let abort_flag = AtomicBool::new(false);
+        let time_manager = TimeManager::new(
+            SearchLimits {
+                time_controls: TimeControls::FixedMoveTime(Duration::from_secs(1)),
+                move_overhead: Duration::from_millis(100),
+                ..SearchLimits::infinite()
+            },
+            &abort_flag,
+        );
+
+        assert_eq!(time_manager.hard_bound(), Duration::from_millis(900));
+    }
+
+    #[test]
+    fn ponder_hit_sets_deadline_from_ponder_hit_instant() {
+        let abort_flag = AtomicBool::new(false);
+        let mut time_manager = TimeManager::new(SearchLimits::infinite(), &abort_flag);
+
+        // No deadline while pondering
+        assert_eq!(time_manager.hard_deadline(), None);
+
+        let ponder_hit_instant = time_manager.start_time() + Duration::from_secs(5);
+        time_manager.on_ponder_hit(
+            ponder_hit_instant,
+            TimeControls::FixedMoveTime(Duration::from_secs(1)),
+        );
+
+        assert_eq!(
+            time_manager.hard_deadline(),
+            Some(ponder_hit_instant + Duration::from_secs(1) - MOVE_OVERHEAD)
+        );
+    }
+
+    #[test]
+    fn ponder_hit_signal_is_picked_up_by_search() {
+        let abort_flag = AtomicBool::new(false);
+        let ponder_hit = PonderHit::new();
+        let mut time_manager =
+            TimeManager::new(SearchLimits::infinite(), &abort_flag).with_ponder_hit(&ponder_hit);
+
+        // Still pondering, so the search should not stop
+        assert!(!time_manager.check_hard_bound(0));
+
+        let ponder_hit_instant = time_manager.start_time();
+        ponder_hit.signal(
+            ponder_hit_instant,
+            TimeControls::FixedMoveTime(Duration::ZERO),
+        );
+
+        assert!(time_manager.check_hard_bound(0));
+        assert_eq!(time_manager.hard_deadline(), Some(ponder_hit_instant));
+    }
+}
@@ -1,6 +1,13 @@
-use std::{fmt::Display, num::NonZeroUsize};
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    num::NonZeroUsize,
+    path::Path,
+};
 
 use hardfiskur_core::board::{Board, Move, OptionalMove, UCIMove, ZobristHash};
+use thiserror::Error;
 use zerocopy::FromZeros;
 use zerocopy_derive::FromZeros;
 
@@ -19,6 +26,9 @@ pub struct TranspositionEntry {
     pub depth: i16,
     score: Score,
     pub best_move: Option<Move>,
+    /// Search generation the entry was last written in. Set by the table when
+    /// storing, so the value passed to [`TranspositionTable::set`] is ignored.
+    pub generation: u8,
 }
 
 impl TranspositionEntry {
@@ -34,6 +44,7 @@ impl TranspositionEntry {
             depth,
             score: score.sub_plies_for_mate(ply_from_root),
             best_move,
+            generation: 0,
         }
     }
 
@@ -44,7 +55,7 @@ impl TranspositionEntry {
 
 impl Display for TranspositionEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "depth={}", self.depth)?;
+        writeln!(f, "depth={} generation={}", self.depth, self.generation)?;
         writeln!(f, "score={} {:?}", self.score, self.flag)?;
 
         match self.best_move {
@@ -54,6 +65,18 @@ impl Display for TranspositionEntry {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum TTFileError {
+    #[error("not a transposition table file")]
+    InvalidHeader,
+    #[error("table has {found} buckets, but was saved with {expected}")]
+    SizeMismatch { expected: usize, found: usize },
+    #[error("invalid entry in bucket {0}")]
+    InvalidEntry(usize),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, FromZeros)]
 #[repr(u8)]
 enum TranspositionFlagInternal {
@@ -91,100 +114,295 @@ impl TryFrom<TranspositionFlagInternal> for TranspositionFlag {
 struct TranspositionEntryInternal {
     key: u32,
     flag: TranspositionFlagInternal,
+    generation: u8,
     depth: i16,
     score: Score,
     best_move: OptionalMove,
 }
 
+impl TranspositionEntryInternal {
+    const SERIALIZED_SIZE: usize = 16;
+
+    fn is_empty(&self) -> bool {
+        self.flag == TranspositionFlagInternal::None
+    }
+
+    fn to_bytes(self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0; Self::SERIALIZED_SIZE];
+        bytes[0..4].copy_from_slice(&self.key.to_le_bytes());
+        bytes[4] = self.flag as u8;
+        bytes[5] = self.generation;
+        bytes[6..8].copy_from_slice(&self.depth.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.score.0.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.best_move.to_bits().to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; Self::SERIALIZED_SIZE]) -> Option<Self> {
+        let flag = match bytes[4] {
+            0 => TranspositionFlagInternal::None,
+            1 => TranspositionFlagInternal::Exact,
+            2 => TranspositionFlagInternal::Lowerbound,
+            3 => TranspositionFlagInternal::Upperbound,
+            _ => return None,
+        };
+
+        Some(Self {
+            key: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            flag,
+            generation: bytes[5],
+            depth: i16::from_le_bytes(bytes[6..8].try_into().unwrap()),
+            score: Score(i32::from_le_bytes(bytes[8..12].try_into().unwrap())),
+            best_move: OptionalMove::from_bits(u32::from_le_bytes(
+                bytes[12..16].try_into().unwrap(),
+            ))?,
+        })
+    }
+
+    /// How desirable this entry is to keep around -- deeper entries are worth
+    /// more, but entries from older searches lose value quickly.
+    fn replacement_priority(&self, generation: u8) -> i32 {
+        let age = generation.wrapping_sub(self.generation) as i32;
+        self.depth as i32 - TT_AGE_WEIGHT * age
+    }
+}
+
+/// Number of entries sharing a single index.
+const BUCKET_SIZE: usize = 4;
+
+/// Identifies files written by [`TranspositionTable::save`], including a
+/// format version.
+const TT_FILE_MAGIC: [u8; 8] = *b"HFTT\0\0\0\x01";
+
+/// How many plies of depth a single generation of age is worth when deciding
+/// which entry in a bucket to replace.
+const TT_AGE_WEIGHT: i32 = 8;
+
+#[derive(Debug, Clone, Copy, Default, FromZeros)]
+struct TranspositionBucket {
+    entries: [TranspositionEntryInternal; BUCKET_SIZE],
+}
+
 pub struct TranspositionTable {
-    num_entries: usize,
-    entries: Vec<TranspositionEntryInternal>,
+    num_buckets: usize,
+    buckets: Vec<TranspositionBucket>,
 
     occupied: u64,
+    generation: u8,
 }
 
 impl TranspositionTable {
     pub fn new(max_size_in_mb: NonZeroUsize) -> Self {
-        let num_entries = Self::get_num_entries(max_size_in_mb);
+        let num_buckets = Self::get_num_buckets(max_size_in_mb);
 
         Self {
-            num_entries,
+            num_buckets,
             // It may be tempting to use FromZeros::new_vec_zeroed here, to us
             // calloc(), which is very fast.
             // Don't do it! It means that the OS may not actually allocate and
             // zero memory for the full table and wait until there's a write.
             // This increases the latency drastically during search!
-            entries: vec![FromZeros::new_zeroed(); num_entries],
+            buckets: vec![FromZeros::new_zeroed(); num_buckets],
             occupied: 0,
+            generation: 0,
         }
     }
 
-    fn get_num_entries(max_size_in_mb: NonZeroUsize) -> usize {
+    fn get_num_buckets(max_size_in_mb: NonZeroUsize) -> usize {
         let max_size_in_mb = max_size_in_mb.get();
         const BYTES_PER_MB: usize = 1024 * 1024;
 
-        let entry_size = size_of::<TranspositionEntryInternal>();
+        let bucket_size = size_of::<TranspositionBucket>();
         max_size_in_mb
             .checked_mul(BYTES_PER_MB)
             .expect("overflow when determining TT size (size provided was likely too big)")
-            / entry_size
+            / bucket_size
+    }
+
+    /// Total number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// Marks the start of a new search. Entries written in earlier searches
+    /// are preferred for replacement over ones written in this one.
+    pub fn new_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation
     }
 
     fn index(&self, key: ZobristHash) -> usize {
         // Derive the key only from the lower 32 bits of the hash.
         let key = key.0 as u32;
         // https://lemire.me/blog/2016/06/27/a-fast-alternative-to-the-modulo-reduction/
-        (((key as u64) * (self.entries.len() as u64)) >> 32) as usize
+        (((key as u64) * (self.buckets.len() as u64)) >> 32) as usize
     }
 
     pub fn get(&self, key: ZobristHash) -> Option<TranspositionEntry> {
         let index = self.index(key);
         let verification_key = Self::verification_key(key);
 
-        let entry = self.entries[index];
-        if entry.key != verification_key {
-            return None;
-        }
+        let entry = self.buckets[index]
+            .entries
+            .iter()
+            .find(|entry| !entry.is_empty() && entry.key == verification_key)?;
 
         Some(TranspositionEntry {
             flag: entry.flag.try_into().ok()?,
             depth: entry.depth,
             score: entry.score,
             best_move: entry.best_move.as_option_move(),
+            generation: entry.generation,
         })
     }
 
+    /// Hints to the CPU that the bucket for `key` is about to be probed, so
+    /// the memory fetch can overlap with other work (e.g. move generation).
+    #[inline]
+    pub fn prefetch(&self, key: ZobristHash) {
+        let bucket: *const TranspositionBucket = &self.buckets[self.index(key)];
+
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: prefetching is only a hint and never faults, and the pointer
+        // comes from a valid reference anyway.
+        unsafe {
+            use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+            _mm_prefetch::<_MM_HINT_T0>(bucket.cast());
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = bucket;
+    }
+
     pub fn set(&mut self, key: ZobristHash, entry: TranspositionEntry) {
         let index = self.index(key);
         let verification_key = Self::verification_key(key);
+        let generation = self.generation;
 
         let entry = TranspositionEntryInternal {
             key: verification_key,
             flag: entry.flag.into(),
+            generation,
             depth: entry.depth,
             score: entry.score,
             best_move: entry.best_move.into(),
         };
 
-        // Always-replace
-        if self.entries[index].flag == TranspositionFlagInternal::None {
+        let bucket = &mut self.buckets[index].entries;
+
+        // Overwrite the entry for the same position if there is one, otherwise
+        // fill an empty slot, otherwise evict the least valuable entry
+        let slot = bucket
+            .iter()
+            .position(|e| !e.is_empty() && e.key == verification_key)
+            .or_else(|| bucket.iter().position(|e| e.is_empty()))
+            .unwrap_or_else(|| {
+                (0..BUCKET_SIZE)
+                    .min_by_key(|&i| bucket[i].replacement_priority(generation))
+                    .unwrap()
+            });
+
+        if bucket[slot].is_empty() {
             self.occupied += 1;
         }
-        self.entries[index] = entry;
+        bucket[slot] = entry;
     }
 
     pub fn resize(&mut self, max_size_in_mb: NonZeroUsize) {
-        self.num_entries = Self::get_num_entries(max_size_in_mb);
+        self.num_buckets = Self::get_num_buckets(max_size_in_mb);
         self.clear();
     }
 
     pub fn clear(&mut self) {
-        self.entries = vec![FromZeros::new_zeroed(); self.num_entries];
+        self.buckets = vec![FromZeros::new_zeroed(); self.num_buckets];
         self.occupied = 0;
+        self.generation = 0;
+    }
+
+    /// Writes the whole table to `path`, so that a long analysis can be
+    /// resumed later with [`Self::load`].
+    ///
+    /// The file starts with the number of buckets and current generation,
+    /// followed by every entry.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TTFileError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(&TT_FILE_MAGIC)?;
+        writer.write_all(&(self.num_buckets as u64).to_le_bytes())?;
+        writer.write_all(&[self.generation])?;
+
+        for bucket in &self.buckets {
+            for entry in bucket.entries {
+                writer.write_all(&entry.to_bytes())?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Replaces the contents of the table with a file written by
+    /// [`Self::save`].
+    ///
+    /// Entries can't be rehashed, as only part of each hash is stored, so the
+    /// file has to have been saved from a table of the same size. If the file
+    /// isn't a table of the right size, the table is left unchanged, but if
+    /// reading its entries fails, the table is left cleared.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), TTFileError> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; TT_FILE_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != TT_FILE_MAGIC {
+            return Err(TTFileError::InvalidHeader);
+        }
+
+        let mut num_buckets = [0; 8];
+        reader.read_exact(&mut num_buckets)?;
+        let num_buckets = u64::from_le_bytes(num_buckets) as usize;
+        if num_buckets != self.num_buckets {
+            return Err(TTFileError::SizeMismatch {
+                expected: num_buckets,
+                found: self.num_buckets,
+            });
+        }
+
+        let mut generation = [0];
+        reader.read_exact(&mut generation)?;
+
+        self.clear();
+        let result = self.read_buckets(&mut reader);
+        match result {
+            Ok(()) => self.generation = generation[0],
+            Err(_) => self.clear(),
+        }
+
+        result
+    }
+
+    fn read_buckets(&mut self, reader: &mut impl Read) -> Result<(), TTFileError> {
+        let mut bytes = [0; TranspositionEntryInternal::SERIALIZED_SIZE];
+
+        for (i, bucket) in self.buckets.iter_mut().enumerate() {
+            for entry in bucket.entries.iter_mut() {
+                reader.read_exact(&mut bytes)?;
+                *entry = TranspositionEntryInternal::from_bytes(bytes)
+                    .ok_or(TTFileError::InvalidEntry(i))?;
+
+                if !entry.is_empty() {
+                    self.occupied += 1;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn occupancy(&self) -> u64 {
-        self.occupied * 1000 / self.entries.len() as u64
+        self.occupied * 1000 / self.capacity() as u64
     }
 
     pub fn extract_pv(&self, board: &mut Board) -> Vec<Move> {
@@ -222,6 +440,69 @@ impl TranspositionTable {
         moves
     }
 
+    /// Like [`Self::extract_pv`], but also returns the score stored for each
+    /// position along the PV, from the perspective of the side to move there,
+    /// along with whether that score is exact or only a bound. Stops after
+    /// `max_len` moves, on a repeated position, or if the stored move isn't
+    /// legal (e.g. because of a hash collision).
+    pub fn extract_pv_with_scores(
+        &self,
+        board: &mut Board,
+        max_len: usize,
+    ) -> Vec<(Move, Score, TranspositionFlag)> {
+        let mut pv = Vec::new();
+        let mut seen_hashes = vec![board.zobrist_hash()];
+
+        while pv.len() < max_len {
+            let Some(entry) = self.get(board.zobrist_hash()) else {
+                break;
+            };
+            let Some(m) = entry.best_move.filter(|&m| board.is_legal(m)) else {
+                break;
+            };
+
+            pv.push((m, entry.get_score(pv.len() as u16), entry.flag));
+            board.push_move_unchecked(m);
+
+            if seen_hashes.contains(&board.zobrist_hash()) {
+                break;
+            }
+            seen_hashes.push(board.zobrist_hash());
+        }
+
+        // Unwind the moves
+        for _ in 0..pv.len() {
+            board.pop_move();
+        }
+
+        pv
+    }
+
+    /// Makes sure the best move stored for each position along `pv` matches
+    /// the PV, so that [`Self::extract_pv`] reproduces it. Positions without
+    /// an entry are left alone, as there's no score to store for them.
+    pub fn reinsert_pv(&mut self, board: &mut Board, pv: &[Move]) {
+        for &m in pv {
+            if let Some(entry) = self.get(board.zobrist_hash()) {
+                if entry.best_move != Some(m) {
+                    self.set(
+                        board.zobrist_hash(),
+                        TranspositionEntry {
+                            best_move: Some(m),
+                            ..entry
+                        },
+                    );
+                }
+            }
+
+            board.push_move_unchecked(m);
+        }
+
+        for _ in pv {
+            board.pop_move();
+        }
+    }
+
     fn verification_key(key: ZobristHash) -> u32 {
         (key.0 >> 32) as u32
     }
@@ -240,20 +521,52 @@ mod test {
     const TEST_HASH_2: ZobristHash = ZobristHash(0x1010_1010);
 
     #[test]
-    fn gets_correct_number_of_entries() {
+    fn entry_score_only_ply_adjusts_mate_scores() {
+        let entry = TranspositionEntry::new(TranspositionFlag::Exact, 3, Score(-150), None, 3);
+        assert_eq!(entry.get_score(5), Score(-150));
+
+        let entry = TranspositionEntry::new(TranspositionFlag::Exact, 3, Score(150), None, 3);
+        assert_eq!(entry.get_score(5), Score(150));
+    }
+
+    #[test]
+    fn entry_score_mate_is_relative_to_node() {
+        // Mate found 5 plies from root, stored at ply 2 (so mate in 3 from the
+        // node), then probed at ply 4 via a transposition
+        let entry = TranspositionEntry::new(
+            TranspositionFlag::Exact,
+            3,
+            Score::mate_in_plies(5),
+            None,
+            2,
+        );
+        assert_eq!(entry.get_score(4), Score::mate_in_plies(7));
+
+        let entry = TranspositionEntry::new(
+            TranspositionFlag::Exact,
+            3,
+            -Score::mate_in_plies(5),
+            None,
+            2,
+        );
+        assert_eq!(entry.get_score(4), -Score::mate_in_plies(7));
+    }
+
+    #[test]
+    fn gets_correct_number_of_buckets() {
         for case_mb in [
             1, 2, 3, 4, 6, 8, 12, 24, 25, 32, 64, 96, 128, 144, 200, 256, 512, 999, 1024,
         ] {
-            let num_entries =
-                TranspositionTable::get_num_entries(NonZeroUsize::new(case_mb).unwrap());
+            let num_buckets =
+                TranspositionTable::get_num_buckets(NonZeroUsize::new(case_mb).unwrap());
 
             let target_bytes = case_mb * BYTES_IN_MB;
             let minimum_bytes = (case_mb - 1) * BYTES_IN_MB;
 
-            let used_bytes = num_entries * size_of::<TranspositionEntryInternal>();
+            let used_bytes = num_buckets * size_of::<TranspositionBucket>();
 
             dbg!(case_mb);
-            dbg!(num_entries);
+            dbg!(num_buckets);
             dbg!(used_bytes);
             dbg!(minimum_bytes, target_bytes);
             assert!(used_bytes > minimum_bytes);
@@ -265,11 +578,11 @@ mod test {
     fn constructs_table_correctly() {
         let max_size_in_mb = NonZeroUsize::new(12).unwrap();
 
-        let expected_entries = TranspositionTable::get_num_entries(max_size_in_mb);
+        let expected_buckets = TranspositionTable::get_num_buckets(max_size_in_mb);
         let tt = TranspositionTable::new(max_size_in_mb);
 
-        assert_eq!(tt.entries.len(), expected_entries);
-        assert_eq!(tt.num_entries, expected_entries);
+        assert_eq!(tt.buckets.len(), expected_buckets);
+        assert_eq!(tt.num_buckets, expected_buckets);
         assert_eq!(tt.occupancy(), 0);
     }
 
@@ -278,12 +591,12 @@ mod test {
         let mut tt = TranspositionTable::new(8.try_into().unwrap());
 
         let max_size_in_mb = NonZeroUsize::new(2).unwrap();
-        let expected_entries = TranspositionTable::get_num_entries(max_size_in_mb);
+        let expected_buckets = TranspositionTable::get_num_buckets(max_size_in_mb);
 
         tt.resize(max_size_in_mb);
 
-        assert_eq!(tt.entries.len(), expected_entries);
-        assert_eq!(tt.num_entries, expected_entries);
+        assert_eq!(tt.buckets.len(), expected_buckets);
+        assert_eq!(tt.num_buckets, expected_buckets);
         assert_eq!(tt.occupancy(), 0);
     }
 
@@ -296,6 +609,7 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry.clone());
@@ -313,6 +627,7 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry.clone());
@@ -329,6 +644,7 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry.clone());
@@ -337,7 +653,7 @@ mod test {
     }
 
     #[test]
-    fn replace_same_slot_different_hash() {
+    fn same_bucket_different_hash_keeps_both() {
         let mut tt = TranspositionTable::new(1.try_into().unwrap());
 
         let entry1 = TranspositionEntry {
@@ -345,20 +661,99 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
         let entry2 = TranspositionEntry {
             flag: TranspositionFlag::Exact,
             depth: 3,
             score: Score(-123),
             best_move: Some(MoveBuilder::new(Square::G1, Square::F3, Piece::WHITE_KNIGHT).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry1.clone());
-        // Replace!
         tt.set(ZobristHash(0x8000_0000_0000_0000), entry2.clone());
 
-        assert_eq!(tt.get(TEST_HASH_1), None);
+        assert_eq!(tt.get(TEST_HASH_1), Some(entry1));
         assert_eq!(tt.get(ZobristHash(0x8000_0000_0000_0000)), Some(entry2));
+        assert_eq!(tt.occupied, 2);
+    }
+
+    fn same_bucket_hash(i: u64) -> ZobristHash {
+        // Only the lower 32 bits are used for the index
+        ZobristHash((i + 1) << 32)
+    }
+
+    fn entry_with_depth(depth: i16) -> TranspositionEntry {
+        TranspositionEntry {
+            flag: TranspositionFlag::Exact,
+            depth,
+            score: Score(0),
+            best_move: None,
+            generation: 0,
+        }
+    }
+
+    #[test]
+    fn full_bucket_replaces_shallowest_entry() {
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+
+        for i in 0..BUCKET_SIZE {
+            tt.set(same_bucket_hash(i as u64), entry_with_depth(5 + i as i16));
+        }
+
+        let new_hash = same_bucket_hash(BUCKET_SIZE as u64);
+        tt.set(new_hash, entry_with_depth(1));
+
+        assert_eq!(tt.get(same_bucket_hash(0)), None);
+        assert_eq!(tt.get(new_hash), Some(entry_with_depth(1)));
+        for i in 1..BUCKET_SIZE {
+            assert!(tt.get(same_bucket_hash(i as u64)).is_some());
+        }
+        assert_eq!(tt.occupied, BUCKET_SIZE as u64);
+    }
+
+    #[test]
+    fn full_bucket_replaces_stale_entries_first() {
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+
+        // A deep entry from an old search...
+        tt.set(same_bucket_hash(0), entry_with_depth(8));
+
+        // ...and shallower ones from the current search
+        tt.new_generation();
+        for i in 1..BUCKET_SIZE {
+            tt.set(same_bucket_hash(i as u64), entry_with_depth(2));
+        }
+
+        let new_hash = same_bucket_hash(BUCKET_SIZE as u64);
+        tt.set(new_hash, entry_with_depth(1));
+
+        assert_eq!(tt.get(same_bucket_hash(0)), None);
+        assert_eq!(
+            tt.get(new_hash),
+            Some(TranspositionEntry {
+                generation: 1,
+                ..entry_with_depth(1)
+            })
+        );
+    }
+
+    #[test]
+    fn set_overwrites_same_position_and_refreshes_generation() {
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+
+        tt.set(TEST_HASH_1, entry_with_depth(3));
+        tt.new_generation();
+        tt.set(TEST_HASH_1, entry_with_depth(4));
+
+        assert_eq!(
+            tt.get(TEST_HASH_1),
+            Some(TranspositionEntry {
+                generation: 1,
+                ..entry_with_depth(4)
+            })
+        );
         assert_eq!(tt.occupied, 1);
     }
 
@@ -371,12 +766,14 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
         let entry2 = TranspositionEntry {
             flag: TranspositionFlag::Exact,
             depth: 3,
             score: Score(-123),
             best_move: Some(MoveBuilder::new(Square::G1, Square::F3, Piece::WHITE_KNIGHT).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry1.clone());
@@ -396,12 +793,14 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
         let entry2 = TranspositionEntry {
             flag: TranspositionFlag::Exact,
             depth: 3,
             score: Score(-123),
             best_move: Some(MoveBuilder::new(Square::G1, Square::F3, Piece::WHITE_KNIGHT).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry1.clone());
@@ -409,11 +808,13 @@ mod test {
 
         assert_eq!(tt.occupied, 2);
 
+        tt.new_generation();
         tt.clear();
 
         assert_eq!(tt.get(TEST_HASH_1), None);
         assert_eq!(tt.get(TEST_HASH_2), None);
         assert_eq!(tt.occupied, 0);
+        assert_eq!(tt.generation(), 0);
     }
 
     #[test]
@@ -425,12 +826,14 @@ mod test {
             depth: 2,
             score: Score(1234),
             best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
         };
         let entry2 = TranspositionEntry {
             flag: TranspositionFlag::Exact,
             depth: 3,
             score: Score(-123),
             best_move: Some(MoveBuilder::new(Square::G1, Square::F3, Piece::WHITE_KNIGHT).build()),
+            generation: 0,
         };
 
         tt.set(TEST_HASH_1, entry1.clone());
@@ -445,14 +848,89 @@ mod test {
         assert_eq!(tt.occupied, 0);
     }
 
+    #[test]
+    fn save_and_load_round_trips() {
+        let path = std::env::temp_dir().join(format!(
+            "hardfiskur_tt_round_trip_{}.bin",
+            std::process::id()
+        ));
+
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+        let entry = TranspositionEntry {
+            flag: TranspositionFlag::Upperbound,
+            depth: 7,
+            score: Score(-321),
+            best_move: Some(MoveBuilder::new(Square::E2, Square::E4, Piece::WHITE_PAWN).build()),
+            generation: 0,
+        };
+        tt.new_generation();
+        tt.set(TEST_HASH_1, entry.clone());
+        tt.set(TEST_HASH_2, entry_with_depth(3));
+        tt.save(&path).unwrap();
+
+        let mut loaded = TranspositionTable::new(1.try_into().unwrap());
+        loaded.load(&path).unwrap();
+
+        assert_eq!(
+            loaded.get(TEST_HASH_1),
+            Some(TranspositionEntry {
+                generation: 1,
+                ..entry
+            })
+        );
+        assert_eq!(
+            loaded.get(TEST_HASH_2),
+            Some(TranspositionEntry {
+                generation: 1,
+                ..entry_with_depth(3)
+            })
+        );
+        assert_eq!(loaded.occupied, 2);
+        assert_eq!(loaded.generation(), 1);
+
+        // Only part of each hash is stored, so the entries can't be moved
+        // into a table with a different number of buckets. What was in the
+        // table already is kept.
+        let mut bigger = TranspositionTable::new(2.try_into().unwrap());
+        bigger.set(TEST_HASH_2, entry_with_depth(5));
+        assert!(matches!(
+            bigger.load(&path),
+            Err(TTFileError::SizeMismatch { .. })
+        ));
+        assert_eq!(bigger.get(TEST_HASH_2), Some(entry_with_depth(5)));
+        assert_eq!(bigger.get(TEST_HASH_1), None);
+        assert_eq!(bigger.occupied, 1);
+
+        // A truncated file leaves the table cleared instead of half loaded
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(matches!(loaded.load(&path), Err(TTFileError::Io(_))));
+        assert_eq!(loaded.get(TEST_HASH_1), None);
+        assert_eq!(loaded.occupied, 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_other_files() {
+        let path =
+            std::env::temp_dir().join(format!("hardfiskur_tt_invalid_{}.bin", std::process::id()));
+        std::fs::write(&path, b"definitely not a transposition table").unwrap();
+
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+        assert!(matches!(tt.load(&path), Err(TTFileError::InvalidHeader)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn occupancy_reports_permille_occupied() {
         let mut tt = TranspositionTable::new(1.try_into().unwrap());
 
-        tt.occupied = (tt.num_entries / 2) as _;
+        tt.occupied = (tt.capacity() / 2) as _;
         assert_eq!(tt.occupancy(), 500);
 
-        tt.occupied = (tt.num_entries / 3) as _;
+        tt.occupied = (tt.capacity() / 3) as _;
         assert_eq!(tt.occupancy(), 333);
     }
 
@@ -467,6 +945,7 @@ mod test {
             depth: 5,
             score: Score(0),
             best_move: None,
+            generation: 0,
         };
 
         let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
@@ -508,6 +987,53 @@ mod test {
         assert_eq!(pv, vec![e4, e5, nf3]);
     }
 
+    #[test]
+    fn extract_pv_with_scores_reports_bounds() {
+        // Arrange
+        let mut board = Board::starting_position();
+        let mut tt = TranspositionTable::new(1.try_into().unwrap());
+
+        let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
+        tt.set(
+            board.zobrist_hash(),
+            TranspositionEntry {
+                flag: TranspositionFlag::Exact,
+                depth: 5,
+                score: Score(30),
+                best_move: Some(e4),
+                generation: 0,
+            },
+        );
+        board.push_move_repr(e4);
+
+        let e5 = board.get_move(Square::E7, Square::E5, None).unwrap();
+        tt.set(
+            board.zobrist_hash(),
+            TranspositionEntry {
+                flag: TranspositionFlag::Upperbound,
+                depth: 4,
+                score: Score(10),
+                best_move: Some(e5),
+                generation: 0,
+            },
+        );
+
+        board = Board::starting_position();
+
+        // Act
+        let pv = tt.extract_pv_with_scores(&mut board, 10);
+
+        // Assert
+        assert_eq!(board, Board::starting_position());
+        assert_eq!(
+            pv,
+            vec![
+                (e4, Score(30), TranspositionFlag::Exact),
+                (e5, Score(10), TranspositionFlag::Upperbound)
+            ]
+        );
+    }
+
     #[test]
     fn extract_pv_extracts_until_no_replaced_entry() {
         // Arrange
@@ -519,6 +1045,7 @@ mod test {
             depth: 5,
             score: Score(0),
             best_move: None,
+            generation: 0,
         };
 
         let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
@@ -543,15 +1070,14 @@ mod test {
 
         let nf3 = board.get_move(Square::G1, Square::F3, None).unwrap();
         tt.set(
-            // Deliberately create an entry in the same slot but with a different hash key
+            // Deliberately create an entry in the same bucket but with a different hash key
             board.zobrist_hash() ^ ZobristHash(0x8000_0000_0000_0000),
             TranspositionEntry {
                 best_move: Some(nf3),
                 ..default_entry.clone()
             },
         );
-        // This assert is part of the setup - if we switch to using buckets so
-        // each slot can hold more than one entry this assert will fail
+        // This assert is part of the setup
         assert_eq!(tt.get(board.zobrist_hash()), None);
 
         board = Board::starting_position();
@@ -575,6 +1101,7 @@ mod test {
             depth: 5,
             score: Score(0),
             best_move: None,
+            generation: 0,
         };
 
         let moves = ["g1f3", "b8c6", "f3g1", "c6b8"];
@@ -0,0 +1,109 @@
+use crate::{
+    evaluation::phase::Phase,
+    parameters::{WDL_MIDPOINT_EG, WDL_MIDPOINT_MG, WDL_SCALE_EG, WDL_SCALE_MG},
+    score::Score,
+};
+
+/// Win, draw and loss probabilities in permille, from the perspective of the
+/// side the score is for. The three values always sum to 1000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wdl {
+    pub win: u16,
+    pub draw: u16,
+    pub loss: u16,
+}
+
+impl Wdl {
+    pub fn from_score(score: Score, phase: Phase) -> Self {
+        let cp = match score.as_centipawns() {
+            Some(cp) => cp as f64,
+            None if score.is_mate_for_us() => {
+                return Self {
+                    win: 1000,
+                    draw: 0,
+                    loss: 0,
+                }
+            }
+            None => {
+                return Self {
+                    win: 0,
+                    draw: 0,
+                    loss: 1000,
+                }
+            }
+        };
+
+        let phase = phase.fraction() as f64;
+        let midpoint = WDL_MIDPOINT_MG * phase + WDL_MIDPOINT_EG * (1.0 - phase);
+        let scale = WDL_SCALE_MG * phase + WDL_SCALE_EG * (1.0 - phase);
+
+        let logistic = |x: f64| 1000.0 / (1.0 + ((midpoint - x) / scale).exp());
+
+        let win = logistic(cp).round() as u16;
+        let loss = logistic(-cp).round() as u16;
+
+        Self {
+            win,
+            draw: 1000 - win - loss,
+            loss,
+        }
+    }
+}
+
+impl From<Wdl> for (u16, u16, u16) {
+    fn from(value: Wdl) -> Self {
+        (value.win, value.draw, value.loss)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn wdl_sums_to_1000() {
+        for cp in [-5000, -300, -50, 0, 1, 75, 250, 1200] {
+            for phase in [0, 12, 24] {
+                let wdl = Wdl::from_score(Score(cp), Phase(phase));
+                assert_eq!(wdl.win + wdl.draw + wdl.loss, 1000);
+            }
+        }
+    }
+
+    #[test]
+    fn wdl_is_symmetric() {
+        let ahead = Wdl::from_score(Score(150), Phase(10));
+        let behind = Wdl::from_score(Score(-150), Phase(10));
+
+        assert_eq!(ahead.win, behind.loss);
+        assert_eq!(ahead.draw, behind.draw);
+        assert_eq!(ahead.loss, behind.win);
+    }
+
+    #[test]
+    fn wdl_win_chance_increases_with_score() {
+        let small = Wdl::from_score(Score(50), Phase(24));
+        let large = Wdl::from_score(Score(400), Phase(24));
+
+        assert!(small.win < large.win);
+        assert!(small.loss > large.loss);
+    }
+
+    #[test]
+    fn wdl_endgame_advantage_converts_more_easily() {
+        let midgame = Wdl::from_score(Score(200), Phase(24));
+        let endgame = Wdl::from_score(Score(200), Phase(0));
+
+        assert!(midgame.win < endgame.win);
+    }
+
+    #[test]
+    fn wdl_mate_scores() {
+        let wdl = Wdl::from_score(Score::mate_in_plies(3), Phase(0));
+        assert_eq!((wdl.win, wdl.draw, wdl.loss), (1000, 0, 0));
+
+        let wdl = Wdl::from_score(-Score::mate_in_plies(3), Phase(0));
+        assert_eq!((wdl.win, wdl.draw, wdl.loss), (0, 0, 1000));
+    }
+}
@@ -0,0 +1,456 @@
+use std::{path::PathBuf, time::Duration};
+
+use hardfiskur_engine::time_manager::MOVE_OVERHEAD;
+use hardfiskur_uci::UCIOptionConfig;
+use thiserror::Error;
+
+pub const MAX_HASH_SIZE: usize = 4096;
+pub const MAX_THREADS: usize = 1;
+pub const MAX_MULTI_PV: usize = 256;
+pub const MAX_CONTEMPT: i32 = 500;
+pub const MAX_MOVE_OVERHEAD_MS: usize = 5000;
+pub const MAX_VARIETY: usize = 20;
+
+/// Conventional value for an unset string option, as GUIs can't send an
+/// empty string.
+const EMPTY_STRING_OPTION: &str = "<empty>";
+
+/// The options advertised over UCI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineOption {
+    Hash,
+    ClearHash,
+    Threads,
+    MultiPV,
+    Contempt,
+    MoveOverhead,
+    Variety,
+    ShowWdl,
+    Chess960,
+    OwnBook,
+    BookFile,
+    EvalFile,
+}
+
+impl EngineOption {
+    /// Every option, in the order they're advertised.
+    pub const ALL: [Self; 12] = [
+        Self::Hash,
+        Self::ClearHash,
+        Self::Threads,
+        Self::MultiPV,
+        Self::Contempt,
+        Self::MoveOverhead,
+        Self::Variety,
+        Self::ShowWdl,
+        Self::Chess960,
+        Self::OwnBook,
+        Self::BookFile,
+        Self::EvalFile,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Hash => "Hash",
+            Self::ClearHash => "Clear Hash",
+            Self::Threads => "Threads",
+            Self::MultiPV => "MultiPV",
+            Self::Contempt => "Contempt",
+            Self::MoveOverhead => "Move Overhead",
+            Self::Variety => "Variety",
+            Self::ShowWdl => "UCI_ShowWDL",
+            Self::Chess960 => "UCI_Chess960",
+            Self::OwnBook => "OwnBook",
+            Self::BookFile => "Book File",
+            Self::EvalFile => "EvalFile",
+        }
+    }
+
+    /// Looks up an option by name. Option names are case-insensitive
+    /// according to the UCI spec.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|option| option.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SetOptionError {
+    #[error("Invalid value for {name}: {value:?} (expected {expected})")]
+    InvalidValue {
+        name: &'static str,
+        value: Option<String>,
+        expected: &'static str,
+    },
+
+    #[error("Invalid value for {name}: {value} (min={min}, max={max})")]
+    OutOfRange {
+        name: &'static str,
+        value: i64,
+        min: i64,
+        max: i64,
+    },
+}
+
+/// Typed values of all the options advertised over UCI.
+///
+/// [`Self::uci_options`] lists the options to advertise, and
+/// [`Self::set_option`] parses and validates a `setoption` command. Applying
+/// the new value (e.g. resizing the transposition table) is left to the
+/// caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOptions {
+    hash_size: usize,
+    threads: usize,
+    multi_pv: usize,
+    contempt: i32,
+    move_overhead: Duration,
+    variety: usize,
+    show_wdl: bool,
+    chess960: bool,
+    own_book: bool,
+    book_path: Option<PathBuf>,
+    eval_file: Option<PathBuf>,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            hash_size: 32,
+            threads: 1,
+            multi_pv: 1,
+            contempt: 0,
+            move_overhead: MOVE_OVERHEAD,
+            variety: 0,
+            show_wdl: false,
+            chess960: false,
+            own_book: false,
+            book_path: None,
+            eval_file: None,
+        }
+    }
+}
+
+impl EngineOptions {
+    /// The `option` lines to send in response to `uci`, advertising the
+    /// current values as the defaults.
+    pub fn uci_options(&self) -> Vec<UCIOptionConfig> {
+        EngineOption::ALL
+            .into_iter()
+            .map(|option| {
+                let name = option.name();
+                let spin = |default: i64, min: i64, max: i64| {
+                    UCIOptionConfig::spin(name, default, min, max)
+                };
+                let check = |default: bool| UCIOptionConfig::check(name, default);
+                let path = |value: &Option<PathBuf>| {
+                    UCIOptionConfig::string(
+                        name,
+                        value
+                            .as_ref()
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_default(),
+                    )
+                };
+
+                match option {
+                    EngineOption::Hash => spin(self.hash_size as i64, 1, MAX_HASH_SIZE as i64),
+                    EngineOption::ClearHash => UCIOptionConfig::button(name),
+                    EngineOption::Threads => spin(self.threads as i64, 1, MAX_THREADS as i64),
+                    EngineOption::MultiPV => spin(self.multi_pv as i64, 1, MAX_MULTI_PV as i64),
+                    EngineOption::Contempt => spin(
+                        self.contempt as i64,
+                        -MAX_CONTEMPT as i64,
+                        MAX_CONTEMPT as i64,
+                    ),
+                    EngineOption::MoveOverhead => spin(
+                        self.move_overhead.as_millis() as i64,
+                        0,
+                        MAX_MOVE_OVERHEAD_MS as i64,
+                    ),
+                    EngineOption::Variety => spin(self.variety as i64, 0, MAX_VARIETY as i64),
+                    EngineOption::ShowWdl => check(self.show_wdl),
+                    EngineOption::Chess960 => check(self.chess960),
+                    EngineOption::OwnBook => check(self.own_book),
+                    EngineOption::BookFile => path(&self.book_path),
+                    EngineOption::EvalFile => path(&self.eval_file),
+                }
+            })
+            .collect()
+    }
+
+    /// Parses `value` and sets `option` to it. Does nothing for buttons.
+    pub fn set_option(
+        &mut self,
+        option: EngineOption,
+        value: Option<&str>,
+    ) -> Result<(), SetOptionError> {
+        let name = option.name();
+
+        match option {
+            EngineOption::Hash => self.set_hash_size(parse_spin(name, value)?),
+            EngineOption::ClearHash => Ok(()),
+            EngineOption::Threads => self.set_threads(parse_spin(name, value)?),
+            EngineOption::MultiPV => self.set_multi_pv(parse_spin(name, value)?),
+            EngineOption::Contempt => self.set_contempt(parse_spin(name, value)?),
+            EngineOption::MoveOverhead => {
+                let millis = parse_spin(name, value)?;
+                check_range(name, millis, 0, MAX_MOVE_OVERHEAD_MS as i64)?;
+                self.set_move_overhead(Duration::from_millis(millis as u64))
+            }
+            EngineOption::Variety => self.set_variety(parse_spin(name, value)?),
+            EngineOption::ShowWdl => {
+                self.show_wdl = parse_check(name, value)?;
+                Ok(())
+            }
+            EngineOption::Chess960 => {
+                self.chess960 = parse_check(name, value)?;
+                Ok(())
+            }
+            EngineOption::OwnBook => {
+                self.own_book = parse_check(name, value)?;
+                Ok(())
+            }
+            EngineOption::BookFile => {
+                self.set_book_path(parse_path(value));
+                Ok(())
+            }
+            EngineOption::EvalFile => {
+                self.eval_file = parse_path(value);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn hash_size(&self) -> usize {
+        self.hash_size
+    }
+
+    /// Sets the transposition table size in megabytes.
+    pub fn set_hash_size(&mut self, hash_size: i64) -> Result<(), SetOptionError> {
+        check_range("Hash", hash_size, 1, MAX_HASH_SIZE as i64)?;
+        self.hash_size = hash_size as usize;
+        Ok(())
+    }
+
+    /// Only a single search thread is supported for now, so this just
+    /// validates the value.
+    pub fn set_threads(&mut self, threads: i64) -> Result<(), SetOptionError> {
+        check_range("Threads", threads, 1, MAX_THREADS as i64)?;
+        self.threads = threads as usize;
+        Ok(())
+    }
+
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    pub fn set_multi_pv(&mut self, multi_pv: i64) -> Result<(), SetOptionError> {
+        check_range("MultiPV", multi_pv, 1, MAX_MULTI_PV as i64)?;
+        self.multi_pv = multi_pv as usize;
+        Ok(())
+    }
+
+    pub fn contempt(&self) -> i32 {
+        self.contempt
+    }
+
+    /// Sets the contempt in centipawns.
+    pub fn set_contempt(&mut self, contempt: i64) -> Result<(), SetOptionError> {
+        check_range(
+            "Contempt",
+            contempt,
+            -MAX_CONTEMPT as i64,
+            MAX_CONTEMPT as i64,
+        )?;
+        self.contempt = contempt as i32;
+        Ok(())
+    }
+
+    pub fn move_overhead(&self) -> Duration {
+        self.move_overhead
+    }
+
+    pub fn set_move_overhead(&mut self, move_overhead: Duration) -> Result<(), SetOptionError> {
+        check_range(
+            "Move Overhead",
+            move_overhead.as_millis().try_into().unwrap_or(i64::MAX),
+            0,
+            MAX_MOVE_OVERHEAD_MS as i64,
+        )?;
+        self.move_overhead = move_overhead;
+        Ok(())
+    }
+
+    pub fn variety(&self) -> usize {
+        self.variety
+    }
+
+    pub fn set_variety(&mut self, variety: i64) -> Result<(), SetOptionError> {
+        check_range("Variety", variety, 0, MAX_VARIETY as i64)?;
+        self.variety = variety as usize;
+        Ok(())
+    }
+
+    pub fn show_wdl(&self) -> bool {
+        self.show_wdl
+    }
+
+    pub fn chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn own_book(&self) -> bool {
+        self.own_book
+    }
+
+    pub fn book_path(&self) -> Option<&PathBuf> {
+        self.book_path.as_ref()
+    }
+
+    pub fn set_book_path(&mut self, book_path: Option<PathBuf>) {
+        self.book_path = book_path;
+    }
+
+    pub fn eval_file(&self) -> Option<&PathBuf> {
+        self.eval_file.as_ref()
+    }
+}
+
+fn check_range(name: &'static str, value: i64, min: i64, max: i64) -> Result<(), SetOptionError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(SetOptionError::OutOfRange {
+            name,
+            value,
+            min,
+            max,
+        })
+    }
+}
+
+fn parse_spin(name: &'static str, value: Option<&str>) -> Result<i64, SetOptionError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| SetOptionError::InvalidValue {
+            name,
+            value: value.map(str::to_string),
+            expected: "an integer",
+        })
+}
+
+fn parse_check(name: &'static str, value: Option<&str>) -> Result<bool, SetOptionError> {
+    match value {
+        Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        _ => Err(SetOptionError::InvalidValue {
+            name,
+            value: value.map(str::to_string),
+            expected: "true or false",
+        }),
+    }
+}
+
+fn parse_path(value: Option<&str>) -> Option<PathBuf> {
+    value
+        .filter(|&path| !path.is_empty() && path != EMPTY_STRING_OPTION)
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn every_option_is_advertised() {
+        let advertised = EngineOptions::default().uci_options();
+        assert_eq!(advertised.len(), EngineOption::ALL.len());
+
+        for (config, option) in advertised.iter().zip(EngineOption::ALL) {
+            let line = config.to_string();
+            assert!(
+                line.starts_with(&format!("name {} type", option.name())),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn options_are_found_case_insensitively() {
+        assert_eq!(
+            EngineOption::from_name("multipv"),
+            Some(EngineOption::MultiPV)
+        );
+        assert_eq!(
+            EngineOption::from_name("CLEAR HASH"),
+            Some(EngineOption::ClearHash)
+        );
+        assert_eq!(EngineOption::from_name("AspirationWindow"), None);
+    }
+
+    #[test]
+    fn set_option_parses_and_validates() {
+        let mut options = EngineOptions::default();
+
+        options.set_option(EngineOption::Hash, Some("256")).unwrap();
+        options
+            .set_option(EngineOption::Contempt, Some("-20"))
+            .unwrap();
+        options
+            .set_option(EngineOption::MultiPV, Some("3"))
+            .unwrap();
+        options
+            .set_option(EngineOption::Chess960, Some("true"))
+            .unwrap();
+        options
+            .set_option(EngineOption::BookFile, Some("book.bin"))
+            .unwrap();
+        options
+            .set_option(EngineOption::MoveOverhead, Some("50"))
+            .unwrap();
+
+        assert_eq!(options.hash_size(), 256);
+        assert_eq!(options.contempt(), -20);
+        assert_eq!(options.multi_pv(), 3);
+        assert!(options.chess960());
+        assert_eq!(options.book_path(), Some(&PathBuf::from("book.bin")));
+        assert_eq!(options.move_overhead(), Duration::from_millis(50));
+
+        options
+            .set_option(EngineOption::BookFile, Some(EMPTY_STRING_OPTION))
+            .unwrap();
+        assert_eq!(options.book_path(), None);
+
+        assert_eq!(
+            options.set_option(EngineOption::Threads, Some("4")),
+            Err(SetOptionError::OutOfRange {
+                name: "Threads",
+                value: 4,
+                min: 1,
+                max: 1
+            })
+        );
+        assert_eq!(
+            options.set_option(EngineOption::ShowWdl, Some("yes")),
+            Err(SetOptionError::InvalidValue {
+                name: "UCI_ShowWDL",
+                value: Some("yes".to_string()),
+                expected: "true or false"
+            })
+        );
+        assert_eq!(
+            options.set_option(EngineOption::Hash, None),
+            Err(SetOptionError::InvalidValue {
+                name: "Hash",
+                value: None,
+                expected: "an integer"
+            })
+        );
+
+        // Failed updates leave the previous values alone
+        assert_eq!(options.hash_size(), 256);
+    }
+}
@@ -1,3 +1,4 @@
+mod engine_options;
 mod uci;
 
 use hardfiskur_engine::Engine;
@@ -19,9 +20,20 @@ fn main() {
     let args: Vec<_> = std::env::args().collect();
     let mut engine = Engine::new();
 
-    if args.len() == 2 && args[1] == "bench" {
-        let (nodes, time) = engine.bench(None);
-        let nps = nodes * 1000 / time.as_millis() as u64;
+    // `bench [depth]` prints the total node count over the bench suite, which
+    // is deterministic for a given depth and so can be compared across commits
+    if (args.len() == 2 || args.len() == 3) && args[1] == "bench" {
+        let depth = match args.get(2).map(|depth| depth.parse()) {
+            None => None,
+            Some(Ok(depth)) => Some(depth),
+            Some(Err(e)) => {
+                eprintln!("Invalid bench depth {:?}: {e}", args[2]);
+                std::process::exit(1);
+            }
+        };
+
+        let (nodes, time) = engine.bench(depth);
+        let nps = nodes * 1000 / (time.as_millis() as u64).max(1);
         println!("{nodes} nodes {nps} nps {} time", time.as_millis());
         return;
     }
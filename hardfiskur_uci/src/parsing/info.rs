@@ -2,19 +2,19 @@ use nom::{
     branch::alt,
     combinator::{opt, rest, success},
     multi::many0,
-    sequence::{pair, preceded},
+    sequence::{preceded, tuple},
     IResult, Parser,
 };
 use nom_permutation::permutation_opt;
 
 use super::utils::{
-    token_i32, token_millis_ignore_negative, token_tag, token_u32, token_u64,
+    token_i32, token_millis_ignore_negative, token_tag, token_u16, token_u32, token_u64,
     token_uci_move,
 };
 use crate::{UCIInfo, UCIInfoCurrLine, UCIInfoScore};
 
 fn info_score(input: &str) -> IResult<&str, UCIInfoScore> {
-    pair(
+    tuple((
         alt((
             preceded(token_tag("cp"), token_i32).map(|cp| (Some(cp), None)),
             preceded(token_tag("mate"), token_i32).map(|mate| (None, Some(mate))),
@@ -24,13 +24,20 @@ fn info_score(input: &str) -> IResult<&str, UCIInfoScore> {
             token_tag("upperbound").map(|_| (false, true)),
             success((false, false)),
         )),
+        opt(preceded(
+            token_tag("wdl"),
+            tuple((token_u16, token_u16, token_u16)),
+        )),
+    ))
+    .map(
+        |((cp, mate), (lower_bound, upper_bound), wdl)| UCIInfoScore {
+            cp,
+            mate,
+            lower_bound,
+            upper_bound,
+            wdl,
+        },
     )
-    .map(|((cp, mate), (lower_bound, upper_bound))| UCIInfoScore {
-        cp,
-        mate,
-        lower_bound,
-        upper_bound,
-    })
     .parse(input)
 }
 
@@ -100,8 +100,8 @@ fn go_body(input: &str) -> IResult<&str, UCIMessage> {
             infinite,
         )| {
             UCIMessage::Go {
+                ponder: ponder.is_some(),
                 time_control: UCITimeControl::from_raw(
-                    ponder.is_some(),
                     white_time.flatten(),
                     black_time.flatten(),
                     white_increment.flatten(),
@@ -270,6 +270,7 @@ fn parse_go() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: None,
             search_control: None
         }
@@ -282,7 +283,27 @@ fn parse_go_ponder() {
     assert_eq!(
         msg,
         UCIMessage::Go {
-            time_control: Some(UCITimeControl::Ponder),
+            ponder: true,
+            time_control: None,
+            search_control: None
+        }
+    );
+}
+
+#[test]
+fn parse_go_ponder_with_time_left() {
+    let msg: UCIMessage = "go ponder wtime 59000 btime 58000".parse().unwrap();
+    assert_eq!(
+        msg,
+        UCIMessage::Go {
+            ponder: true,
+            time_control: Some(UCITimeControl::TimeLeft {
+                white_time: Some(Duration::from_secs(59)),
+                black_time: Some(Duration::from_secs(58)),
+                white_increment: None,
+                black_increment: None,
+                moves_to_go: None,
+            }),
             search_control: None
         }
     );
@@ -294,6 +315,7 @@ fn parse_go_infinite() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: Some(UCITimeControl::Infinite),
             search_control: None
         }
@@ -306,6 +328,7 @@ fn parse_go_movetime() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: Some(UCITimeControl::MoveTime(Duration::from_millis(1234))),
             search_control: None
         }
@@ -320,6 +343,7 @@ fn parse_go_timeleft() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: Some(UCITimeControl::TimeLeft {
                 white_time: Some(Duration::from_secs(59)),
                 black_time: Some(Duration::from_secs(58)),
@@ -340,6 +364,7 @@ fn parse_go_search_control() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: None,
             search_control: Some(UCISearchControl {
                 search_moves: vec!["e2e4".parse().unwrap(), "e7e6".parse().unwrap()],
@@ -359,6 +384,7 @@ fn parse_go_arbitrary_option_order() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: Some(UCITimeControl::TimeLeft {
                 white_time: Some(Duration::from_secs(59)),
                 black_time: Some(Duration::from_secs(58)),
@@ -386,6 +412,7 @@ fn parse_go_negative_times_ignored() {
     assert_eq!(
         msg,
         UCIMessage::Go {
+            ponder: false,
             time_control: Some(UCITimeControl::TimeLeft {
                 white_time: Some(Duration::from_secs(59)),
                 black_time: None,
@@ -655,6 +682,25 @@ fn parse_info_score_upperbound() {
     );
 }
 
+#[test]
+fn parse_info_score_wdl() {
+    let msg: UCIMessage = "info score cp 12 upperbound wdl 100 850 50"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        msg,
+        UCIMessage::Info(UCIInfo {
+            score: Some(UCIInfoScore {
+                cp: Some(12),
+                upper_bound: true,
+                wdl: Some((100, 850, 50)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    );
+}
+
 #[test]
 fn parse_info_currmove() {
     let msg: UCIMessage = "info currmove e2e4".parse().unwrap();
@@ -753,6 +799,16 @@ fn parse_info_string() {
     );
 }
 
+#[test]
+fn info_string_round_trips() {
+    let msg = UCIMessage::info_string("time allocated: soft 100ms, hard 500ms");
+    assert_eq!(
+        msg.to_string(),
+        "info string time allocated: soft 100ms, hard 500ms"
+    );
+    assert_eq!(msg.to_string().parse(), Ok(msg));
+}
+
 #[test]
 fn parse_info_string_goes_until_eof() {
     let msg: UCIMessage = "info nodes 1 string looks like another option: depth 2 currmove e2e4"
@@ -1009,3 +1065,109 @@ fn parse_option_string_empty() {
         })
     );
 }
+
+fn assert_info_round_trips(info: UCIInfo) {
+    let msg = UCIMessage::Info(info);
+    let formatted = msg.to_string();
+    assert_eq!(
+        formatted.parse(),
+        Ok(msg),
+        "failed to round trip {formatted:?}"
+    );
+}
+
+#[test]
+fn info_round_trips_all_fields() {
+    assert_info_round_trips(UCIInfo {
+        depth: Some(12),
+        sel_depth: Some(19),
+        time: Some(Duration::from_millis(1534)),
+        nodes: Some(1_234_567),
+        pv: vec!["e2e4".parse().unwrap(), "e7e5".parse().unwrap()],
+        multi_pv: Some(2),
+        score: Some(UCIInfoScore {
+            cp: Some(-35),
+            upper_bound: true,
+            wdl: Some((50, 800, 150)),
+            ..Default::default()
+        }),
+        curr_move: Some("g1f3".parse().unwrap()),
+        curr_move_number: Some(4),
+        hash_full: Some(243),
+        nps: Some(804_800),
+        tb_hits: Some(17),
+        sb_hits: Some(3),
+        cpu_load: Some(950),
+        string: Some("all the fields at once".to_string()),
+        refutation: vec!["d1h5".parse().unwrap(), "g6h5".parse().unwrap()],
+        curr_line: Some(UCIInfoCurrLine {
+            cpu_nr: Some(1),
+            moves: vec!["d2d4".parse().unwrap(), "d7d5".parse().unwrap()],
+        }),
+    });
+}
+
+#[test]
+fn info_round_trips_promotions_in_pv() {
+    assert_info_round_trips(UCIInfo {
+        depth: Some(3),
+        pv: vec![
+            "e7e8q".parse().unwrap(),
+            "a2a1n".parse().unwrap(),
+            "b7a8r".parse().unwrap(),
+        ],
+        ..Default::default()
+    });
+}
+
+#[test]
+fn info_round_trips_mate_scores() {
+    for mate in [1, 5, -1, -6] {
+        assert_info_round_trips(UCIInfo {
+            depth: Some(8),
+            score: Some(UCIInfoScore {
+                mate: Some(mate),
+                lower_bound: mate > 0,
+                ..Default::default()
+            }),
+            pv: vec!["h5f7".parse().unwrap()],
+            ..Default::default()
+        });
+    }
+}
+
+#[test]
+fn info_round_trips_empty_pv() {
+    assert_info_round_trips(UCIInfo {
+        depth: Some(1),
+        nodes: Some(20),
+        score: Some(UCIInfoScore {
+            cp: Some(0),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    assert_info_round_trips(UCIInfo::default());
+}
+
+#[test]
+fn info_round_trips_currline() {
+    let line = UCIInfoCurrLine {
+        cpu_nr: None,
+        moves: vec!["d1h5".parse().unwrap(), "g8h5".parse().unwrap()],
+    };
+    let msg = UCIMessage::Info(UCIInfo {
+        curr_line: Some(line.clone()),
+        ..Default::default()
+    });
+    assert_eq!(msg.to_string(), "info currline d1h5 g8h5");
+
+    assert_info_round_trips(UCIInfo {
+        nodes: Some(5),
+        curr_line: Some(UCIInfoCurrLine {
+            cpu_nr: Some(3),
+            ..line
+        }),
+        ..Default::default()
+    });
+}
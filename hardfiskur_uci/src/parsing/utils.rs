@@ -4,7 +4,7 @@ use hardfiskur_core::board::UCIMove;
 use nom::{
     branch::alt,
     bytes::complete::take_till1,
-    character::complete::{i32, i64, space0, space1, u32, u64},
+    character::complete::{i32, i64, space0, space1, u16, u32, u64},
     combinator::{eof, fail, rest},
     error::{context, Error, ErrorKind, ParseError},
     error_position, IResult, Parser,
@@ -100,6 +100,10 @@ pub fn token_i64(input: &str) -> IResult<&str, i64> {
     tokenize(i64)(input)
 }
 
+pub fn token_u16(input: &str) -> IResult<&str, u16> {
+    tokenize(u16)(input)
+}
+
 pub fn token_u32(input: &str) -> IResult<&str, u32> {
     tokenize(u32)(input)
 }
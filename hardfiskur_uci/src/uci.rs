@@ -1,12 +1,22 @@
 use std::{io::stdin, str::FromStr};
 
-use hardfiskur_core::board::{Board, UCIMove};
+use hardfiskur_core::{
+    board::{Board, Move, UCIMove},
+    polyglot::PolyglotBook,
+};
 use hardfiskur_engine::{
+    evaluation::{nnue::Network, EvalContext, EvalKind},
     search_limits::{SearchLimits, TimeControls},
-    search_result::{SearchInfo, SearchResult},
+    search_result::{CurrMoveInfo, PonderOutcome, SearchInfo, SearchResult},
+    time_manager::TimeManager,
     Engine, SearchReporter,
 };
-use hardfiskur_uci::{UCIMessage, UCIOptionConfig, UCIPosition, UCIPositionBase};
+use hardfiskur_uci::{
+    UCIInfo, UCIMessage, UCIPosition, UCIPositionBase, UCISearchControl, UCITimeControl,
+};
+use rand::seq::SliceRandom;
+
+use crate::engine_options::{EngineOption, EngineOptions};
 
 fn version_string() -> String {
     let rev = option_env!("VERGEN_GIT_DESCRIBE").unwrap_or("unknown");
@@ -33,18 +43,54 @@ fn read_message() -> Option<UCIMessage> {
     }
 }
 
-struct UCIReporter;
+struct UCIReporter {
+    show_wdl: bool,
+    chess960: bool,
+}
+
+impl UCIReporter {
+    fn uci_move(&self, m: Move) -> UCIMove {
+        UCIMove::from_move(m, self.chess960)
+    }
+
+    fn info_message(&self, info: SearchInfo) -> UCIMessage {
+        let pv = info.pv.iter().map(|&m| self.uci_move(m)).collect();
+        let mut info = UCIInfo::from(info);
+        info.pv = pv;
+        if !self.show_wdl {
+            if let Some(score) = info.score.as_mut() {
+                score.wdl = None;
+            }
+        }
+
+        UCIMessage::Info(info)
+    }
+}
+
 impl SearchReporter for UCIReporter {
     fn receive_search_info(&self, info: SearchInfo) {
-        println!("{}", UCIMessage::Info(info.into()));
+        println!("{}", self.info_message(info));
     }
 
     fn search_complete(&self, result: SearchResult) {
         let SearchResult {
-            best_move, info, ..
+            best_move,
+            info,
+            ponder_outcome,
         } = result;
 
-        println!("{}", UCIMessage::Info(info.into()));
+        let ebf = info.raw_stats.effective_branching_factor();
+        println!("{}", self.info_message(info));
+
+        if let Some(ebf) = ebf {
+            println!("{}", UCIMessage::info_string(&format!("ebf {ebf:.2}")));
+        }
+
+        match ponder_outcome {
+            Some(PonderOutcome::Hit) => println!("{}", UCIMessage::info_string("ponder hit")),
+            Some(PonderOutcome::Miss) => println!("{}", UCIMessage::info_string("ponder miss")),
+            None => (),
+        }
 
         let best_move = match best_move {
             Some(x) => x,
@@ -54,48 +100,190 @@ impl SearchReporter for UCIReporter {
             }
         };
 
-        println!("{}", UCIMessage::best_move(best_move.into()))
+        println!("{}", UCIMessage::best_move(self.uci_move(best_move)))
+    }
+
+    fn receive_curr_move(&self, info: CurrMoveInfo) {
+        let curr_move = self.uci_move(info.curr_move);
+        let mut info = UCIInfo::from(info);
+        info.curr_move = Some(curr_move);
+
+        println!("{}", UCIMessage::Info(info));
     }
 }
 
-fn uci_options() -> Vec<UCIOptionConfig> {
-    vec![
-        UCIOptionConfig::Spin {
-            name: "Hash".into(),
-            default: Some(32),
-            min: Some(1),
-            max: Some(131072),
-        },
-        UCIOptionConfig::Spin {
-            name: "Threads".into(),
-            default: Some(1),
-            min: Some(1),
-            max: Some(1),
-        },
-    ]
+const MAX_ASPIRATION_MIN_DEPTH: usize = 64;
+const MAX_ASPIRATION_WINDOW: usize = 1000;
+
+fn parse_spin_value(
+    option_name: &str,
+    option_value: Option<&str>,
+    min: usize,
+    max: usize,
+) -> Option<usize> {
+    let value = match option_value.and_then(|x| x.parse().ok()) {
+        Some(x) => x,
+        None => {
+            eprintln!("Could not parse {option_value:?} as usize");
+            return None;
+        }
+    };
+
+    if !(min..=max).contains(&value) {
+        eprintln!("Invalid value for {option_name}: {value} (min={min}, max={max})");
+        return None;
+    }
+
+    Some(value)
 }
 
-fn handle_option(engine: &mut Engine, option_name: &str, option_value: Option<&str>) {
-    if option_name == "Hash" {
-        let value = match option_value.and_then(|x| x.parse().ok()) {
-            Some(x) => x,
-            None => {
-                eprintln!("Could not parse {option_value:?} as usize");
-                return;
+#[derive(Debug, Default)]
+struct UCISettings {
+    // Set by the debug command, enables extra info strings
+    debug: bool,
+    options: EngineOptions,
+
+    book: Option<PolyglotBook>,
+}
+
+impl UCISettings {
+    /// Prints an info string, but only if debug mode is on.
+    fn debug_info(&self, string: impl FnOnce() -> String) {
+        if self.debug {
+            println!("{}", UCIMessage::info_string(&string()));
+        }
+    }
+
+    /// Picks a move from the book for this position, if enabled. Plays the
+    /// highest weighted move, or a weighted random one if variety is enabled.
+    fn book_move(&self, board: &Board) -> Option<Move> {
+        if !self.options.own_book() {
+            return None;
+        }
+
+        let moves = self.book.as_ref()?.moves(board);
+        if self.options.variety() > 0 {
+            if let Ok(&(m, _)) =
+                moves.choose_weighted(&mut rand::thread_rng(), |&(_, weight)| weight)
+            {
+                return Some(m);
             }
-        };
+        }
 
-        if !(1..=131072).contains(&value) {
-            eprintln!("Invalid value for Hash: {value} (min=1, max=131072)");
-            return;
+        moves
+            .iter()
+            .max_by_key(|&&(_, weight)| weight)
+            .map(|&(m, _)| m)
+    }
+}
+
+fn handle_option(
+    engine: &mut Engine,
+    settings: &mut UCISettings,
+    option_name: &str,
+    option_value: Option<&str>,
+) {
+    if let Some(option) = EngineOption::from_name(option_name) {
+        match settings.options.set_option(option, option_value) {
+            Ok(()) => apply_option(engine, settings, option),
+            Err(e) => eprintln!("{e}"),
+        }
+    } else if option_name.eq_ignore_ascii_case("AspirationMinDepth") {
+        // Hidden tuning option, not listed in EngineOptions
+        if let Some(value) = parse_spin_value(
+            "AspirationMinDepth",
+            option_value,
+            1,
+            MAX_ASPIRATION_MIN_DEPTH,
+        ) {
+            let mut params = engine.search_params();
+            params.aspiration_min_depth = value as i16;
+            engine.set_search_params(params);
+        }
+    } else if option_name.eq_ignore_ascii_case("AspirationWindow") {
+        // Hidden tuning option, not listed in EngineOptions
+        if let Some(value) =
+            parse_spin_value("AspirationWindow", option_value, 1, MAX_ASPIRATION_WINDOW)
+        {
+            let mut params = engine.search_params();
+            params.aspiration_initial_window = value as i32;
+            engine.set_search_params(params);
         }
+    } else if option_name.eq_ignore_ascii_case("EvalKind") {
+        // Hidden option for debugging search independently of evaluation, so
+        // it isn't listed in EngineOptions.
+        match option_value {
+            Some(x) if x.eq_ignore_ascii_case("Full") => engine.set_eval_kind(EvalKind::Full),
+            Some(x) if x.eq_ignore_ascii_case("Material") => {
+                engine.set_eval_kind(EvalKind::Material)
+            }
+            _ => eprintln!(
+                "Invalid value for EvalKind: {option_value:?} (expected Full or Material)"
+            ),
+        }
+    } else {
+        eprintln!("Unknown option: {option_name}");
+    }
+}
 
-        engine.set_tt_size(value);
+/// Passes a newly set option on to wherever it's used.
+fn apply_option(engine: &mut Engine, settings: &mut UCISettings, option: EngineOption) {
+    let options = &settings.options;
+
+    match option {
+        EngineOption::Hash => {
+            let size = options.hash_size();
+            engine.set_tt_size(size);
+            settings.debug_info(|| format!("transposition table resized to {size} MB"));
+        }
+        EngineOption::ClearHash => engine.clear_tt(),
+        EngineOption::MultiPV => engine.set_multipv(options.multi_pv()),
+        EngineOption::MoveOverhead => engine.set_move_overhead(options.move_overhead()),
+        EngineOption::Contempt => engine.set_contempt(options.contempt()),
+        EngineOption::Variety => engine.set_variety(options.variety() as i32),
+        EngineOption::BookFile => {
+            settings.book = options.book_path().and_then(|path| {
+                PolyglotBook::open(path)
+                    .inspect_err(|e| eprintln!("Could not load book {path:?}: {e}"))
+                    .ok()
+            });
+        }
+        EngineOption::EvalFile => {
+            // Falls back to the hand-crafted evaluation if unset or invalid
+            let network = options.eval_file().and_then(|path| {
+                Network::load(path)
+                    .inspect_err(|e| eprintln!("Could not load network {path:?}: {e}"))
+                    .ok()
+            });
+            engine.set_nnue(network);
+        }
+        // Read from the options when needed
+        EngineOption::Threads
+        | EngineOption::ShowWdl
+        | EngineOption::Chess960
+        | EngineOption::OwnBook => (),
     }
 }
 
+/// Whether a `go` command is for a normal move in a game, which can be
+/// answered straight away from the book. Anything else needs a real search:
+/// `go infinite` mustn't send a best move before `stop`, depth, node and mate
+/// limits ask for a particular search, and for pondering and searchmoves the
+/// book move might not be one the GUI is expecting.
+fn can_use_book(
+    ponder: bool,
+    time_control: Option<&UCITimeControl>,
+    search_control: Option<&UCISearchControl>,
+) -> bool {
+    let timed = !matches!(time_control, Some(UCITimeControl::Infinite));
+    !ponder && timed && search_control.is_none()
+}
+
 pub fn main_loop(engine: &mut Engine) {
     let mut current_board = Board::starting_position();
+    let mut settings = UCISettings::default();
+    // Time controls to switch to when receiving ponderhit
+    let mut ponder_time_controls = None;
 
     'main_loop: loop {
         let command = match read_message() {
@@ -116,7 +304,7 @@ pub fn main_loop(engine: &mut Engine) {
                 );
                 println!("{}", UCIMessage::id_author("Tyler Zhang"));
 
-                for option in uci_options() {
+                for option in settings.options.uci_options() {
                     let message = UCIMessage::Option(option);
                     println!("{message}");
                 }
@@ -124,7 +312,9 @@ pub fn main_loop(engine: &mut Engine) {
                 println!("{}", UCIMessage::UCIOk);
             }
 
-            UCIMessage::SetOption { name, value } => handle_option(engine, &name, value.as_deref()),
+            UCIMessage::SetOption { name, value } => {
+                handle_option(engine, &mut settings, &name, value.as_deref())
+            }
 
             UCIMessage::UCINewGame => {
                 current_board = Board::starting_position();
@@ -136,6 +326,13 @@ pub fn main_loop(engine: &mut Engine) {
             }
 
             UCIMessage::Position(UCIPosition { base, moves }) => {
+                // A new position while still pondering means the opponent
+                // didn't play the ponder move, so drop that search
+                if ponder_time_controls.take().is_some() {
+                    engine.abort_search();
+                    settings.debug_info(|| "ponder search aborted".to_string());
+                }
+
                 match base {
                     UCIPositionBase::StartPos => current_board = Board::starting_position(),
                     UCIPositionBase::Fen(fen) => {
@@ -149,21 +346,69 @@ pub fn main_loop(engine: &mut Engine) {
                     }
                 }
 
-                for m in moves {
+                // Stop at the first illegal move rather than playing the rest
+                // of the moves on the wrong position, and let the GUI know
+                for (i, m) in moves.iter().enumerate() {
                     if current_board.push_move(m.from, m.to, m.promotion).is_none() {
-                        eprintln!("Invalid move received: {m}")
+                        let message = format!(
+                            "illegal move {m} (move {} of {}), ignoring it and any moves \
+                             after it",
+                            i + 1,
+                            moves.len()
+                        );
+                        println!("{}", UCIMessage::info_string(&message));
+                        break;
                     }
                 }
             }
 
             UCIMessage::Go {
+                ponder,
                 time_control,
                 search_control,
             } => {
-                let time_controls = time_control
+                if can_use_book(ponder, time_control.as_ref(), search_control.as_ref()) {
+                    if let Some(m) = settings.book_move(&current_board) {
+                        settings.debug_info(|| "book move".to_string());
+                        println!(
+                            "{}",
+                            UCIMessage::best_move(UCIMove::from_move(
+                                m,
+                                settings.options.chess960()
+                            ))
+                        );
+                        continue 'main_loop;
+                    }
+                }
+
+                let mut time_controls = time_control
                     .map(|time_control| time_control.as_time_controls(current_board.to_move()))
                     .unwrap_or(TimeControls::Infinite);
 
+                // While pondering, search indefinitely until ponderhit, at
+                // which point the clock starts with the original time controls
+                ponder_time_controls = None;
+                if ponder {
+                    ponder_time_controls = Some(std::mem::replace(
+                        &mut time_controls,
+                        TimeControls::Infinite,
+                    ));
+                }
+
+                if !matches!(time_controls, TimeControls::Infinite) {
+                    let (soft_bound, hard_bound) = TimeManager::time_bounds(
+                        time_controls.clone(),
+                        settings.options.move_overhead(),
+                    );
+                    settings.debug_info(|| {
+                        format!(
+                            "time allocated: soft {}ms, hard {}ms",
+                            soft_bound.as_millis(),
+                            hard_bound.as_millis()
+                        )
+                    });
+                }
+
                 let search_limits = SearchLimits {
                     time_controls,
                     node_budget: search_control
@@ -175,12 +420,52 @@ pub fn main_loop(engine: &mut Engine) {
                         .and_then(|s| s.depth)
                         .and_then(|d| d.try_into().ok())
                         .unwrap_or(i16::MAX),
+                    search_moves: search_control
+                        .as_ref()
+                        .map(|s| {
+                            s.search_moves
+                                .iter()
+                                .filter_map(|m| {
+                                    let legal_move =
+                                        current_board.get_move(m.from, m.to, m.promotion);
+                                    if legal_move.is_none() {
+                                        eprintln!("Ignoring illegal searchmove: {m}");
+                                    }
+                                    legal_move
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    pondering: ponder,
+                    move_overhead: settings.options.move_overhead(),
+                    mate: search_control
+                        .as_ref()
+                        .and_then(|s| s.mate)
+                        .map(|mate| mate.try_into().unwrap_or(u16::MAX)),
                 };
 
-                engine.start_search(&current_board, search_limits, UCIReporter);
+                engine.start_search(
+                    &current_board,
+                    search_limits,
+                    UCIReporter {
+                        show_wdl: settings.options.show_wdl(),
+                        chess960: settings.options.chess960(),
+                    },
+                );
             }
 
-            UCIMessage::Stop => engine.abort_search(),
+            UCIMessage::Debug(on) => settings.debug = on,
+
+            UCIMessage::Stop => {
+                engine.abort_search();
+                settings.debug_info(|| "search aborted".to_string());
+            }
+
+            UCIMessage::PonderHit => {
+                if let Some(time_controls) = ponder_time_controls.take() {
+                    engine.ponder_hit(time_controls);
+                }
+            }
 
             UCIMessage::D => {
                 println!("{current_board}");
@@ -200,7 +485,7 @@ pub fn main_loop(engine: &mut Engine) {
                 let m = m.or_else(|| {
                     let entry = engine.get_tt_entry(&current_board);
                     entry.and_then(|m| m.best_move).map(|m| {
-                        let m = m.into();
+                        let m = UCIMove::from_move(m, settings.options.chess960());
                         println!("Using best move from TT: {m}");
                         m
                     })
@@ -218,7 +503,10 @@ pub fn main_loop(engine: &mut Engine) {
 
             UCIMessage::UndoMove => {
                 if let Some(m) = current_board.pop_move() {
-                    println!("Undid move {}", UCIMove::from(m));
+                    println!(
+                        "Undid move {}",
+                        UCIMove::from_move(m, settings.options.chess960())
+                    );
                 }
             }
 
@@ -226,22 +514,26 @@ pub fn main_loop(engine: &mut Engine) {
                 let pv = engine.get_pv(&current_board);
                 print!("PV:");
                 for m in pv {
-                    print!(" {}", UCIMove::from(m));
+                    print!(" {}", UCIMove::from_move(m, settings.options.chess960()));
                 }
                 println!();
             }
 
-            UCIMessage::Eval => println!("{}", engine.debug_eval(&current_board)),
+            UCIMessage::Eval => {
+                println!("{}", EvalContext::explain(&current_board));
+                if engine.uses_nnue() {
+                    println!("NNUE evaluation: {}", engine.debug_eval(&current_board));
+                }
+            }
 
             UCIMessage::Bench { depth } => {
                 let (nodes, time) = engine.bench(depth);
 
-                let nps = nodes * 1000 / time.as_millis() as u64;
+                // Clamp to avoid dividing by zero if the bench is very quick
+                let nps = nodes * 1000 / (time.as_millis() as u64).max(1);
 
-                println!(
-                    "info string nodes {nodes} time {} nps {nps}",
-                    time.as_millis()
-                );
+                let message = format!("nodes {nodes} time {} nps {nps}", time.as_millis());
+                println!("{}", UCIMessage::info_string(&message));
             }
 
             // ignore all other messages
@@ -1,7 +1,11 @@
 use std::{fmt::Display, time::Duration};
 
 use hardfiskur_core::board::UCIMove;
-use hardfiskur_engine::{score::Score, search_result::SearchInfo};
+use hardfiskur_engine::{
+    score::Score,
+    search_result::{CurrMoveInfo, SearchInfo},
+    wdl::Wdl,
+};
 
 use crate::format_utils::SpaceSepFormatter;
 
@@ -11,6 +15,7 @@ pub struct UCIInfoScore {
     pub mate: Option<i32>,
     pub lower_bound: bool,
     pub upper_bound: bool,
+    pub wdl: Option<(u16, u16, u16)>,
 }
 
 impl Display for UCIInfoScore {
@@ -28,10 +33,37 @@ impl Display for UCIInfoScore {
             formatter.push_str("upperbound")?;
         }
 
+        if let Some((win, draw, loss)) = self.wdl {
+            formatter.push_str("wdl")?;
+            formatter.push(&win)?;
+            formatter.push(&draw)?;
+            formatter.push(&loss)?;
+        }
+
         Ok(())
     }
 }
 
+impl UCIInfoScore {
+    /// Converts back into a [`Score`], ignoring the bounds and WDL. Returns
+    /// `None` if neither `cp` nor `mate` is set. Mate distances too long to
+    /// represent are clamped to the longest one that is.
+    pub fn to_score(&self) -> Option<Score> {
+        match (self.cp, self.mate) {
+            (Some(cp), _) => Some(Score(cp)),
+            (None, Some(mate)) => {
+                let moves = u16::try_from(mate.unsigned_abs()).unwrap_or(u16::MAX);
+                Some(if mate > 0 {
+                    Score::mate_in(moves)
+                } else {
+                    Score::mated_in(moves)
+                })
+            }
+            (None, None) => None,
+        }
+    }
+}
+
 impl From<Score> for UCIInfoScore {
     fn from(score: Score) -> Self {
         Self {
@@ -104,13 +136,6 @@ impl Display for UCIInfo {
         formatter.push_option("sbhits", self.sb_hits)?;
         formatter.push_option("cpuload", self.cpu_load)?;
 
-        if !self.pv.is_empty() {
-            formatter.push_str("pv")?;
-            for m in self.pv.iter() {
-                formatter.push(m)?;
-            }
-        }
-
         if !self.refutation.is_empty() {
             formatter.push_str("refutation")?;
             for m in self.refutation.iter() {
@@ -125,7 +150,10 @@ impl Display for UCIInfo {
             }
         }
 
-        formatter.push_option("currline", self.curr_line.as_ref())?;
+        // UCIInfoCurrLine already writes its own "currline" keyword.
+        if let Some(curr_line) = &self.curr_line {
+            formatter.push(curr_line)?;
+        }
 
         formatter.push_option("string", self.string.as_ref())?;
 
@@ -133,18 +161,228 @@ impl Display for UCIInfo {
     }
 }
 
+impl UCIInfo {
+    fn nodes_per_second(nodes: u64, elapsed: Duration) -> u64 {
+        // Work from the same whole milliseconds as the reported time, clamped
+        // to at least 1 so that very short searches don't divide by zero or
+        // report a wildly inflated figure.
+        let millis = elapsed.as_millis().max(1);
+        (nodes as u128 * 1000 / millis)
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+}
+
 impl From<SearchInfo> for UCIInfo {
     fn from(value: SearchInfo) -> Self {
         Self {
-            score: Some(value.score.into()),
+            score: Some(UCIInfoScore {
+                wdl: Some(Wdl::from_score(value.score, value.phase).into()),
+                ..value.score.into()
+            }),
             depth: Some(value.raw_stats.depth.into()),
             sel_depth: Some(value.raw_stats.sel_depth.into()),
             nodes: Some(value.raw_stats.nodes_searched),
             tb_hits: Some(value.raw_stats.tt_hits),
-            time: Some(value.elapsed),
+            // UCI reports time in whole milliseconds, so truncate here to
+            // keep this consistent with what a GUI will parse back.
+            time: Some(Duration::from_millis(
+                value.elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+            )),
             pv: value.pv.iter().map(|m| UCIMove::from(*m)).collect(),
-            hash_full: Some(value.hash_full.try_into().unwrap_or(1000)),
+            hash_full: Some(value.hash_full.min(1000) as u32),
+            multi_pv: value.multi_pv,
+            nps: Some(Self::nodes_per_second(
+                value.raw_stats.nodes_searched,
+                value.elapsed,
+            )),
             ..Default::default()
         }
     }
 }
+
+impl From<CurrMoveInfo> for UCIInfo {
+    fn from(value: CurrMoveInfo) -> Self {
+        Self {
+            depth: Some(value.depth.max(0) as u32),
+            curr_move: Some(value.curr_move.into()),
+            curr_move_number: Some(value.curr_move_number),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hardfiskur_core::board::{Board, PieceType, Square};
+    use hardfiskur_engine::{evaluation::phase::Phase, search_stats::SearchStats};
+
+    use crate::UCIMessage;
+    use pretty_assertions::assert_eq;
+
+    fn search_info(nodes_searched: u64, elapsed: Duration, hash_full: u64) -> SearchInfo {
+        SearchInfo {
+            score: Score(0),
+            raw_stats: SearchStats {
+                nodes_searched,
+                ..Default::default()
+            },
+            elapsed,
+            pv: Vec::new(),
+            hash_full,
+            phase: Phase(24),
+            multi_pv: None,
+        }
+    }
+
+    #[test]
+    fn from_search_info_computes_nps() {
+        let info = UCIInfo::from(search_info(50_000, Duration::from_millis(250), 0));
+        assert_eq!(info.nps, Some(200_000));
+    }
+
+    #[test]
+    fn from_search_info_handles_zero_elapsed() {
+        let info = UCIInfo::from(search_info(1234, Duration::ZERO, 0));
+        assert_eq!(info.nps, Some(1_234_000));
+
+        let info = UCIInfo::from(search_info(1234, Duration::from_micros(10), 0));
+        assert_eq!(info.nps, Some(1_234_000));
+    }
+
+    #[test]
+    fn from_search_info_reports_hashfull_in_permille() {
+        let info = UCIInfo::from(search_info(0, Duration::ZERO, 512));
+        assert_eq!(info.hash_full, Some(512));
+
+        let info = UCIInfo::from(search_info(0, Duration::ZERO, 1500));
+        assert_eq!(info.hash_full, Some(1000));
+    }
+
+    #[test]
+    fn display_includes_nps_and_hashfull() {
+        let info = UCIInfo {
+            depth: Some(5),
+            hash_full: Some(12),
+            nps: Some(3456),
+            pv: vec!["e2e4".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(info.to_string(), "depth 5 hashfull 12 nps 3456 pv e2e4");
+    }
+
+    #[test]
+    fn from_curr_move_info() {
+        let board = Board::starting_position();
+        let e4 = board.get_move(Square::E2, Square::E4, None).unwrap();
+
+        let info = UCIInfo::from(CurrMoveInfo {
+            depth: 12,
+            curr_move: e4,
+            curr_move_number: 3,
+        });
+
+        assert_eq!(info.to_string(), "depth 12 currmove e2e4 currmovenumber 3");
+    }
+
+    #[test]
+    fn from_search_info_includes_wdl() {
+        let info = UCIInfo::from(search_info(0, Duration::ZERO, 0));
+        let score = info.score.unwrap();
+
+        let (win, draw, loss) = score.wdl.unwrap();
+        assert_eq!(win + draw + loss, 1000);
+        assert_eq!(win, loss);
+    }
+
+    #[test]
+    fn score_round_trip() {
+        let scores = [
+            Score(0),
+            Score(-57),
+            Score::mate_in(1),
+            Score::mate_in(7),
+            Score::mated_in(1),
+            Score::mated_in(4),
+        ];
+
+        for score in scores {
+            let uci_score = UCIInfoScore::from(score);
+            assert_eq!(uci_score.to_score(), Some(score));
+            assert_eq!(uci_score.to_string().parse(), Ok(score));
+        }
+
+        assert_eq!(UCIInfoScore::from(Score::mate_in(3)).to_string(), "mate 3");
+        assert_eq!(
+            UCIInfoScore::from(Score::mated_in(2)).to_string(),
+            "mate -2"
+        );
+
+        let long_mate = UCIInfoScore {
+            mate: Some(-i32::MAX),
+            ..Default::default()
+        };
+        assert_eq!(long_mate.to_score(), Some(Score::mated_in(u16::MAX)));
+    }
+
+    #[test]
+    fn display_score_with_wdl() {
+        let score = UCIInfoScore {
+            cp: Some(35),
+            wdl: Some((120, 850, 30)),
+            ..Default::default()
+        };
+
+        assert_eq!(score.to_string(), "cp 35 wdl 120 850 30");
+    }
+
+    /// Formats the message the way the engine would send it, then parses it
+    /// back the way a GUI would.
+    fn through_gui(info: UCIInfo) -> UCIInfo {
+        let line = UCIMessage::Info(info).to_string();
+        match line.parse() {
+            Ok(UCIMessage::Info(info)) => info,
+            other => panic!("GUI failed to parse {line:?}: got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn engine_search_info_survives_gui_parsing() {
+        let mut board = Board::try_parse_fen("8/1P2k3/8/8/8/7K/5p2/8 w - - 0 1").unwrap();
+        let pv = [
+            board.push_move(Square::B7, Square::B8, Some(PieceType::Queen)),
+            board.push_move(Square::F2, Square::F1, Some(PieceType::Knight)),
+        ]
+        .map(Option::unwrap);
+
+        let scores = [Score(0), Score(-212), Score::mate_in(3), Score::mated_in(2)];
+        for (score, pv) in scores.into_iter().zip([&pv[..], &pv[..1], &[], &pv[..]]) {
+            let info = UCIInfo::from(SearchInfo {
+                score,
+                pv: pv.to_vec(),
+                multi_pv: Some(1),
+                ..search_info(98_765, Duration::from_micros(123_456), 345)
+            });
+
+            assert_eq!(info.time, Some(Duration::from_millis(123)));
+            assert_eq!(through_gui(info.clone()), info);
+        }
+    }
+
+    #[test]
+    fn engine_curr_move_info_survives_gui_parsing() {
+        let board = Board::starting_position();
+        let nf3 = board.get_move(Square::G1, Square::F3, None).unwrap();
+
+        let info = UCIInfo::from(CurrMoveInfo {
+            depth: 7,
+            curr_move: nf3,
+            curr_move_number: 12,
+        });
+
+        assert_eq!(through_gui(info.clone()), info);
+    }
+}
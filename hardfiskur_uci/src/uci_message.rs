@@ -38,6 +38,7 @@ pub enum UCIMessage {
 
     /// `go ...`
     Go {
+        ponder: bool,
         time_control: Option<UCITimeControl>,
         search_control: Option<UCISearchControl>,
     },
@@ -135,6 +136,14 @@ impl UCIMessage {
             ponder: None,
         }
     }
+
+    /// An `info string` message, for arbitrary text to be shown to the user.
+    pub fn info_string(string: &str) -> Self {
+        Self::Info(UCIInfo {
+            string: Some(string.to_string()),
+            ..Default::default()
+        })
+    }
 }
 
 impl FromStr for UCIMessage {
@@ -191,10 +200,14 @@ impl Display for UCIMessage {
             UCIMessage::Position(pos) => write!(f, "position {pos}"),
 
             UCIMessage::Go {
+                ponder,
                 time_control,
                 search_control,
             } => {
                 write!(f, "go")?;
+                if *ponder {
+                    write!(f, " ponder")?;
+                }
                 if let Some(time_control) = time_control {
                     write!(f, " {time_control}")?;
                 }
@@ -29,6 +29,51 @@ pub enum UCIOptionConfig {
 }
 
 impl UCIOptionConfig {
+    /// A checkbox option, which is either `true` or `false`.
+    pub fn check(name: impl Into<String>, default: bool) -> Self {
+        Self::Check {
+            name: name.into(),
+            default: Some(default),
+        }
+    }
+
+    /// An integer option in the range `min..=max`.
+    pub fn spin(name: impl Into<String>, default: i64, min: i64, max: i64) -> Self {
+        Self::Spin {
+            name: name.into(),
+            default: Some(default),
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    /// An option which can take one of the values in `var`. Each value is
+    /// sent to the GUI as a separate `var` token.
+    pub fn combo<S: Into<String>>(
+        name: impl Into<String>,
+        default: impl Into<String>,
+        var: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self::Combo {
+            name: name.into(),
+            default: Some(default.into()),
+            var: var.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// A button with no value, which the GUI can press to trigger an action.
+    pub fn button(name: impl Into<String>) -> Self {
+        Self::Button { name: name.into() }
+    }
+
+    /// A freeform text option. An empty `default` is sent as `<empty>`.
+    pub fn string(name: impl Into<String>, default: impl Into<String>) -> Self {
+        Self::String {
+            name: name.into(),
+            default: Some(default.into()),
+        }
+    }
+
     pub(crate) fn from_raw(
         name: &str,
         option_type: &str,
@@ -99,7 +144,6 @@ impl UCIOptionConfig {
 }
 
 impl Display for UCIOptionConfig {
-    // FIXME: Gotta use <empty>s.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut formatter = SpaceSepFormatter::new(f);
 
@@ -168,3 +212,89 @@ impl Display for UCIOptionConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::UCIMessage;
+
+    fn option_line(option: UCIOptionConfig) -> String {
+        UCIMessage::Option(option).to_string()
+    }
+
+    #[test]
+    fn display_check() {
+        assert_eq!(
+            option_line(UCIOptionConfig::check("Ponder", false)),
+            "option name Ponder type check default false"
+        );
+    }
+
+    #[test]
+    fn display_spin() {
+        assert_eq!(
+            option_line(UCIOptionConfig::spin("Hash", 16, 1, 4096)),
+            "option name Hash type spin default 16 min 1 max 4096"
+        );
+        assert_eq!(
+            option_line(UCIOptionConfig::spin("Contempt", 0, -500, 500)),
+            "option name Contempt type spin default 0 min -500 max 500"
+        );
+    }
+
+    #[test]
+    fn display_combo() {
+        assert_eq!(
+            option_line(UCIOptionConfig::combo(
+                "Style",
+                "Normal",
+                ["Solid", "Normal", "Risky"]
+            )),
+            "option name Style type combo default Normal var Solid var Normal var Risky"
+        );
+        assert_eq!(
+            option_line(UCIOptionConfig::combo("Empty", "", ["", "Full"])),
+            "option name Empty type combo default <empty> var <empty> var Full"
+        );
+    }
+
+    #[test]
+    fn display_button() {
+        assert_eq!(
+            option_line(UCIOptionConfig::button("Clear Hash")),
+            "option name Clear Hash type button"
+        );
+    }
+
+    #[test]
+    fn display_string() {
+        assert_eq!(
+            option_line(UCIOptionConfig::string("NalimovPath", "c:\\chess\\tb")),
+            "option name NalimovPath type string default c:\\chess\\tb"
+        );
+        assert_eq!(
+            option_line(UCIOptionConfig::string("EvalFile", "")),
+            "option name EvalFile type string default <empty>"
+        );
+    }
+
+    #[test]
+    fn constructed_options_round_trip() {
+        let options = [
+            UCIOptionConfig::check("Own Book", true),
+            UCIOptionConfig::spin("Move Overhead", 10, 0, 5000),
+            UCIOptionConfig::combo("Style", "", ["Solid", "", "Risky"]),
+            UCIOptionConfig::button("Clear Hash"),
+            UCIOptionConfig::string("Book File", ""),
+            UCIOptionConfig::string("Book File", "books/main.bin"),
+        ];
+
+        for option in options {
+            let msg = UCIMessage::Option(option);
+            assert_eq!(msg.to_string().parse(), Ok(msg));
+        }
+    }
+}
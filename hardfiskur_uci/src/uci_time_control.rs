@@ -16,13 +16,10 @@ pub enum UCITimeControl {
         black_increment: Option<Duration>,
         moves_to_go: Option<u32>,
     },
-    Ponder,
 }
 
 impl UCITimeControl {
-    #[allow(clippy::too_many_arguments)]
     pub(crate) fn from_raw(
-        ponder: bool,
         white_time: Option<Duration>,
         black_time: Option<Duration>,
         white_increment: Option<Duration>,
@@ -48,8 +45,6 @@ impl UCITimeControl {
                 black_increment,
                 moves_to_go,
             })
-        } else if ponder {
-            Some(UCITimeControl::Ponder)
         } else {
             None
         }
@@ -86,7 +81,6 @@ impl UCITimeControl {
                     },
                 }
             }
-            UCITimeControl::Ponder => TimeControls::Infinite,
         }
     }
 }
@@ -94,8 +88,6 @@ impl UCITimeControl {
 impl Display for UCITimeControl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            UCITimeControl::Ponder => write!(f, "ponder"),
-
             UCITimeControl::Infinite => write!(f, "infinite"),
 
             UCITimeControl::TimeLeft {
@@ -0,0 +1,106 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// Polyglot key of the starting position.
+const STARTPOS_KEY: u64 = 0x463b96181691fc9c;
+/// e2e4 in Polyglot's move encoding.
+const E2E4: u16 = 4 << 6 | 1 << 9 | 4 | 3 << 3;
+
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    book_path: PathBuf,
+}
+
+impl Engine {
+    /// Starts the engine with a book containing only 1. e4.
+    fn with_book(name: &str) -> Self {
+        let mut entry = Vec::new();
+        entry.extend(STARTPOS_KEY.to_be_bytes());
+        entry.extend(E2E4.to_be_bytes());
+        entry.extend(1u16.to_be_bytes());
+        entry.extend(0u32.to_be_bytes());
+
+        let path =
+            std::env::temp_dir().join(format!("hardfiskur_{name}_{}.bin", std::process::id()));
+        std::fs::write(&path, entry).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_hardfiskur_uci"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                if sender.send(line.unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stdin = child.stdin.take().unwrap();
+        let command = format!("setoption name Book File value {}", path.display());
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            book_path: path,
+        };
+        engine.send(&command);
+        engine.send("setoption name OwnBook value true");
+        engine.send("position startpos");
+        engine
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{command}").unwrap();
+    }
+
+    /// Waits up to `timeout` for a `bestmove` line.
+    fn best_move(&self, timeout: Duration) -> Option<String> {
+        loop {
+            let line = self.lines.recv_timeout(timeout).ok()?;
+            if line.starts_with("bestmove") {
+                return Some(line);
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.book_path);
+    }
+}
+
+#[test]
+fn timed_search_plays_book_move() {
+    let mut engine = Engine::with_book("timed");
+    engine.send("go movetime 10000");
+
+    let best_move = engine.best_move(Duration::from_secs(2));
+    assert_eq!(best_move.as_deref(), Some("bestmove e2e4"));
+}
+
+#[test]
+fn infinite_search_ignores_book() {
+    let mut engine = Engine::with_book("infinite");
+    engine.send("go infinite");
+
+    assert_eq!(engine.best_move(Duration::from_millis(500)), None);
+
+    engine.send("stop");
+    assert!(engine.best_move(Duration::from_secs(10)).is_some());
+}
@@ -1,11 +1,11 @@
 use egui::{
     emath::Rot2,
     epaint::{PathShape, PathStroke},
-    Painter, Pos2, Rect, Stroke,
+    Color32, Painter, Pos2, Rect, Stroke,
 };
 use hardfiskur_core::board::{Color, Square};
 
-use crate::{board_style::BoardStyle, constants::ARROW_COLOR};
+use crate::board_style::BoardStyle;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Arrow {
@@ -20,19 +20,27 @@ impl Arrow {
         style: &BoardStyle,
         board_rect: Rect,
         perspective: Color,
+        color: Color32,
         is_selected: bool,
     ) {
         let origin = style.square_center(self.start, board_rect, perspective);
 
         if self.start == self.end {
-            self.draw_circle(painter, style, origin, is_selected);
+            self.draw_circle(painter, style, origin, color, is_selected);
         } else {
             let end = style.square_center(self.end, board_rect, perspective);
-            self.draw_arrow(painter, style, origin, end, is_selected);
+            self.draw_arrow(painter, style, origin, end, color, is_selected);
         }
     }
 
-    fn draw_circle(&self, painter: &Painter, style: &BoardStyle, origin: Pos2, is_selected: bool) {
+    fn draw_circle(
+        &self,
+        painter: &Painter,
+        style: &BoardStyle,
+        origin: Pos2,
+        color: Color32,
+        is_selected: bool,
+    ) {
         let stroke_width = if is_selected {
             style.highlighted_circle_selected_width
         } else {
@@ -44,7 +52,7 @@ impl Arrow {
             style.square_size / 2.0 - style.highlighted_circle_selected_width,
             Stroke {
                 width: stroke_width,
-                color: ARROW_COLOR,
+                color,
             },
         );
     }
@@ -55,6 +63,7 @@ impl Arrow {
         style: &BoardStyle,
         origin: Pos2,
         end: Pos2,
+        color: Color32,
         is_selected: bool,
     ) {
         use std::f32::consts::PI;
@@ -87,7 +96,7 @@ impl Arrow {
         painter.add(PathShape {
             points,
             closed: true,
-            fill: ARROW_COLOR,
+            fill: color,
             stroke: PathStroke::NONE,
         });
     }
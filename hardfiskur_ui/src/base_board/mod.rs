@@ -6,13 +6,7 @@ use egui::{
 use hardfiskur_core::board::{Bitboard, Color, Piece, PieceType, Square};
 use sprite_state::{AnimatedPieceState, SpriteState};
 
-use crate::{
-    board_style::BoardStyle,
-    constants::{
-        BOARD_BITBOARD_HIGHLIGHT, BOARD_BLACK, BOARD_BLACK_FADED, BOARD_LAST_MOVE, BOARD_WHITE,
-        BOARD_WHITE_FADED, CHESS_PIECES_SPRITE, MOVE_COLOR,
-    },
-};
+use crate::{board_style::BoardStyle, board_theme::BoardTheme, constants::CHESS_PIECES_SPRITE};
 
 use self::{arrow::Arrow, promo_ui::PromotionUi};
 pub use promo_ui::PromotionResult;
@@ -29,11 +23,15 @@ pub struct BaseBoardUIProps<'a> {
     display_bitboard: Bitboard,
     drag_mask: Bitboard,
     allow_arrows: bool,
+    engine_arrows: &'a [(Square, Square)],
     handle_promo_on: Option<(Square, Color)>,
     checked_king_position: Option<Square>,
     fade_out_board: bool,
     show_last_move: Option<(Square, Square)>,
+    skip_animation: bool,
+    animation_speed: f32,
     board_style: BoardStyle,
+    theme: BoardTheme,
 }
 
 impl<'a> BaseBoardUIProps<'a> {
@@ -45,11 +43,15 @@ impl<'a> BaseBoardUIProps<'a> {
             display_bitboard: Bitboard::EMPTY,
             drag_mask: Bitboard::ALL,
             allow_arrows: true,
+            engine_arrows: &[],
             handle_promo_on: None,
             checked_king_position: None,
             fade_out_board: false,
             show_last_move: None,
+            skip_animation: false,
+            animation_speed: 1.0,
             board_style: BoardStyle::default(),
+            theme: BoardTheme::default(),
         }
     }
 
@@ -83,6 +85,14 @@ impl<'a> BaseBoardUIProps<'a> {
         self
     }
 
+    /// Arrows suggested by an engine, drawn underneath the user's arrows in a
+    /// different color. Unlike the user's arrows, these can't be toggled by
+    /// clicking.
+    pub fn engine_arrows(mut self, arrows: &'a [(Square, Square)]) -> Self {
+        self.engine_arrows = arrows;
+        self
+    }
+
     pub fn handle_promo_on(mut self, square: Square, color: Color) -> Self {
         self.handle_promo_on = Some((square, color));
         self
@@ -103,6 +113,21 @@ impl<'a> BaseBoardUIProps<'a> {
         self
     }
 
+    /// Snaps pieces straight to their new squares instead of animating them,
+    /// and finishes any animation that's still in progress. Useful when
+    /// quickly stepping through a game.
+    pub fn skip_animation(mut self, skip_animation: bool) -> Self {
+        self.skip_animation = skip_animation;
+        self
+    }
+
+    /// Multiplier for the speed of piece animations, where 1.0 is the normal
+    /// speed and larger values are faster.
+    pub fn animation_speed(mut self, animation_speed: f32) -> Self {
+        self.animation_speed = animation_speed;
+        self
+    }
+
     pub fn with_style(mut self, style: BoardStyle) -> Self {
         self.board_style = style;
         self
@@ -113,6 +138,11 @@ impl<'a> BaseBoardUIProps<'a> {
         self
     }
 
+    pub fn with_theme(mut self, theme: BoardTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
     fn piece_at(&self, square: Square) -> Option<Piece> {
         self.pieces.get(square.index()).copied().flatten()
     }
@@ -175,6 +205,14 @@ impl BaseBoardUI {
         }
     }
 
+    /// Replaces the default piece sprites. The texture should be laid out
+    /// like the default sprite sheet: king, queen, bishop, knight, rook and
+    /// pawn from left to right, with the white pieces on the top row and the
+    /// black pieces on the bottom row.
+    pub fn set_piece_sprites(&mut self, piece_sprites: TextureHandle) {
+        self.piece_sprites = Some(piece_sprites);
+    }
+
     pub fn props<'a>() -> BaseBoardUIProps<'a> {
         BaseBoardUIProps::new()
     }
@@ -199,8 +237,12 @@ impl BaseBoardUI {
             )
         });
 
-        self.sprite_state
-            .merge_pieces(ui, props.pieces, self.dropped_last_frame);
+        self.sprite_state.set_animation_speed(props.animation_speed);
+        self.sprite_state.merge_pieces(
+            ui,
+            props.pieces,
+            self.dropped_last_frame || props.skip_animation,
+        );
         self.sprite_state.update(ui);
         self.dropped_last_frame = false;
 
@@ -387,10 +429,11 @@ impl BaseBoardUI {
     }
 
     fn board_colors(&self, props: &BaseBoardUIProps<'_>) -> (Color32, Color32) {
+        let theme = &props.theme;
         if props.fade_out_board {
-            (BOARD_WHITE_FADED, BOARD_BLACK_FADED)
+            (theme.board_white_faded, theme.board_black_faded)
         } else {
-            (BOARD_WHITE, BOARD_BLACK)
+            (theme.board_white, theme.board_black)
         }
     }
 
@@ -450,7 +493,7 @@ impl BaseBoardUI {
             }
 
             if self.square_is_last_move(square, props) {
-                painter.rect_filled(rect, 0.0, BOARD_LAST_MOVE);
+                painter.rect_filled(rect, 0.0, props.theme.last_move);
             }
         }
     }
@@ -543,7 +586,7 @@ impl BaseBoardUI {
                         .board_style
                         .board_square(square, self.board_rect, props.perspective),
                     0.0,
-                    MOVE_COLOR,
+                    props.theme.move_color,
                 ));
             } else if props.pieces[square.index()].is_some() {
                 for points in
@@ -554,7 +597,7 @@ impl BaseBoardUI {
                     painter.add(PathShape {
                         points,
                         closed: true,
-                        fill: MOVE_COLOR,
+                        fill: props.theme.move_color,
                         stroke: PathStroke::NONE,
                     });
                 }
@@ -564,7 +607,7 @@ impl BaseBoardUI {
                         .board_style
                         .square_center(square, self.board_rect, props.perspective),
                     props.board_style.square_size * 0.125,
-                    MOVE_COLOR,
+                    props.theme.move_color,
                 );
             }
         }
@@ -578,7 +621,7 @@ impl BaseBoardUI {
                 Shadow {
                     blur: square_size * 0.25,
                     spread: -square_size * 0.125,
-                    color: Color32::RED,
+                    color: props.theme.in_check,
                     ..Default::default()
                 }
                 .as_shape(
@@ -598,18 +641,30 @@ impl BaseBoardUI {
                     props
                         .board_style
                         .board_square(square, self.board_rect, props.perspective);
-                painter.rect_filled(rect, 0.0, BOARD_BITBOARD_HIGHLIGHT);
+                painter.rect_filled(rect, 0.0, props.theme.bitboard_highlight);
             }
         }
     }
 
     fn paint_arrows(&mut self, painter: &Painter, props: &BaseBoardUIProps<'_>) {
+        for &(start, end) in props.engine_arrows {
+            Arrow { start, end }.draw(
+                painter,
+                &props.board_style,
+                self.board_rect,
+                props.perspective,
+                props.theme.engine_arrow,
+                false,
+            );
+        }
+
         for arrow in self.arrows.iter() {
             arrow.draw(
                 painter,
                 &props.board_style,
                 self.board_rect,
                 props.perspective,
+                props.theme.arrow,
                 false,
             );
         }
@@ -624,6 +679,7 @@ impl BaseBoardUI {
                 &props.board_style,
                 self.board_rect,
                 props.perspective,
+                props.theme.arrow,
                 true,
             );
         }
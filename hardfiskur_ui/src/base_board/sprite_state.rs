@@ -3,6 +3,9 @@ use std::iter::repeat;
 use egui::{emath::ease_in_ease_out, Id, Ui};
 use hardfiskur_core::board::{Piece, Square};
 
+/// Time in seconds taken by piece animations at normal speed.
+const ANIMATION_DURATION: f32 = 0.250;
+
 #[derive(Debug, Clone, Copy)]
 pub enum AnimatedPieceState {
     Static(Square),
@@ -34,6 +37,7 @@ pub struct SpriteState {
 
     animation_value: f32,
     animation_id: Id,
+    animation_speed: f32,
     just_started_anim: bool,
 }
 
@@ -44,10 +48,17 @@ impl SpriteState {
             being_captured_pieces: Vec::new(),
             animation_value: 1.0,
             animation_id: id.with("hardfiskur__base_board_animations"),
+            animation_speed: 1.0,
             just_started_anim: false,
         }
     }
 
+    /// Sets a multiplier for how fast animations play. Values of 2.0 and 0.5
+    /// make animations take half and twice as long respectively.
+    pub fn set_animation_speed(&mut self, speed: f32) {
+        self.animation_speed = speed.max(f32::EPSILON);
+    }
+
     fn replace_pieces(&mut self, incoming_pieces: &[Option<Piece>]) {
         self.clear_current_animation();
 
@@ -142,7 +153,7 @@ impl SpriteState {
         self.animation_value = ui.ctx().animate_bool_with_time_and_easing(
             self.animation_id,
             value,
-            0.250,
+            ANIMATION_DURATION / self.animation_speed,
             ease_in_ease_out,
         );
     }
@@ -0,0 +1,46 @@
+use egui::Color32;
+
+use crate::constants::{
+    ARROW_COLOR, BOARD_BITBOARD_HIGHLIGHT, BOARD_BLACK, BOARD_BLACK_FADED, BOARD_LAST_MOVE,
+    BOARD_WHITE, BOARD_WHITE_FADED, ENGINE_ARROW_COLOR, MOVE_COLOR,
+};
+
+/// Colors used to paint the board. The default theme uses the colors in
+/// [`crate::constants`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardTheme {
+    pub board_white: Color32,
+    pub board_black: Color32,
+    /// Square colors used when the board is faded out, e.g. when viewing an
+    /// earlier position.
+    pub board_white_faded: Color32,
+    pub board_black_faded: Color32,
+
+    pub last_move: Color32,
+    pub bitboard_highlight: Color32,
+    pub in_check: Color32,
+    /// Color of the highlighted squares and dots showing possible moves.
+    pub move_color: Color32,
+
+    pub arrow: Color32,
+    pub engine_arrow: Color32,
+}
+
+impl Default for BoardTheme {
+    fn default() -> Self {
+        Self {
+            board_white: BOARD_WHITE,
+            board_black: BOARD_BLACK,
+            board_white_faded: BOARD_WHITE_FADED,
+            board_black_faded: BOARD_BLACK_FADED,
+
+            last_move: BOARD_LAST_MOVE,
+            bitboard_highlight: BOARD_BITBOARD_HIGHLIGHT,
+            in_check: Color32::RED,
+            move_color: MOVE_COLOR,
+
+            arrow: ARROW_COLOR,
+            engine_arrow: ENGINE_ARROW_COLOR,
+        }
+    }
+}
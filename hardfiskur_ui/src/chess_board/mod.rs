@@ -1,8 +1,9 @@
-use egui::{Id, Ui};
+use egui::{Id, TextureHandle, Ui};
 use hardfiskur_core::board::{Bitboard, Board, Color, Move, Piece, Square};
 
 use crate::{
     base_board::{BaseBoardUI, BaseBoardUIProps, BaseBoardUIResponse, PromotionResult},
+    board_theme::BoardTheme,
     constants::MIN_BOARD_SIZE,
 };
 
@@ -13,6 +14,10 @@ pub struct ChessBoardUIProps<'a> {
     perspective: Color,
     fade_out_board: bool,
     show_last_move: Option<(Square, Square)>,
+    engine_arrows: &'a [(Square, Square)],
+    skip_animation: bool,
+    animation_speed: f32,
+    theme: BoardTheme,
 
     // min, max
     board_size: (Option<f32>, Option<f32>),
@@ -26,6 +31,10 @@ impl<'a> ChessBoardUIProps<'a> {
             perspective: Color::White,
             fade_out_board: false,
             show_last_move: None,
+            engine_arrows: &[],
+            skip_animation: false,
+            animation_speed: 1.0,
+            theme: BoardTheme::default(),
             board_size: (None, Some(640.0)),
         }
     }
@@ -50,6 +59,28 @@ impl<'a> ChessBoardUIProps<'a> {
         self
     }
 
+    pub fn engine_arrows(mut self, arrows: &'a [(Square, Square)]) -> Self {
+        self.engine_arrows = arrows;
+        self
+    }
+
+    /// See [`BaseBoardUIProps::skip_animation`].
+    pub fn skip_animation(mut self, skip_animation: bool) -> Self {
+        self.skip_animation = skip_animation;
+        self
+    }
+
+    /// See [`BaseBoardUIProps::animation_speed`].
+    pub fn animation_speed(mut self, animation_speed: f32) -> Self {
+        self.animation_speed = animation_speed;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: BoardTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
     pub fn min_size(mut self, min_size: f32) -> Self {
         self.board_size.0 = Some(min_size);
         self
@@ -96,7 +127,13 @@ impl ChessBoardUI {
         }
     }
 
-    pub fn props(board: &Board) -> ChessBoardUIProps {
+    /// Replaces the default piece sprites. See
+    /// [`BaseBoardUI::set_piece_sprites`] for the expected layout.
+    pub fn set_piece_sprites(&mut self, piece_sprites: TextureHandle) {
+        self.base_board.set_piece_sprites(piece_sprites);
+    }
+
+    pub fn props(board: &Board) -> ChessBoardUIProps<'_> {
         ChessBoardUIProps::new(board)
     }
 
@@ -144,7 +181,7 @@ impl ChessBoardUI {
     fn gather_baseboard_props<'a>(
         &mut self,
         ui: &Ui,
-        props: ChessBoardUIProps<'_>,
+        props: ChessBoardUIProps<'a>,
         pieces: &'a [Option<Piece>],
         possible_moves: &'a [(Square, Square)],
         in_check: bool,
@@ -155,6 +192,10 @@ impl ChessBoardUI {
             perspective,
             fade_out_board,
             show_last_move: last_move,
+            engine_arrows,
+            skip_animation,
+            animation_speed,
+            theme,
 
             board_size,
         } = props;
@@ -185,6 +226,10 @@ impl ChessBoardUI {
                 Bitboard::EMPTY
             })
             .fade_out_board(fade_out_board)
+            .engine_arrows(engine_arrows)
+            .skip_animation(skip_animation)
+            .animation_speed(animation_speed)
+            .with_theme(theme)
             .with_size(board_size);
 
         if let Some(((_start, end), color)) = self.promotion_progress {
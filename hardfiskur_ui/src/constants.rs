@@ -16,3 +16,4 @@ pub const BOARD_BITBOARD_HIGHLIGHT: Color32 = Color32::from_rgba_premultiplied(1
 pub const MOVE_COLOR: Color32 = Color32::from_rgba_premultiplied(13, 72, 16, 154);
 
 pub const ARROW_COLOR: Color32 = Color32::from_rgba_premultiplied(13, 72, 16, 154);
+pub const ENGINE_ARROW_COLOR: Color32 = Color32::from_rgba_premultiplied(16, 48, 110, 154);
@@ -1,4 +1,5 @@
 pub mod base_board;
 pub mod board_style;
+pub mod board_theme;
 pub mod chess_board;
 pub mod constants;
@@ -1,9 +1,13 @@
-use std::time::{Duration, Instant};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    process::Command,
+    time::{Duration, Instant},
+};
 
 use clap::Parser;
 use hardfiskur_core::{
     board::{Board, Piece, UCIMove},
-    perft::perft,
+    perft::{perft, perft_detailed, perft_hashed, PerftCounts, PerftTable},
 };
 
 /// Perft tester for Harðfiskur.
@@ -44,6 +48,57 @@ struct Args {
     /// identifying the exact sequence of moves under which they occur.
     #[arg(long)]
     divide: bool,
+
+    /// Break down the leaf nodes at each depth by category.
+    ///
+    /// Counts the captures, en passant captures, castles and promotions
+    /// leading to the leaves, as well as how many leaves are checks and
+    /// checkmates, matching the standard perft tables. This is slower than
+    /// plain perft, and can't be combined with `--hashed`.
+    #[arg(long, conflicts_with_all = ["divide", "hashed", "compare", "compare_cmd"])]
+    detailed: bool,
+
+    /// Cache node counts of positions in a hash table.
+    ///
+    /// Transpositions are then only counted once, which greatly speeds up
+    /// perft at higher depths.
+    #[arg(long)]
+    hashed: bool,
+
+    /// Size of the hash table used by `--hashed`, in megabytes.
+    #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u64).range(1..))]
+    hash_size: u64,
+
+    /// Compare divide output against a file of `move: count` lines.
+    ///
+    /// Lists the moves whose counts differ from the reference. As the file
+    /// only covers the starting position, use `--compare-cmd` to narrow the
+    /// difference down further.
+    #[arg(long, value_name = "FILE", conflicts_with = "compare_cmd")]
+    compare: Option<String>,
+
+    /// Compare divide output against a command and find where it diverges.
+    ///
+    /// The command is run through `sh` with the depth and FEN appended as two
+    /// extra arguments, and should print `move: count` lines, e.g. a script
+    /// wrapping another engine. Moves whose counts differ are recursed into
+    /// until the first diverging position is found.
+    #[arg(long, value_name = "COMMAND")]
+    compare_cmd: Option<String>,
+}
+
+/// Runs either plain or hashed perft depending on the command line options.
+struct Perft {
+    table: Option<PerftTable>,
+}
+
+impl Perft {
+    fn run(&mut self, board: &mut Board, depth: usize) -> u64 {
+        match self.table.as_mut() {
+            Some(table) => perft_hashed(board, depth, table),
+            None => perft(board, depth),
+        }
+    }
 }
 
 fn parse_position(s: &str) -> Result<Board, String> {
@@ -56,7 +111,7 @@ fn parse_position(s: &str) -> Result<Board, String> {
     }
 }
 
-fn generic_perft(mut board: Board, max_depth: usize) {
+fn generic_perft(mut perft: Perft, mut board: Board, max_depth: usize) {
     let mut total_time = Duration::ZERO;
     let mut last_depth_time = Duration::ZERO;
     let mut total_nodes = 0;
@@ -64,7 +119,7 @@ fn generic_perft(mut board: Board, max_depth: usize) {
     for depth in 0..max_depth {
         let start_time = Instant::now();
 
-        let nodes = perft(&mut board, depth);
+        let nodes = perft.run(&mut board, depth);
 
         let time_taken = start_time.elapsed();
 
@@ -89,12 +144,35 @@ fn generic_perft(mut board: Board, max_depth: usize) {
     );
 }
 
-fn specific_perft(mut board: Board, depth: usize) {
+fn detailed_perft(mut board: Board, max_depth: usize) {
+    println!(
+        "{:>5} {:>14} {:>12} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Depth", "Nodes", "Captures", "E.p.", "Castles", "Promotions", "Checks", "Checkmates"
+    );
+
+    for depth in 0..max_depth {
+        let PerftCounts {
+            nodes,
+            captures,
+            en_passants,
+            castles,
+            promotions,
+            checks,
+            checkmates,
+        } = perft_detailed(&mut board, depth);
+
+        println!(
+            "{depth:>5} {nodes:>14} {captures:>12} {en_passants:>10} {castles:>10} {promotions:>10} {checks:>10} {checkmates:>10}"
+        );
+    }
+}
+
+fn divide(perft: &mut Perft, board: &mut Board, depth: usize) -> BTreeMap<String, u64> {
     assert!(depth >= 1);
 
     let legal_moves = board.legal_moves();
 
-    let mut total_nodes = 0;
+    let mut results = BTreeMap::new();
     for m in legal_moves {
         let move_spec = UCIMove {
             from: m.from_square(),
@@ -103,15 +181,162 @@ fn specific_perft(mut board: Board, depth: usize) {
         };
 
         board.push_move_unchecked(m);
-        let nodes = perft(&mut board, depth - 1);
+        let nodes = perft.run(board, depth - 1);
         board.pop_move();
 
-        total_nodes += nodes;
+        results.insert(move_spec.to_string(), nodes);
+    }
+
+    results
+}
+
+fn specific_perft(mut perft: Perft, mut board: Board, depth: usize) {
+    let results = divide(&mut perft, &mut board, depth);
+
+    for (move_spec, nodes) in &results {
         println!("{move_spec}: {nodes}");
     }
 
     println!();
-    println!("Nodes searched: {total_nodes}");
+    println!("Nodes searched: {}", results.values().sum::<u64>());
+}
+
+/// Where to get the expected divide results from for `--compare`.
+enum Reference {
+    /// Results for the starting position only.
+    File(String),
+    /// Command that can produce results for any position.
+    Command(String),
+}
+
+impl Reference {
+    fn divide(&self, board: &Board, depth: usize) -> Result<BTreeMap<String, u64>, String> {
+        let output = match self {
+            Self::File(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Could not read reference file {path}: {e}"))?,
+            Self::Command(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(format!("{command} \"$@\""))
+                    .arg("sh")
+                    .arg(depth.to_string())
+                    .arg(board.fen())
+                    .output()
+                    .map_err(|e| format!("Could not run reference command: {e}"))?;
+
+                if !output.status.success() {
+                    return Err(format!(
+                        "Reference command failed ({}): {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+        };
+
+        Ok(parse_divide_output(&output))
+    }
+}
+
+/// Parses `move: count` lines, ignoring any other lines (e.g. totals).
+fn parse_divide_output(output: &str) -> BTreeMap<String, u64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (move_spec, nodes) = line.split_once(':')?;
+            let move_spec: UCIMove = move_spec.trim().parse().ok()?;
+            Some((move_spec.to_string(), nodes.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Prints the moves whose counts differ between `ours` and `expected`,
+/// returning them along with whether each was generated and whether it was
+/// expected.
+fn print_mismatches(
+    ours: &BTreeMap<String, u64>,
+    expected: &BTreeMap<String, u64>,
+) -> Vec<(String, bool, bool)> {
+    let all_moves: BTreeSet<_> = ours.keys().chain(expected.keys()).collect();
+
+    let mut mismatches = Vec::new();
+    for move_spec in all_moves {
+        let (got, want) = (ours.get(move_spec), expected.get(move_spec));
+        if got == want {
+            continue;
+        }
+
+        match (got, want) {
+            (Some(got), Some(want)) => println!("{move_spec}: {got} (expected {want})"),
+            (Some(_), None) => println!("{move_spec}: generated but not in reference"),
+            (None, _) => println!("{move_spec}: in reference but not generated"),
+        }
+
+        mismatches.push((move_spec.clone(), got.is_some(), want.is_some()));
+    }
+
+    mismatches
+}
+
+fn compare_perft(
+    mut perft: Perft,
+    mut board: Board,
+    depth: usize,
+    reference: Reference,
+) -> Result<(), String> {
+    let mut path = Vec::new();
+
+    for depth in (1..=depth).rev() {
+        let ours = divide(&mut perft, &mut board, depth);
+        let expected = reference.divide(&board, depth)?;
+        let mismatches = print_mismatches(&ours, &expected);
+
+        let Some((move_spec, generated, expected)) = mismatches.first().cloned() else {
+            if path.is_empty() {
+                println!("No differences found.");
+            } else {
+                println!("All counts match here, so the reference disagrees with its own total for the previous move.");
+            }
+            return Ok(());
+        };
+
+        // A reference file can't be recursed into, as it only has counts for
+        // the starting position
+        if let Reference::File(_) = reference {
+            let moves: Vec<_> = mismatches.iter().map(|(m, _, _)| m.as_str()).collect();
+            println!();
+            println!("Only the root divide was compared against the reference file.");
+            println!("Mismatching moves: {}", moves.join(" "));
+            println!("Use --compare-cmd to narrow the difference down further.");
+            return Ok(());
+        }
+
+        // Can only recurse into moves both sides agree are legal
+        if !(generated && expected) || depth == 1 {
+            break;
+        }
+
+        println!("Recursing into {move_spec}");
+        println!();
+
+        let m: UCIMove = move_spec.parse().expect("generated move should be valid");
+        board
+            .push_move(m.from, m.to, m.promotion)
+            .expect("generated move should be legal");
+        path.push(move_spec);
+    }
+
+    println!();
+    if path.is_empty() {
+        println!("The root position already diverges.");
+    } else {
+        println!("Diverging position reached after moves: {}", path.join(" "));
+    }
+    println!("FEN: {}", board.fen());
+
+    Ok(())
 }
 
 fn main() -> Result<(), String> {
@@ -120,8 +345,23 @@ fn main() -> Result<(), String> {
         moves,
         depth,
         divide,
+        detailed,
+        hashed,
+        hash_size,
+        compare,
+        compare_cmd,
     } = Args::parse();
 
+    let perft = Perft {
+        table: hashed.then(|| {
+            let size = usize::try_from(hash_size)
+                .ok()
+                .and_then(|size| size.try_into().ok())
+                .expect("hash size should be non-zero and fit in usize");
+            PerftTable::new(size)
+        }),
+    };
+
     let mut board = position;
     for move_spec in moves {
         if board
@@ -135,10 +375,18 @@ fn main() -> Result<(), String> {
         }
     }
 
-    if divide {
-        specific_perft(board, depth as _)
+    let reference = compare
+        .map(Reference::File)
+        .or(compare_cmd.map(Reference::Command));
+
+    if let Some(reference) = reference {
+        compare_perft(perft, board, depth as _, reference)?;
+    } else if detailed {
+        detailed_perft(board, depth as _);
+    } else if divide {
+        specific_perft(perft, board, depth as _)
     } else {
-        generic_perft(board, depth as _);
+        generic_perft(perft, board, depth as _);
     }
 
     Ok(())
@@ -40,7 +40,7 @@ impl PSTViewerUI {
     pub fn new(_cc: &eframe::CreationContext) -> Self {
         Self {
             table_type: TableType::PawnPST,
-            endgame_phase: 24,
+            endgame_phase: Phase::MAX,
         }
     }
 }
@@ -76,7 +76,7 @@ impl eframe::App for PSTViewerUI {
                 });
 
             ui.label("Endgame Phase");
-            ui.add(egui::Slider::new(&mut self.endgame_phase, 0..=24));
+            ui.add(egui::Slider::new(&mut self.endgame_phase, 0..=Phase::MAX));
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
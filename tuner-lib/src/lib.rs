@@ -3,11 +3,15 @@ use std::ffi::{c_char, CStr};
 
 use hardfiskur_core::board::Board;
 use hardfiskur_engine::evaluation::{
-    trace::{EvalParameters, EvalTrace, Parameter},
+    trace::{EvalParameters, EvalTrace, Parameter, SparseTrace},
     EvalContext,
 };
 use zerocopy::{transmute_mut, transmute_ref};
 
+pub mod tuning;
+
+use tuning::{find_optimal_k, initial_parameters, TuningEntry};
+
 #[no_mangle]
 pub extern "C" fn hf_parameter_len() -> usize {
     EvalParameters::LEN
@@ -51,7 +55,7 @@ pub unsafe extern "C" fn hf_print_parameters(parameters: *const [f64; 2], parame
     print_parameters_internal(parameters);
 }
 
-fn print_parameters_internal(parameters: &[[f64; 2]]) {
+pub fn print_parameters_internal(parameters: &[[f64; 2]]) {
     let parameters: &[Parameter; EvalParameters::LEN] = parameters
         .try_into()
         .expect("Wrong parameters length in print_parameters");
@@ -90,11 +94,183 @@ pub unsafe extern "C" fn hf_get_fen_eval_result(
 }
 
 fn get_fen_eval_result_internal(fen: &str, out_coeffs: &mut [i16; EvalTrace::LEN]) {
-    let board = Board::try_parse_fen(fen).expect("Could not parse FEN");
     let trace: &mut EvalTrace = transmute_mut!(out_coeffs);
-    // trace.zero();
+    *trace = fen_trace(fen);
+}
+
+fn fen_trace(fen: &str) -> EvalTrace {
+    let board = Board::try_parse_fen(fen).expect("Could not parse FEN");
+
+    let mut trace = EvalTrace::default();
+    let (_score, _phase) = EvalContext::new(&board).evaluate_ex(&mut trace);
+    trace
+}
+
+/// Like [`hf_get_fen_eval_result`], but only writes out the nonzero
+/// coefficients, with their indices in `out_indices` and values in
+/// `out_values`. Returns the number of nonzero coefficients, which may be
+/// more than were written if `out_size` is too small.
+///
+/// # Safety
+/// `out_indices` and `out_values` must both refer to valid contiguously
+/// allocated parts of memory of size `out_size`.
+#[no_mangle]
+pub unsafe extern "C" fn hf_get_fen_eval_result_sparse(
+    fen: *const c_char,
+    out_indices: *mut u16,
+    out_values: *mut i16,
+    out_size: usize,
+) -> usize {
+    let fen = if fen.is_null() {
+        ""
+    } else {
+        unsafe { CStr::from_ptr(fen) }
+            .to_str()
+            .expect("Could not convert FEN to &str")
+    };
+
+    let (out_indices, out_values): (&mut [u16], &mut [i16]) =
+        if out_indices.is_null() || out_values.is_null() {
+            (&mut [], &mut [])
+        } else {
+            unsafe {
+                (
+                    slice::from_raw_parts_mut(out_indices, out_size),
+                    slice::from_raw_parts_mut(out_values, out_size),
+                )
+            }
+        };
+
+    get_fen_eval_result_sparse_internal(fen, out_indices, out_values)
+}
+
+fn get_fen_eval_result_sparse_internal(
+    fen: &str,
+    out_indices: &mut [u16],
+    out_values: &mut [i16],
+) -> usize {
+    let trace = SparseTrace::new(&fen_trace(fen));
+
+    for ((index, value), (out_index, out_value)) in trace
+        .entries()
+        .iter()
+        .zip(out_indices.iter_mut().zip(out_values.iter_mut()))
+    {
+        *out_index = *index;
+        *out_value = *value;
+    }
+
+    trace.len()
+}
+
+/// Finds the scaling constant K which best fits the current evaluation to the
+/// results of the given positions, i.e. minimises the mean squared error of
+/// `1 / (1 + 10^(-K * eval / 400))` against the results. Should be run before
+/// tuning, as the weights are only meaningful relative to K.
+///
+/// `results` are from white's perspective: 1.0 for a white win, 0.5 for a
+/// draw and 0.0 for a black win.
+///
+/// # Safety
+/// `fens` and `results` must both refer to valid contiguously allocated parts
+/// of memory of size `n`, and each FEN must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hf_find_optimal_k(
+    fens: *const *const c_char,
+    results: *const f64,
+    n: usize,
+) -> f64 {
+    let (fens, results): (&[*const c_char], &[f64]) = if fens.is_null() || results.is_null() {
+        (&[], &[])
+    } else {
+        unsafe {
+            (
+                slice::from_raw_parts(fens, n),
+                slice::from_raw_parts(results, n),
+            )
+        }
+    };
+
+    let fens: Vec<&str> = fens
+        .iter()
+        .map(|&fen| {
+            unsafe { CStr::from_ptr(fen) }
+                .to_str()
+                .expect("Could not convert FEN to &str")
+        })
+        .collect();
+
+    find_optimal_k_internal(&fens, results)
+}
+
+fn find_optimal_k_internal(fens: &[&str], results: &[f64]) -> f64 {
+    let entries: Vec<_> = fens
+        .iter()
+        .zip(results)
+        .map(|(fen, &result)| {
+            let board = Board::try_parse_fen(fen).expect("Could not parse FEN");
+            TuningEntry::new(&board, result)
+        })
+        .collect();
+
+    find_optimal_k(&entries, &initial_parameters())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const FEN: &str = "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5";
+
+    #[test]
+    fn sparse_result_matches_dense() {
+        let mut dense = [0; EvalTrace::LEN];
+        get_fen_eval_result_internal(FEN, &mut dense);
+
+        let mut indices = [0; EvalTrace::LEN];
+        let mut values = [0; EvalTrace::LEN];
+        let len = get_fen_eval_result_sparse_internal(FEN, &mut indices, &mut values);
+
+        let expected: Vec<_> = (0..EvalTrace::LEN)
+            .filter(|&i| dense[i] != 0)
+            .map(|i| (i as u16, dense[i]))
+            .collect();
+        let actual: Vec<_> = indices[..len]
+            .iter()
+            .copied()
+            .zip(values[..len].iter().copied())
+            .collect();
+        assert_eq!(actual, expected);
+
+        let trace = SparseTrace::new(&fen_trace(FEN));
+        let round_trip = trace.to_dense();
+        let round_trip: &[i16; EvalTrace::LEN] = transmute_ref!(&round_trip);
+        assert_eq!(round_trip, &dense);
+    }
+
+    #[test]
+    fn optimal_k_is_positive_for_consistent_results() {
+        // Results agree with the sign of the evaluation, so a larger K fits
+        // them better than K = 0, which would predict 0.5 everywhere.
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/QQQ1K3 w - - 0 1",
+            "qqq1k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        ];
+        let results = [0.5, 1.0, 0.0];
+
+        let k = find_optimal_k_internal(&fens, &results);
+        assert!(k > 0.0);
+    }
+
+    #[test]
+    fn sparse_result_reports_length_when_truncated() {
+        let mut indices = [0; 4];
+        let mut values = [0; 4];
+        let len = get_fen_eval_result_sparse_internal(FEN, &mut indices, &mut values);
 
-    let mut new_trace = EvalTrace::default();
-    let (_score, _phase) = EvalContext::new(&board).evaluate_ex(&mut new_trace);
-    *trace = new_trace;
+        assert!(len > 4);
+        assert!(values.iter().all(|&value| value != 0));
+    }
 }
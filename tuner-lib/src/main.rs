@@ -0,0 +1,90 @@
+use std::{path::PathBuf, time::Instant};
+
+use clap::Parser;
+use tuner_lib::{
+    print_parameters_internal,
+    tuning::{
+        find_optimal_k, gradient, initial_parameters, load_dataset, mean_squared_error, Adam,
+    },
+};
+
+/// Texel tuner for Harðfiskur's evaluation.
+///
+/// Optimises the evaluation parameters to minimise the error between the
+/// predicted and actual results of a dataset of positions, starting from the
+/// parameters the engine is currently using.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Dataset to tune on.
+    ///
+    /// Each line should consist of a position in Forsyth-Edwards Notation
+    /// (FEN) followed by the result of the game from white's perspective,
+    /// either as a score (1.0, 0.5, 0.0) or in PGN notation (1-0, 1/2-1/2,
+    /// 0-1). The result may be wrapped in brackets or separated from the FEN
+    /// by `|` or `;`.
+    dataset: PathBuf,
+
+    /// Number of passes over the dataset.
+    #[arg(short, long, default_value_t = 1000)]
+    epochs: usize,
+
+    /// Learning rate for Adam, in centipawns.
+    #[arg(short, long, default_value_t = 1.0)]
+    learning_rate: f64,
+
+    /// Print the current parameters every this many epochs.
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    print_every: u64,
+
+    /// Scaling constant for mapping evaluations to expected scores.
+    ///
+    /// If not provided, it's fit to the dataset with the initial parameters
+    /// before tuning.
+    #[arg(short)]
+    k: Option<f64>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let start_time = Instant::now();
+    let entries = match load_dataset(&args.dataset) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Could not load dataset {:?}: {e}", args.dataset);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Loaded {} positions in {:.3}s",
+        entries.len(),
+        start_time.elapsed().as_secs_f64()
+    );
+
+    let mut parameters = initial_parameters();
+
+    let k = args.k.unwrap_or_else(|| {
+        let k = find_optimal_k(&entries, &parameters);
+        println!("Optimal K: {k:.6}");
+        k
+    });
+
+    println!(
+        "Initial error: {:.8}",
+        mean_squared_error(&entries, &parameters, k)
+    );
+
+    let mut adam = Adam::new(parameters.len(), args.learning_rate);
+    for epoch in 1..=args.epochs {
+        let gradient = gradient(&entries, &parameters, k);
+        adam.step(&mut parameters, &gradient);
+
+        if (epoch as u64).is_multiple_of(args.print_every) || epoch == args.epochs {
+            println!(
+                "Epoch {epoch}: error {:.8}",
+                mean_squared_error(&entries, &parameters, k)
+            );
+            print_parameters_internal(&parameters);
+        }
+    }
+}
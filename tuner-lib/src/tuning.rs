@@ -0,0 +1,391 @@
+//! Texel tuning of [`EvalParameters`] against a dataset of positions labelled
+//! with game results.
+//!
+//! The evaluation is linear in its parameters before tapering, so each
+//! position is stored as its trace coefficients and phase, and evaluated with
+//! candidate parameters by a dot product instead of rerunning the evaluation.
+//! Only the nonzero coefficients are stored, which is a small fraction of
+//! them for any position, so large datasets fit in memory and evaluations and
+//! gradients only touch the parameters that matter.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    thread,
+};
+
+use hardfiskur_core::board::Board;
+use hardfiskur_engine::evaluation::{
+    phase::Phase,
+    trace::{EvalParameters, EvalTrace, Parameter, SparseTrace},
+    EvalContext,
+};
+use zerocopy::transmute_ref;
+
+/// Range of scaling constants searched by [`find_optimal_k`].
+const K_SEARCH_RANGE: (f64, f64) = (0.0, 10.0);
+const K_SEARCH_ITERATIONS: usize = 100;
+
+/// A position's trace coefficients along with the result of the game it was
+/// taken from.
+#[derive(Debug, Clone)]
+pub struct TuningEntry {
+    pub coeffs: SparseTrace,
+    pub phase: Phase,
+    /// Result from white's perspective: 1.0 for a win, 0.5 for a draw and 0.0
+    /// for a loss.
+    pub result: f64,
+}
+
+impl TuningEntry {
+    pub fn new(board: &Board, result: f64) -> Self {
+        let mut trace = EvalTrace::default();
+        let (_score, phase) = EvalContext::new(board).evaluate_ex(&mut trace);
+
+        Self {
+            coeffs: SparseTrace::new(&trace),
+            phase,
+            result,
+        }
+    }
+
+    /// Evaluates the position from white's perspective with the given
+    /// parameters, without rounding.
+    pub fn evaluate(&self, parameters: &[Parameter]) -> f64 {
+        let (mut mg, mut eg) = (0.0, 0.0);
+        for &(index, coeff) in self.coeffs.entries() {
+            let [param_mg, param_eg] = parameters[index as usize];
+            mg += coeff as f64 * param_mg;
+            eg += coeff as f64 * param_eg;
+        }
+
+        let (mg_weight, eg_weight) = self.taper_weights();
+        mg * mg_weight + eg * eg_weight
+    }
+
+    fn taper_weights(&self) -> (f64, f64) {
+        // Clamped the same way as Phase::taper, so extra material from
+        // promotions doesn't push the weights outside 0..=1
+        let mg_weight = self.phase.0.clamp(0, Phase::MAX) as f64 / Phase::MAX as f64;
+        (mg_weight, 1.0 - mg_weight)
+    }
+}
+
+/// Parses a game result, either as a score (`1.0`, `0.5`, `0.0`) or in PGN
+/// notation (`1-0`, `1/2-1/2`, `0-1`), optionally wrapped in brackets or
+/// quotes.
+pub fn parse_result(s: &str) -> Option<f64> {
+    let s = s.trim_matches(|c: char| "[]\"';".contains(c) || c.is_whitespace());
+
+    match s {
+        "1-0" => Some(1.0),
+        "1/2-1/2" => Some(0.5),
+        "0-1" => Some(0.0),
+        _ => s.parse().ok().filter(|x| (0.0..=1.0).contains(x)),
+    }
+}
+
+/// Parses a line of a dataset, consisting of a FEN followed by the result,
+/// optionally separated by `|` or `;`.
+pub fn parse_dataset_line(line: &str) -> Option<(Board, f64)> {
+    let line = line.trim();
+    let (fen, result) = match line.rsplit_once(['|', ';']) {
+        Some((fen, result)) if !result.trim().is_empty() => (fen, result),
+        _ => line.rsplit_once(char::is_whitespace)?,
+    };
+
+    let board = Board::try_parse_fen(fen.trim()).ok()?;
+    let result = parse_result(result)?;
+
+    Some((board, result))
+}
+
+/// Loads a dataset with a position and result on each line, as described in
+/// [`parse_dataset_line`]. Empty lines are skipped.
+pub fn load_dataset(path: impl AsRef<Path>) -> io::Result<Vec<TuningEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (board, result) = parse_dataset_line(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid dataset entry on line {}: {line}", line_number + 1),
+            )
+        })?;
+
+        entries.push(TuningEntry::new(&board, result));
+    }
+
+    Ok(entries)
+}
+
+/// The parameters the engine is currently using.
+pub fn initial_parameters() -> Vec<Parameter> {
+    let parameters = EvalParameters::default();
+    let parameters: &[Parameter; EvalParameters::LEN] = transmute_ref!(&parameters);
+    parameters.to_vec()
+}
+
+/// Maps an evaluation in centipawns to an expected score between 0 and 1.
+pub fn sigmoid(eval: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+/// Splits `entries` into one chunk per available thread, runs `f` on each
+/// chunk, and returns the results.
+fn map_chunks<T: Send>(entries: &[TuningEntry], f: impl Fn(&[TuningEntry]) -> T + Sync) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = entries.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = entries
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| f(chunk)))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Mean squared error between the predicted and actual results.
+pub fn mean_squared_error(entries: &[TuningEntry], parameters: &[Parameter], k: f64) -> f64 {
+    if entries.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = map_chunks(entries, |chunk| {
+        chunk
+            .iter()
+            .map(|entry| (sigmoid(entry.evaluate(parameters), k) - entry.result).powi(2))
+            .sum::<f64>()
+    })
+    .into_iter()
+    .sum();
+
+    total / entries.len() as f64
+}
+
+/// Finds the scaling constant which minimises the error of the given
+/// parameters, with a ternary search. The error is unimodal in `k`, so this
+/// finds the global minimum.
+pub fn find_optimal_k(entries: &[TuningEntry], parameters: &[Parameter]) -> f64 {
+    let (mut lo, mut hi) = K_SEARCH_RANGE;
+
+    for _ in 0..K_SEARCH_ITERATIONS {
+        let m1 = lo + (hi - lo) / 3.0;
+        let m2 = hi - (hi - lo) / 3.0;
+
+        if mean_squared_error(entries, parameters, m1) < mean_squared_error(entries, parameters, m2)
+        {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+
+    (lo + hi) / 2.0
+}
+
+/// Gradient of [`mean_squared_error`] with respect to each parameter.
+pub fn gradient(entries: &[TuningEntry], parameters: &[Parameter], k: f64) -> Vec<Parameter> {
+    let partials = map_chunks(entries, |chunk| {
+        let mut gradient = vec![[0.0; 2]; parameters.len()];
+
+        for entry in chunk {
+            let predicted = sigmoid(entry.evaluate(parameters), k);
+            // d/d(eval) of (sigmoid(eval) - result)^2
+            let error_gradient = 2.0
+                * (predicted - entry.result)
+                * predicted
+                * (1.0 - predicted)
+                * k
+                * std::f64::consts::LN_10
+                / 400.0;
+
+            let (mg_weight, eg_weight) = entry.taper_weights();
+            for &(index, coeff) in entry.coeffs.entries() {
+                let [grad_mg, grad_eg] = &mut gradient[index as usize];
+                *grad_mg += error_gradient * coeff as f64 * mg_weight;
+                *grad_eg += error_gradient * coeff as f64 * eg_weight;
+            }
+        }
+
+        gradient
+    });
+
+    let scale = 1.0 / entries.len().max(1) as f64;
+    let mut gradient = vec![[0.0; 2]; parameters.len()];
+    for partial in partials {
+        for ([total_mg, total_eg], [mg, eg]) in gradient.iter_mut().zip(partial) {
+            *total_mg += mg * scale;
+            *total_eg += eg * scale;
+        }
+    }
+
+    gradient
+}
+
+/// The Adam optimiser, with a learning rate in centipawns.
+#[derive(Debug, Clone)]
+pub struct Adam {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+
+    step: i32,
+    first_moments: Vec<Parameter>,
+    second_moments: Vec<Parameter>,
+}
+
+impl Adam {
+    pub fn new(parameter_count: usize, learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+
+            step: 0,
+            first_moments: vec![[0.0; 2]; parameter_count],
+            second_moments: vec![[0.0; 2]; parameter_count],
+        }
+    }
+
+    pub fn step(&mut self, parameters: &mut [Parameter], gradient: &[Parameter]) {
+        self.step += 1;
+        let first_correction = 1.0 - self.beta1.powi(self.step);
+        let second_correction = 1.0 - self.beta2.powi(self.step);
+
+        for (((param, grad), m), v) in parameters
+            .iter_mut()
+            .zip(gradient)
+            .zip(&mut self.first_moments)
+            .zip(&mut self.second_moments)
+        {
+            for i in 0..2 {
+                m[i] = self.beta1 * m[i] + (1.0 - self.beta1) * grad[i];
+                v[i] = self.beta2 * v[i] + (1.0 - self.beta2) * grad[i] * grad[i];
+
+                let m_hat = m[i] / first_correction;
+                let v_hat = v[i] / second_correction;
+                param[i] -= self.learning_rate * m_hat / (v_hat.sqrt() + self.epsilon);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TEST_FENS: [&str; 3] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQK2R w KQkq - 1 5",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ];
+
+    #[test]
+    fn parses_results() {
+        assert_eq!(parse_result("1-0"), Some(1.0));
+        assert_eq!(parse_result("[0.5]"), Some(0.5));
+        assert_eq!(parse_result("\"0-1\";"), Some(0.0));
+        assert_eq!(parse_result("2.0"), None);
+        assert_eq!(parse_result("win"), None);
+    }
+
+    #[test]
+    fn parses_dataset_lines() {
+        let (board, result) = parse_dataset_line(&format!("{} [1.0]", TEST_FENS[1])).unwrap();
+        assert_eq!(board, Board::try_parse_fen(TEST_FENS[1]).unwrap());
+        assert_eq!(result, 1.0);
+
+        let (_, result) = parse_dataset_line(&format!("{} | 1/2-1/2", TEST_FENS[1])).unwrap();
+        assert_eq!(result, 0.5);
+
+        assert!(parse_dataset_line(TEST_FENS[1]).is_none());
+    }
+
+    #[test]
+    fn evaluation_from_coefficients_matches_engine() {
+        let parameters = initial_parameters();
+
+        for fen in TEST_FENS {
+            let board = Board::try_parse_fen(fen).unwrap();
+            let entry = TuningEntry::new(&board, 0.5);
+            let (score, _) = EvalContext::new(&board).evaluate_ex(&mut EvalTrace::default());
+
+            let eval = entry.evaluate(&parameters);
+            assert!(
+                (eval - score.get() as f64).abs() <= 1.0,
+                "{fen}: {eval} vs {}",
+                score.get()
+            );
+        }
+    }
+
+    #[test]
+    fn evaluation_matches_engine_above_max_phase() {
+        // Promoted queens take the phase past Phase::MAX
+        let board =
+            Board::try_parse_fen("rnbqkbnr/pppppppp/8/8/8/8/QQQPPPPP/RNBQKBNR w KQkq - 0 1")
+                .unwrap();
+        let entry = TuningEntry::new(&board, 1.0);
+        assert!(entry.phase.0 > Phase::MAX);
+
+        let (score, _) = EvalContext::new(&board).evaluate_ex(&mut EvalTrace::default());
+        let eval = entry.evaluate(&initial_parameters());
+        assert!(
+            (eval - score.get() as f64).abs() <= 1.0,
+            "{eval} vs {}",
+            score.get()
+        );
+    }
+
+    #[test]
+    fn optimal_k_minimises_error() {
+        let entries: Vec<_> = TEST_FENS
+            .iter()
+            .zip([0.5, 1.0, 1.0])
+            .map(|(fen, result)| TuningEntry::new(&Board::try_parse_fen(fen).unwrap(), result))
+            .collect();
+        let parameters = initial_parameters();
+
+        let k = find_optimal_k(&entries, &parameters);
+        let error = mean_squared_error(&entries, &parameters, k);
+        assert!(error <= mean_squared_error(&entries, &parameters, k * 0.9));
+        assert!(error <= mean_squared_error(&entries, &parameters, k * 1.1));
+    }
+
+    #[test]
+    fn adam_reduces_error() {
+        let entries: Vec<_> = TEST_FENS
+            .iter()
+            .zip([0.0, 1.0, 0.0])
+            .map(|(fen, result)| TuningEntry::new(&Board::try_parse_fen(fen).unwrap(), result))
+            .collect();
+
+        let mut parameters = initial_parameters();
+        let k = 1.0;
+        let initial_error = mean_squared_error(&entries, &parameters, k);
+
+        let mut adam = Adam::new(parameters.len(), 1.0);
+        for _ in 0..10 {
+            let gradient = gradient(&entries, &parameters, k);
+            adam.step(&mut parameters, &gradient);
+        }
+
+        assert!(mean_squared_error(&entries, &parameters, k) < initial_error);
+    }
+}